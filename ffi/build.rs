@@ -0,0 +1,22 @@
+/*!
+ * Generates include/braincrub.h from src/lib.rs with the settings of cbindgen.toml,
+ * so the header committed is always the one of the functions built.
+ */
+use std::{env, path::Path};
+
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").expect("cargo sets CARGO_MANIFEST_DIR");
+    let crate_dir = Path::new(&crate_dir);
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+        .expect("cbindgen.toml is a cbindgen configuration");
+
+    println!("cargo::rerun-if-changed=cbindgen.toml");
+    println!("cargo::rerun-if-changed=src/lib.rs");
+
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src/lib.rs"))
+        .generate()
+        .expect("src/lib.rs has the C interface cbindgen reads")
+        .write_to_file(crate_dir.join("include/braincrub.h"));
+}
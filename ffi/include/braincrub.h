@@ -0,0 +1,170 @@
+#ifndef BRAINCRUB_H
+#define BRAINCRUB_H
+
+/* Generated with cbindgen from ffi/src/lib.rs, don't edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ * Bytes of the message of a BcError, the NUL included.
+ */
+#define BC_ERROR_MESSAGE_SIZE 256
+
+typedef enum BcStatus {
+  BC_STATUS_OK = 0,
+  BC_STATUS_NULL_ARGUMENT = 1,
+  BC_STATUS_PARSE = 2,
+  BC_STATUS_RUNTIME = 3,
+  BC_STATUS_LIMIT = 4,
+  BC_STATUS_PANIC = 5,
+} BcStatus;
+
+/*
+ * A parsed program, opaque for C.
+ */
+typedef struct BcProgram BcProgram;
+
+/*
+ * What a run did, opaque for C: the status of the run, its error, the bytes the
+ * program printed, the ones before failing included, and its summary.
+ */
+typedef struct BcResult BcResult;
+
+/*
+ * Why bc_parse failed. The position is the byte of the source code with the
+ * unbalanced bracket, and the message is always terminated by a NUL.
+ */
+typedef struct BcError {
+  enum BcStatus status;
+  size_t position;
+  char message[BC_ERROR_MESSAGE_SIZE];
+} BcError;
+
+/*
+ * The limits of a run. Without options, bc_run uses the ones of the CLI.
+ */
+typedef struct BcRunOptions {
+  size_t memory_size;
+  uint64_t instruction_limit;
+} BcRunOptions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ * Parses the len bytes of src. On success *out is the program, to release with
+ * bc_free_program. On failure *out is NULL and err, when it isn't NULL, says why.
+ *
+ * # Safety
+ *
+ * src must point to len readable bytes, out to a writable pointer and err to a
+ * writable BcError or be NULL.
+ */
+enum BcStatus bc_parse(const char *src, size_t len, struct BcProgram **out, struct BcError *err);
+
+/*
+ * Runs the program with the input_len bytes of input, which can be NULL when
+ * input_len is 0, and the options, or the defaults when they are NULL. *out is
+ * the result to release with bc_free_result, also when the program failed, so its
+ * output and its summary can be read. It is NULL only when the status is
+ * BC_STATUS_NULL_ARGUMENT or BC_STATUS_PANIC.
+ *
+ * # Safety
+ *
+ * program must come from bc_parse and not be freed, input must point to
+ * input_len readable bytes, options to a BcRunOptions or be NULL, and out to a
+ * writable pointer.
+ */
+enum BcStatus bc_run(const struct BcProgram *program,
+                     const uint8_t *input,
+                     size_t input_len,
+                     const struct BcRunOptions *options,
+                     struct BcResult **out);
+
+/*
+ * The bytes the program printed, with their number in *len. The pointer is valid
+ * until the result is freed.
+ *
+ * # Safety
+ *
+ * result must come from bc_run and not be freed, and len must be writable.
+ */
+const uint8_t *bc_result_output(const struct BcResult *result, size_t *len);
+
+/*
+ * The status of the run, BC_STATUS_NULL_ARGUMENT for NULL.
+ *
+ * # Safety
+ *
+ * result must come from bc_run and not be freed.
+ */
+enum BcStatus bc_result_status(const struct BcResult *result);
+
+/*
+ * The message of the error of the run, NUL terminated, or NULL when it succeeded.
+ * The pointer is valid until the result is freed.
+ *
+ * # Safety
+ *
+ * result must come from bc_run and not be freed.
+ */
+const char *bc_result_error_message(const struct BcResult *result);
+
+/*
+ * # Safety
+ *
+ * result must come from bc_run and not be freed, or be NULL, which gives 0.
+ */
+uint64_t bc_result_instructions_executed(const struct BcResult *result);
+
+/*
+ * # Safety
+ *
+ * result must come from bc_run and not be freed, or be NULL, which gives 0.
+ */
+size_t bc_result_bytes_written(const struct BcResult *result);
+
+/*
+ * # Safety
+ *
+ * result must come from bc_run and not be freed, or be NULL, which gives 0.
+ */
+size_t bc_result_bytes_read(const struct BcResult *result);
+
+/*
+ * # Safety
+ *
+ * result must come from bc_run and not be freed, or be NULL, which gives 0.
+ */
+size_t bc_result_max_memory_position(const struct BcResult *result);
+
+/*
+ * Releases the program and sets *program to NULL. Both NULL and a pointer to NULL
+ * are ignored.
+ *
+ * # Safety
+ *
+ * *program must come from bc_parse, or be NULL.
+ */
+void bc_free_program(struct BcProgram **program);
+
+/*
+ * Releases the result and sets *result to NULL. Both NULL and a pointer to NULL
+ * are ignored.
+ *
+ * # Safety
+ *
+ * *result must come from bc_run, or be NULL.
+ */
+void bc_free_result(struct BcResult **result);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* BRAINCRUB_H */
@@ -0,0 +1,599 @@
+/*!
+ * The C interface of the braincrub library, built as a cdylib. A program is parsed
+ * once with bc_parse and run as many times as needed with bc_run. Everything the
+ * library allocates is released with its bc_free_* function, which takes the
+ * address of the pointer and sets it to NULL, so freeing it twice does nothing.
+ * The panics are caught before reaching C and returned as BC_STATUS_PANIC.
+ *
+ * include/braincrub.h is the header of these functions, generated by build.rs with
+ * cbindgen from this file and cbindgen.toml.
+ */
+use std::{
+    ffi::{CString, c_char},
+    panic::{AssertUnwindSafe, catch_unwind},
+    ptr, slice,
+};
+
+use braincrub::{
+    interpreter::{InterpreterErrors, RunSummary},
+    parser::{BrainfuckNodeAST, from_source_to_node_ast},
+    run::{BraincrubError, RunConfig, run_ast},
+};
+
+/**
+ * Bytes of the message of a BcError, the NUL included.
+ */
+pub const BC_ERROR_MESSAGE_SIZE: usize = 256;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BcStatus {
+    Ok = 0,
+    NullArgument = 1,
+    Parse = 2,
+    Runtime = 3,
+    Limit = 4,
+    Panic = 5,
+}
+
+/**
+ * Why bc_parse failed. The position is the byte of the source code with the
+ * unbalanced bracket, and the message is always terminated by a NUL.
+ */
+#[repr(C)]
+pub struct BcError {
+    pub status: BcStatus,
+    pub position: usize,
+    pub message: [c_char; BC_ERROR_MESSAGE_SIZE],
+}
+
+/**
+ * The limits of a run. Without options, bc_run uses the ones of the CLI.
+ */
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct BcRunOptions {
+    pub memory_size: usize,
+    pub instruction_limit: u64,
+}
+
+/**
+ * A parsed program, opaque for C.
+ */
+pub struct BcProgram {
+    ast: Vec<BrainfuckNodeAST>,
+}
+
+/**
+ * What a run did, opaque for C: the status of the run, its error, the bytes the
+ * program printed, the ones before failing included, and its summary.
+ */
+pub struct BcResult {
+    status: BcStatus,
+    message: Option<CString>,
+    output: Vec<u8>,
+    summary: RunSummary,
+}
+
+fn write_error(error: *mut BcError, status: BcStatus, position: usize, message: &str) {
+    if error.is_null() {
+        return;
+    }
+
+    let mut buffer = [0 as c_char; BC_ERROR_MESSAGE_SIZE];
+    for (target, byte) in buffer
+        .iter_mut()
+        .zip(message.bytes().take(BC_ERROR_MESSAGE_SIZE - 1))
+    {
+        *target = byte as c_char;
+    }
+
+    // SAFETY: the caller gives either NULL, checked above, or a writable BcError.
+    unsafe {
+        error.write(BcError {
+            status,
+            position,
+            message: buffer,
+        })
+    };
+}
+
+/**
+ * Runs the operation and catches its panic, which can't unwind into C, as
+ * BC_STATUS_PANIC.
+ */
+fn catch_panic<T>(operation: impl FnOnce() -> T) -> Result<T, BcStatus> {
+    catch_unwind(AssertUnwindSafe(operation)).map_err(|_| BcStatus::Panic)
+}
+
+fn status_of(error: &BraincrubError) -> BcStatus {
+    match error {
+        BraincrubError::Parse(_) => BcStatus::Parse,
+        BraincrubError::Runtime(
+            InterpreterErrors::UnableToCompleteTheProgram
+            | InterpreterErrors::OutputLimitReached { .. },
+        ) => BcStatus::Limit,
+        BraincrubError::Runtime(_) | BraincrubError::Build(_) => BcStatus::Runtime,
+    }
+}
+
+/**
+ * Parses the len bytes of src. On success *out is the program, to release with
+ * bc_free_program. On failure *out is NULL and err, when it isn't NULL, says why.
+ *
+ * # Safety
+ *
+ * src must point to len readable bytes, out to a writable pointer and err to a
+ * writable BcError or be NULL.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bc_parse(
+    src: *const c_char,
+    len: usize,
+    out: *mut *mut BcProgram,
+    err: *mut BcError,
+) -> BcStatus {
+    if src.is_null() || out.is_null() {
+        write_error(err, BcStatus::NullArgument, 0, "src and out can't be NULL");
+        return BcStatus::NullArgument;
+    }
+    // SAFETY: out isn't NULL and the caller gives a writable pointer.
+    unsafe { out.write(ptr::null_mut()) };
+
+    let parsed = catch_panic(|| {
+        // SAFETY: the caller gives len readable bytes at src, which isn't NULL.
+        let bytes = unsafe { slice::from_raw_parts(src.cast::<u8>(), len) };
+        from_source_to_node_ast(&String::from_utf8_lossy(bytes))
+    });
+
+    match parsed {
+        Ok(Ok(ast)) => {
+            // SAFETY: out isn't NULL and the caller gives a writable pointer.
+            unsafe { out.write(Box::into_raw(Box::new(BcProgram { ast }))) };
+            BcStatus::Ok
+        }
+        Ok(Err(error)) => {
+            write_error(err, BcStatus::Parse, error.position(), &error.to_string());
+            BcStatus::Parse
+        }
+        Err(status) => {
+            write_error(err, status, 0, "The parser panicked");
+            status
+        }
+    }
+}
+
+/**
+ * Runs the program with the input_len bytes of input, which can be NULL when
+ * input_len is 0, and the options, or the defaults when they are NULL. *out is
+ * the result to release with bc_free_result, also when the program failed, so its
+ * output and its summary can be read. It is NULL only when the status is
+ * BC_STATUS_NULL_ARGUMENT or BC_STATUS_PANIC.
+ *
+ * # Safety
+ *
+ * program must come from bc_parse and not be freed, input must point to
+ * input_len readable bytes, options to a BcRunOptions or be NULL, and out to a
+ * writable pointer.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bc_run(
+    program: *const BcProgram,
+    input: *const u8,
+    input_len: usize,
+    options: *const BcRunOptions,
+    out: *mut *mut BcResult,
+) -> BcStatus {
+    if program.is_null() || out.is_null() || (input.is_null() && input_len > 0) {
+        return BcStatus::NullArgument;
+    }
+    // SAFETY: out isn't NULL and the caller gives a writable pointer.
+    unsafe { out.write(ptr::null_mut()) };
+
+    let config = match options.is_null() {
+        true => RunConfig::default(),
+        false => {
+            // SAFETY: options isn't NULL and the caller gives a BcRunOptions.
+            let options = unsafe { *options };
+
+            RunConfig {
+                memory_size: options.memory_size,
+                number_of_reads: options.instruction_limit,
+                ..RunConfig::default()
+            }
+        }
+    };
+    let ran = catch_panic(|| {
+        // SAFETY: program comes from bc_parse, and input has input_len bytes or
+        // is only read when input_len isn't 0.
+        let (program, input) = unsafe {
+            (
+                &*program,
+                match input_len {
+                    0 => &[][..],
+                    _ => slice::from_raw_parts(input, input_len),
+                },
+            )
+        };
+
+        run_ast(&program.ast, input, &config)
+    });
+
+    let result = match ran {
+        Ok((result, Ok(()))) => BcResult {
+            status: BcStatus::Ok,
+            message: None,
+            output: result.output,
+            summary: result.summary,
+        },
+        Ok((result, Err(error))) => BcResult {
+            status: status_of(&error),
+            message: CString::new(error.to_string()).ok(),
+            output: result.output,
+            summary: result.summary,
+        },
+        Err(status) => return status,
+    };
+    let status = result.status;
+
+    // SAFETY: out isn't NULL and the caller gives a writable pointer.
+    unsafe { out.write(Box::into_raw(Box::new(result))) };
+    status
+}
+
+/**
+ * The bytes the program printed, with their number in *len. The pointer is valid
+ * until the result is freed.
+ *
+ * # Safety
+ *
+ * result must come from bc_run and not be freed, and len must be writable.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bc_result_output(result: *const BcResult, len: *mut usize) -> *const u8 {
+    if result.is_null() || len.is_null() {
+        return ptr::null();
+    }
+
+    // SAFETY: result comes from bc_run and len is writable.
+    unsafe {
+        len.write((*result).output.len());
+        (*result).output.as_ptr()
+    }
+}
+
+/**
+ * The status of the run, BC_STATUS_NULL_ARGUMENT for NULL.
+ *
+ * # Safety
+ *
+ * result must come from bc_run and not be freed.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bc_result_status(result: *const BcResult) -> BcStatus {
+    match result.is_null() {
+        true => BcStatus::NullArgument,
+        // SAFETY: result comes from bc_run.
+        false => unsafe { (*result).status },
+    }
+}
+
+/**
+ * The message of the error of the run, NUL terminated, or NULL when it succeeded.
+ * The pointer is valid until the result is freed.
+ *
+ * # Safety
+ *
+ * result must come from bc_run and not be freed.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bc_result_error_message(result: *const BcResult) -> *const c_char {
+    match result.is_null() {
+        true => ptr::null(),
+        // SAFETY: result comes from bc_run.
+        false => {
+            unsafe { (*result).message.as_ref() }.map_or(ptr::null(), |message| message.as_ptr())
+        }
+    }
+}
+
+fn summary_field<T: Default>(result: *const BcResult, field: fn(&RunSummary) -> T) -> T {
+    match result.is_null() {
+        true => T::default(),
+        // SAFETY: the accessors only take the results of bc_run.
+        false => field(unsafe { &(*result).summary }),
+    }
+}
+
+/**
+ * # Safety
+ *
+ * result must come from bc_run and not be freed, or be NULL, which gives 0.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bc_result_instructions_executed(result: *const BcResult) -> u64 {
+    summary_field(result, |summary| summary.instructions_executed)
+}
+
+/**
+ * # Safety
+ *
+ * result must come from bc_run and not be freed, or be NULL, which gives 0.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bc_result_bytes_written(result: *const BcResult) -> usize {
+    summary_field(result, |summary| summary.bytes_written)
+}
+
+/**
+ * # Safety
+ *
+ * result must come from bc_run and not be freed, or be NULL, which gives 0.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bc_result_bytes_read(result: *const BcResult) -> usize {
+    summary_field(result, |summary| summary.bytes_read)
+}
+
+/**
+ * # Safety
+ *
+ * result must come from bc_run and not be freed, or be NULL, which gives 0.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bc_result_max_memory_position(result: *const BcResult) -> usize {
+    summary_field(result, |summary| summary.max_memory_position)
+}
+
+/**
+ * Releases the program and sets *program to NULL. Both NULL and a pointer to NULL
+ * are ignored.
+ *
+ * # Safety
+ *
+ * *program must come from bc_parse, or be NULL.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bc_free_program(program: *mut *mut BcProgram) {
+    if program.is_null() {
+        return;
+    }
+
+    // SAFETY: *program comes from Box::into_raw in bc_parse, or is NULL, and it
+    // is set to NULL so it can't be released again.
+    unsafe {
+        let owned = program.replace(ptr::null_mut());
+        if !owned.is_null() {
+            drop(Box::from_raw(owned));
+        }
+    }
+}
+
+/**
+ * Releases the result and sets *result to NULL. Both NULL and a pointer to NULL
+ * are ignored.
+ *
+ * # Safety
+ *
+ * *result must come from bc_run, or be NULL.
+ */
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn bc_free_result(result: *mut *mut BcResult) {
+    if result.is_null() {
+        return;
+    }
+
+    // SAFETY: *result comes from Box::into_raw in bc_run, or is NULL, and it is
+    // set to NULL so it can't be released again.
+    unsafe {
+        let owned = result.replace(ptr::null_mut());
+        if !owned.is_null() {
+            drop(Box::from_raw(owned));
+        }
+    }
+}
+
+#[cfg(test)]
+mod ffi_test {
+    use std::ffi::CStr;
+
+    use super::*;
+
+    fn empty_error() -> BcError {
+        BcError {
+            status: BcStatus::Ok,
+            position: 0,
+            message: [0; BC_ERROR_MESSAGE_SIZE],
+        }
+    }
+
+    fn parse(source_code: &str) -> (BcStatus, *mut BcProgram, BcError) {
+        let mut program = ptr::null_mut();
+        let mut error = empty_error();
+
+        let status = unsafe {
+            bc_parse(
+                source_code.as_ptr().cast(),
+                source_code.len(),
+                &mut program,
+                &mut error,
+            )
+        };
+
+        (status, program, error)
+    }
+
+    #[test]
+    fn given_a_program_when_it_goes_through_the_c_functions_then_its_output_and_summary_are_read() {
+        let (status, mut program, _) = parse(",[.,]");
+        let options = BcRunOptions {
+            memory_size: 30,
+            instruction_limit: 10_000,
+        };
+        let mut result = ptr::null_mut();
+        let mut len = 0;
+
+        assert_eq!(status, BcStatus::Ok);
+        unsafe {
+            assert_eq!(
+                bc_run(program, b"abc".as_ptr(), 3, &options, &mut result),
+                BcStatus::Ok
+            );
+            let output = bc_result_output(result, &mut len);
+            assert_eq!(slice::from_raw_parts(output, len), b"abc");
+            assert_eq!(bc_result_status(result), BcStatus::Ok);
+            assert!(bc_result_error_message(result).is_null());
+            assert_eq!(bc_result_bytes_read(result), 3);
+            assert_eq!(bc_result_bytes_written(result), 3);
+            assert_eq!(bc_result_max_memory_position(result), 0);
+            assert!(bc_result_instructions_executed(result) > 0);
+
+            bc_free_result(&mut result);
+            bc_free_program(&mut program);
+        }
+
+        assert!(result.is_null());
+        assert!(program.is_null())
+    }
+
+    #[test]
+    fn given_an_unbalanced_program_when_it_is_parsed_then_the_error_has_its_position() {
+        let (status, program, error) = parse("+[+");
+
+        assert_eq!(status, BcStatus::Parse);
+        assert!(program.is_null());
+        assert_eq!(error.status, BcStatus::Parse);
+        assert_eq!(error.position, 1);
+        assert_eq!(
+            unsafe { CStr::from_ptr(error.message.as_ptr()) }.to_str(),
+            Ok("The source code have more open loop brackets than closing loop brackets.")
+        )
+    }
+
+    #[test]
+    fn given_a_program_that_never_ends_when_it_runs_then_the_result_keeps_its_output() {
+        let (_, mut program, _) = parse("+.[]");
+        let options = BcRunOptions {
+            memory_size: 30,
+            instruction_limit: 100,
+        };
+        let mut result = ptr::null_mut();
+        let mut len = 0;
+
+        unsafe {
+            assert_eq!(
+                bc_run(program, ptr::null(), 0, &options, &mut result),
+                BcStatus::Limit
+            );
+            assert_eq!(
+                slice::from_raw_parts(bc_result_output(result, &mut len), len),
+                [1]
+            );
+            assert!(!bc_result_error_message(result).is_null());
+
+            bc_free_result(&mut result);
+            bc_free_program(&mut program);
+        }
+    }
+
+    #[test]
+    fn given_null_arguments_when_the_functions_are_called_then_they_fail_without_crashing() {
+        let mut program = ptr::null_mut();
+        let mut result = ptr::null_mut();
+        let mut error = empty_error();
+        let mut len = 0;
+
+        unsafe {
+            assert_eq!(
+                bc_parse(ptr::null(), 3, &mut program, &mut error),
+                BcStatus::NullArgument
+            );
+            assert_eq!(error.status, BcStatus::NullArgument);
+            assert_eq!(
+                bc_parse(c"+".as_ptr(), 1, ptr::null_mut(), ptr::null_mut()),
+                BcStatus::NullArgument
+            );
+            assert_eq!(
+                bc_run(ptr::null(), ptr::null(), 0, ptr::null(), &mut result),
+                BcStatus::NullArgument
+            );
+            assert!(bc_result_output(ptr::null(), &mut len).is_null());
+            assert_eq!(bc_result_status(ptr::null()), BcStatus::NullArgument);
+            assert_eq!(bc_result_bytes_read(ptr::null()), 0);
+
+            bc_free_program(ptr::null_mut());
+            bc_free_program(&mut program);
+            bc_free_result(ptr::null_mut());
+            bc_free_result(&mut result);
+        }
+
+        assert!(result.is_null())
+    }
+
+    #[test]
+    fn given_a_freed_program_when_it_is_freed_again_then_nothing_happens() {
+        let (_, mut program, _) = parse("+");
+        let (_, mut other, _) = parse("-");
+        let mut result = ptr::null_mut();
+
+        unsafe {
+            assert_eq!(
+                bc_run(other, ptr::null(), 0, ptr::null(), &mut result),
+                BcStatus::Ok
+            );
+            bc_free_program(&mut program);
+            bc_free_program(&mut program);
+            bc_free_result(&mut result);
+            bc_free_result(&mut result);
+            bc_free_program(&mut other);
+        }
+
+        assert!(program.is_null());
+        assert!(result.is_null())
+    }
+
+    #[test]
+    fn given_a_program_that_leaves_the_tape_when_it_runs_then_it_is_a_runtime_status() {
+        let (_, mut program, _) = parse("+[>+]");
+        let options = BcRunOptions {
+            memory_size: 4,
+            instruction_limit: 10_000,
+        };
+        let mut result = ptr::null_mut();
+
+        unsafe {
+            assert_eq!(
+                bc_run(program, ptr::null(), 0, &options, &mut result),
+                BcStatus::Runtime
+            );
+            assert!(!bc_result_error_message(result).is_null());
+
+            bc_free_result(&mut result);
+            bc_free_program(&mut program);
+        }
+    }
+
+    #[test]
+    fn given_input_without_its_bytes_when_the_program_runs_then_it_is_a_null_argument() {
+        let (_, mut program, _) = parse(",");
+        let mut result = ptr::null_mut();
+
+        unsafe {
+            assert_eq!(
+                bc_run(program, ptr::null(), 4, ptr::null(), &mut result),
+                BcStatus::NullArgument
+            );
+            bc_free_program(&mut program);
+        }
+
+        assert!(result.is_null())
+    }
+
+    #[test]
+    fn given_an_operation_that_panics_when_it_is_run_for_c_then_the_panic_is_its_status() {
+        let unwound = catch_panic(|| -> usize { panic!("the operation failed") });
+
+        assert_eq!(unwound, Err(BcStatus::Panic));
+        assert_eq!(catch_panic(|| 3), Ok(3))
+    }
+}
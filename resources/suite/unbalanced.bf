@@ -0,0 +1,2 @@
+An extra closing bracket
++]
\ No newline at end of file
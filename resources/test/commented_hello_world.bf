@@ -0,0 +1,110 @@
+Hello world explained step by step
+
+This program prints the classic greeting It is the same program as the one in the
+wikipedia article with every step on its own line and a comment that says what the
+step does so it can be used to learn how the loops build the values of the letters
+instead of adding them one by one The comments avoid the characters that are
+commands so the program only runs the code at the start of each line
+
+++++++++
+    Set the first cell to eight it is the counter of the outer loop that builds the letters
+
+[
+    Start the outer loop it runs eight times once for each unit of the counter in the first cell
+
+>++++
+    Move to the second cell and add four this cell is the counter of the inner loop
+
+[
+    Start the inner loop it runs four times and fills the next cells with multiples of eight
+
+>++>+++>+++>+<<<<-
+    Add two three three and one to the next four cells and go back to decrement the inner counter
+
+]
+    End of the inner loop when the second cell is zero the four cells have their values
+
+>+>+>->>+
+    Adjust the cells with small steps so the letters are close to their final values
+
+[<]<-
+    Go back to the first cell which is the counter of the outer loop and decrement it
+
+]
+    End of the outer loop now the cells have values close to the letters of the message
+
+>>.
+    Print the H from the third cell
+
+>---.
+    Print the e after removing three from the fourth cell
+
++++++++..+++.
+    Print the two l and the o adding the distance between the letters each time
+
+>>.
+    Print the space from the sixth cell
+
+<-.
+    Print the W from the fifth cell after removing one
+
+<.
+    Print the o again from the fourth cell
+
++++.------.--------.
+    Print the r the l and the d walking down the alphabet
+
+>>+.
+    Print the exclamation mark from the sixth cell
+
+>++.
+    Print the new line from the seventh cell
+
+Why the loops: adding the value of each letter one by one would need more than a
+thousand plus signs while the loops multiply small numbers eight times four times
+two and the program stays short and readable as long as the comments explain it
+
+Why the loops: adding the value of each letter one by one would need more than a
+thousand plus signs while the loops multiply small numbers eight times four times
+two and the program stays short and readable as long as the comments explain it
+
+Why the loops: adding the value of each letter one by one would need more than a
+thousand plus signs while the loops multiply small numbers eight times four times
+two and the program stays short and readable as long as the comments explain it
+
+Why the loops: adding the value of each letter one by one would need more than a
+thousand plus signs while the loops multiply small numbers eight times four times
+two and the program stays short and readable as long as the comments explain it
+
+Why the loops: adding the value of each letter one by one would need more than a
+thousand plus signs while the loops multiply small numbers eight times four times
+two and the program stays short and readable as long as the comments explain it
+
+Why the loops: adding the value of each letter one by one would need more than a
+thousand plus signs while the loops multiply small numbers eight times four times
+two and the program stays short and readable as long as the comments explain it
+
+Why the loops: adding the value of each letter one by one would need more than a
+thousand plus signs while the loops multiply small numbers eight times four times
+two and the program stays short and readable as long as the comments explain it
+
+Why the loops: adding the value of each letter one by one would need more than a
+thousand plus signs while the loops multiply small numbers eight times four times
+two and the program stays short and readable as long as the comments explain it
+
+Why the loops: adding the value of each letter one by one would need more than a
+thousand plus signs while the loops multiply small numbers eight times four times
+two and the program stays short and readable as long as the comments explain it
+
+Why the loops: adding the value of each letter one by one would need more than a
+thousand plus signs while the loops multiply small numbers eight times four times
+two and the program stays short and readable as long as the comments explain it
+
+Why the loops: adding the value of each letter one by one would need more than a
+thousand plus signs while the loops multiply small numbers eight times four times
+two and the program stays short and readable as long as the comments explain it
+
+Why the loops: adding the value of each letter one by one would need more than a
+thousand plus signs while the loops multiply small numbers eight times four times
+two and the program stays short and readable as long as the comments explain it
+
@@ -0,0 +1 @@
+@include "include_cycle_b.bf"
@@ -0,0 +1,3 @@
++
+@include "include_print.bf"
+-
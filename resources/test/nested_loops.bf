@@ -0,0 +1,3 @@
+Clear a cell in a loop
+++[>+[-]<-]
+>.
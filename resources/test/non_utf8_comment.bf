@@ -0,0 +1,2 @@
+Prints A  in the comment
+++++++++[>++++++++<-]>+.
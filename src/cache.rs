@@ -0,0 +1,240 @@
+use std::{
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::parser::{BrainfuckNodeAST, Dialect};
+
+/**
+ * Bumped every time the layout of the cache files changes, so the old ones are
+ * ignored instead of misread.
+ */
+pub const CACHE_VERSION: u32 = 2;
+
+/**
+ * Extension of the cache files, written next to the source code.
+ */
+pub const CACHE_EXTENSION: &str = "bfc";
+
+/**
+ * The cache files start with it, so a file that happens to be at the cache path is
+ * never taken for a cache nor written over.
+ */
+const CACHE_MAGIC: &[u8] = b"BFC\0";
+
+/**
+ * The AST of a source code, with what it depends on. There is no optimizer yet, so
+ * the dialect is the only setting that changes the AST of the same source code.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CachedProgram {
+    source_hash: u64,
+    dialect: Dialect,
+    ast: Vec<BrainfuckNodeAST>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CacheLookup {
+    Hit(Vec<BrainfuckNodeAST>),
+    Miss,
+    /**
+     * The cache file can't be used, the reason is shown as a warning and the source
+     * code is parsed again.
+     */
+    Ignored(String),
+}
+
+/**
+ * The cache of prog.bf is prog.bf.bfc. The extension is appended instead of
+ * replaced, so prog.b and prog.bf don't share a cache, and the cache of prog.bfc
+ * isn't the source code itself.
+ */
+pub fn cache_path(source_path: &str) -> PathBuf {
+    let mut path = Path::new(source_path).as_os_str().to_owned();
+    path.push(".");
+    path.push(CACHE_EXTENSION);
+    PathBuf::from(path)
+}
+
+/**
+ * The hasher of the standard library may change between Rust versions, which only
+ * makes the old cache files miss.
+ */
+fn hash_source(source_code: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source_code.hash(&mut hasher);
+    hasher.finish()
+}
+
+/**
+ * The AST cached for the source code, when the cache file was written for the same
+ * source code and dialect.
+ */
+pub fn load_cached_ast(cache_path: &Path, source_code: &str, dialect: Dialect) -> CacheLookup {
+    let Ok(bytes) = fs::read(cache_path) else {
+        return CacheLookup::Miss;
+    };
+
+    let ignored = |reason: &str| {
+        CacheLookup::Ignored(format!(
+            "The cache file {} is ignored, {}",
+            cache_path.display(),
+            reason
+        ))
+    };
+
+    let Some(bytes) = bytes.strip_prefix(CACHE_MAGIC) else {
+        return ignored("it isn't a cache file");
+    };
+
+    match postcard::take_from_bytes::<u32>(bytes) {
+        Ok((CACHE_VERSION, rest)) => match postcard::from_bytes::<CachedProgram>(rest) {
+            Ok(cached)
+                if cached.source_hash == hash_source(source_code) && cached.dialect == dialect =>
+            {
+                CacheLookup::Hit(cached.ast)
+            }
+            Ok(_) => CacheLookup::Miss,
+            Err(_) => ignored("it's corrupted"),
+        },
+        Ok((version, _)) => ignored(&format!(
+            "it has the version {} and the version {} is expected",
+            version, CACHE_VERSION
+        )),
+        Err(_) => ignored("it's corrupted"),
+    }
+}
+
+/**
+ * Writes the cache file, unless there is already a file at the cache path that
+ * isn't a cache.
+ */
+pub fn store_cached_ast(
+    cache_path: &Path,
+    source_code: &str,
+    dialect: Dialect,
+    ast: &[BrainfuckNodeAST],
+) -> io::Result<()> {
+    let cached = CachedProgram {
+        source_hash: hash_source(source_code),
+        dialect,
+        ast: ast.to_vec(),
+    };
+    if let Ok(existing) = fs::read(cache_path)
+        && !existing.starts_with(CACHE_MAGIC)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "the file already exists and isn't a cache file",
+        ));
+    }
+
+    let mut bytes = CACHE_MAGIC.to_vec();
+    bytes.extend(postcard::to_stdvec(&CACHE_VERSION).map_err(io::Error::other)?);
+    bytes.extend(postcard::to_stdvec(&cached).map_err(io::Error::other)?);
+
+    fs::write(cache_path, bytes)
+}
+
+#[cfg(test)]
+mod cache_test {
+    use super::*;
+    use crate::parser::from_source_to_node_ast;
+
+    fn cache_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("braincrab_{}.bfc", name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn given_a_stored_ast_when_the_same_source_is_loaded_then_it_is_a_hit() {
+        let path = cache_file("hit");
+        let ast = from_source_to_node_ast("+[->+<].").unwrap();
+
+        store_cached_ast(&path, "+[->+<].", Dialect::Standard, &ast).unwrap();
+
+        assert_eq!(
+            load_cached_ast(&path, "+[->+<].", Dialect::Standard),
+            CacheLookup::Hit(ast)
+        );
+        fs::remove_file(path).unwrap()
+    }
+
+    #[test]
+    fn given_a_stored_ast_when_the_source_or_the_dialect_change_then_it_is_a_miss() {
+        let path = cache_file("miss");
+        let ast = from_source_to_node_ast("+.").unwrap();
+
+        store_cached_ast(&path, "+.", Dialect::Standard, &ast).unwrap();
+
+        assert_eq!(
+            load_cached_ast(&path, "++.", Dialect::Standard),
+            CacheLookup::Miss
+        );
+        assert_eq!(
+            load_cached_ast(&path, "+.", Dialect::Multitape),
+            CacheLookup::Miss
+        );
+        fs::remove_file(path).unwrap()
+    }
+
+    #[test]
+    fn given_a_corrupted_or_old_cache_file_when_is_loaded_then_it_is_ignored() {
+        let path = cache_file("ignored");
+
+        fs::write(
+            &path,
+            [CACHE_MAGIC, &[CACHE_VERSION as u8, 0xFF, 0xFF]].concat(),
+        )
+        .unwrap();
+        assert!(matches!(
+            load_cached_ast(&path, "+.", Dialect::Standard),
+            CacheLookup::Ignored(reason) if reason.ends_with("it's corrupted")
+        ));
+
+        let newer_version = postcard::to_stdvec(&(CACHE_VERSION + 1)).unwrap();
+        fs::write(&path, [CACHE_MAGIC, &newer_version].concat()).unwrap();
+        assert!(matches!(
+            load_cached_ast(&path, "+.", Dialect::Standard),
+            CacheLookup::Ignored(reason) if reason.contains("has the version 3")
+        ));
+        fs::remove_file(path).unwrap()
+    }
+
+    #[test]
+    fn given_a_source_path_when_the_cache_path_is_asked_then_the_extension_is_appended() {
+        assert_eq!(
+            cache_path("programs/hello.bf"),
+            PathBuf::from("programs/hello.bf.bfc")
+        );
+        assert_eq!(cache_path("hello.b"), PathBuf::from("hello.b.bfc"));
+        assert_eq!(cache_path("hello.bfc"), PathBuf::from("hello.bfc.bfc"));
+        assert_eq!(cache_path("hello.bf.gz"), PathBuf::from("hello.bf.gz.bfc"))
+    }
+
+    #[test]
+    fn given_a_file_that_is_not_a_cache_when_the_ast_is_stored_then_the_file_is_kept() {
+        let path = cache_file("not_a_cache");
+        let ast = from_source_to_node_ast("+.").unwrap();
+
+        fs::write(&path, "+.").unwrap();
+
+        assert!(matches!(
+            load_cached_ast(&path, "+.", Dialect::Standard),
+            CacheLookup::Ignored(reason) if reason.ends_with("it isn't a cache file")
+        ));
+        assert_eq!(
+            store_cached_ast(&path, "+.", Dialect::Standard, &ast)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "+.");
+        fs::remove_file(path).unwrap()
+    }
+}
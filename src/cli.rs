@@ -0,0 +1,386 @@
+use std::{ops::Range, path::PathBuf};
+
+use crate::{
+    diagnostic::ColorMode,
+    interpreter::{EofBehavior, OverflowPolicy},
+    io::{
+        BoundsPolicy, CellSize, MAX_MEMORY_SIZE, MemoryModel, NewlineMode, OutputMode,
+        ProgramValue, StdinMode,
+    },
+    parser::Dialect,
+    profile::ProfileFormat,
+    report::LintFormat,
+    trace::TraceFormat,
+};
+
+/**
+ * Multipliers of the suffixes accepted by the sizes, in lowercase. The decimal ones
+ * are powers of 1000 and the binary ones powers of 1024, and all of them can end
+ * with a B, like 64KiB.
+ */
+const SIZE_SUFFIXES: [(&str, u64); 6] = [
+    ("k", 1_000),
+    ("m", 1_000_000),
+    ("g", 1_000_000_000),
+    ("ki", 1 << 10),
+    ("mi", 1 << 20),
+    ("gi", 1 << 30),
+];
+
+/**
+ * Parses a count of bytes or cells, like 3000, 30k or 1Mi. The suffixes don't
+ * care about the case.
+ */
+pub fn parse_size(size: &str) -> Result<usize, String> {
+    let suffix_start = size
+        .find(|character: char| !character.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (digits, suffix) = size.split_at(suffix_start);
+
+    if digits.is_empty() {
+        return Err("Expected a number optionally followed by k, M, G, Ki, Mi or Gi".to_string());
+    }
+
+    let value = digits
+        .parse::<usize>()
+        .map_err(|_| "The value is too big".to_string())?;
+
+    let suffix = suffix.to_ascii_lowercase();
+    let unit = suffix.strip_suffix('b').unwrap_or(&suffix);
+    let multiplier = match unit {
+        "" => 1,
+        unit => SIZE_SUFFIXES
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, multiplier)| *multiplier)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown suffix {:?}, accepted ones are k, M, G, Ki, Mi and Gi",
+                    &size[suffix_start..]
+                )
+            })?,
+    };
+
+    usize::try_from(multiplier)
+        .ok()
+        .and_then(|multiplier| value.checked_mul(multiplier))
+        .ok_or_else(|| "The value is too big".to_string())
+}
+
+pub fn path_parser(path_string: &str) -> Result<PathBuf, String> {
+    Ok(PathBuf::from(path_string))
+}
+
+pub fn memory_size_parser(memory_size: &str) -> Result<usize, String> {
+    match parse_size(memory_size)? {
+        value if value > MAX_MEMORY_SIZE => Err(format!(
+            "Maximum value accepted is {} (1 GiB of cells)",
+            MAX_MEMORY_SIZE
+        )),
+        value if value < 1 => Err("Minimum value accepted is 1".to_string()),
+        value => Ok(value),
+    }
+}
+
+/**
+ * The limit accepts the same suffixes as the sizes, like 10M, and 0 means without
+ * limit.
+ */
+pub fn limit_read_instructions_parser(limit_read_instructions: &str) -> Result<u64, String> {
+    parse_size(limit_read_instructions).map(|value| value as u64)
+}
+
+pub fn output_mode_parser(output_mode: &str) -> Result<OutputMode, String> {
+    match output_mode {
+        "raw" => Ok(OutputMode::Raw),
+        "debug" => Ok(OutputMode::Debug),
+        "numeric" => Ok(OutputMode::Numeric),
+        "hex" => Ok(OutputMode::Hex),
+        "utf8" => Ok(OutputMode::Utf8),
+        "hexdump" => Ok(OutputMode::Hexdump),
+        "csv" => Ok(OutputMode::Csv),
+        _ => Err("Accepted values are raw, debug, numeric, hex, utf8, hexdump or csv".to_string()),
+    }
+}
+
+pub fn trace_format_parser(trace_format: &str) -> Result<TraceFormat, String> {
+    match trace_format {
+        "chrome" => Ok(TraceFormat::Chrome),
+        _ => Err("The only accepted value is chrome".to_string()),
+    }
+}
+
+pub fn profile_format_parser(profile_format: &str) -> Result<ProfileFormat, String> {
+    match profile_format {
+        "text" => Ok(ProfileFormat::Text),
+        "json" => Ok(ProfileFormat::Json),
+        _ => Err("The accepted values are text and json".to_string()),
+    }
+}
+
+pub fn byte_limit_parser(byte_limit: &str) -> Result<u64, String> {
+    match parse_size(byte_limit)? {
+        value if value < 1 => Err("Minimum value accepted is 1".to_string()),
+        value => Ok(value as u64),
+    }
+}
+
+/**
+ * The range is written as start..end with the end excluded. The positions can be
+ * negative for the memories that extend to the left.
+ */
+pub fn dump_range_parser(range: &str) -> Result<Range<isize>, String> {
+    let (start, end) = range
+        .split_once("..")
+        .ok_or("The range must be written as start..end, like 0..32")?;
+
+    match (start.parse::<isize>(), end.parse::<isize>()) {
+        (Ok(start), Ok(end)) if start < end => Ok(start..end),
+        (Ok(_), Ok(_)) => Err("The start of the range must be before its end".to_string()),
+        (Err(err), _) | (_, Err(err)) => Err(err.to_string()),
+    }
+}
+
+/**
+ * A file copied into the memory before the run, written as path@offset. The
+ * offset is the first cell written, 0 when it's missing. Only a number after the
+ * last @ is an offset, so a path like user@host/data.bin is kept whole.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataFile {
+    pub path: PathBuf,
+    pub offset: isize,
+}
+
+fn is_offset(text: &str) -> bool {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+
+    !digits.is_empty() && digits.chars().all(|digit| digit.is_ascii_digit())
+}
+
+pub fn data_file_parser(data_file: &str) -> Result<DataFile, String> {
+    let (path, offset) = match data_file.rsplit_once('@') {
+        Some((path, offset)) if is_offset(offset) => (
+            path,
+            offset
+                .parse::<isize>()
+                .map_err(|err| format!("Invalid offset {:?}: {}", offset, err))?,
+        ),
+        _ => (data_file, 0),
+    };
+
+    if path.is_empty() {
+        return Err("The path of the data file is missing".to_string());
+    }
+
+    Ok(DataFile {
+        path: PathBuf::from(path),
+        offset,
+    })
+}
+
+pub fn color_mode_parser(color_mode: &str) -> Result<ColorMode, String> {
+    match color_mode {
+        "auto" => Ok(ColorMode::Auto),
+        "always" => Ok(ColorMode::Always),
+        "never" => Ok(ColorMode::Never),
+        _ => Err("Accepted values are auto, always or never".to_string()),
+    }
+}
+
+/**
+ * The values are parsed with the same rules as the prompt, so the errors are shown
+ * before the program runs.
+ */
+pub fn input_value_parser(input_value: &str) -> Result<u8, String> {
+    ProgramValue::try_from(input_value)
+        .map(u8::from)
+        .map_err(|error| error.to_string())
+}
+
+pub fn input_newlines_parser(input_newlines: &str) -> Result<NewlineMode, String> {
+    match input_newlines {
+        "passthrough" => Ok(NewlineMode::Passthrough),
+        "lf" => Ok(NewlineMode::Lf),
+        "crlf" => Ok(NewlineMode::Crlf),
+        _ => Err("Accepted values are passthrough, lf or crlf".to_string()),
+    }
+}
+
+pub fn memory_model_parser(memory_model: &str) -> Result<MemoryModel, String> {
+    match memory_model {
+        "vec" => Ok(MemoryModel::Vec),
+        "bidirectional" => Ok(MemoryModel::Bidirectional),
+        "sparse" => Ok(MemoryModel::Sparse),
+        _ => Err("Accepted values are vec, bidirectional or sparse".to_string()),
+    }
+}
+
+pub fn cell_size_parser(cell_size: &str) -> Result<CellSize, String> {
+    match cell_size {
+        "8" => Ok(CellSize::U8),
+        "16" => Ok(CellSize::U16),
+        "32" => Ok(CellSize::U32),
+        "i8" => Ok(CellSize::I8),
+        "i32" => Ok(CellSize::I32),
+        _ => Err("Accepted values are 8, 16, 32, i8 or i32".to_string()),
+    }
+}
+
+/**
+ * -1 is accepted for 255, as both are the same byte.
+ */
+pub fn eof_value_parser(eof_value: &str) -> Result<EofBehavior, String> {
+    match eof_value {
+        "0" => Ok(EofBehavior::SetZero),
+        "255" | "-1" => Ok(EofBehavior::SetMax),
+        "unchanged" => Ok(EofBehavior::Unchanged),
+        _ => Err("Accepted values are 0, 255 (or -1) or unchanged".to_string()),
+    }
+}
+
+pub fn lint_format_parser(lint_format: &str) -> Result<LintFormat, String> {
+    match lint_format {
+        "text" => Ok(LintFormat::Text),
+        "json" => Ok(LintFormat::Json),
+        "sarif" => Ok(LintFormat::Sarif),
+        _ => Err("Accepted values are text, json or sarif".to_string()),
+    }
+}
+
+pub fn overflow_policy_parser(overflow_policy: &str) -> Result<OverflowPolicy, String> {
+    match overflow_policy {
+        "wrap" => Ok(OverflowPolicy::Wrap),
+        "saturate" => Ok(OverflowPolicy::Saturate),
+        "error" => Ok(OverflowPolicy::Error),
+        _ => Err("Accepted values are wrap, saturate or error".to_string()),
+    }
+}
+
+pub fn bounds_policy_parser(bounds_policy: &str) -> Result<BoundsPolicy, String> {
+    match bounds_policy {
+        "error" => Ok(BoundsPolicy::Error),
+        "wrap" => Ok(BoundsPolicy::Wrap),
+        "grow" => Ok(BoundsPolicy::Grow),
+        _ => Err("Accepted values are error, wrap or grow".to_string()),
+    }
+}
+
+pub fn dialect_parser(dialect: &str) -> Result<Dialect, String> {
+    match dialect {
+        "standard" => Ok(Dialect::Standard),
+        "multitape" => Ok(Dialect::Multitape),
+        _ => Err("Accepted values are standard or multitape".to_string()),
+    }
+}
+
+pub fn exclude_pattern_parser(pattern: &str) -> Result<glob::Pattern, String> {
+    glob::Pattern::new(pattern).map_err(|error| error.to_string())
+}
+
+pub fn stdin_mode_parser(stdin_mode: &str) -> Result<StdinMode, String> {
+    match stdin_mode {
+        "prompt" => Ok(StdinMode::Prompt),
+        "bytes" => Ok(StdinMode::Bytes),
+        _ => Err("Accepted values are prompt or bytes".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod cli_test {
+    use super::*;
+
+    #[test]
+    fn given_a_bare_number_when_the_size_is_parsed_then_it_is_the_number() {
+        assert_eq!(parse_size("3000"), Ok(3000))
+    }
+
+    #[test]
+    fn given_each_suffix_when_the_size_is_parsed_then_it_is_multiplied() {
+        for (size, expected) in [
+            ("30k", 30_000),
+            ("1M", 1_000_000),
+            ("2g", 2_000_000_000),
+            ("64Ki", 65_536),
+            ("64KiB", 65_536),
+            ("1mi", 1_048_576),
+            ("1Gi", 1_073_741_824),
+            ("5kB", 5_000),
+            ("7b", 7),
+        ] {
+            assert_eq!(parse_size(size), Ok(expected), "{}", size)
+        }
+    }
+
+    #[test]
+    fn given_an_unknown_suffix_when_the_size_is_parsed_then_return_an_error() {
+        for size in ["10kk", "10T", "1 k", "10KiBB"] {
+            assert!(
+                parse_size(size).unwrap_err().starts_with("Unknown suffix"),
+                "{}",
+                size
+            )
+        }
+    }
+
+    #[test]
+    fn given_a_size_without_digits_when_is_parsed_then_return_an_error() {
+        for size in ["abc", " 5", "k", "", "-1"] {
+            assert_eq!(
+                parse_size(size),
+                Err("Expected a number optionally followed by k, M, G, Ki, Mi or Gi".to_string()),
+                "{:?}",
+                size
+            )
+        }
+    }
+
+    #[test]
+    fn given_a_size_bigger_than_usize_when_is_parsed_then_return_an_error() {
+        assert_eq!(
+            parse_size(&format!("{}k", usize::MAX)),
+            Err("The value is too big".to_string())
+        );
+        assert_eq!(
+            parse_size("99999999999999999999999"),
+            Err("The value is too big".to_string())
+        )
+    }
+    #[test]
+    fn given_a_data_file_with_an_offset_when_it_is_parsed_then_the_offset_is_split_from_the_path() {
+        assert_eq!(
+            data_file_parser("table.bin@-4"),
+            Ok(DataFile {
+                path: PathBuf::from("table.bin"),
+                offset: -4
+            })
+        );
+        assert_eq!(
+            data_file_parser("table.bin"),
+            Ok(DataFile {
+                path: PathBuf::from("table.bin"),
+                offset: 0
+            })
+        )
+    }
+
+    #[test]
+    fn given_a_data_file_with_an_at_in_its_path_when_it_is_parsed_then_only_a_numeric_suffix_is_the_offset()
+     {
+        assert_eq!(
+            data_file_parser("backups@2024/table.bin"),
+            Ok(DataFile {
+                path: PathBuf::from("backups@2024/table.bin"),
+                offset: 0
+            })
+        );
+        assert_eq!(
+            data_file_parser("user@host/table.bin@16"),
+            Ok(DataFile {
+                path: PathBuf::from("user@host/table.bin"),
+                offset: 16
+            })
+        );
+        assert!(data_file_parser(&format!("table.bin@{}0", isize::MAX)).is_err())
+    }
+}
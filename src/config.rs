@@ -0,0 +1,304 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use clap::{Arg, Command};
+use toml::{Table, Value};
+
+/**
+ * Name of the config file looked up in the current directory. The one of the user
+ * is braincrub/config.toml in the config directory.
+ */
+pub const CONFIG_FILE_NAME: &str = "braincrub.toml";
+
+/**
+ * The flags that can be given in the config file or in the environment, by their
+ * id, with the environment variable of each one.
+ */
+pub const CONFIGURABLE_ARGS: [(&str, &str); 16] = [
+    ("color", "BRAINCRUB_COLOR"),
+    ("dialect", "BRAINCRUB_DIALECT"),
+    ("ext", "BRAINCRUB_EXT"),
+    ("max-source-size", "BRAINCRUB_MAX_SOURCE_SIZE"),
+    ("memory-size", "BRAINCRUB_MEMORY_SIZE"),
+    ("memory", "BRAINCRUB_MEMORY"),
+    ("memory-bounds", "BRAINCRUB_MEMORY_BOUNDS"),
+    ("cell-size", "BRAINCRUB_CELL_SIZE"),
+    ("cell-overflow", "BRAINCRUB_CELL_OVERFLOW"),
+    ("limit-read-instructions", "BRAINCRUB_LIMIT"),
+    ("stdin-mode", "BRAINCRUB_STDIN_MODE"),
+    ("eof-value", "BRAINCRUB_EOF_VALUE"),
+    ("input-newlines", "BRAINCRUB_INPUT_NEWLINES"),
+    ("output-mode", "BRAINCRUB_OUTPUT_MODE"),
+    ("output-separator", "BRAINCRUB_OUTPUT_SEPARATOR"),
+    ("max-output", "BRAINCRUB_MAX_OUTPUT"),
+];
+
+/**
+ * The environment variables for the manual page, in roff. Keep it in sync with
+ * the flags above.
+ */
+pub const ENVIRONMENT_MAN_SECTION: &str = r#".SH ENVIRONMENT
+A flag wins over its environment variable, the environment variable over the config file, and the config file over the default of the flag.
+.TP
+\fBBRAINCRUB_COLOR\fR, \fBBRAINCRUB_DIALECT\fR, \fBBRAINCRUB_EXT\fR, \fBBRAINCRUB_MAX_SOURCE_SIZE\fR
+The defaults of \-\-color, \-\-dialect, \-\-ext and \-\-max\-source\-size.
+.TP
+\fBBRAINCRUB_MEMORY_SIZE\fR, \fBBRAINCRUB_MEMORY\fR, \fBBRAINCRUB_MEMORY_BOUNDS\fR
+The defaults of \-\-memory\-size, \-\-memory and \-\-memory\-bounds.
+.TP
+\fBBRAINCRUB_CELL_SIZE\fR, \fBBRAINCRUB_CELL_OVERFLOW\fR
+The defaults of \-\-cell\-size and \-\-cell\-overflow.
+.TP
+\fBBRAINCRUB_LIMIT\fR
+The default of \-\-limit.
+.TP
+\fBBRAINCRUB_STDIN_MODE\fR, \fBBRAINCRUB_EOF_VALUE\fR, \fBBRAINCRUB_INPUT_NEWLINES\fR
+The defaults of \-\-stdin\-mode, \-\-eof\-value and \-\-input\-newlines.
+.TP
+\fBBRAINCRUB_OUTPUT_MODE\fR, \fBBRAINCRUB_OUTPUT_SEPARATOR\fR, \fBBRAINCRUB_MAX_OUTPUT\fR
+The defaults of \-\-output\-mode, \-\-output\-separator and \-\-max\-output.
+.TP
+\fBNO_COLOR\fR
+Don't color the errors and the warnings when \-\-color is auto.
+.SH FILES
+.TP
+\fBbraincrub.toml\fR
+The config file of the current directory, with the defaults of the flags by their name, like memory\-size = "30k".
+.TP
+\fBbraincrub/config.toml\fR
+The config file of the user, in the config directory, read when the current directory doesn't have one.
+"#;
+
+/**
+ * The defaults of the flags read from the config file, with the same names as the
+ * flags, like memory-size = "30k". The keys that aren't flags are kept to warn
+ * about them.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliDefaults {
+    values: BTreeMap<&'static str, String>,
+    unknown: Vec<String>,
+}
+
+impl CliDefaults {
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.values.get(id).map(String::as_str)
+    }
+
+    pub fn unknown_keys(&self) -> impl Iterator<Item = &str> {
+        self.unknown.iter().map(String::as_str)
+    }
+}
+
+/**
+ * The values are written as they would be in the command line, the numbers and
+ * the booleans can be written without quotes.
+ */
+pub fn parse_config(content: &str) -> Result<CliDefaults, String> {
+    let table: Table = toml::from_str(content).map_err(|error| error.message().to_string())?;
+    let mut defaults = CliDefaults::default();
+
+    for (key, value) in table {
+        let Some((id, _)) = CONFIGURABLE_ARGS.iter().find(|(id, _)| *id == key) else {
+            defaults.unknown.push(key);
+            continue;
+        };
+
+        let value = match value {
+            Value::String(text) => text,
+            Value::Integer(number) => number.to_string(),
+            Value::Boolean(boolean) => boolean.to_string(),
+            other => {
+                return Err(format!(
+                    "The value of {} must be a string or a number, not {}",
+                    key,
+                    other.type_str()
+                ));
+            }
+        };
+        defaults.values.insert(id, value);
+    }
+
+    Ok(defaults)
+}
+
+/**
+ * The config of the current directory goes before the one of the user, in
+ * $XDG_CONFIG_HOME or in ~/.config when it isn't set.
+ */
+pub fn config_paths(current_dir: &Path, config_home: Option<PathBuf>) -> Vec<PathBuf> {
+    let mut paths = vec![current_dir.join(CONFIG_FILE_NAME)];
+
+    if let Some(config_home) = config_home {
+        paths.push(config_home.join("braincrub").join("config.toml"));
+    }
+
+    paths
+}
+
+/**
+ * The first config file that exists, only one of them is used.
+ */
+pub fn find_config() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+    let current_dir = env::current_dir().unwrap_or_default();
+
+    config_paths(&current_dir, config_home)
+        .into_iter()
+        .find(|path| path.is_file())
+}
+
+pub fn load_config(path: &Path) -> Result<CliDefaults, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|error| format!("Unable to read the config file {:?}: {}", path, error))?;
+
+    parse_config(&content).map_err(|error| {
+        format!(
+            "The config file {:?} is invalid: {}",
+            path,
+            error.trim_end()
+        )
+    })
+}
+
+fn configure_arg(
+    command: Command,
+    id: &'static str,
+    env_name: &'static str,
+    value: Option<&str>,
+) -> Command {
+    let configure = |arg: Arg| {
+        let arg = arg.env(env_name);
+
+        match value {
+            Some(value) => arg.default_value(value.to_string()),
+            None => arg,
+        }
+    };
+    let has_arg = command.get_arguments().any(|arg| arg.get_id() == id);
+    let command = match has_arg {
+        true => command.mut_arg(id, configure),
+        false => command,
+    };
+    let subcommands: Vec<String> = command
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect();
+
+    subcommands.iter().fold(command, |command, name| {
+        command.mut_subcommand(name, |subcommand| {
+            configure_arg(subcommand, id, env_name, value)
+        })
+    })
+}
+
+/**
+ * Every flag that can be configured reads its environment variable, and the value
+ * of the config becomes its default. So the flag wins over the environment, the
+ * environment over the config, and the config over the default of the flag. The
+ * values of the environment and the config go through the parser of the flag.
+ */
+pub fn with_defaults(command: Command, defaults: &CliDefaults) -> Command {
+    CONFIGURABLE_ARGS
+        .iter()
+        .fold(command, |command, (id, env_name)| {
+            configure_arg(command, id, env_name, defaults.get(id))
+        })
+}
+
+#[cfg(test)]
+mod config_test {
+    use super::*;
+
+    #[test]
+    fn given_a_config_with_numbers_and_text_when_it_is_parsed_then_they_are_written_as_flags() {
+        let defaults = parse_config("memory-size = 30000\noutput-mode = \"numeric\"\n").unwrap();
+
+        assert_eq!(defaults.get("memory-size"), Some("30000"));
+        assert_eq!(defaults.get("output-mode"), Some("numeric"));
+        assert_eq!(defaults.unknown_keys().count(), 0)
+    }
+
+    #[test]
+    fn given_a_config_with_keys_that_arent_flags_when_it_is_parsed_then_they_are_kept_as_unknown() {
+        let defaults = parse_config("optimize = true\nmemory = \"sparse\"\n").unwrap();
+
+        assert_eq!(defaults.unknown_keys().collect::<Vec<_>>(), ["optimize"]);
+        assert_eq!(defaults.get("memory"), Some("sparse"))
+    }
+
+    #[test]
+    fn given_the_configurable_flags_when_the_manual_page_is_checked_then_it_has_their_variables() {
+        for (_, env_name) in CONFIGURABLE_ARGS {
+            assert!(
+                ENVIRONMENT_MAN_SECTION.contains(&format!("\\fB{}\\fR", env_name)),
+                "{} is missing",
+                env_name
+            );
+        }
+    }
+
+    #[test]
+    fn given_a_config_that_isnt_toml_when_it_is_parsed_then_return_an_error() {
+        assert!(parse_config("memory-size = ").is_err())
+    }
+
+    #[test]
+    fn given_a_config_with_an_array_as_value_when_it_is_parsed_then_return_an_error() {
+        assert_eq!(
+            parse_config("memory-size = [1, 2]"),
+            Err("The value of memory-size must be a string or a number, not array".to_string())
+        )
+    }
+
+    #[test]
+    fn when_the_config_paths_are_listed_then_the_current_directory_goes_first() {
+        assert_eq!(
+            config_paths(Path::new("project"), Some(PathBuf::from("home/.config"))),
+            [
+                PathBuf::from("project/braincrub.toml"),
+                PathBuf::from("home/.config/braincrub/config.toml")
+            ]
+        );
+        assert_eq!(config_paths(Path::new("project"), None).len(), 1)
+    }
+
+    #[test]
+    fn given_defaults_when_they_are_applied_then_the_flags_of_every_subcommand_take_them() {
+        let command = Command::new("braincrub").subcommand(
+            Command::new("run").arg(
+                Arg::new("memory-size")
+                    .long("memory-size")
+                    .default_value("3000"),
+            ),
+        );
+        let defaults = parse_config("memory-size = \"30k\"").unwrap();
+
+        let configured = with_defaults(command, &defaults);
+        let from_config = configured.clone().get_matches_from(["braincrub", "run"]);
+        let from_flag = configured.get_matches_from(["braincrub", "run", "--memory-size", "5"]);
+
+        assert_eq!(
+            from_config
+                .subcommand_matches("run")
+                .unwrap()
+                .get_one::<String>("memory-size")
+                .unwrap(),
+            "30k"
+        );
+        assert_eq!(
+            from_flag
+                .subcommand_matches("run")
+                .unwrap()
+                .get_one::<String>("memory-size")
+                .unwrap(),
+            "5"
+        )
+    }
+}
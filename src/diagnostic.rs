@@ -0,0 +1,289 @@
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+use anstyle::{AnsiColor, Style};
+
+use crate::file::{INLINE_PROGRAM, SourceMap};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    fn label(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+
+    fn style(&self) -> Style {
+        let color = match self {
+            Severity::Error => AnsiColor::Red,
+            Severity::Warning => AnsiColor::Yellow,
+        };
+
+        Style::new().fg_color(Some(color.into())).bold()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /**
+     * With auto the colors are only used when a person is reading the stderr, and
+     * the NO_COLOR convention is respected. Asking for them explicitly wins.
+     */
+    pub fn use_color(&self, stderr_is_terminal: bool, no_color: bool) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => stderr_is_terminal && !no_color,
+        }
+    }
+}
+
+/**
+ * A message for the user. The position is the byte offset in the source code the
+ * message is about, if any. The code names the kind of message for the reports,
+ * it isn't rendered.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub position: Option<usize>,
+    pub code: Option<&'static str>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Display) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.to_string(),
+            position: None,
+            code: None,
+        }
+    }
+
+    pub fn warning(message: impl Display) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message: message.to_string(),
+            position: None,
+            code: None,
+        }
+    }
+
+    pub fn at(mut self, position: Option<usize>) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+}
+
+/**
+ * Renders the diagnostics the same way for every subcommand, like rustc does: the
+ * message, the location and the source line with a caret under the column.
+ */
+pub struct DiagnosticRenderer<'a> {
+    source: Option<(&'a str, &'a str)>,
+    source_map: Option<&'a SourceMap>,
+    color: bool,
+}
+
+impl<'a> DiagnosticRenderer<'a> {
+    pub fn new(color: bool) -> Self {
+        DiagnosticRenderer {
+            source: None,
+            source_map: None,
+            color,
+        }
+    }
+
+    pub fn with_source(mut self, path: &'a str, source_code: &'a str) -> Self {
+        self.source = Some((path, source_code));
+        self
+    }
+
+    /**
+     * The locations are taken from the map when the source code has includes, so
+     * they point at the file the line comes from.
+     */
+    pub fn with_source_map(mut self, source_map: &'a SourceMap) -> Self {
+        self.source_map = Some(source_map);
+        self
+    }
+
+    fn paint(&self, style: Style, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style.render(), text, style.render_reset())
+        } else {
+            text.to_string()
+        }
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic, writer: &mut dyn Write) -> io::Result<()> {
+        let severity_style = diagnostic.severity.style();
+        let gutter_style = Style::new().fg_color(Some(AnsiColor::Blue.into())).bold();
+
+        writeln!(
+            writer,
+            "{}: {}",
+            self.paint(severity_style, diagnostic.severity.label()),
+            diagnostic.message
+        )?;
+
+        let (Some((path, source_code)), Some(position)) = (self.source, diagnostic.position) else {
+            return Ok(());
+        };
+
+        let line_start = source_code[..position]
+            .rfind('\n')
+            .map_or(0, |new_line| new_line + 1);
+        let line_end = source_code[position..]
+            .find('\n')
+            .map_or(source_code.len(), |new_line| position + new_line);
+        let line_number = source_code[..line_start].matches('\n').count() + 1;
+        let (path, line_number) = self
+            .source_map
+            .and_then(|source_map| source_map.locate(line_number))
+            .unwrap_or((path, line_number));
+        let before_column = &source_code[line_start..position];
+        let column = before_column.chars().count() + 1;
+
+        // The tabs are kept so the caret is under the column whatever the tab width is.
+        let padding: String = before_column
+            .chars()
+            .map(|character| if character == '\t' { '\t' } else { ' ' })
+            .collect();
+        let gutter_width = line_number.to_string().len();
+        let empty_gutter = " ".repeat(gutter_width);
+        let location = match (path, line_number) {
+            (INLINE_PROGRAM, 1) => format!("{}, column {}", path, column),
+            (INLINE_PROGRAM, _) => format!("{}, line {}, column {}", path, line_number, column),
+            _ => format!("{}:{}:{}", path, line_number, column),
+        };
+
+        writeln!(
+            writer,
+            "{}{} {}",
+            empty_gutter,
+            self.paint(gutter_style, "-->"),
+            location
+        )?;
+        writeln!(writer, "{} {}", empty_gutter, self.paint(gutter_style, "|"))?;
+        writeln!(
+            writer,
+            "{} {}",
+            self.paint(gutter_style, &format!("{} |", line_number)),
+            source_code[line_start..line_end].trim_end_matches('\r')
+        )?;
+        writeln!(
+            writer,
+            "{} {} {}{}",
+            empty_gutter,
+            self.paint(gutter_style, "|"),
+            padding,
+            self.paint(severity_style, "^")
+        )
+    }
+}
+
+#[cfg(test)]
+mod diagnostic_test {
+    use super::*;
+    use crate::file::expand_includes;
+
+    fn render_to_string(renderer: &DiagnosticRenderer, diagnostic: &Diagnostic) -> String {
+        let mut buffer: Vec<u8> = vec![];
+        renderer.render(diagnostic, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn given_a_diagnostic_with_a_position_when_rendered_without_color_then_the_caret_points_to_the_column()
+     {
+        let renderer = DiagnosticRenderer::new(false).with_source("program.bf", "++\n+]-\n");
+        let diagnostic = Diagnostic::error("Missing open loop").at(Some(4));
+
+        assert_eq!(
+            render_to_string(&renderer, &diagnostic),
+            "error: Missing open loop\n \
+             --> program.bf:2:2\n  \
+             |\n\
+             2 | +]-\n  \
+             |  ^\n"
+        )
+    }
+
+    #[test]
+    fn given_an_inline_program_when_rendered_then_the_location_is_the_column() {
+        let renderer = DiagnosticRenderer::new(false).with_source(INLINE_PROGRAM, "+]");
+        let diagnostic = Diagnostic::error("Missing open loop").at(Some(1));
+
+        assert!(
+            render_to_string(&renderer, &diagnostic).contains(" --> inline program, column 2\n")
+        )
+    }
+
+    #[test]
+    fn given_a_source_map_when_rendered_then_the_location_is_the_included_file() {
+        let expanded = expand_includes(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test/main.bf"),
+            "+\n@include \"include_loop.bf\"\n",
+            64,
+        )
+        .unwrap();
+        let renderer = DiagnosticRenderer::new(false)
+            .with_source("main.bf", &expanded.source_code)
+            .with_source_map(&expanded.source_map);
+        let diagnostic = Diagnostic::error("Missing open loop").at(Some(4));
+
+        let rendered = render_to_string(&renderer, &diagnostic);
+
+        assert!(rendered.contains("resources/test/include_loop.bf:2:1\n"));
+        assert!(rendered.contains("2 | ]-\n"))
+    }
+
+    #[test]
+    fn given_a_warning_without_position_when_rendered_then_only_the_message_is_shown() {
+        let renderer = DiagnosticRenderer::new(false).with_source("program.bf", "+");
+
+        assert_eq!(
+            render_to_string(&renderer, &Diagnostic::warning("Unused input")),
+            "warning: Unused input\n"
+        )
+    }
+
+    #[test]
+    fn given_the_color_forced_when_an_error_is_rendered_then_the_label_and_caret_are_red() {
+        let renderer = DiagnosticRenderer::new(true).with_source("program.bf", "[");
+        let rendered = render_to_string(&renderer, &Diagnostic::error("Missing").at(Some(0)));
+
+        assert!(rendered.starts_with("\x1b[1m\x1b[31merror\x1b[0m: Missing\n"));
+        assert!(rendered.contains("\x1b[1m\x1b[31m^\x1b[0m"))
+    }
+
+    #[test]
+    fn when_the_color_mode_is_auto_then_the_colors_are_only_used_in_a_terminal_without_no_color() {
+        assert!(ColorMode::Auto.use_color(true, false));
+        assert!(!ColorMode::Auto.use_color(false, false));
+        assert!(!ColorMode::Auto.use_color(true, true));
+        assert!(ColorMode::Always.use_color(false, true));
+        assert!(!ColorMode::Never.use_color(true, false))
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt::Write;
+
+use crate::{
+    parser::from_source_to_node_ast_in_dialect,
+    run::{RunConfig, run_ast},
+};
+
+/**
+ * Bytes shown around the first byte that differs.
+ */
+const CONTEXT_BYTES: usize = 8;
+
+/**
+ * What a program did: the bytes it printed, and the message of the parse or
+ * runtime error when it failed.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Behavior {
+    pub output: Vec<u8>,
+    pub error: Option<String>,
+}
+
+/**
+ * Runs the program with the input, keeping what it printed before failing. Both
+ * programs run with the same settings, but each one with its own memory and its
+ * own limit of instructions.
+ */
+pub fn run_program(source_code: &str, input: &[u8], config: &RunConfig) -> Behavior {
+    let ast = match from_source_to_node_ast_in_dialect(source_code, config.dialect) {
+        Ok(ast) => ast,
+        Err(error) => {
+            return Behavior {
+                output: vec![],
+                error: Some(error.to_string()),
+            };
+        }
+    };
+    let (result, ran) = run_ast(&ast, input, config);
+
+    Behavior {
+        output: result.output,
+        error: ran.err().map(|error| error.to_string()),
+    }
+}
+
+/**
+ * The offset of the first byte that differs, or the length of the shortest output
+ * when it is the start of the other one.
+ */
+pub fn first_divergence(a: &[u8], b: &[u8]) -> Option<usize> {
+    match a.iter().zip(b).position(|(a, b)| a != b) {
+        Some(offset) => Some(offset),
+        None if a.len() != b.len() => Some(a.len().min(b.len())),
+        None => None,
+    }
+}
+
+/**
+ * The bytes around the offset in hexadecimal, with the one at the offset between
+ * brackets, or the end of the output between them when it is shorter.
+ */
+fn hex_context(output: &[u8], offset: usize) -> String {
+    let start = offset.saturating_sub(CONTEXT_BYTES);
+    let end = (offset + CONTEXT_BYTES + 1).min(output.len());
+    let mut bytes: Vec<String> = (start..end)
+        .map(|index| match index == offset {
+            true => format!("[{:02x}]", output[index]),
+            false => format!("{:02x}", output[index]),
+        })
+        .collect();
+
+    if offset >= output.len() {
+        bytes.push("[end]".to_string());
+    }
+
+    bytes.join(" ")
+}
+
+fn describe(error: &Option<String>) -> String {
+    match error {
+        Some(error) => format!("failed: {}", error),
+        None => "finished".to_string(),
+    }
+}
+
+/**
+ * Compares the outputs byte by byte and how the programs ended. It is None when
+ * they did the same, otherwise it says where the outputs diverge and how each
+ * program ended when it isn't the same.
+ */
+pub fn render_difference(a: &Behavior, b: &Behavior) -> Option<String> {
+    let divergence = first_divergence(&a.output, &b.output);
+
+    if divergence.is_none() && a.error == b.error {
+        return None;
+    }
+
+    let mut rendered = String::new();
+
+    if let Some(offset) = divergence {
+        let _ = writeln!(
+            rendered,
+            "outputs differ at the byte {} (a printed {} bytes, b printed {} bytes)",
+            offset,
+            a.output.len(),
+            b.output.len()
+        );
+        let _ = writeln!(rendered, "a: {}", hex_context(&a.output, offset));
+        let _ = writeln!(rendered, "b: {}", hex_context(&b.output, offset));
+    }
+
+    if a.error != b.error {
+        let _ = writeln!(rendered, "a {}", describe(&a.error));
+        let _ = writeln!(rendered, "b {}", describe(&b.error));
+    }
+
+    Some(rendered)
+}
+
+#[cfg(test)]
+mod diff_test {
+    use super::*;
+    use crate::parser::Dialect;
+
+    const CONFIG: RunConfig = RunConfig {
+        memory_size: 30,
+        number_of_reads: 10_000,
+        dialect: Dialect::Standard,
+    };
+
+    #[test]
+    fn given_outputs_when_they_are_compared_then_the_first_divergence_is_found() {
+        assert_eq!(first_divergence(b"abc", b"abc"), None);
+        assert_eq!(first_divergence(b"abc", b"abd"), Some(2));
+        assert_eq!(first_divergence(b"ab", b"abc"), Some(2))
+    }
+
+    #[test]
+    fn given_different_outputs_when_they_are_rendered_then_the_bytes_around_are_shown() {
+        let a = Behavior {
+            output: b"0123456789abcdefghij".to_vec(),
+            error: None,
+        };
+        let b = Behavior {
+            output: b"0123456789Xbcd".to_vec(),
+            error: None,
+        };
+
+        assert_eq!(
+            render_difference(&a, &b).unwrap(),
+            "outputs differ at the byte 10 (a printed 20 bytes, b printed 14 bytes)
+a: 32 33 34 35 36 37 38 39 [61] 62 63 64 65 66 67 68 69
+b: 32 33 34 35 36 37 38 39 [58] 62 63 64
+"
+        )
+    }
+
+    #[test]
+    fn given_a_program_that_fails_when_it_is_compared_then_the_failure_is_a_difference() {
+        let a = run_program("+++.", &[], &CONFIG);
+        let b = run_program("+++.+[]", &[], &CONFIG);
+
+        assert_eq!(
+            render_difference(&a, &b).unwrap(),
+            format!(
+                "a finished\nb failed: {}\n",
+                b.error.as_deref().unwrap_or_default()
+            )
+        );
+        assert_eq!(render_difference(&a, &a), None)
+    }
+
+    #[test]
+    fn given_a_shorter_output_when_it_is_rendered_then_its_end_is_marked() {
+        assert_eq!(hex_context(b"ab", 2), "61 62 [end]")
+    }
+}
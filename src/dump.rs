@@ -0,0 +1,384 @@
+use std::{fmt::Write, ops::Range};
+
+use crate::io::{MemoryCell, MemoryTape};
+
+const CELLS_PER_ROW: usize = 16;
+
+fn hex(value: i64, digits: usize) -> String {
+    if value < 0 {
+        format!("-{:0digits$X}", value.unsigned_abs())
+    } else {
+        format!("{:0digits$X}", value)
+    }
+}
+
+fn zero_cells_line(zero_cells: usize) -> String {
+    match zero_cells {
+        1 => "… 1 zero cell …".to_string(),
+        _ => format!("… {} zero cells …", zero_cells),
+    }
+}
+
+fn printable(value: i64) -> char {
+    match u8::try_from(value) {
+        Ok(byte) if byte.is_ascii_graphic() || byte == b' ' => byte as char,
+        _ => '.',
+    }
+}
+
+/**
+ * Renders the cells of the range in rows of sixteen, each one with the decimal
+ * value, the hexadecimal value and the character of the cells. The positions
+ * outside of the tape are skipped, and the rows full of zeros are collapsed. With
+ * several tapes the dump starts with the tape it shows.
+ *
+ * Only the rows with cells that aren't 0 are walked, so the dump of a huge range
+ * takes as long as the one of the cells in use.
+ */
+pub fn render_memory_dump<Cell: MemoryCell>(
+    memory: &impl MemoryTape<Cell>,
+    range: Range<isize>,
+) -> String {
+    let hex_digits = hex(Cell::MAX.to_i64(), 0).len();
+    let width = Cell::WIDTH.max(hex_digits);
+    let tape = memory.tape_range();
+    let start = range.start.max(tape.start);
+    let end = range.end.min(tape.end).max(start);
+    let mut rows: Vec<isize> = memory
+        .iter_nonzero()
+        .map(|(position, _)| position)
+        .filter(|position| (start..end).contains(position))
+        .map(|position| position - (position.abs_diff(start) % CELLS_PER_ROW) as isize)
+        .collect();
+    rows.sort_unstable();
+    rows.dedup();
+
+    let mut dump = String::new();
+    let mut next_position = start;
+
+    if let Some(tape) = memory.active_tape() {
+        let _ = writeln!(dump, "Tape {}", tape);
+    }
+
+    for row_start in rows {
+        let zero_cells = row_start.abs_diff(next_position);
+
+        if zero_cells > 0 {
+            let _ = writeln!(dump, "{}", zero_cells_line(zero_cells));
+        }
+
+        next_position = row_start.saturating_add_unsigned(CELLS_PER_ROW).min(end);
+        let row: Vec<i64> = (row_start..next_position)
+            .filter_map(|position| memory.get_cell_at(position))
+            .map(|value| value.to_i64())
+            .collect();
+
+        let column = |render: &dyn Fn(i64) -> String| {
+            row.iter()
+                .map(|value| format!("{:>width$}", render(*value)))
+                .collect::<Vec<String>>()
+                .join(" ")
+        };
+
+        let _ = writeln!(
+            dump,
+            "{:>8}  dec {}",
+            row_start,
+            column(&|value| value.to_string())
+        );
+        let _ = writeln!(
+            dump,
+            "{:>8}  hex {}",
+            "",
+            column(&|value| hex(value, hex_digits))
+        );
+        let _ = writeln!(
+            dump,
+            "{:>8}  chr {}",
+            "",
+            column(&|value| printable(value).to_string())
+        );
+    }
+
+    let zero_cells = end.abs_diff(next_position);
+
+    if zero_cells > 0 {
+        let _ = writeln!(dump, "{}", zero_cells_line(zero_cells));
+    }
+
+    dump
+}
+
+/**
+ * Draws the cells around the center in a row, like [ 0 | 72 |>101<| 0 ], with the
+ * pointer between arrows and the positions under the cells. The cells outside of
+ * the tape aren't drawn, so the row is shorter at the edges.
+ */
+pub fn render_window<Cell: MemoryCell>(
+    memory: &impl MemoryTape<Cell>,
+    center: isize,
+    radius: usize,
+) -> String {
+    let pointer = memory.get_logical_position();
+    let start = center.saturating_sub_unsigned(radius);
+    let end = center.saturating_add_unsigned(radius);
+    let cells = || {
+        (start..=end)
+            .filter_map(|position| memory.get_cell_at(position).map(|value| (position, value)))
+    };
+
+    let width = cells()
+        .map(|(position, _)| position.to_string().len())
+        .fold(Cell::WIDTH, usize::max);
+    let mut row = String::with_capacity((width + 3) * (2 * radius + 1) * 2);
+    let mut labels = String::from(" ");
+
+    row.push('[');
+    for (index, (position, value)) in cells().enumerate() {
+        if index > 0 {
+            row.push('|');
+            labels.push(' ');
+        }
+
+        let (left, right) = if position == pointer {
+            ('>', '<')
+        } else {
+            (' ', ' ')
+        };
+        let _ = write!(row, "{}{:>width$}{}", left, value, right);
+        let _ = write!(labels, "{:^slot$}", position, slot = width + 2);
+    }
+    row.push(']');
+
+    format!("{}\n{}", row, labels.trim_end())
+}
+
+/**
+ * Cells shown at each side of the pointer after a runtime error.
+ */
+pub const POSTMORTEM_RADIUS: isize = 16;
+
+/**
+ * Cells drawn in the row of the pointer after a runtime error, at each side.
+ */
+const POSTMORTEM_WINDOW_RADIUS: usize = 4;
+
+/**
+ * The pointer and the cells around it, to show the state of the memory when the
+ * program failed.
+ */
+pub fn render_postmortem<Cell: MemoryCell>(memory: &impl MemoryTape<Cell>) -> String {
+    let pointer = memory.get_logical_position();
+    let cell = match memory.active_tape() {
+        Some(tape) => format!("{} of the tape {}", pointer, tape),
+        None => pointer.to_string(),
+    };
+    let pointer_line = match memory.get_cell_at(pointer) {
+        Some(value) => format!(
+            "The pointer is at the cell {} with the value {}",
+            cell, value
+        ),
+        None => format!("The pointer is at the cell {}, outside of the memory", cell),
+    };
+    let window =
+        pointer.saturating_sub(POSTMORTEM_RADIUS)..pointer.saturating_add(POSTMORTEM_RADIUS);
+
+    format!(
+        "{}\n{}\n{}",
+        pointer_line,
+        render_window(memory, pointer, POSTMORTEM_WINDOW_RADIUS),
+        render_memory_dump(memory, window)
+    )
+}
+
+#[cfg(test)]
+mod dump_test {
+    use super::*;
+    use crate::io::{BidirectionalMemory, BrainfuckMemory, MultiTape, SparseMemory};
+
+    fn memory_with(values: &[(isize, u8)]) -> BrainfuckMemory {
+        let mut memory = BrainfuckMemory::default();
+
+        for (position, value) in values {
+            let step = position - memory.get_logical_position();
+            memory.move_pointer_position(step).unwrap();
+            memory.update_memory_cell_value(|_| Ok(*value)).unwrap();
+        }
+
+        memory
+    }
+
+    #[test]
+    fn given_a_memory_with_values_when_it_is_dumped_then_each_cell_has_its_decimal_hex_and_character()
+     {
+        let memory = memory_with(&[(0, 1), (1, 2), (2, 72), (3, 105)]);
+
+        assert_eq!(
+            render_memory_dump(&memory, 0..4),
+            concat!(
+                "       0  dec   1   2  72 105\n",
+                "          hex  01  02  48  69\n",
+                "          chr   .   .   H   i\n"
+            )
+        )
+    }
+
+    #[test]
+    fn given_a_memory_with_a_run_of_zeros_when_it_is_dumped_then_the_zero_rows_are_collapsed() {
+        let memory = memory_with(&[(0, 65), (40, 66)]);
+
+        let dump = render_memory_dump(&memory, 0..48);
+        let lines: Vec<&str> = dump.lines().collect();
+
+        assert_eq!(lines.len(), 7);
+        assert!(lines[0].starts_with("       0  dec  65   0"));
+        assert_eq!(lines[3], "… 16 zero cells …");
+        assert!(lines[4].starts_with("      32  dec   0"))
+    }
+
+    #[test]
+    fn given_a_huge_range_when_it_is_dumped_then_it_is_clamped_to_the_tape() {
+        let memory = memory_with(&[(0, 65), (40, 66)]);
+
+        let dump = render_memory_dump(&memory, 0..99_999_999_999);
+        let lines: Vec<&str> = dump.lines().collect();
+
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[3], "… 16 zero cells …");
+        assert!(lines[4].starts_with("      32  dec   0"));
+        assert_eq!(lines[7], "… 2952 zero cells …");
+
+        let mut sparse_memory = SparseMemory::unbounded();
+        sparse_memory.load_slice(1 << 40, b"A").unwrap();
+
+        let dump = render_memory_dump(&sparse_memory, isize::MIN..isize::MAX);
+        let lines: Vec<&str> = dump.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], zero_cells_line(1 << 40));
+        assert!(lines[1].starts_with("1099511627776  dec  65   0"));
+        assert_eq!(
+            lines[4],
+            zero_cells_line(isize::MAX as usize - (1 << 40) - CELLS_PER_ROW)
+        )
+    }
+
+    #[test]
+    fn given_a_range_outside_of_the_tape_when_it_is_dumped_then_only_the_cells_of_the_tape_are_shown()
+     {
+        let mut memory = BidirectionalMemory::with_caps(1, 2);
+        memory.move_pointer_position(-1).unwrap();
+        memory.update_memory_cell_value(|_| Ok(33)).unwrap();
+
+        assert_eq!(
+            render_memory_dump(&memory, -5..5),
+            concat!(
+                "      -1  dec  33   0   0\n",
+                "          hex  21  00  00\n",
+                "          chr   !   .   .\n"
+            )
+        )
+    }
+
+    #[test]
+    fn given_a_memory_when_the_postmortem_is_rendered_then_it_has_the_pointer_and_the_cells_around_it()
+     {
+        let memory = memory_with(&[(18, 7), (20, 9)]);
+
+        let postmortem = render_postmortem(&memory);
+        let lines: Vec<&str> = postmortem.lines().collect();
+
+        assert_eq!(lines[0], "The pointer is at the cell 20 with the value 9");
+        assert_eq!(
+            lines[1],
+            "[   0 |   0 |   7 |   0 |>  9<|   0 |   0 |   0 |   0 ]"
+        );
+        assert!(lines[3].starts_with("       4  dec   0"));
+        assert!(lines[6].starts_with("      20  dec   9"));
+        assert_eq!(lines.len(), 9)
+    }
+
+    #[test]
+    fn given_a_pointer_outside_of_the_memory_when_the_postmortem_is_rendered_then_it_says_so() {
+        let memory = BrainfuckMemory::<u8>::new(0);
+
+        let postmortem = render_postmortem(&memory);
+
+        assert!(postmortem.starts_with("The pointer is at the cell 0, outside of the memory\n"))
+    }
+
+    #[test]
+    fn given_several_tapes_when_they_are_dumped_then_the_dump_and_the_postmortem_name_the_tape() {
+        let mut memory =
+            MultiTape::from_tapes(vec![memory_with(&[(0, 65)]), memory_with(&[(1, 66)])]);
+        memory.switch_tape(1);
+
+        assert!(render_memory_dump(&memory, 0..2).starts_with("Tape 1\n       0  dec   0  66\n"));
+        assert!(
+            render_postmortem(&memory)
+                .starts_with("The pointer is at the cell 1 of the tape 1 with the value 66\n")
+        )
+    }
+
+    fn small_tape() -> BrainfuckMemory {
+        let mut memory = BrainfuckMemory::<u8>::new(6);
+
+        for value in [1, 22, 255, 0, 72, 101] {
+            memory.update_memory_cell_value(|_| Ok(value)).unwrap();
+            let _ = memory.move_pointer_position(1);
+        }
+
+        memory
+    }
+
+    fn at(mut memory: BrainfuckMemory, position: isize) -> BrainfuckMemory {
+        let step = position - memory.get_logical_position();
+        memory.move_pointer_position(step).unwrap();
+        memory
+    }
+
+    #[test]
+    fn given_the_pointer_at_the_left_edge_when_the_window_is_rendered_then_it_starts_in_the_first_cell()
+     {
+        let memory = at(small_tape(), 0);
+
+        assert_eq!(
+            render_window(&memory, 0, 2),
+            "[>  1<|  22 | 255 ]\n   0     1     2"
+        )
+    }
+
+    #[test]
+    fn given_the_pointer_in_the_middle_when_the_window_is_rendered_then_the_cells_at_both_sides_are_shown()
+     {
+        let memory = at(small_tape(), 3);
+
+        assert_eq!(
+            render_window(&memory, 3, 2),
+            "[  22 | 255 |>  0<|  72 | 101 ]\n   1     2     3     4     5"
+        )
+    }
+
+    #[test]
+    fn given_the_pointer_at_the_right_edge_when_the_window_is_rendered_then_it_ends_in_the_last_cell()
+     {
+        let memory = at(small_tape(), 5);
+
+        assert_eq!(
+            render_window(&memory, 5, 2),
+            "[   0 |  72 |>101<]\n   3     4     5"
+        )
+    }
+
+    #[test]
+    fn given_positions_wider_than_the_values_when_the_window_is_rendered_then_the_cells_are_widened()
+     {
+        let mut memory = BidirectionalMemory::default();
+        memory.move_pointer_position(-1000).unwrap();
+
+        assert_eq!(
+            render_window(&memory, -1000, 1),
+            "[     0 |>    0<|     0 ]\n  -1001   -1000   -999"
+        )
+    }
+}
@@ -1,9 +1,38 @@
+use flate2::read::GzDecoder;
+use std::collections::HashSet;
+use std::fmt::{self, Display};
 use std::fs;
+use std::fs::{File, OpenOptions};
 use std::io;
-use std::io::ErrorKind;
-use std::path::Path;
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+/**
+ * Binary suffixes used to write the sizes, after the bytes.
+ */
+const SIZE_UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+/**
+ * Writes a count of bytes with the biggest binary suffix that keeps it above one,
+ * like 1.5 MiB, for the messages.
+ */
+pub fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{} bytes", bytes);
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < SIZE_UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", value, SIZE_UNITS[unit])
+}
+
 /**
  * io::Error doesn't implement PartialEq but I can't implement PartialEq as the type
  * don't belong to my crate. I'm wrapping the io::Error on my own struct and implement
@@ -29,19 +58,99 @@ pub enum FileError {
     FilePathMalformed { path: String },
     #[error("The ${path:?} doesn't point to a file, it's a directory")]
     IsADirectory { path: String },
-    #[error("Unable to read the file due lack of permission")]
-    NotEnoughPermission,
-    #[error("Unexpected error processing the file")]
-    UnexpectedError(#[from] PublicError),
+    #[error("The directory {path:?} doesn't exist")]
+    DirectoryNotFound { path: String },
+    #[error("Unable to read the file {path:?} due lack of permission{denied_by}")]
+    NotEnoughPermission { path: String, denied_by: DeniedBy },
+    #[error("The file {path:?} already exists, use --force to overwrite it")]
+    FileAlreadyExists { path: String },
+    #[error("The source code is read from the stdin with the path -, but nothing is piped into it")]
+    NothingPiped,
+    /**
+     * From the stdin the size isn't known, so it's the bytes read until the limit
+     * was passed.
+     */
+    #[error(
+        "The file {path:?} is {}, bigger than the limit of {}. Raise it with --max-source-size",
+        format_size(*size),
+        format_size(*limit)
+    )]
+    FileTooLarge { path: String, size: u64, limit: u64 },
+    #[error("The include of {} goes back to a file already included", chain.join(" -> "))]
+    IncludeCycle { chain: Vec<String> },
+    #[error("The includes {} go deeper than {limit} files", chain.join(" -> "))]
+    IncludeTooDeep { chain: Vec<String>, limit: usize },
+    #[error("Unable to decompress the file {path:?}: {error}")]
+    DecompressionFailed { path: String, error: PublicError },
+    #[error("Unexpected error processing the file {path:?}: {error}")]
+    UnexpectedError { path: String, error: PublicError },
+}
+
+/**
+ * What refused the access to a file: the file itself, or a directory in its path
+ * that can't be traversed.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeniedBy {
+    File,
+    Directory(String),
+}
+
+impl Display for DeniedBy {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeniedBy::File => Ok(()),
+            DeniedBy::Directory(directory) => {
+                write!(
+                    formatter,
+                    ", the directory {:?} can't be traversed",
+                    directory
+                )
+            }
+        }
+    }
+}
+
+/**
+ * Reading the metadata only needs to traverse the directories, so the first path,
+ * from the root, whose metadata is denied is in a directory that can't be
+ * traversed. When all of them are readable it's the file that is denied.
+ */
+fn denied_by(path: &str) -> DeniedBy {
+    let ancestors: Vec<&Path> = Path::new(path)
+        .ancestors()
+        .filter(|ancestor| !ancestor.as_os_str().is_empty())
+        .collect();
+
+    ancestors
+        .into_iter()
+        .rev()
+        .find(|ancestor| {
+            fs::metadata(ancestor).is_err_and(|error| error.kind() == ErrorKind::PermissionDenied)
+        })
+        .map_or(DeniedBy::File, |denied| {
+            let directory = denied
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+
+            DeniedBy::Directory(directory.to_string_lossy().into_owned())
+        })
+}
+
+pub fn unexpected_error(path: &str, error: io::Error) -> FileError {
+    FileError::UnexpectedError {
+        path: path.to_string(),
+        error: PublicError(error),
+    }
 }
 
 fn get_file_name_string(path: &str) -> Option<String> {
     let path_normalized = Path::new(path);
 
-    match path_normalized.file_name() {
-        Some(file_name) => Some(file_name.to_string_lossy().to_string()),
-        None => None,
-    }
+    path_normalized
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
 }
 
 fn get_ancestor_path(path: &str) -> String {
@@ -53,31 +162,566 @@ fn get_ancestor_path(path: &str) -> String {
     parent_paths
         .next()
         .map(|parent_path| parent_path.to_string_lossy().to_string())
-        .unwrap_or_else(|| String::new())
+        .unwrap_or_default()
+}
+
+/**
+ * Whether the directory where the file goes exists. A file name alone goes in the
+ * current directory, which does.
+ */
+fn parent_exists(path: &str) -> bool {
+    match get_ancestor_path(path).as_str() {
+        "" => true,
+        parent => Path::new(parent).exists(),
+    }
 }
 
-pub fn read_source_code_file(path: &str) -> Result<String, FileError> {
-    match fs::read_to_string(path) {
-        Ok(content) => Ok(content),
-        Err(error) if error.kind() == ErrorKind::NotFound => match get_file_name_string(path) {
-            Some(file_name) => Err(FileError::FileNotFound {
+pub fn map_read_error(path: &str, error: io::Error) -> FileError {
+    match error.kind() {
+        ErrorKind::NotFound => match get_file_name_string(path) {
+            Some(file_name) => FileError::FileNotFound {
                 file_name,
                 path: get_ancestor_path(path),
-            }),
-            None => Err(FileError::UnexpectedError(PublicError(error))),
+            },
+            None => unexpected_error(path, error),
+        },
+        ErrorKind::NotADirectory => FileError::FilePathMalformed {
+            path: path.to_string(),
+        },
+        ErrorKind::PermissionDenied => FileError::NotEnoughPermission {
+            path: path.to_string(),
+            denied_by: denied_by(path),
         },
-        Err(error) if error.kind() == ErrorKind::NotADirectory => {
+        ErrorKind::IsADirectory => FileError::IsADirectory {
+            path: path.to_string(),
+        },
+        _ => unexpected_error(path, error),
+    }
+}
+
+/**
+ * Biggest source code read by default. No program needs that much, so a bigger
+ * file is likely a binary or a device given by mistake.
+ */
+pub const DEFAULT_MAX_SOURCE_SIZE: u64 = 64 << 20;
+
+/**
+ * Reads one byte past the limit at most, enough to know the limit was passed
+ * without loading an endless device.
+ */
+fn read_up_to(
+    path: &str,
+    reader: impl Read,
+    limit: u64,
+    map_error: impl FnOnce(io::Error) -> FileError,
+) -> Result<Vec<u8>, FileError> {
+    let mut bytes = Vec::new();
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut bytes)
+        .map_err(map_error)?;
+
+    match bytes.len() as u64 {
+        size if size > limit => Err(FileError::FileTooLarge {
+            path: path.to_string(),
+            size,
+            limit,
+        }),
+        _ => Ok(bytes),
+    }
+}
+
+/**
+ * The first bytes of every gzip stream.
+ */
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/**
+ * The gzipped sources, found by their first bytes or by the .gz extension, are
+ * decompressed on the fly. The limit is for the decompressed source code, so a
+ * small file can't blow up the memory. The bytes that aren't valid UTF-8 can only
+ * be in the comments, as the commands are ASCII, so they are replaced instead of
+ * failing the whole file.
+ */
+fn decode_source(path: &str, mut reader: impl BufRead, limit: u64) -> Result<String, FileError> {
+    let starts_with_magic = reader
+        .fill_buf()
+        .map_err(|error| map_read_error(path, error))?
+        .starts_with(&GZIP_MAGIC);
+    let gzipped = starts_with_magic
+        || Path::new(path)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("gz"));
+
+    let bytes = if gzipped {
+        read_up_to(path, GzDecoder::new(reader), limit, |error| {
+            FileError::DecompressionFailed {
+                path: path.to_string(),
+                error: PublicError(error),
+            }
+        })?
+    } else {
+        read_up_to(path, reader, limit, |error| map_read_error(path, error))?
+    };
+
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/**
+ * The size is checked before reading, and while reading for the files that don't
+ * know it or are compressed.
+ */
+pub fn read_source_code_file(path: &str, limit: u64) -> Result<String, FileError> {
+    let file = File::open(path).map_err(|error| map_read_error(path, error))?;
+    let size = file
+        .metadata()
+        .map_err(|error| map_read_error(path, error))?
+        .len();
+
+    if size > limit {
+        return Err(FileError::FileTooLarge {
+            path: path.to_string(),
+            size,
+            limit,
+        });
+    }
+
+    decode_source(path, BufReader::new(file), limit)
+}
+
+/**
+ * The path that means the stdin, as in most of the command line tools.
+ */
+pub const STDIN_PATH: &str = "-";
+
+/**
+ * The name of the source code given with --eval, in place of a path.
+ */
+pub const INLINE_PROGRAM: &str = "inline program";
+
+/**
+ * Reads the source code from the file, or from the stdin when the path is -. A
+ * terminal fails right away instead of waiting for a program nobody is typing.
+ */
+pub fn read_source(
+    path: &str,
+    stdin: impl Read,
+    stdin_is_terminal: bool,
+    limit: u64,
+) -> Result<String, FileError> {
+    if path != STDIN_PATH {
+        return read_source_code_file(path, limit);
+    }
+
+    if stdin_is_terminal {
+        return Err(FileError::NothingPiped);
+    }
+
+    decode_source(path, BufReader::new(stdin), limit)
+}
+
+/**
+ * The comment line that pulls another source file in its place, like
+ * @include "lib/print.bf". The path is relative to the file with the directive.
+ */
+pub const INCLUDE_DIRECTIVE: &str = "@include";
+
+/**
+ * Files that can be nested with includes, counting the first one.
+ */
+pub const MAX_INCLUDE_DEPTH: usize = 16;
+
+/**
+ * A run of lines of the expanded source code that comes from the same file, from
+ * its first line in the expanded source code and in the file.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceRegion {
+    pub first_line: usize,
+    pub path: String,
+    pub line: usize,
+}
+
+/**
+ * Where every line of a source code with includes comes from. An empty map means
+ * the source code is a single file.
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SourceMap {
+    regions: Vec<SourceRegion>,
+}
+
+impl SourceMap {
+    /**
+     * The file and the line in it of a line of the expanded source code. The lines
+     * count from one, as in the diagnostics.
+     */
+    pub fn locate(&self, line: usize) -> Option<(&str, usize)> {
+        self.regions
+            .iter()
+            .rev()
+            .find(|region| region.first_line <= line)
+            .map(|region| {
+                (
+                    region.path.as_str(),
+                    region.line + (line - region.first_line),
+                )
+            })
+    }
+
+    pub fn regions(&self) -> &[SourceRegion] {
+        &self.regions
+    }
+}
+
+/**
+ * A source code with its includes replaced by the content of the included files.
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExpandedSource {
+    pub source_code: String,
+    pub source_map: SourceMap,
+    lines: usize,
+}
+
+impl From<String> for ExpandedSource {
+    fn from(source_code: String) -> Self {
+        ExpandedSource {
+            source_code,
+            ..ExpandedSource::default()
+        }
+    }
+}
+
+impl ExpandedSource {
+    fn push_line(&mut self, path: &str, line_number: usize, line: &str) {
+        let line_in_expansion = self.lines + 1;
+        let continues_region = self.source_map.regions.last().is_some_and(|region| {
+            region.path == path
+                && region.line + (line_in_expansion - region.first_line) == line_number
+        });
+
+        if !continues_region {
+            self.source_map.regions.push(SourceRegion {
+                first_line: line_in_expansion,
+                path: path.to_string(),
+                line: line_number,
+            });
+        }
+
+        self.source_code.push_str(line);
+        if !line.ends_with('\n') {
+            self.source_code.push('\n');
+        }
+        self.lines += 1;
+    }
+
+    /**
+     * Adds the source code of another file after this one, so several files run as
+     * one program. Every line keeps the file and the line it comes from.
+     */
+    pub fn append(&mut self, path: &str, other: &ExpandedSource) {
+        for (index, line) in other.source_code.split_inclusive('\n').enumerate() {
+            let (line_path, line_number) = other
+                .source_map
+                .locate(index + 1)
+                .unwrap_or((path, index + 1));
+
+            self.push_line(line_path, line_number, line);
+        }
+    }
+}
+
+fn include_path(line: &str) -> Option<&str> {
+    line.trim_start()
+        .strip_prefix(INCLUDE_DIRECTIVE)?
+        .trim()
+        .strip_prefix('"')?
+        .strip_suffix('"')
+}
+
+/**
+ * The files are compared by their canonical path, so the cycles are found even if
+ * the includes write the path in different ways.
+ */
+fn canonical_path(path: &str) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+fn expand_into(
+    expanded: &mut ExpandedSource,
+    chain: &[String],
+    source_code: &str,
+    limit: u64,
+) -> Result<(), FileError> {
+    let path = chain.last().cloned().unwrap_or_default();
+    let directory = Path::new(&path).parent().unwrap_or(Path::new(""));
+
+    for (index, line) in source_code.split_inclusive('\n').enumerate() {
+        let Some(included) = include_path(line) else {
+            expanded.push_line(&path, index + 1, line);
+            continue;
+        };
+
+        let included_path = directory.join(included).to_string_lossy().into_owned();
+        let included_canonical = canonical_path(&included_path);
+        let mut included_chain = chain.to_vec();
+        included_chain.push(included_path.clone());
+
+        if chain
+            .iter()
+            .any(|path| canonical_path(path) == included_canonical)
+        {
+            return Err(FileError::IncludeCycle {
+                chain: included_chain,
+            });
+        }
+
+        if included_chain.len() > MAX_INCLUDE_DEPTH {
+            return Err(FileError::IncludeTooDeep {
+                chain: included_chain,
+                limit: MAX_INCLUDE_DEPTH,
+            });
+        }
+
+        let included_source = read_source_code_file(&included_path, limit)?;
+        expand_into(expanded, &included_chain, &included_source, limit)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Replaces every include directive line by the content of the file, recursively,
+ * recording where each line comes from. The included files have the same size
+ * limit as the first one.
+ */
+pub fn expand_includes(
+    path: &str,
+    source_code: &str,
+    limit: u64,
+) -> Result<ExpandedSource, FileError> {
+    let mut expanded = ExpandedSource::default();
+
+    expand_into(&mut expanded, &[path.to_string()], source_code, limit)?;
+
+    Ok(expanded)
+}
+
+/**
+ * Expands the patterns the shell didn't, like 'src/**/*.bf', into the files they
+ * match. The paths without a match are kept as they are, so reading them reports
+ * why. The result is sorted and without repeated paths.
+ */
+pub fn expand_paths<'a>(patterns: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut paths: Vec<String> = patterns
+        .into_iter()
+        .flat_map(|pattern| {
+            let matches: Vec<String> = glob::glob(pattern)
+                .map(|entries| {
+                    entries
+                        .filter_map(Result::ok)
+                        .map(|path| path.to_string_lossy().into_owned())
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            match matches.is_empty() {
+                true => vec![pattern.to_string()],
+                false => matches,
+            }
+        })
+        .collect();
+
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/**
+ * Extensions of the source files, the ones picked when walking a directory and the
+ * ones that don't get a warning.
+ */
+pub const SOURCE_EXTENSIONS: [&str; 2] = ["b", "bf"];
+
+/**
+ * Whether the file has one of the extensions, looking under the .gz of the
+ * compressed ones.
+ */
+pub fn has_source_extension(path: &Path, extensions: &[String]) -> bool {
+    let path = match path.extension() {
+        Some(extension) if extension.eq_ignore_ascii_case("gz") => path.with_extension(""),
+        _ => path.to_path_buf(),
+    };
+
+    path.extension().is_some_and(|extension| {
+        extensions
+            .iter()
+            .any(|accepted| extension.eq_ignore_ascii_case(accepted.as_str()))
+    })
+}
+
+/**
+ * A directory of the walk that couldn't be read, so its files are missing.
+ */
+#[derive(Debug, PartialEq)]
+pub struct UnreadableDirectory {
+    pub path: String,
+    pub error: FileError,
+}
+
+/**
+ * The source files inside of the directories, walked in depth and in the order of
+ * the names, one at a time so the big trees don't wait for the whole walk. The
+ * hidden directories are skipped, and every directory is entered once, so the
+ * symbolic links going back to a parent don't loop. The paths that aren't
+ * directories are given as they are, and the directories that can't be read are
+ * given with their error.
+ */
+pub struct SourceFiles {
+    // The paths still to walk, the next one at the end, and if they were given.
+    pending: Vec<(PathBuf, bool)>,
+    visited: HashSet<PathBuf>,
+    extensions: Vec<String>,
+    excludes: Vec<glob::Pattern>,
+}
+
+impl SourceFiles {
+    /**
+     * The excludes are matched against the whole path and against the name, so
+     * both vendor and '*.min.bf' work.
+     */
+    pub fn new<'a>(
+        roots: impl IntoIterator<Item = &'a str>,
+        extensions: Vec<String>,
+        excludes: Vec<glob::Pattern>,
+    ) -> Self {
+        let mut pending: Vec<(PathBuf, bool)> = roots
+            .into_iter()
+            .map(|root| (PathBuf::from(root), true))
+            .collect();
+        pending.reverse();
+
+        SourceFiles {
+            pending,
+            visited: HashSet::new(),
+            extensions,
+            excludes,
+        }
+    }
+
+    fn is_excluded(&self, path: &Path) -> bool {
+        let name = path.file_name().map(|name| name.to_string_lossy());
+
+        self.excludes.iter().any(|pattern| {
+            pattern.matches_path(path) || name.as_ref().is_some_and(|name| pattern.matches(name))
+        })
+    }
+
+    fn push_entries(&mut self, directory: &Path) -> Result<(), UnreadableDirectory> {
+        let entries = fs::read_dir(directory).map_err(|error| {
+            let path = directory.to_string_lossy().into_owned();
+
+            UnreadableDirectory {
+                error: map_read_error(&path, error),
+                path,
+            }
+        })?;
+        let mut entries: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                let hidden = path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+
+                !(hidden && path.is_dir())
+            })
+            .collect();
+
+        entries.sort_by(|left, right| right.cmp(left));
+        self.pending
+            .extend(entries.into_iter().map(|entry| (entry, false)));
+        Ok(())
+    }
+}
+
+impl Iterator for SourceFiles {
+    type Item = Result<String, UnreadableDirectory>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((path, given)) = self.pending.pop() {
+            if self.is_excluded(&path) {
+                continue;
+            }
+
+            if !path.is_dir() {
+                if given || has_source_extension(&path, &self.extensions) {
+                    return Some(Ok(path.to_string_lossy().into_owned()));
+                }
+                continue;
+            }
+
+            if self.visited.insert(canonical_path(&path.to_string_lossy()))
+                && let Err(error) = self.push_entries(&path)
+            {
+                return Some(Err(error));
+            }
+        }
+
+        None
+    }
+}
+
+/**
+ * Reads the bytes of the file as they are, like the input recordings.
+ */
+pub fn read_binary_file(path: &str) -> Result<Vec<u8>, FileError> {
+    fs::read(path).map_err(|error| map_read_error(path, error))
+}
+
+/**
+ * Creates the file where the program output is written. An existing file is only
+ * truncated when the user has forced it, otherwise we could lose data by accident.
+ */
+pub fn create_output_file(path: &str, force: bool) -> Result<File, FileError> {
+    let mut options = OpenOptions::new();
+
+    if force {
+        options.write(true).create(true).truncate(true);
+    } else {
+        options.write(true).create_new(true);
+    }
+
+    match options.open(path) {
+        Ok(file) => Ok(file),
+        Err(error) if error.kind() == ErrorKind::AlreadyExists => {
+            Err(FileError::FileAlreadyExists {
+                path: path.to_string(),
+            })
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound && !parent_exists(path) => {
+            Err(FileError::DirectoryNotFound {
+                path: get_ancestor_path(path),
+            })
+        }
+        Err(error)
+            if error.kind() == ErrorKind::NotFound || error.kind() == ErrorKind::NotADirectory =>
+        {
             Err(FileError::FilePathMalformed {
                 path: path.to_string(),
             })
         }
         Err(error) if error.kind() == ErrorKind::PermissionDenied => {
-            Err(FileError::NotEnoughPermission)
+            Err(FileError::NotEnoughPermission {
+                path: path.to_string(),
+                denied_by: denied_by(path),
+            })
         }
         Err(error) if error.kind() == ErrorKind::IsADirectory => Err(FileError::IsADirectory {
             path: path.to_string(),
         }),
-        Err(error) => Err(FileError::UnexpectedError(PublicError(error))),
+        Err(error) => Err(unexpected_error(path, error)),
     }
 }
 
@@ -86,6 +730,15 @@ mod read_file {
 
     use super::*;
 
+    #[test]
+    fn given_a_count_of_bytes_when_it_is_formatted_then_it_uses_the_biggest_binary_suffix() {
+        assert_eq!(format_size(1023), "1023 bytes");
+        assert_eq!(format_size(1024), "1.0 KiB");
+        assert_eq!(format_size(3 << 19), "1.5 MiB");
+        assert_eq!(format_size(1 << 30), "1.0 GiB");
+        assert_eq!(format_size(1 << 50), "1024.0 TiB")
+    }
+
     // Only works in Linux and Mac
     // CARGO_MAFIDEST_DIR is a stable env to located files for unit test resources that need to read files.
     macro_rules! file_test_case {
@@ -94,21 +747,314 @@ mod read_file {
         };
     }
 
+    #[test]
+    fn when_the_path_is_a_dash_then_the_source_is_read_from_the_stdin() {
+        let content =
+            read_source(STDIN_PATH, "+.".as_bytes(), false, DEFAULT_MAX_SOURCE_SIZE).unwrap();
+
+        assert_eq!(content, "+.")
+    }
+
+    #[test]
+    fn when_the_path_is_a_dash_and_the_stdin_is_a_terminal_then_return_nothing_piped_error() {
+        let file_error =
+            read_source(STDIN_PATH, "+.".as_bytes(), true, DEFAULT_MAX_SOURCE_SIZE).unwrap_err();
+
+        assert_eq!(file_error, FileError::NothingPiped)
+    }
+
+    #[test]
+    fn when_the_path_is_a_file_then_the_stdin_is_not_read() {
+        let path = file_test_case!("file_exists.txt");
+
+        let content = read_source(path, "-".as_bytes(), true, DEFAULT_MAX_SOURCE_SIZE).unwrap();
+
+        assert_eq!(content, "+\n")
+    }
+
     #[test]
     fn when_file_exists_then_return_the_file_content() {
         let path = file_test_case!("file_exists.txt");
 
-        let content_file = read_source_code_file(&path).unwrap();
+        let content_file = read_source_code_file(path, DEFAULT_MAX_SOURCE_SIZE).unwrap();
 
         assert_eq!(content_file, "+\n")
     }
 
+    #[test]
+    fn when_the_file_has_bytes_that_are_not_utf8_then_they_are_replaced_and_the_commands_kept() {
+        let path = file_test_case!("non_utf8_comment.bf");
+
+        let content_file = read_source_code_file(path, DEFAULT_MAX_SOURCE_SIZE).unwrap();
+
+        assert!(content_file.starts_with("Prints A \u{FFFD} in the comment\n"));
+        assert_eq!(
+            crate::parser::from_source_to_node_ast(&content_file)
+                .unwrap()
+                .len(),
+            24
+        )
+    }
+
+    #[test]
+    fn when_the_file_is_bigger_than_the_limit_then_return_file_too_large_error() {
+        let path = file_test_case!("file_exists.txt");
+
+        assert_eq!(read_source_code_file(path, 2), Ok("+\n".to_string()));
+        assert_eq!(
+            read_source_code_file(path, 1),
+            Err(FileError::FileTooLarge {
+                path: path.to_string(),
+                size: 2,
+                limit: 1,
+            })
+        )
+    }
+
+    #[test]
+    fn when_the_stdin_passes_the_limit_then_return_file_too_large_error() {
+        assert_eq!(
+            read_source(STDIN_PATH, "+.".as_bytes(), false, 2),
+            Ok("+.".to_string())
+        );
+        assert_eq!(
+            read_source(STDIN_PATH, "+.+".as_bytes(), false, 2),
+            Err(FileError::FileTooLarge {
+                path: STDIN_PATH.to_string(),
+                size: 3,
+                limit: 2,
+            })
+        )
+    }
+
+    #[test]
+    fn when_the_file_is_bigger_than_the_limit_then_the_error_has_the_readable_sizes() {
+        let error = FileError::FileTooLarge {
+            path: "big.bf".to_string(),
+            size: 3 << 19,
+            limit: 1 << 20,
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "The file \"big.bf\" is 1.5 MiB, bigger than the limit of 1.0 MiB. Raise it with --max-source-size"
+        )
+    }
+
+    fn expand_test_case(path: &str) -> Result<ExpandedSource, FileError> {
+        let source_code = read_source_code_file(path, DEFAULT_MAX_SOURCE_SIZE).unwrap();
+
+        expand_includes(path, &source_code, DEFAULT_MAX_SOURCE_SIZE)
+    }
+
+    #[test]
+    fn when_a_file_includes_another_then_its_line_is_replaced_by_the_other_file() {
+        let path = file_test_case!("include_main.bf");
+        let included = file_test_case!("include_print.bf");
+
+        let expanded = expand_test_case(path).unwrap();
+
+        assert_eq!(expanded.source_code, "+\n.\n.\n-\n");
+        assert_eq!(expanded.source_map.locate(1), Some((path, 1)));
+        assert_eq!(expanded.source_map.locate(3), Some((included, 2)));
+        assert_eq!(expanded.source_map.locate(4), Some((path, 3)))
+    }
+
+    #[test]
+    fn given_several_files_when_they_are_appended_then_each_line_keeps_its_file() {
+        let first = file_test_case!("include_print.bf");
+        let second = file_test_case!("include_main.bf");
+        let included = file_test_case!("include_print.bf");
+        let mut program = ExpandedSource::default();
+
+        program.append(first, &ExpandedSource::from(".\n.".to_string()));
+        program.append(second, &expand_test_case(second).unwrap());
+
+        assert_eq!(program.source_code, ".\n.\n+\n.\n.\n-\n");
+        assert_eq!(program.source_map.locate(2), Some((first, 2)));
+        assert_eq!(program.source_map.locate(3), Some((second, 1)));
+        assert_eq!(program.source_map.locate(5), Some((included, 2)));
+        assert_eq!(program.source_map.locate(6), Some((second, 3)))
+    }
+
+    #[test]
+    fn when_an_included_file_doesnt_exist_then_return_its_file_not_found_error() {
+        let file_error = expand_test_case(file_test_case!("include_missing.bf")).unwrap_err();
+
+        assert_eq!(
+            file_error,
+            FileError::FileNotFound {
+                file_name: "missing.bf".to_string(),
+                path: file_test_case!("nested").to_string(),
+            }
+        )
+    }
+
+    #[test]
+    fn when_the_includes_go_back_to_a_file_then_return_the_include_chain() {
+        let path = file_test_case!("include_cycle_a.bf");
+
+        let file_error = expand_test_case(path).unwrap_err();
+
+        assert_eq!(
+            file_error,
+            FileError::IncludeCycle {
+                chain: vec![
+                    path.to_string(),
+                    file_test_case!("include_cycle_b.bf").to_string(),
+                    path.to_string(),
+                ]
+            }
+        );
+        assert!(
+            expand_test_case(file_test_case!("include_self.bf"))
+                .unwrap_err()
+                .to_string()
+                .starts_with("The include of ")
+        )
+    }
+
+    #[test]
+    fn when_the_paths_have_patterns_then_they_are_expanded_sorted_and_without_repetitions() {
+        let paths = expand_paths([
+            file_test_case!("include_p*.bf"),
+            file_test_case!("file_exists.txt"),
+            file_test_case!("include_cycle_?.bf"),
+            file_test_case!("file_exists.txt"),
+            "-",
+            "missing/*.bf",
+        ]);
+
+        assert_eq!(
+            paths,
+            [
+                "-",
+                file_test_case!("file_exists.txt"),
+                file_test_case!("include_cycle_a.bf"),
+                file_test_case!("include_cycle_b.bf"),
+                file_test_case!("include_print.bf"),
+                "missing/*.bf",
+            ]
+        )
+    }
+
+    #[test]
+    fn when_the_extension_of_a_path_is_checked_then_the_gz_ones_are_looked_under() {
+        let extensions = ["b".to_string(), "bf".to_string()];
+
+        assert!(has_source_extension(Path::new("hello.bf"), &extensions));
+        assert!(has_source_extension(Path::new("hello.B"), &extensions));
+        assert!(has_source_extension(Path::new("hello.bf.gz"), &extensions));
+        assert!(!has_source_extension(Path::new("notes.txt"), &extensions));
+        assert!(!has_source_extension(Path::new("hello.gz"), &extensions));
+        assert!(!has_source_extension(Path::new("bf"), &extensions))
+    }
+
+    #[test]
+    fn when_a_directory_is_walked_then_its_source_files_are_given_in_order_without_the_excluded() {
+        let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test");
+        let given_file = file_test_case!("file_exists.txt");
+        let files: Vec<String> = SourceFiles::new(
+            [directory, given_file],
+            vec!["bf".to_string()],
+            vec![glob::Pattern::new("include_*").unwrap()],
+        )
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+        assert_eq!(
+            files,
+            [
+                file_test_case!("commented_hello_world.bf"),
+                file_test_case!("nested_loops.bf"),
+                file_test_case!("non_utf8_comment.bf"),
+                file_test_case!("file_exists.txt")
+            ]
+        )
+    }
+
+    #[test]
+    fn when_the_permission_is_denied_then_the_error_has_the_path_and_what_denied_it() {
+        let denied_file = FileError::NotEnoughPermission {
+            path: "programs/hello.bf".to_string(),
+            denied_by: DeniedBy::File,
+        };
+        let denied_directory = FileError::NotEnoughPermission {
+            path: "programs/hello.bf".to_string(),
+            denied_by: DeniedBy::Directory("programs".to_string()),
+        };
+
+        assert_eq!(
+            denied_file.to_string(),
+            "Unable to read the file \"programs/hello.bf\" due lack of permission"
+        );
+        assert_eq!(
+            denied_directory.to_string(),
+            "Unable to read the file \"programs/hello.bf\" due lack of permission, the directory \"programs\" can't be traversed"
+        )
+    }
+
+    #[test]
+    fn when_a_file_is_readable_then_it_is_not_denied_by_any_directory() {
+        assert_eq!(
+            denied_by(file_test_case!("file_exists.txt")),
+            DeniedBy::File
+        )
+    }
+
+    fn gzip(content: &[u8]) -> Vec<u8> {
+        use flate2::{Compression, write::GzEncoder};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn when_the_source_is_gzipped_then_it_is_decompressed() {
+        let compressed = gzip(b"+[-].");
+
+        assert_eq!(
+            read_source(STDIN_PATH, compressed.as_slice(), false, 5),
+            Ok("+[-].".to_string())
+        )
+    }
+
+    #[test]
+    fn when_the_decompressed_source_passes_the_limit_then_return_file_too_large_error() {
+        let compressed = gzip(&[b'+'; 4096]);
+
+        assert!(compressed.len() < 64);
+        assert_eq!(
+            read_source(STDIN_PATH, compressed.as_slice(), false, 64),
+            Err(FileError::FileTooLarge {
+                path: STDIN_PATH.to_string(),
+                size: 65,
+                limit: 64,
+            })
+        )
+    }
+
+    #[test]
+    fn when_the_gzip_stream_is_corrupted_then_return_decompression_failed_error() {
+        let mut compressed = gzip(b"+[-].");
+        compressed.truncate(compressed.len() / 2);
+
+        let file_error = read_source(STDIN_PATH, compressed.as_slice(), false, 64).unwrap_err();
+
+        assert!(matches!(
+            file_error,
+            FileError::DecompressionFailed { ref path, .. } if path == STDIN_PATH
+        ))
+    }
+
     #[test]
     fn when_path_point_a_file_that_dont_exist_then_return_file_not_found_error() {
         let parent_path = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test");
         let path = file_test_case!("not_exists.txt");
 
-        let file_error = read_source_code_file(&path).unwrap_err();
+        let file_error = read_source_code_file(path, DEFAULT_MAX_SOURCE_SIZE).unwrap_err();
 
         assert_eq!(
             file_error,
@@ -124,7 +1070,7 @@ mod read_file {
         let parent_path = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test/");
         let path = file_test_case!("");
 
-        let file_error = read_source_code_file(&path).unwrap_err();
+        let file_error = read_source_code_file(path, DEFAULT_MAX_SOURCE_SIZE).unwrap_err();
 
         assert_eq!(
             file_error,
@@ -143,7 +1089,7 @@ mod read_file {
         );
         let path = file_test_case!("file_exists.txt/..");
 
-        let file_error = read_source_code_file(&path).unwrap_err();
+        let file_error = read_source_code_file(path, DEFAULT_MAX_SOURCE_SIZE).unwrap_err();
 
         assert_eq!(
             file_error,
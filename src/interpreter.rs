@@ -1,19 +1,132 @@
-use core::ascii;
+use std::{io::Write, marker::PhantomData, ops::Deref, sync::Arc};
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
-    io::{InputValue, MemoryErrors, MemoryTape, OutputValue, ProgramValue},
+    io::{
+        BrainfuckMemory, InputContext, InputError, InputValue, MAX_MEMORY_SIZE, MemoryCell,
+        MemoryErrors, MemoryTape, NullOutput, OutputError, OutputValue, ProgramValue, QueuedInput,
+        RawFormatter, WriterOutput,
+    },
     parser::{BrainfuckNodeAST, BrainfuckOperations},
 };
 
+/**
+ * What the interpreter writes in the current cell when the input source reports
+ * that there is no more input. There isn't a standard between brainfuck
+ * implementations, so each program can pick the one it expects.
+ */
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EofBehavior {
+    #[default]
+    SetZero,
+    SetMax,
+    Unchanged,
+}
+
+/**
+ * What an increment of the highest value of a cell, or a decrement of the lowest
+ * one, does. Most programs expect the cells to wrap around, as the bytes do in the
+ * usual implementations.
+ */
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    #[default]
+    Wrap,
+    Saturate,
+    Error,
+}
+
+impl OverflowPolicy {
+    fn increment<Cell: MemoryCell>(
+        self,
+        value: Cell,
+        position: isize,
+    ) -> Result<Cell, MemoryErrors> {
+        match self {
+            OverflowPolicy::Wrap => Ok(value.wrapping_add(Cell::ONE)),
+            OverflowPolicy::Saturate => Ok(value.saturating_add(Cell::ONE)),
+            OverflowPolicy::Error => value
+                .checked_add(Cell::ONE)
+                .ok_or(MemoryErrors::CellOverflow { position, delta: 1 }),
+        }
+    }
+
+    fn decrement<Cell: MemoryCell>(
+        self,
+        value: Cell,
+        position: isize,
+    ) -> Result<Cell, MemoryErrors> {
+        match self {
+            OverflowPolicy::Wrap => Ok(value.wrapping_sub(Cell::ONE)),
+            OverflowPolicy::Saturate => Ok(value.saturating_sub(Cell::ONE)),
+            OverflowPolicy::Error => value
+                .checked_sub(Cell::ONE)
+                .ok_or(MemoryErrors::CellUnderflow { position, delta: 1 }),
+        }
+    }
+}
+
+impl std::fmt::Display for OverflowPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            OverflowPolicy::Wrap => "wrap",
+            OverflowPolicy::Saturate => "saturate",
+            OverflowPolicy::Error => "error",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/**
+ * The settings of a run. Serialized, the fields that aren't known are refused
+ * instead of ignored, so a typo doesn't fall back to the default.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct InterpreterConfig {
-    number_of_reads: usize,
+    number_of_reads: u64,
+    eof_behavior: EofBehavior,
+    overflow_policy: OverflowPolicy,
+    flush_every: Option<usize>,
 }
 
 impl InterpreterConfig {
-    pub fn new(number_of_reads: usize) -> Self {
-        InterpreterConfig { number_of_reads }
+    /**
+     * The number of reads is the limit of instructions executed before considering
+     * the program stuck in an infinite loop. With 0 the program runs without limit.
+     */
+    pub fn new(number_of_reads: u64) -> Self {
+        InterpreterConfig {
+            number_of_reads,
+            eof_behavior: EofBehavior::default(),
+            overflow_policy: OverflowPolicy::default(),
+            flush_every: None,
+        }
+    }
+
+    pub fn with_eof_behavior(mut self, eof_behavior: EofBehavior) -> Self {
+        self.eof_behavior = eof_behavior;
+        self
+    }
+
+    pub fn with_overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+
+    /**
+     * By default the output is only flushed before reading an input and when the
+     * program ends. Programs that print a lot before finishing can flush every
+     * N bytes to show the progress.
+     */
+    pub fn with_flush_every(mut self, number_of_bytes: usize) -> Self {
+        self.flush_every = Some(number_of_bytes);
+        self
     }
 }
 
@@ -21,15 +134,182 @@ impl Default for InterpreterConfig {
     fn default() -> Self {
         InterpreterConfig {
             number_of_reads: 60000,
+            eof_behavior: EofBehavior::default(),
+            overflow_policy: OverflowPolicy::default(),
+            flush_every: None,
+        }
+    }
+}
+
+/**
+ * Figures collected while the program runs. They are kept when the run fails, so
+ * the caller can tell how far the program went.
+ */
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub instructions_executed: u64,
+    pub bytes_written: usize,
+    pub bytes_read: usize,
+    pub inputs_requested: usize,
+    pub max_memory_position: usize,
+    pub cells_touched: usize,
+    pub last_instruction: Option<usize>,
+    /**
+     * The value of the cell of the pointer when the program ends, or None when the
+     * pointer is outside of the memory.
+     */
+    pub final_cell: Option<i64>,
+    pub memory: Option<MemoryStats>,
+    // Always written, as the binary formats can't tell a skipped field, but the
+    // reports written before it existed still read.
+    #[serde(default)]
+    pub busiest_loop: Option<BusiestLoop>,
+}
+
+/**
+ * The loop with the most iterations when the limit of instructions was reached,
+ * with the instructions executed inside of it, its nested loops included. They are
+ * only counted since the program went over half of the limit.
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BusiestLoop {
+    pub node: usize,
+    pub iterations: u64,
+    pub instructions: u64,
+    pub instructions_counted: u64,
+}
+
+/**
+ * The iterations and the instructions of every loop, by the position of its start
+ * in the AST. It is only built once the program gets close to the limit, so the
+ * programs that finish don't pay for it.
+ */
+struct LoopCounters {
+    iterations: Vec<u64>,
+    instructions: Vec<u64>,
+    instructions_counted: u64,
+}
+
+impl LoopCounters {
+    fn new(nodes: usize) -> Self {
+        LoopCounters {
+            iterations: vec![0; nodes],
+            instructions: vec![0; nodes],
+            instructions_counted: 0,
+        }
+    }
+
+    fn count_instruction(&mut self, running_loops: &[usize]) {
+        self.instructions_counted += 1;
+
+        for node in running_loops {
+            self.instructions[*node] += 1;
+        }
+    }
+
+    fn busiest_loop(&self) -> Option<BusiestLoop> {
+        self.iterations
+            .iter()
+            .enumerate()
+            .filter(|(_, iterations)| **iterations > 0)
+            // The first loop wins the ties.
+            .rev()
+            .max_by_key(|(_, iterations)| **iterations)
+            .map(|(node, iterations)| BusiestLoop {
+                node,
+                iterations: *iterations,
+                instructions: self.instructions[node],
+                instructions_counted: self.instructions_counted,
+            })
+    }
+}
+
+/**
+ * Figures of the cells when the run ends. Collecting them scans the memory, so the
+ * interpreter doesn't do it and the caller asks for them only when they are shown.
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MemoryStats {
+    pub nonzero_cells: usize,
+    pub min_value: i64,
+    pub max_value: i64,
+}
+
+impl MemoryStats {
+    /**
+     * The range of the values counts the 0 of the touched cells that aren't written.
+     * The cells that still have the value they had before the run, like the ones of
+     * --init-value, are in the range but weren't changed by the program, so they
+     * aren't counted as non-zero.
+     */
+    pub fn collect<Cell: MemoryCell>(memory: &impl MemoryTape<Cell>) -> Self {
+        let (stored_cells, nonzero_cells, min_value, max_value) = memory.iter_nonzero().fold(
+            (0, 0, i64::MAX, i64::MIN),
+            |(stored, changed, min_value, max_value), (position, value)| {
+                let is_changed = value != memory.initial_value(position);
+                let value = value.to_i64();
+                (
+                    stored + 1,
+                    changed + usize::from(is_changed),
+                    min_value.min(value),
+                    max_value.max(value),
+                )
+            },
+        );
+
+        if stored_cells < memory.cells_touched() || stored_cells == 0 {
+            MemoryStats {
+                nonzero_cells,
+                min_value: min_value.min(0),
+                max_value: max_value.max(0),
+            }
+        } else {
+            MemoryStats {
+                nonzero_cells,
+                min_value,
+                max_value,
+            }
         }
     }
 }
 
-pub struct Interpreter<'a, Display, Input, Memory>
+/**
+ * Follows the execution of the program, like the traces do. The nodes are the
+ * positions in the AST. A loop starts when the program reaches it and finishes
+ * when its cell is 0, after all its iterations.
+ */
+pub trait ExecutionTracer {
+    fn loop_started(&mut self, node: usize);
+    fn loop_finished(&mut self, node: usize);
+    fn output(&mut self, node: usize, value: u8);
+    /**
+     * The value is None at the end of the input.
+     */
+    fn input(&mut self, node: usize, value: Option<u8>);
+    /**
+     * Called before the node runs, with the instructions executed counting it.
+     */
+    fn instruction(&mut self, node: usize, instructions_executed: u64, pointer: isize);
+}
+
+/**
+ * The cells of the memory are bytes by default. With wider cells the numeric
+ * outputs print the whole value and the others only its lowest byte.
+ *
+ * The interpreter is Send when its parts are, so it can run on another thread
+ * with an owned program, loaded with load_owned_ast_program, and a tracer that is
+ * Send too. The outputs and the memories of io are, and so are the inputs but
+ * BasicInput, whose inquire prompt only works on the thread of the terminal.
+ * A WriterOutput or a ReaderInput is Send when its writer or reader is, which
+ * isn't the case of the locked stdin and stdout, nor of the boxed outputs the CLI
+ * builds, as the box doesn't require it.
+ */
+pub struct Interpreter<'a, Display, Input, Memory, Cell = u8>
 where
-    Memory: MemoryTape<u8>,
+    Memory: MemoryTape<Cell>,
     Display: OutputValue,
     Input: InputValue,
+    Cell: MemoryCell,
 {
     pub memory: Memory,
     pub ast_program: Option<ProgramAST<'a>>,
@@ -37,6 +317,9 @@ where
     pub display: Display,
     pub input: Input,
     pub config: InterpreterConfig,
+    pub summary: RunSummary,
+    tracer: Option<&'a mut (dyn ExecutionTracer + Send)>,
+    cell: PhantomData<Cell>,
 }
 
 #[derive(Error, Debug, PartialEq)]
@@ -45,23 +328,55 @@ pub enum InterpreterErrors {
     EmptyAST,
     #[error("The interpreter can't understand the AST node ${node:?}")]
     UnknownASTNode { node: BrainfuckNodeAST },
-    #[error("The program is trying to access to position out of range in the memory")]
-    OutOfRangeMemoryAccess,
-    #[error(
-        "Unexpected value in the memory cell. The value that panicked the program is {value:?}"
-    )]
-    InvalidValidU8Value { value: u8 },
+    #[error(transparent)]
+    Memory(#[from] MemoryErrors),
     #[error(
         "Not enought reads to complete the program. Check if the program have infinite loops or increased the amount of reads"
     )]
     UnableToCompleteTheProgram,
+    #[error("The input source failed to provide a value")]
+    InputFailed,
+    #[error("The program diverged from the recorded input. {0}")]
+    ReplayDiverged(InputError),
+    #[error(transparent)]
+    OutputFailed(OutputError),
+    #[error("The program output reached the limit of {limit} bytes")]
+    OutputLimitReached { limit: u64 },
+    #[error("The program output was closed by the reader")]
+    OutputClosed,
+    #[error("The run was interrupted while it was waiting for the input")]
+    Interrupted,
+}
+
+impl InterpreterErrors {
+    /**
+     * The errors caused by what the program does, and not by the input or the
+     * output, where the state of the memory helps to find the cause.
+     */
+    pub fn is_program_fault(&self) -> bool {
+        matches!(
+            self,
+            InterpreterErrors::Memory(_) | InterpreterErrors::UnableToCompleteTheProgram
+        )
+    }
+}
+
+impl From<OutputError> for InterpreterErrors {
+    fn from(error: OutputError) -> Self {
+        match error {
+            OutputError::LimitReached { limit } => InterpreterErrors::OutputLimitReached { limit },
+            OutputError::Closed => InterpreterErrors::OutputClosed,
+            error => InterpreterErrors::OutputFailed(error),
+        }
+    }
 }
 
-impl<'a, Display, Input, Memory> Interpreter<'a, Display, Input, Memory>
+impl<'a, Display, Input, Memory, Cell> Interpreter<'a, Display, Input, Memory, Cell>
 where
-    Memory: MemoryTape<u8>,
+    Memory: MemoryTape<Cell>,
     Display: OutputValue,
     Input: InputValue,
+    Cell: MemoryCell,
 {
     pub fn new(display: Display, input: Input, memory: Memory, config: InterpreterConfig) -> Self {
         Interpreter {
@@ -71,6 +386,9 @@ where
             display,
             input,
             config,
+            summary: RunSummary::default(),
+            tracer: None,
+            cell: PhantomData,
         }
     }
 
@@ -78,96 +396,221 @@ where
         self.ast_program = Some(ProgramAST::new(ast_program, self.config.number_of_reads));
     }
 
+    /**
+     * Loads a program the interpreter keeps, so it doesn't borrow the stack of the
+     * thread that parsed it. The Arc lets several interpreters share the program.
+     */
+    pub fn load_owned_ast_program(&mut self, ast_program: impl Into<Arc<[BrainfuckNodeAST]>>) {
+        self.ast_program = Some(ProgramAST::new(
+            ProgramNodes::Owned(ast_program.into()),
+            self.config.number_of_reads,
+        ));
+    }
+
     pub fn set_interpreter_config(&mut self, new_config: InterpreterConfig) {
         self.config = new_config;
     }
 
+    pub fn set_tracer(&mut self, tracer: &'a mut (dyn ExecutionTracer + Send)) {
+        self.tracer = Some(tracer);
+    }
+
     pub fn run(&mut self) -> Result<(), InterpreterErrors> {
+        self.run_with_output(None)
+    }
+
+    /**
+     * Runs the program with its output going to the writer instead of the display,
+     * as it is, byte by byte. A write that fails aborts the run with OutputFailed.
+     */
+    pub fn run_to_writer(&mut self, writer: &mut impl Write) -> Result<(), InterpreterErrors> {
+        let output = WriterOutput::new(writer, RawFormatter);
+
+        self.run_with_output(Some(&output))
+    }
+
+    fn run_with_output(
+        &mut self,
+        output: Option<&dyn OutputValue>,
+    ) -> Result<(), InterpreterErrors> {
+        let result = self.run_program(output);
+        self.summary.max_memory_position =
+            usize::try_from(self.memory.max_position_reached()).unwrap_or(0);
+        self.summary.cells_touched = self.memory.cells_touched();
+        self.summary.final_cell = self
+            .memory
+            .get_cell_at(self.memory.get_logical_position())
+            .map(MemoryCell::to_i64);
+        let flushed = output.unwrap_or(&self.display).finish();
+
+        result.and(flushed.map_err(InterpreterErrors::from))
+    }
+
+    fn run_program(&mut self, output: Option<&dyn OutputValue>) -> Result<(), InterpreterErrors> {
+        self.summary = RunSummary::default();
         let mut ast = match &self.ast_program {
             Some(ast) if ast.is_empty() => {
                 return Err(InterpreterErrors::EmptyAST);
             }
-            Some(ast) => ast.into_iter(),
+            Some(ast) => ast.clone(),
             None => {
                 return Err(InterpreterErrors::EmptyAST);
             }
         };
 
+        let mut last_output = None;
+        // The loops running, to tell the first test of a loop from the ones after
+        // each iteration.
+        let mut running_loops: Vec<usize> = vec![];
+        let mut loop_counters: Option<LoopCounters> = None;
+        let counters_from = self.config.number_of_reads / 2;
+
         while let Some(node) = ast.next() {
+            self.summary.instructions_executed += 1;
+            self.summary.last_instruction = ast.last_read();
+            let node_index = self.summary.last_instruction.unwrap_or_default();
+
+            // The loops that were already running are counted from their next test.
+            if counters_from > 0
+                && self.summary.instructions_executed > counters_from
+                && loop_counters.is_none()
+            {
+                loop_counters = Some(LoopCounters::new(ast.ast.len()));
+            }
+
+            if let Some(loop_counters) = loop_counters.as_mut() {
+                loop_counters.count_instruction(&running_loops);
+            }
+
+            if let Some(tracer) = self.tracer.as_deref_mut() {
+                tracer.instruction(
+                    node_index,
+                    self.summary.instructions_executed,
+                    self.memory.get_logical_position(),
+                );
+            }
+
+            if self.memory.has_watches() {
+                self.memory
+                    .set_current_instruction(self.summary.last_instruction);
+            }
+
             match node {
                 BrainfuckNodeAST::Command(command)
                     if command.operation == BrainfuckOperations::IncrementByOneCurrentCell =>
                 {
-                    let _ = self.memory.update_memory_cell_value(|value| {
-                        value
-                            .checked_add(1)
-                            .map_or_else(|| Err(MemoryErrors::CellOverflow), Ok)
-                    });
+                    let position = self.memory.get_logical_position();
+                    let overflow_policy = self.config.overflow_policy;
+                    self.memory.update_memory_cell_value(|value| {
+                        overflow_policy.increment(value, position)
+                    })?;
+
                     self.program_counter = Some(BrainfuckOperations::IncrementByOneCurrentCell)
                 }
                 BrainfuckNodeAST::Command(command)
                     if command.operation == BrainfuckOperations::DecrementByOneCurrentCell =>
                 {
-                    let _ = self.memory.update_memory_cell_value(|value| {
-                        value
-                            .checked_sub(1)
-                            .map_or_else(|| Err(MemoryErrors::CellUnderflow), Ok)
-                    });
+                    let position = self.memory.get_logical_position();
+                    let overflow_policy = self.config.overflow_policy;
+                    self.memory.update_memory_cell_value(|value| {
+                        overflow_policy.decrement(value, position)
+                    })?;
+
                     self.program_counter = Some(BrainfuckOperations::DecrementByOneCurrentCell)
                 }
                 BrainfuckNodeAST::Command(command)
                     if command.operation == BrainfuckOperations::MovePointerRight =>
                 {
-                    let result_move = self.memory.move_pointer_position(1);
-
-                    if result_move.is_err() {
-                        return Err(InterpreterErrors::OutOfRangeMemoryAccess);
-                    }
+                    self.memory.move_pointer_position(1)?;
 
                     self.program_counter = Some(BrainfuckOperations::MovePointerRight)
                 }
                 BrainfuckNodeAST::Command(command)
                     if command.operation == BrainfuckOperations::MovePointerLeft =>
                 {
-                    let result_move = self.memory.move_pointer_position(-1);
-
-                    if result_move.is_err() {
-                        return Err(InterpreterErrors::OutOfRangeMemoryAccess);
-                    }
+                    self.memory.move_pointer_position(-1)?;
 
                     self.program_counter = Some(BrainfuckOperations::MovePointerLeft)
                 }
                 BrainfuckNodeAST::Command(command)
                     if command.operation == BrainfuckOperations::OutputCommand =>
                 {
-                    match ascii::Char::from_u8(self.memory.get_current_cell_value()) {
-                        Some(character) => {
-                            self.display.print(ProgramValue::new(character.to_char()))
-                        }
-                        None => {
-                            return Err(InterpreterErrors::InvalidValidU8Value {
-                                value: self.memory.get_current_cell_value(),
-                            });
-                        }
+                    let cell_value = self.memory.get_current_cell_value().to_i64();
+                    let value = cell_value as u8;
+                    output.unwrap_or(&self.display).print_cell(cell_value)?;
+                    self.summary.bytes_written += 1;
+                    last_output = Some(value);
+
+                    if let Some(tracer) = self.tracer.as_deref_mut() {
+                        tracer.output(node_index, value);
+                    }
+
+                    if let Some(flush_every) = self.config.flush_every
+                        && self.summary.bytes_written.is_multiple_of(flush_every)
+                    {
+                        output.unwrap_or(&self.display).flush()?;
                     }
+
                     self.program_counter = Some(BrainfuckOperations::OutputCommand)
                 }
                 BrainfuckNodeAST::Command(command)
                     if command.operation == BrainfuckOperations::InputCommand =>
                 {
-                    let input_value = self.input.get_input();
+                    output.unwrap_or(&self.display).flush()?;
+
+                    let input_value = self.input.get_input_with_context(&InputContext {
+                        cell_index: self.memory.get_logical_position(),
+                        cell_value: self.memory.get_current_cell_value().to_i64(),
+                        reads_so_far: self.summary.inputs_requested,
+                        last_output,
+                    });
+                    self.summary.inputs_requested += 1;
+
+                    if let Some(tracer) = self.tracer.as_deref_mut() {
+                        let value = match &input_value {
+                            Ok(ProgramValue(value)) => Some(*value),
+                            Err(_) => None,
+                        };
+                        tracer.input(node_index, value);
+                    }
 
                     match input_value {
-                        Ok(value) => {
-                            let _ = self
-                                .memory
-                                .update_memory_cell_value(|_value| Ok(value.into()));
+                        Ok(ProgramValue(value)) => {
+                            self.summary.bytes_read += 1;
+                            self.memory.update_memory_cell_value(|_value| {
+                                Ok(Cell::from_i64(value.into()))
+                            })?;
                         }
-                        Err(_) => {
-                            panic!("The input implementation should never fail")
+                        Err(InputError::EndOfInput) => match self.config.eof_behavior {
+                            EofBehavior::SetZero => {
+                                self.memory
+                                    .update_memory_cell_value(|_value| Ok(Cell::ZERO))?;
+                            }
+                            EofBehavior::SetMax => {
+                                self.memory
+                                    .update_memory_cell_value(|_value| Ok(Cell::MAX))?;
+                            }
+                            EofBehavior::Unchanged => (),
+                        },
+                        Err(InputError::Unknown) => return Err(InterpreterErrors::InputFailed),
+                        Err(InputError::Interrupted) => return Err(InterpreterErrors::Interrupted),
+                        Err(error @ InputError::ReplayDiverged { .. }) => {
+                            return Err(InterpreterErrors::ReplayDiverged(error));
                         }
                     }
                 }
+                BrainfuckNodeAST::Command(command)
+                    if command.operation == BrainfuckOperations::SwitchTapeNext =>
+                {
+                    self.memory.switch_tape(1);
+                    self.program_counter = Some(BrainfuckOperations::SwitchTapeNext)
+                }
+                BrainfuckNodeAST::Command(command)
+                    if command.operation == BrainfuckOperations::SwitchTapePrev =>
+                {
+                    self.memory.switch_tape(-1);
+                    self.program_counter = Some(BrainfuckOperations::SwitchTapePrev)
+                }
                 BrainfuckNodeAST::Command(command)
                     if command.operation == BrainfuckOperations::LoopEnd =>
                 {
@@ -176,17 +619,40 @@ where
                 BrainfuckNodeAST::Loop(loop_node)
                     if loop_node.operation == BrainfuckOperations::LoopStart =>
                 {
-                    if self.memory.get_current_cell_value() > 0 {
+                    let enters = self.memory.get_current_cell_value() != Cell::ZERO;
+
+                    if self.tracer.is_some() || loop_counters.is_some() {
+                        if running_loops.last() != Some(&node_index) {
+                            running_loops.push(node_index);
+                            if let Some(tracer) = self.tracer.as_deref_mut() {
+                                tracer.loop_started(node_index);
+                            }
+                        }
+
+                        if !enters {
+                            running_loops.pop();
+                            if let Some(tracer) = self.tracer.as_deref_mut() {
+                                tracer.loop_finished(node_index);
+                            }
+                        }
+                    }
+
+                    if enters && let Some(loop_counters) = loop_counters.as_mut() {
+                        loop_counters.iterations[node_index] += 1;
+                    }
+
+                    if enters {
                         continue;
                     }
 
                     ast.jump_to_node(loop_node.next_position_as_false);
                 }
-                _ => return Err(InterpreterErrors::UnknownASTNode { node: *node }),
+                _ => return Err(InterpreterErrors::UnknownASTNode { node }),
             }
         }
 
         if ast.program_run_out_of_reads() {
+            self.summary.busiest_loop = loop_counters.and_then(|counters| counters.busiest_loop());
             return Err(InterpreterErrors::UnableToCompleteTheProgram);
         }
 
@@ -194,49 +660,250 @@ where
     }
 }
 
+#[derive(Error, Debug, PartialEq)]
+pub enum BuilderErrors {
+    #[error("The interpreter needs a program, give it with program")]
+    MissingProgram,
+    #[error("The memory needs at least one cell")]
+    EmptyMemory,
+    #[error("The memory of {size} cells is bigger than the maximum of {max} cells")]
+    MemoryTooBig { size: usize, max: usize },
+    #[error("The size of the memory can't be given for a memory already built")]
+    MemorySizeWithCustomMemory,
+}
+
+/**
+ * Builds an interpreter from the parts that aren't the default: without them it
+ * runs the program on 3000 byte cells, reading nothing and dropping the output.
+ * The memory is built with the size given, unless a memory is given instead.
+ */
+pub struct InterpreterBuilder<
+    'a,
+    Display = NullOutput,
+    Input = QueuedInput,
+    Memory = BrainfuckMemory<u8>,
+    Cell = u8,
+> {
+    display: Display,
+    input: Input,
+    memory: Option<Memory>,
+    memory_size: Option<usize>,
+    config: InterpreterConfig,
+    program: Option<ProgramNodes<'a>>,
+    cell: PhantomData<Cell>,
+}
+
+/**
+ * Cells of the memory built when the builder isn't given a size or a memory.
+ */
+pub const DEFAULT_MEMORY_SIZE: usize = 3000;
+
+impl<'a> InterpreterBuilder<'a> {
+    pub fn new() -> Self {
+        InterpreterBuilder {
+            display: NullOutput,
+            input: QueuedInput::default(),
+            memory: None,
+            memory_size: None,
+            config: InterpreterConfig::default(),
+            program: None,
+            cell: PhantomData,
+        }
+    }
+}
+
+impl Default for InterpreterBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, Display, Input, Memory, Cell> InterpreterBuilder<'a, Display, Input, Memory, Cell> {
+    pub fn memory_size(mut self, memory_size: usize) -> Self {
+        self.memory_size = Some(memory_size);
+        self
+    }
+
+    pub fn memory<NewMemory: MemoryTape<NewCell>, NewCell: MemoryCell>(
+        self,
+        memory: NewMemory,
+    ) -> InterpreterBuilder<'a, Display, Input, NewMemory, NewCell> {
+        InterpreterBuilder {
+            display: self.display,
+            input: self.input,
+            memory: Some(memory),
+            memory_size: self.memory_size,
+            config: self.config,
+            program: self.program,
+            cell: PhantomData,
+        }
+    }
+
+    pub fn input<NewInput: InputValue>(
+        self,
+        input: NewInput,
+    ) -> InterpreterBuilder<'a, Display, NewInput, Memory, Cell> {
+        InterpreterBuilder {
+            display: self.display,
+            input,
+            memory: self.memory,
+            memory_size: self.memory_size,
+            config: self.config,
+            program: self.program,
+            cell: PhantomData,
+        }
+    }
+
+    pub fn output<NewDisplay: OutputValue>(
+        self,
+        display: NewDisplay,
+    ) -> InterpreterBuilder<'a, NewDisplay, Input, Memory, Cell> {
+        InterpreterBuilder {
+            display,
+            input: self.input,
+            memory: self.memory,
+            memory_size: self.memory_size,
+            config: self.config,
+            program: self.program,
+            cell: PhantomData,
+        }
+    }
+
+    pub fn config(mut self, config: InterpreterConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn program(mut self, program: &'a [BrainfuckNodeAST]) -> Self {
+        self.program = Some(ProgramNodes::Borrowed(program));
+        self
+    }
+
+    /**
+     * The program kept by the interpreter, for the ones that run on other threads.
+     */
+    pub fn owned_program(mut self, program: impl Into<Arc<[BrainfuckNodeAST]>>) -> Self {
+        self.program = Some(ProgramNodes::Owned(program.into()));
+        self
+    }
+}
+
+impl<'a, Display, Input, Memory, Cell> InterpreterBuilder<'a, Display, Input, Memory, Cell>
+where
+    Display: OutputValue,
+    Input: InputValue,
+    Memory: MemoryTape<Cell>,
+    Cell: MemoryCell,
+{
+    /**
+     * The interpreter with the program loaded, ready to run.
+     */
+    pub fn build(self) -> Result<Interpreter<'a, Display, Input, Memory, Cell>, BuilderErrors> {
+        // An empty program is built, its run fails with EmptyAST like without the
+        // builder.
+        let Some(program) = self.program else {
+            return Err(BuilderErrors::MissingProgram);
+        };
+        let memory = match (self.memory, self.memory_size) {
+            (Some(_), Some(_)) => return Err(BuilderErrors::MemorySizeWithCustomMemory),
+            (Some(memory), None) => memory,
+            (None, Some(0)) => return Err(BuilderErrors::EmptyMemory),
+            (None, Some(size)) if size > MAX_MEMORY_SIZE => {
+                return Err(BuilderErrors::MemoryTooBig {
+                    size,
+                    max: MAX_MEMORY_SIZE,
+                });
+            }
+            (None, size) => Memory::new(size.unwrap_or(DEFAULT_MEMORY_SIZE)),
+        };
+        let mut interpreter = Interpreter::new(self.display, self.input, memory, self.config);
+
+        interpreter.ast_program =
+            Some(ProgramAST::new(program, interpreter.config.number_of_reads));
+        Ok(interpreter)
+    }
+}
+
 #[cfg(test)]
 #[derive(Debug, PartialEq)]
 struct DebugMemoryPosition {
-    position: usize,
-    raw_value: u8,
+    position: isize,
+    raw_value: i64,
     ascii_value: Option<char>,
 }
 
 #[cfg(test)]
-impl<'a, Display, Input, Memory> Interpreter<'a, Display, Input, Memory>
+impl<'a, Display, Input, Memory, Cell> Interpreter<'a, Display, Input, Memory, Cell>
 where
     Display: OutputValue,
     Input: InputValue,
-    Memory: MemoryTape<u8>,
+    Memory: MemoryTape<Cell>,
+    Cell: MemoryCell,
 {
     fn get_debug_info_current_position(&self) -> DebugMemoryPosition {
+        let raw_value = self.memory.get_current_cell_value().to_i64();
+
         DebugMemoryPosition {
-            position: self.memory.get_position(),
-            raw_value: self.memory.get_current_cell_value(),
-            ascii_value: ascii::Char::from_u8(self.memory.get_current_cell_value())
-                .map(|charecter| charecter.to_char()),
+            position: self.memory.get_logical_position(),
+            raw_value,
+            ascii_value: u8::try_from(raw_value)
+                .ok()
+                .filter(u8::is_ascii)
+                .map(char::from),
         }
     }
 }
 
 struct VecAST {
     vec: Vec<BrainfuckNodeAST>,
-    number_of_reads: usize,
+    number_of_reads: u64,
+}
+
+/**
+ * The nodes of the program, borrowed from the caller or kept by the interpreter.
+ */
+#[derive(Debug, Clone)]
+pub enum ProgramNodes<'a> {
+    Borrowed(&'a [BrainfuckNodeAST]),
+    Owned(Arc<[BrainfuckNodeAST]>),
 }
 
-#[derive(Clone, Copy)]
+impl Deref for ProgramNodes<'_> {
+    type Target = [BrainfuckNodeAST];
+
+    fn deref(&self) -> &[BrainfuckNodeAST] {
+        match self {
+            ProgramNodes::Borrowed(nodes) => nodes,
+            ProgramNodes::Owned(nodes) => nodes,
+        }
+    }
+}
+
+impl<'a> From<&'a Vec<BrainfuckNodeAST>> for ProgramNodes<'a> {
+    fn from(nodes: &'a Vec<BrainfuckNodeAST>) -> Self {
+        ProgramNodes::Borrowed(nodes)
+    }
+}
+
+#[derive(Clone)]
 pub struct ProgramAST<'a> {
     current: usize,
-    number_of_reads: usize,
-    ast: &'a Vec<BrainfuckNodeAST>,
+    last_read: Option<usize>,
+    // The reads left, without limit when it is None.
+    number_of_reads: Option<u64>,
+    ast: ProgramNodes<'a>,
 }
 
 impl<'a> ProgramAST<'a> {
-    pub fn new(ast: &'a Vec<BrainfuckNodeAST>, number_of_reads: usize) -> Self {
+    /**
+     * With 0 reads the program runs without limit.
+     */
+    pub fn new(ast: impl Into<ProgramNodes<'a>>, number_of_reads: u64) -> Self {
         Self {
             current: 0,
-            ast,
-            number_of_reads,
+            last_read: None,
+            ast: ast.into(),
+            number_of_reads: (number_of_reads > 0).then_some(number_of_reads),
         }
     }
 
@@ -248,33 +915,43 @@ impl<'a> ProgramAST<'a> {
         }
     }
 
-    pub fn is_empty(self) -> bool {
+    pub fn is_empty(&self) -> bool {
         self.ast.is_empty()
     }
 
-    pub fn is_program_completed(self) -> bool {
+    pub fn is_program_completed(&self) -> bool {
         self.current >= self.ast.len()
     }
 
-    pub fn program_run_out_of_reads(self) -> bool {
-        self.number_of_reads == 0 && self.current < self.ast.len()
+    pub fn program_run_out_of_reads(&self) -> bool {
+        self.number_of_reads == Some(0) && self.current < self.ast.len()
+    }
+
+    /**
+     * Position in the AST of the last node returned by the iterator.
+     */
+    pub fn last_read(&self) -> Option<usize> {
+        self.last_read
     }
 }
 
-impl<'a> Iterator for ProgramAST<'a> {
-    type Item = &'a BrainfuckNodeAST;
+impl Iterator for ProgramAST<'_> {
+    type Item = BrainfuckNodeAST;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.number_of_reads == 0 {
+        if self.number_of_reads == Some(0) {
             return None;
         }
 
         if self.current < self.ast.len() {
             let index = self.current;
             self.current += 1;
-            self.number_of_reads -= 1;
+            self.last_read = Some(index);
+            if let Some(number_of_reads) = &mut self.number_of_reads {
+                *number_of_reads -= 1;
+            }
 
-            self.ast.get(index)
+            self.ast.get(index).copied()
         } else {
             None
         }
@@ -282,51 +959,85 @@ impl<'a> Iterator for ProgramAST<'a> {
 }
 
 impl<'a> IntoIterator for &'a VecAST {
-    type Item = &'a BrainfuckNodeAST;
+    type Item = BrainfuckNodeAST;
     type IntoIter = ProgramAST<'a>;
 
     fn into_iter(self) -> ProgramAST<'a> {
-        ProgramAST {
-            current: 0,
-            ast: &self.vec,
-            number_of_reads: self.number_of_reads,
-        }
+        ProgramAST::new(&self.vec, self.number_of_reads)
     }
 }
 
 #[cfg(test)]
 mod interpreter_test {
-    use std::convert::Infallible;
+    use std::cell::RefCell;
+    use std::io;
     use std::iter::repeat_n;
-
-    use crate::io::BrainfuckMemory;
-    use crate::parser::{BrainfuckASTBuilder, CommandInformation};
+    use std::sync::mpsc::{Receiver, Sender, channel};
+    use std::thread;
+
+    use crate::io::testing::{
+        ClosedWriter, CollectingOutput, FailingInput, FailingOutput, FailingWriter, NullOutput,
+        PanicInput, QueuedInput,
+    };
+    use crate::io::{
+        BidirectionalMemory, BoundsPolicy, BrainfuckMemory, LimitedWriter, MultiTape, RawFormatter,
+        ReaderInput, Recording, ReplayInput, SharedWriter, TeeOutput, Utf8Formatter, WriterOutput,
+    };
+    use crate::parser::{
+        BrainfuckASTBuilder, CommandInformation, Dialect, from_source_to_node_ast,
+        from_source_to_node_ast_in_dialect,
+    };
 
     use super::*;
 
-    #[derive(Debug, Copy, Clone)]
-    struct NoRender;
+    /**
+     * Harness to run the interpreter in another thread. The test receives the events in
+     * the same order the interpreter produces them, and answers the input requests.
+     */
+    #[derive(Debug, PartialEq)]
+    enum HarnessEvent {
+        Output(Vec<u8>),
+        InputRequested,
+    }
+
+    struct ChannelOutput {
+        buffer: RefCell<Vec<u8>>,
+        events: Sender<HarnessEvent>,
+    }
+
+    impl OutputValue for ChannelOutput {
+        fn print(&self, value: u8) -> Result<(), OutputError> {
+            self.buffer.borrow_mut().push(value);
+            Ok(())
+        }
+
+        fn flush(&self) -> Result<(), OutputError> {
+            let bytes: Vec<u8> = self.buffer.borrow_mut().drain(..).collect();
 
-    impl OutputValue for NoRender {
-        fn print(&self, _value: ProgramValue) {
-            ();
+            if !bytes.is_empty() {
+                self.events.send(HarnessEvent::Output(bytes)).unwrap()
+            }
+            Ok(())
         }
     }
 
-    #[derive(Debug, Copy, Clone)]
-    struct NoInput;
+    struct ChannelInput {
+        events: Sender<HarnessEvent>,
+        answers: Receiver<ProgramValue>,
+    }
 
-    impl InputValue for NoInput {
-        fn get_input(&self) -> Result<ProgramValue, Infallible> {
-            panic!("No input expect for this test")
+    impl InputValue for ChannelInput {
+        fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+            self.events.send(HarnessEvent::InputRequested).unwrap();
+            self.answers.recv().map_err(|_| InputError::EndOfInput)
         }
     }
 
     #[test]
     fn given_an_ast_empty_when_interpreter_is_run_then_return_error() {
         let mut interpeter = Interpreter::new(
-            NoRender,
-            NoInput,
+            NullOutput,
+            PanicInput,
             BrainfuckMemory::default(),
             InterpreterConfig::default(),
         );
@@ -342,8 +1053,8 @@ mod interpreter_test {
     #[test]
     fn give_an_ast_that_output_a_ascii_code_when_interpreter_is_run_then_display_a_ascii_value() {
         let mut interpeter = Interpreter::new(
-            NoRender,
-            NoInput,
+            CollectingOutput::default(),
+            PanicInput,
             BrainfuckMemory::default(),
             InterpreterConfig::default(),
         );
@@ -368,15 +1079,16 @@ mod interpreter_test {
         };
 
         assert!(result.is_ok());
-        assert_eq!(interpeter.get_debug_info_current_position(), debug_expect)
+        assert_eq!(interpeter.get_debug_info_current_position(), debug_expect);
+        assert_eq!(*interpeter.display.as_bytes(), *b"A")
     }
 
     #[test]
     fn given_an_ast_that_move_one_to_the_right_when_interpreter_is_run_then_the_current_position_is_1()
      {
         let mut interpeter = Interpreter::new(
-            NoRender,
-            NoInput,
+            NullOutput,
+            PanicInput,
             BrainfuckMemory::default(),
             InterpreterConfig::default(),
         );
@@ -401,85 +1113,337 @@ mod interpreter_test {
     }
 
     #[test]
-    fn given_an_ast_that_move_two_to_the_right_and_one_to_left_when_interpreter_is_run_then_the_current_position_is_1()
+    fn given_a_memory_with_16_bits_cells_when_a_cell_goes_over_255_then_it_keeps_the_value_and_prints_the_lowest_byte()
      {
+        let source = format!("{}.", "+".repeat(321));
+        let ast = from_source_to_node_ast(&source).unwrap();
         let mut interpeter = Interpreter::new(
-            NoRender,
-            NoInput,
-            BrainfuckMemory::default(),
+            CollectingOutput::new(),
+            PanicInput,
+            BrainfuckMemory::<u16>::new(10),
             InterpreterConfig::default(),
         );
-        let mut builder = BrainfuckASTBuilder::new();
 
-        let ast = builder
-            .add_command_node(BrainfuckOperations::MovePointerRight, 1)
-            .add_command_node(BrainfuckOperations::MovePointerRight, 2)
-            .add_command_node(BrainfuckOperations::MovePointerLeft, 3)
-            .build();
+        interpeter.load_ast_program(&ast);
 
-        interpeter.load_ast_program(ast);
+        assert_eq!(interpeter.run(), Ok(()));
+        assert_eq!(interpeter.get_debug_info_current_position().raw_value, 321);
+        assert_eq!(*interpeter.display.as_bytes(), [65])
+    }
 
-        let result = interpeter.run();
+    #[test]
+    fn given_a_memory_with_signed_cells_when_a_cell_is_decremented_from_zero_then_it_is_negative_and_loops()
+     {
+        let ast = from_source_to_node_ast("--[+>+<]>").unwrap();
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            PanicInput,
+            BrainfuckMemory::<i8>::new(10),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        assert_eq!(interpeter.run(), Ok(()));
+        assert_eq!(
+            interpeter.get_debug_info_current_position(),
+            DebugMemoryPosition {
+                position: 1,
+                raw_value: 2,
+                ascii_value: Some('\u{2}'),
+            }
+        )
+    }
+
+    #[test]
+    fn given_a_bidirectional_memory_when_the_program_moves_left_from_the_start_then_the_cell_minus_one_has_the_value()
+     {
+        let ast = from_source_to_node_ast("<+").unwrap();
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            PanicInput,
+            BidirectionalMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
 
         let debug_expect = DebugMemoryPosition {
-            position: 1,
-            raw_value: 0,
-            ascii_value: Some('\0'),
+            position: -1,
+            raw_value: 1,
+            ascii_value: Some('\u{1}'),
         };
 
-        assert!(result.is_ok());
+        assert_eq!(interpeter.run(), Ok(()));
         assert_eq!(interpeter.get_debug_info_current_position(), debug_expect)
     }
 
     #[test]
-    fn given_an_ast_with_input_command_when_interpreter_is_run_then_the_current_position_is_modified_with_the_value_provided()
+    fn given_a_program_that_leaves_a_pattern_when_the_memory_stats_are_collected_then_they_describe_it()
      {
-        #[derive(Debug, Copy, Clone)]
-        struct AutomaticInput;
+        let ast = from_source_to_node_ast("++>>+++++>->").unwrap();
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            PanicInput,
+            BrainfuckMemory::<i8>::new(8),
+            InterpreterConfig::default(),
+        );
 
-        impl InputValue for AutomaticInput {
-            fn get_input(&self) -> Result<ProgramValue, Infallible> {
-                Ok(ProgramValue('B'))
+        interpeter.load_ast_program(&ast);
+        interpeter.run().unwrap();
+
+        assert_eq!(interpeter.summary.cells_touched, 5);
+        assert_eq!(
+            MemoryStats::collect(&interpeter.memory),
+            MemoryStats {
+                nonzero_cells: 3,
+                min_value: -1,
+                max_value: 5,
             }
-        }
+        )
+    }
 
+    #[test]
+    fn given_a_memory_filled_before_the_run_when_the_memory_stats_are_collected_then_only_the_changed_cells_are_non_zero()
+     {
+        let ast = from_source_to_node_ast("+>.").unwrap();
         let mut interpeter = Interpreter::new(
-            NoRender,
-            AutomaticInput,
-            BrainfuckMemory::default(),
+            NullOutput,
+            PanicInput,
+            BrainfuckMemory::<u8>::with_pattern(20, &[65]),
             InterpreterConfig::default(),
         );
 
-        let mut builder = BrainfuckASTBuilder::new();
+        interpeter.load_ast_program(&ast);
+        interpeter.run().unwrap();
 
-        let ast = builder
-            .add_command_node(BrainfuckOperations::InputCommand, 1)
-            .build();
+        assert_eq!(
+            MemoryStats::collect(&interpeter.memory),
+            MemoryStats {
+                nonzero_cells: 1,
+                min_value: 65,
+                max_value: 66,
+            }
+        )
+    }
 
-        interpeter.load_ast_program(ast);
+    #[test]
+    fn given_each_bounds_policy_when_the_program_goes_past_the_end_then_the_memory_applies_it() {
+        let ast = from_source_to_node_ast(">>>>>+").unwrap();
+        let run = |bounds| {
+            let mut interpeter = Interpreter::new(
+                NullOutput,
+                PanicInput,
+                BrainfuckMemory::<u8>::new(4).with_bounds_policy(bounds),
+                InterpreterConfig::default(),
+            );
+
+            interpeter.load_ast_program(&ast);
+            (interpeter.run(), interpeter.memory)
+        };
 
-        let result = interpeter.run();
+        let (result, _) = run(BoundsPolicy::Error);
+        assert!(matches!(
+            result,
+            Err(InterpreterErrors::Memory(
+                MemoryErrors::OutOfRangePosition { target: 4, .. }
+            ))
+        ));
+
+        let (result, memory) = run(BoundsPolicy::Wrap);
+        assert_eq!(result, Ok(()));
+        assert_eq!(memory.get_logical_position(), 1);
+        assert_eq!(memory.get_cell_at(1), Some(1));
+
+        let (result, memory) = run(BoundsPolicy::Grow);
+        assert_eq!(result, Ok(()));
+        assert_eq!(memory.size(), 6);
+        assert_eq!(memory.get_cell_at(5), Some(1))
+    }
 
-        let debug_expect = DebugMemoryPosition {
-            position: 0,
-            raw_value: 66,
-            ascii_value: Some('B'),
+    #[test]
+    fn given_each_overflow_policy_when_the_program_decrements_zero_then_the_interpreter_applies_it()
+    {
+        let ast = from_source_to_node_ast("->-").unwrap();
+        let run = |overflow_policy| {
+            let mut interpeter = Interpreter::new(
+                NullOutput,
+                PanicInput,
+                BrainfuckMemory::<u8>::new(4),
+                InterpreterConfig::default().with_overflow_policy(overflow_policy),
+            );
+
+            interpeter.load_ast_program(&ast);
+            (interpeter.run(), interpeter.memory)
         };
 
-        assert!(result.is_ok());
-        assert_eq!(interpeter.get_debug_info_current_position(), debug_expect)
+        let (result, memory) = run(OverflowPolicy::Wrap);
+        assert_eq!(result, Ok(()));
+        assert_eq!(memory.get_cell_at(0), Some(255));
+
+        let (result, memory) = run(OverflowPolicy::Saturate);
+        assert_eq!(result, Ok(()));
+        assert_eq!(memory.get_cell_at(0), Some(0));
+
+        let (result, memory) = run(OverflowPolicy::Error);
+        assert_eq!(
+            result,
+            Err(InterpreterErrors::Memory(MemoryErrors::CellUnderflow {
+                position: 0,
+                delta: 1
+            }))
+        );
+        assert_eq!(memory.get_logical_position(), 0)
     }
 
     #[test]
-    fn given_an_ast_with_loops_to_render_a_uppercase_when_is_run_then_a_uppercase_is_show() {
+    fn given_the_multitape_dialect_when_the_program_writes_in_each_tape_then_the_tapes_are_isolated()
+     {
+        let ast = from_source_to_node_ast_in_dialect(
+            "++++++++[>++++++++<-]>+}++++++++[>++++++++<-]>++{.}.",
+            Dialect::Multitape,
+        )
+        .unwrap();
         let mut interpeter = Interpreter::new(
-            NoRender,
-            NoInput,
-            BrainfuckMemory::default(),
+            CollectingOutput::new(),
+            PanicInput,
+            MultiTape::<BrainfuckMemory>::new(4),
             InterpreterConfig::default(),
         );
-        let mut builder = BrainfuckASTBuilder::new();
-        builder
+
+        interpeter.load_ast_program(&ast);
+
+        assert_eq!(interpeter.run(), Ok(()));
+        assert_eq!(interpeter.display.into_string_lossy(), "AB");
+        assert_eq!(interpeter.memory.tapes()[0].view(0..2), [0, 65]);
+        assert_eq!(interpeter.memory.tapes()[1].view(0..2), [0, 66])
+    }
+
+    #[test]
+    fn given_a_watched_region_when_the_program_writes_around_it_then_only_its_changes_are_reported()
+    {
+        let ast = from_source_to_node_ast(">+>+>+>+>+>+<<<-").unwrap();
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            PanicInput,
+            BrainfuckMemory::<u8>::new(8),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.memory.watch(2..5);
+        interpeter.load_ast_program(&ast);
+
+        assert_eq!(interpeter.run(), Ok(()));
+        assert_eq!(
+            interpeter
+                .memory
+                .drain_changes()
+                .iter()
+                .map(|change| (change.position, change.old, change.new, change.instruction))
+                .collect::<Vec<_>>(),
+            [
+                (2, 0, 1, Some(3)),
+                (3, 0, 1, Some(5)),
+                (4, 0, 1, Some(7)),
+                (3, 1, 0, Some(15))
+            ]
+        )
+    }
+
+    #[test]
+    fn given_an_ast_that_move_two_to_the_right_and_one_to_left_when_interpreter_is_run_then_the_current_position_is_1()
+     {
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            PanicInput,
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+        let mut builder = BrainfuckASTBuilder::new();
+
+        let ast = builder
+            .add_command_node(BrainfuckOperations::MovePointerRight, 1)
+            .add_command_node(BrainfuckOperations::MovePointerRight, 2)
+            .add_command_node(BrainfuckOperations::MovePointerLeft, 3)
+            .build();
+
+        interpeter.load_ast_program(ast);
+
+        let result = interpeter.run();
+
+        let debug_expect = DebugMemoryPosition {
+            position: 1,
+            raw_value: 0,
+            ascii_value: Some('\0'),
+        };
+
+        assert!(result.is_ok());
+        assert_eq!(interpeter.get_debug_info_current_position(), debug_expect)
+    }
+
+    #[test]
+    fn given_an_ast_with_input_command_when_interpreter_is_run_then_the_current_position_is_modified_with_the_value_provided()
+     {
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            QueuedInput::from("B"),
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        let mut builder = BrainfuckASTBuilder::new();
+
+        let ast = builder
+            .add_command_node(BrainfuckOperations::InputCommand, 1)
+            .build();
+
+        interpeter.load_ast_program(ast);
+
+        let result = interpeter.run();
+
+        let debug_expect = DebugMemoryPosition {
+            position: 0,
+            raw_value: 66,
+            ascii_value: Some('B'),
+        };
+
+        assert!(result.is_ok());
+        assert_eq!(interpeter.get_debug_info_current_position(), debug_expect)
+    }
+
+    #[test]
+    fn given_an_input_source_returning_a_non_ascii_value_when_interpreter_is_run_then_the_value_is_stored_without_panicking()
+     {
+        let ast = from_source_to_node_ast(",.").unwrap();
+        let mut interpeter = Interpreter::new(
+            CollectingOutput::default(),
+            QueuedInput::new([0xE9]),
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        let result = interpeter.run();
+
+        assert!(result.is_ok());
+        assert_eq!(
+            interpeter.get_debug_info_current_position().ascii_value,
+            None
+        );
+        assert_eq!(*interpeter.display.as_bytes(), [0xE9])
+    }
+
+    #[test]
+    fn given_an_ast_with_loops_to_render_a_uppercase_when_is_run_then_a_uppercase_is_show() {
+        let mut interpeter = Interpreter::new(
+            CollectingOutput::default(),
+            PanicInput,
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+        let mut builder = BrainfuckASTBuilder::new();
+        builder
             .add_n_command_nodes(BrainfuckOperations::IncrementByOneCurrentCell, 10)
             .add_loop_node(BrainfuckOperations::LoopStart, 11, 21)
             .add_command_node(BrainfuckOperations::MovePointerRight, 12)
@@ -502,7 +1466,8 @@ mod interpreter_test {
         };
 
         assert!(result.is_ok());
-        assert_eq!(interpeter.get_debug_info_current_position(), debug_expect)
+        assert_eq!(interpeter.get_debug_info_current_position(), debug_expect);
+        assert_eq!(interpeter.display.into_string_lossy(), "A")
     }
 
     #[test]
@@ -526,7 +1491,7 @@ mod interpreter_test {
         assert_eq!(first, again_first);
         assert_ne!(
             again_first,
-            Some(&BrainfuckNodeAST::Command(CommandInformation {
+            Some(BrainfuckNodeAST::Command(CommandInformation {
                 operation: BrainfuckOperations::IncrementByOneCurrentCell,
                 next_position: 2
             }))
@@ -569,4 +1534,706 @@ mod interpreter_test {
         assert!(iter.program_run_out_of_reads());
         assert_eq!(end, None);
     }
+
+    #[test]
+    fn given_zero_read_steps_when_the_ast_is_consumed_then_it_runs_without_limit() {
+        let mut builder = BrainfuckASTBuilder::new();
+        let ast = ProgramAST::new(
+            builder
+                .add_n_command_nodes(BrainfuckOperations::IncrementByOneCurrentCell, 10)
+                .build(),
+            0,
+        );
+        let mut iter = ast.into_iter();
+
+        assert_eq!(iter.by_ref().count(), 10);
+        assert!(iter.is_program_completed());
+        assert!(!iter.program_run_out_of_reads());
+    }
+
+    #[test]
+    fn given_a_program_that_never_ends_when_the_limit_is_reached_then_the_summary_has_the_busiest_loop()
+     {
+        let ast = from_source_to_node_ast("+[>+++[-]<]").unwrap();
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            PanicInput,
+            BrainfuckMemory::<u8>::new(4),
+            InterpreterConfig::new(1000),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        assert_eq!(
+            interpeter.run(),
+            Err(InterpreterErrors::UnableToCompleteTheProgram)
+        );
+        let busiest_loop = interpeter.summary.busiest_loop.unwrap();
+        assert_eq!(busiest_loop.node, 6);
+        assert_eq!(busiest_loop.instructions_counted, 500);
+        assert_eq!(busiest_loop.iterations, 89);
+    }
+
+    #[test]
+    fn given_a_program_that_ends_when_it_is_run_then_the_loops_are_not_counted() {
+        let ast = from_source_to_node_ast("+++[-]").unwrap();
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            PanicInput,
+            BrainfuckMemory::<u8>::new(4),
+            InterpreterConfig::new(1000),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        assert_eq!(interpeter.run(), Ok(()));
+        assert_eq!(interpeter.summary.busiest_loop, None)
+    }
+
+    #[test]
+    fn given_the_cat_program_with_a_queued_input_when_is_run_then_the_input_is_echoed_until_the_end_of_input()
+     {
+        let ast = from_source_to_node_ast(",[.,]").unwrap();
+        let mut interpeter = Interpreter::new(
+            CollectingOutput::default(),
+            QueuedInput::from("abc"),
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        let result = interpeter.run();
+
+        assert!(result.is_ok());
+        assert_eq!(interpeter.input.get_input(), Err(InputError::EndOfInput));
+        assert_eq!(interpeter.display.into_string_lossy(), "abc")
+    }
+
+    #[test]
+    fn given_the_cat_program_when_is_run_then_the_summary_counts_the_bytes_and_instructions() {
+        let ast = from_source_to_node_ast(">,[.,]").unwrap();
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            QueuedInput::from("ab"),
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+        interpeter.run().unwrap();
+
+        assert_eq!(
+            interpeter.summary,
+            RunSummary {
+                instructions_executed: 11,
+                bytes_written: 2,
+                bytes_read: 2,
+                inputs_requested: 3,
+                max_memory_position: 1,
+                cells_touched: 2,
+                last_instruction: Some(2),
+                final_cell: Some(0),
+                memory: None,
+                busiest_loop: None,
+            }
+        )
+    }
+
+    #[test]
+    fn given_a_recording_shorter_than_the_program_reads_when_is_replayed_then_the_run_fails() {
+        let ast = from_source_to_node_ast(",,,,").unwrap();
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            ReplayInput::new(Recording::parse(b"ab")),
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        let error = interpeter.run().unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "The program diverged from the recorded input. The program requested a 4th input but the recording only has 3"
+        );
+        assert_eq!(interpeter.summary.inputs_requested, 4)
+    }
+
+    #[test]
+    fn given_a_queued_input_that_empties_mid_loop_when_eof_sets_zero_then_the_loop_terminates() {
+        let ast = from_source_to_node_ast(",[,]").unwrap();
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            QueuedInput::from("ab"),
+            BrainfuckMemory::default(),
+            InterpreterConfig::new(1000).with_eof_behavior(EofBehavior::SetZero),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        let result = interpeter.run();
+
+        assert!(result.is_ok());
+        assert_eq!(interpeter.get_debug_info_current_position().raw_value, 0)
+    }
+
+    #[test]
+    fn given_a_queued_input_that_empties_mid_loop_when_eof_sets_max_then_the_loop_never_terminates()
+    {
+        let ast = from_source_to_node_ast(",[,]").unwrap();
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            QueuedInput::from("ab"),
+            BrainfuckMemory::default(),
+            InterpreterConfig::new(1000).with_eof_behavior(EofBehavior::SetMax),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        let error = interpeter.run().unwrap_err();
+
+        assert_eq!(error, InterpreterErrors::UnableToCompleteTheProgram);
+        assert_eq!(interpeter.get_debug_info_current_position().raw_value, 255)
+    }
+
+    #[test]
+    fn given_a_queued_input_that_empties_mid_loop_when_eof_keeps_the_cell_unchanged_then_the_loop_never_terminates()
+     {
+        let ast = from_source_to_node_ast(",[,]").unwrap();
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            QueuedInput::from("ab"),
+            BrainfuckMemory::default(),
+            InterpreterConfig::new(1000).with_eof_behavior(EofBehavior::Unchanged),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        let error = interpeter.run().unwrap_err();
+
+        assert_eq!(error, InterpreterErrors::UnableToCompleteTheProgram);
+        assert_eq!(
+            interpeter.get_debug_info_current_position().ascii_value,
+            Some('b')
+        )
+    }
+
+    #[test]
+    fn given_a_buffered_output_when_the_program_asks_for_an_input_then_the_previous_output_is_visible_before_the_input_request()
+     {
+        let ast = from_source_to_node_ast(&format!("{}.,.", "+".repeat(63))).unwrap();
+        let (events_sender, events) = channel();
+        let (answers, answers_receiver) = channel();
+
+        thread::scope(|scope| {
+            let ast = &ast;
+            let interpreter_thread = scope.spawn(move || {
+                let mut interpeter = Interpreter::new(
+                    ChannelOutput {
+                        buffer: RefCell::new(vec![]),
+                        events: events_sender.clone(),
+                    },
+                    ChannelInput {
+                        events: events_sender,
+                        answers: answers_receiver,
+                    },
+                    BrainfuckMemory::default(),
+                    InterpreterConfig::default(),
+                );
+
+                interpeter.load_ast_program(ast);
+                interpeter.run()
+            });
+
+            assert_eq!(events.recv().unwrap(), HarnessEvent::Output(b"?".to_vec()));
+            assert_eq!(events.recv().unwrap(), HarnessEvent::InputRequested);
+
+            answers.send(ProgramValue(b'A')).unwrap();
+
+            assert_eq!(events.recv().unwrap(), HarnessEvent::Output(b"A".to_vec()));
+            assert!(interpreter_thread.join().unwrap().is_ok());
+        });
+    }
+
+    #[test]
+    fn given_a_vec_as_writer_when_the_program_outputs_values_then_the_vec_contains_the_output() {
+        let ast = from_source_to_node_ast(&format!("{}.+.", "+".repeat(65))).unwrap();
+        let mut output: Vec<u8> = vec![];
+        let mut interpeter = Interpreter::new(
+            WriterOutput::new(&mut output, RawFormatter),
+            PanicInput,
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        let result = interpeter.run();
+
+        drop(interpeter);
+
+        assert!(result.is_ok());
+        assert_eq!(output, b"AB")
+    }
+
+    #[test]
+    fn given_a_writer_that_fails_after_three_bytes_when_the_program_outputs_more_then_the_run_aborts_with_output_failed()
+     {
+        let ast = from_source_to_node_ast(&format!("{}.....", "+".repeat(65))).unwrap();
+        let mut interpeter = Interpreter::new(
+            WriterOutput::new(FailingWriter::new(3), RawFormatter),
+            PanicInput,
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        let error = interpeter.run().unwrap_err();
+
+        assert_eq!(
+            error,
+            InterpreterErrors::OutputFailed(OutputError::from(io::Error::other(
+                "The writer is closed"
+            )))
+        )
+    }
+
+    #[test]
+    fn given_a_config_when_it_is_serialized_then_it_reads_back_the_same() {
+        let config = InterpreterConfig::new(0)
+            .with_eof_behavior(EofBehavior::Unchanged)
+            .with_overflow_policy(OverflowPolicy::Error);
+
+        let json = serde_json::to_string(&config).unwrap();
+        let bytes = postcard::to_stdvec(&config).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<InterpreterConfig>(&json).unwrap(),
+            config
+        );
+        assert_eq!(
+            postcard::from_bytes::<InterpreterConfig>(&bytes).unwrap(),
+            config
+        );
+        assert!(
+            serde_json::from_str::<InterpreterConfig>(
+                r#"{"number_of_reads": 1, "eof_behavior": "set_zero", "overflow_policy": "wrap", "flush_every": null, "cells": 3}"#
+            )
+            .is_err()
+        )
+    }
+
+    #[test]
+    fn given_the_summary_of_a_failed_run_when_it_is_serialized_then_it_reads_back_the_same() {
+        let ast = from_source_to_node_ast("+>++[>+<]").unwrap();
+        let mut interpreter = InterpreterBuilder::new()
+            .memory_size(4)
+            .config(InterpreterConfig::new(100))
+            .program(&ast)
+            .build()
+            .unwrap();
+        let _ = interpreter.run();
+        let summary = interpreter.summary;
+
+        let json = serde_json::to_string(&summary).unwrap();
+        let bytes = postcard::to_stdvec(&summary).unwrap();
+
+        assert!(summary.busiest_loop.is_some());
+        assert_eq!(serde_json::from_str::<RunSummary>(&json).unwrap(), summary);
+        assert_eq!(postcard::from_bytes::<RunSummary>(&bytes).unwrap(), summary)
+    }
+
+    #[test]
+    fn given_a_summary_without_the_busiest_loop_when_it_is_serialized_then_it_reads_back_the_same()
+    {
+        let summary = RunSummary::default();
+
+        let bytes = postcard::to_stdvec(&summary).unwrap();
+
+        assert_eq!(postcard::from_bytes::<RunSummary>(&bytes).unwrap(), summary);
+        assert_eq!(
+            serde_json::from_str::<RunSummary>(
+                r#"{"instructions_executed": 0, "bytes_written": 0, "bytes_read": 0, "inputs_requested": 0, "max_memory_position": 0, "cells_touched": 0, "last_instruction": null, "final_cell": null, "memory": null}"#
+            )
+            .unwrap(),
+            summary
+        )
+    }
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn given_an_owned_program_and_the_non_interactive_components_when_checked_then_the_interpreter_is_send()
+     {
+        assert_send::<Interpreter<'static, CollectingOutput, QueuedInput, BrainfuckMemory<u8>>>();
+        assert_send::<Interpreter<'static, NullOutput, ReplayInput, BidirectionalMemory>>();
+        assert_send::<
+            Interpreter<
+                'static,
+                WriterOutput<SharedWriter<Vec<u8>>, RawFormatter>,
+                ReaderInput<io::Empty>,
+                MultiTape<BrainfuckMemory<u16>>,
+                u16,
+            >,
+        >();
+    }
+
+    #[test]
+    fn given_an_interpreter_moved_to_another_thread_when_it_runs_then_its_output_comes_back() {
+        let ast = from_source_to_node_ast(",[.,]").unwrap();
+        let mut interpreter = InterpreterBuilder::new()
+            .input(QueuedInput::from("abc"))
+            .output(CollectingOutput::new())
+            .owned_program(ast)
+            .build()
+            .unwrap();
+        let (sender, receiver) = channel();
+
+        let worker = thread::spawn(move || {
+            let result = interpreter.run();
+            sender
+                .send((result, interpreter.display.into_string_lossy()))
+                .unwrap();
+        });
+
+        assert_eq!(receiver.recv().unwrap(), (Ok(()), "abc".to_string()));
+        worker.join().unwrap()
+    }
+
+    #[test]
+    fn given_a_vec_when_hello_world_runs_to_it_then_the_vec_has_its_output() {
+        let ast = from_source_to_node_ast(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/resources/test/commented_hello_world.bf"
+        )))
+        .unwrap();
+        let mut output: Vec<u8> = vec![];
+        let mut interpeter = InterpreterBuilder::new().program(&ast).build().unwrap();
+
+        interpeter.run_to_writer(&mut output).unwrap();
+
+        assert_eq!(output, b"Hello World!\n");
+        assert_eq!(interpeter.summary.bytes_written, 13)
+    }
+
+    #[test]
+    fn given_a_writer_that_fails_after_three_bytes_when_the_program_runs_to_it_then_the_run_aborts_with_output_failed()
+     {
+        let ast = from_source_to_node_ast(&format!("{}.....", "+".repeat(65))).unwrap();
+        let mut interpeter = InterpreterBuilder::new()
+            .output(CollectingOutput::new())
+            .program(&ast)
+            .build()
+            .unwrap();
+
+        let error = interpeter
+            .run_to_writer(&mut FailingWriter::new(3))
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            InterpreterErrors::OutputFailed(OutputError::from(io::Error::other(
+                "The writer is closed"
+            )))
+        );
+        assert_eq!(interpeter.summary.bytes_written, 3);
+        assert_eq!(interpeter.display.into_string_lossy(), "")
+    }
+
+    #[test]
+    fn given_an_output_limit_of_100_bytes_when_the_program_outputs_in_a_loop_then_the_run_aborts_with_exactly_100_bytes_written()
+     {
+        let ast = from_source_to_node_ast("+[.]").unwrap();
+        let mut output: Vec<u8> = vec![];
+        let mut interpeter = Interpreter::new(
+            WriterOutput::new(LimitedWriter::new(&mut output, 100), RawFormatter),
+            PanicInput,
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        let error = interpeter.run().unwrap_err();
+
+        drop(interpeter);
+
+        assert_eq!(error, InterpreterErrors::OutputLimitReached { limit: 100 });
+        assert_eq!(output, [1; 100])
+    }
+
+    #[test]
+    fn given_the_utf8_formatter_when_the_program_outputs_multi_byte_characters_then_the_output_is_decoded()
+     {
+        let ast = from_source_to_node_ast(",.,.,.,.,.,.").unwrap();
+        let mut output: Vec<u8> = vec![];
+        let mut interpeter = Interpreter::new(
+            WriterOutput::new(&mut output, Utf8Formatter::default()),
+            QueuedInput::from(vec![0xC3, 0xA9, 0xE2, 0x82, 0xAC, 0xC3]),
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        let result = interpeter.run();
+
+        drop(interpeter);
+
+        assert!(result.is_ok());
+        assert_eq!(String::from_utf8(output).unwrap(), "é€\u{FFFD}")
+    }
+
+    #[test]
+    fn given_a_tee_output_when_one_of_the_writers_fails_then_the_run_aborts_naming_the_failed_output()
+     {
+        let ast = from_source_to_node_ast(&format!("{}...", "+".repeat(65))).unwrap();
+        let mut copy: Vec<u8> = vec![];
+        let mut interpeter = Interpreter::new(
+            TeeOutput::new(
+                ("copy", WriterOutput::new(&mut copy, RawFormatter)),
+                (
+                    "closed writer",
+                    WriterOutput::new(FailingWriter::new(2), RawFormatter),
+                ),
+            ),
+            PanicInput,
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        let error = interpeter.run().unwrap_err();
+
+        drop(interpeter);
+
+        assert_eq!(
+            error,
+            InterpreterErrors::OutputFailed(OutputError::SinkFailed {
+                sink: "closed writer",
+                error: Box::new(OutputError::from(io::Error::other("The writer is closed"))),
+            })
+        );
+        assert!(error.to_string().contains("closed writer"));
+        assert_eq!(copy, b"AAA")
+    }
+
+    #[test]
+    fn given_an_input_that_fails_when_the_program_reads_it_then_the_run_aborts_with_input_failed() {
+        let ast = from_source_to_node_ast(",.,.").unwrap();
+        let mut interpeter = Interpreter::new(
+            CollectingOutput::default(),
+            FailingInput::new(*b"a"),
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        assert_eq!(interpeter.run(), Err(InterpreterErrors::InputFailed));
+        assert_eq!(interpeter.display.into_string_lossy(), "a")
+    }
+
+    #[test]
+    fn given_a_memory_that_fails_to_write_when_the_program_changes_the_cell_then_the_run_aborts_with_its_error()
+     {
+        let without_cells = MemoryErrors::OutOfRangePosition {
+            target: 0,
+            bounds: 0..0,
+        };
+
+        for source in ["+", "-"] {
+            let ast = from_source_to_node_ast(source).unwrap();
+            let mut interpeter = Interpreter::new(
+                NullOutput,
+                PanicInput,
+                BrainfuckMemory::<u8>::new(0),
+                InterpreterConfig::default(),
+            );
+
+            interpeter.load_ast_program(&ast);
+
+            assert_eq!(
+                interpeter.run(),
+                Err(InterpreterErrors::Memory(without_cells.clone()))
+            );
+        }
+    }
+
+    #[test]
+    fn given_an_output_that_fails_when_the_program_prints_then_the_run_aborts_with_output_failed() {
+        let ast = from_source_to_node_ast("+..").unwrap();
+        let mut interpeter = Interpreter::new(
+            FailingOutput::new(1),
+            PanicInput,
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        assert_eq!(
+            interpeter.run(),
+            Err(InterpreterErrors::OutputFailed(OutputError::from(
+                io::Error::other("The output is closed")
+            )))
+        );
+        assert_eq!(interpeter.summary.bytes_written, 1)
+    }
+
+    #[test]
+    fn given_an_output_closed_by_the_reader_when_the_program_prints_then_it_stops_as_closed() {
+        let ast = from_source_to_node_ast("+[.]").unwrap();
+        let mut interpeter = Interpreter::new(
+            WriterOutput::new(ClosedWriter, RawFormatter),
+            PanicInput,
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+
+        assert_eq!(interpeter.run(), Err(InterpreterErrors::OutputClosed));
+        assert_eq!(interpeter.summary.bytes_written, 0)
+    }
+
+    #[test]
+    fn given_a_program_reading_in_two_cells_when_it_runs_then_the_input_gets_the_context_of_each_read()
+     {
+        struct ContextInput {
+            contexts: Vec<InputContext>,
+        }
+
+        impl InputValue for ContextInput {
+            fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+                panic!("The interpreter must read with the context")
+            }
+
+            fn get_input_with_context(
+                &mut self,
+                context: &InputContext,
+            ) -> Result<ProgramValue, InputError> {
+                self.contexts.push(*context);
+                Ok(ProgramValue(1))
+            }
+        }
+
+        let ast = from_source_to_node_ast(",>+++.,").unwrap();
+        let mut interpeter = Interpreter::new(
+            NullOutput,
+            ContextInput { contexts: vec![] },
+            BrainfuckMemory::default(),
+            InterpreterConfig::default(),
+        );
+
+        interpeter.load_ast_program(&ast);
+        interpeter.run().unwrap();
+
+        assert_eq!(
+            interpeter.input.contexts,
+            [
+                InputContext {
+                    cell_index: 0,
+                    cell_value: 0,
+                    reads_so_far: 0,
+                    last_output: None
+                },
+                InputContext {
+                    cell_index: 1,
+                    cell_value: 3,
+                    reads_so_far: 1,
+                    last_output: Some(3)
+                }
+            ]
+        )
+    }
+
+    #[test]
+    fn given_only_a_program_when_the_interpreter_is_built_then_it_runs_on_the_defaults() {
+        let ast = from_source_to_node_ast(",+.>").unwrap();
+        let mut interpreter = InterpreterBuilder::new().program(&ast).build().unwrap();
+
+        interpreter.run().unwrap();
+
+        assert_eq!(interpreter.memory.get_cell_at(0), Some(1));
+        assert_eq!(interpreter.memory.get_logical_position(), 1);
+        assert_eq!(interpreter.summary.bytes_written, 1);
+        assert_eq!(interpreter.summary.bytes_read, 0)
+    }
+
+    #[test]
+    fn given_every_component_when_the_interpreter_is_built_then_they_are_used() {
+        let ast = from_source_to_node_ast(",[.,]+[]").unwrap();
+        let mut interpreter = InterpreterBuilder::new()
+            .input(QueuedInput::from("hi"))
+            .output(CollectingOutput::new())
+            .memory(BrainfuckMemory::<u16>::new(2))
+            .config(InterpreterConfig::new(20))
+            .program(&ast)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            interpreter.run(),
+            Err(InterpreterErrors::UnableToCompleteTheProgram)
+        );
+        assert_eq!(interpreter.memory.get_cell_at(0), Some(1u16));
+        assert_eq!(interpreter.memory.get_cell_at(2), None);
+        assert_eq!(interpreter.display.into_string_lossy(), "hi")
+    }
+
+    #[test]
+    fn given_a_memory_size_when_the_interpreter_is_built_then_the_memory_has_those_cells() {
+        let ast = from_source_to_node_ast(">+").unwrap();
+        let mut interpreter = InterpreterBuilder::new()
+            .memory_size(2)
+            .program(&ast)
+            .build()
+            .unwrap();
+
+        assert_eq!(interpreter.run(), Ok(()));
+        assert_eq!(interpreter.memory.get_cell_at(1), Some(1));
+        assert_eq!(interpreter.memory.get_cell_at(2), None)
+    }
+
+    #[test]
+    fn given_a_wrong_combination_when_the_interpreter_is_built_then_return_the_error() {
+        let ast = from_source_to_node_ast("+").unwrap();
+        assert_eq!(
+            InterpreterBuilder::new().build().err(),
+            Some(BuilderErrors::MissingProgram)
+        );
+        assert_eq!(
+            InterpreterBuilder::new()
+                .memory_size(0)
+                .program(&ast)
+                .build()
+                .err(),
+            Some(BuilderErrors::EmptyMemory)
+        );
+        assert_eq!(
+            InterpreterBuilder::new()
+                .memory_size(MAX_MEMORY_SIZE + 1)
+                .program(&ast)
+                .build()
+                .err(),
+            Some(BuilderErrors::MemoryTooBig {
+                size: MAX_MEMORY_SIZE + 1,
+                max: MAX_MEMORY_SIZE
+            })
+        );
+        assert_eq!(
+            InterpreterBuilder::new()
+                .memory_size(10)
+                .memory(BrainfuckMemory::<u8>::new(10))
+                .program(&ast)
+                .build()
+                .err(),
+            Some(BuilderErrors::MemorySizeWithCustomMemory)
+        )
+    }
 }
@@ -1,269 +1,4187 @@
-use core::ascii;
+use std::{
+    cell::{Cell, Ref, RefCell},
+    collections::{HashMap, VecDeque},
+    fmt::{Debug, Display},
+    io::{self, Read, Write},
+    num::IntErrorKind,
+    ops::{Range, RangeInclusive},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use thiserror::Error;
 
-use inquire::{CustomType, ui::RenderConfig};
-use std::{convert::Infallible, fmt::Display, num::IntErrorKind};
+use crate::file::PublicError;
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct ProgramValue(pub char);
+pub mod testing;
+
+/**
+ * A value read or written by the program. The machine works on bytes, so the value
+ * is only shown as a character when it is an ascii one.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgramValue(pub u8);
 
 impl ProgramValue {
-    pub fn new(value: char) -> Self {
-        return ProgramValue(value);
+    pub fn new(value: u8) -> Self {
+        ProgramValue(value)
+    }
+
+    /**
+     * Same as try_from, but with hex_input the two digits values like 41 are read
+     * as hexadecimal codes instead of decimal ones.
+     */
+    pub fn from_input(value: &str, hex_input: bool) -> Result<Self, AsciiParseError> {
+        if hex_input && value.len() == 2 && value.chars().all(|digit| digit.is_ascii_hexdigit()) {
+            return parse_hex_code(value);
+        }
+
+        ProgramValue::try_from(value)
     }
 }
 
 impl Display for ProgramValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self.0)
+        match self.0.is_ascii() {
+            true => write!(f, "{:?}", char::from(self.0)),
+            false => write!(f, "'\\x{:02X}'", self.0),
+        }
+    }
+}
+
+/**
+ * Every input source needs a way to tell the interpreter that there isn't more
+ * input to read. The interpreter decides what to do with the current cell based on
+ * the EofBehavior configured.
+ */
+#[derive(Error, Debug, PartialEq)]
+pub enum InputError {
+    #[error("There is no more input to read")]
+    EndOfInput,
+    #[error("Unexpected error reading the input")]
+    Unknown,
+    #[error("The user interrupted the input")]
+    Interrupted,
+    #[error("The program requested a {} input but the recording only has {recorded}", ordinal(*.requested))]
+    ReplayDiverged { requested: usize, recorded: usize },
+}
+
+/**
+ * Writes the position as an english ordinal, like 1st, 2nd or 11th.
+ */
+pub fn ordinal(position: usize) -> String {
+    let suffix = match (position % 10, position % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+
+    format!("{}{}", position, suffix)
+}
+
+/**
+ * The state of the machine when the program asks for an input, so the interactive
+ * sources can show where the value is going to be stored. The last output is the
+ * last value the program printed, if any.
+ */
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct InputContext {
+    pub cell_index: isize,
+    pub cell_value: i64,
+    pub reads_so_far: usize,
+    pub last_output: Option<u8>,
+}
+
+impl InputContext {
+    /**
+     * When the program printed a question without a newline, like "X?", the line
+     * isn't finished and the prompt would be drawn over it.
+     */
+    pub fn output_line_pending(&self) -> bool {
+        self.last_output.is_some_and(|value| value != b'\n')
     }
 }
 
 pub trait InputValue {
-    fn get_input(&self) -> Result<ProgramValue, Infallible>;
+    fn get_input(&mut self) -> Result<ProgramValue, InputError>;
+    /**
+     * The interpreter reads the inputs with this method. Only the sources that
+     * use the context need to implement it.
+     */
+    fn get_input_with_context(
+        &mut self,
+        _context: &InputContext,
+    ) -> Result<ProgramValue, InputError> {
+        self.get_input()
+    }
+    /**
+     * The message shown to the user when the input is asked, if any.
+     */
+    fn prompt(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl<T: InputValue + ?Sized> InputValue for Box<T> {
+    fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+        (**self).get_input()
+    }
+
+    fn get_input_with_context(
+        &mut self,
+        context: &InputContext,
+    ) -> Result<ProgramValue, InputError> {
+        (**self).get_input_with_context(context)
+    }
+
+    fn prompt(&self) -> Option<&str> {
+        (**self).prompt()
+    }
 }
 
-pub struct BasicInput<'a> {
-    prompt: CustomType<'a, ProgramValue>,
+impl<T: InputValue + ?Sized> InputValue for &mut T {
+    fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+        (**self).get_input()
+    }
+
+    fn get_input_with_context(
+        &mut self,
+        context: &InputContext,
+    ) -> Result<ProgramValue, InputError> {
+        (**self).get_input_with_context(context)
+    }
+
+    fn prompt(&self) -> Option<&str> {
+        (**self).prompt()
+    }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Error, Debug, PartialEq)]
 pub enum AsciiParseError {
+    #[error("The value must be in the range of 0 to 255")]
     NotValidNumericRangeValue,
+    #[error("The value isn't an ascii character")]
     NotValidAsciiCharacter,
-    UnknownError,
+    #[error("A backslash starts an escape sequence, type \\\\ to write a backslash")]
+    IncompleteEscapeSequence,
+    #[error("The value isn't a valid hexadecimal number")]
+    NotValidHexValue,
+    #[error("Unknown escape sequence, the valid ones are \\n, \\t, \\r, \\0, \\\\ and \\xNN")]
+    NotValidEscapeSequence,
+    #[error("The value isn't a valid decimal number")]
+    NotValidDecimalValue,
+    #[error(
+        "Only one character can be written, the longer values must be a code or an escape sequence"
+    )]
+    MultipleCharacters,
+    #[error("Type a character or a code value")]
+    EmptyValue,
 }
 
+/**
+ * Parses what comes after the backslash of an escape sequence, so the bytes that
+ * can't be typed in the prompt, like a new line or a tab, can be used as input.
+ */
+fn parse_escape_sequence(sequence: &str) -> Result<ProgramValue, AsciiParseError> {
+    match sequence {
+        "" => Err(AsciiParseError::IncompleteEscapeSequence),
+        "n" => Ok(ProgramValue(b'\n')),
+        "t" => Ok(ProgramValue(b'\t')),
+        "r" => Ok(ProgramValue(b'\r')),
+        "0" => Ok(ProgramValue(b'\0')),
+        "\\" => Ok(ProgramValue(b'\\')),
+        _ => match sequence.strip_prefix('x') {
+            Some(hex) if hex.len() == 2 && hex.chars().all(|digit| digit.is_ascii_hexdigit()) => {
+                u8::from_str_radix(hex, 16)
+                    .map(ProgramValue)
+                    .map_err(|_| AsciiParseError::NotValidEscapeSequence)
+            }
+            _ => Err(AsciiParseError::NotValidEscapeSequence),
+        },
+    }
+}
+
+/**
+ * Parses the digits of an hexadecimal code, without the 0x prefix. Only one or two
+ * digits fit in a byte, longer values are out of range.
+ */
+fn parse_hex_code(digits: &str) -> Result<ProgramValue, AsciiParseError> {
+    if digits.is_empty() || !digits.chars().all(|digit| digit.is_ascii_hexdigit()) {
+        return Err(AsciiParseError::NotValidHexValue);
+    }
+
+    if digits.len() > 2 {
+        return Err(AsciiParseError::NotValidNumericRangeValue);
+    }
+
+    u8::from_str_radix(digits, 16)
+        .map(ProgramValue)
+        .map_err(|_| AsciiParseError::NotValidHexValue)
+}
+
+/**
+ * Parses the digits of a decimal code, without the prefix when it has one.
+ */
+fn parse_decimal_code(digits: &str) -> Result<ProgramValue, AsciiParseError> {
+    if digits.is_empty() || !digits.chars().all(|digit| digit.is_ascii_digit()) {
+        return Err(AsciiParseError::NotValidDecimalValue);
+    }
+
+    match digits.parse::<u8>() {
+        Ok(byte) => Ok(ProgramValue(byte)),
+        Err(error) if *error.kind() == IntErrorKind::PosOverflow => {
+            Err(AsciiParseError::NotValidNumericRangeValue)
+        }
+        Err(_) => Err(AsciiParseError::NotValidDecimalValue),
+    }
+}
+
+/**
+ * The forms accepted as input, with an example of each one. The prompt help is
+ * built from them and the tests parse every example, so they can't drift apart.
+ */
+pub const INPUT_GRAMMAR: &[(&str, &str)] = &[
+    ("a single character, like A, a, 6 or a space", "A"),
+    ("a decimal code of two or three digits, like 65", "65"),
+    (
+        "a decimal code with the # or 0d prefix, like #6 or 0d6",
+        "#6",
+    ),
+    ("a hexadecimal code with the 0x prefix, like 0x41", "0x41"),
+    (
+        "an escape sequence: \\n, \\t, \\r, \\0, \\\\ or \\xNN",
+        "\\xFF",
+    ),
+];
+
+/**
+ * The form added by --hex-input, with its example.
+ */
+pub const HEX_INPUT_FORM: (&str, &str) = (
+    "Two hexadecimal digits, like 41, are a hexadecimal code",
+    "41",
+);
+
+pub fn input_help_message(hex_input: bool) -> String {
+    let forms: Vec<&str> = INPUT_GRAMMAR.iter().map(|(form, _)| *form).collect();
+    let hex_note = if hex_input {
+        format!(". {}", HEX_INPUT_FORM.0)
+    } else {
+        String::new()
+    };
+
+    format!(
+        "Write {}. The codes go from 0 to 255{}",
+        forms.join(", "),
+        hex_note
+    )
+}
+
+/**
+ * The examples of a character and of a code, the code being a hexadecimal one
+ * with --hex-input.
+ */
+pub fn input_placeholder(hex_input: bool) -> String {
+    let (_, character) = INPUT_GRAMMAR[0];
+    let (_, code) = if hex_input {
+        HEX_INPUT_FORM
+    } else {
+        INPUT_GRAMMAR[1]
+    };
+
+    format!("{} or {}", character, code)
+}
+
+/**
+ * A single character is always the character itself, so 6 is the character '6'
+ * and not the code 6. The longer values are codes or escape sequences, and the
+ * codes of one digit need the # or 0d prefix.
+ */
 impl TryFrom<&str> for ProgramValue {
     type Error = AsciiParseError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let value_parsed = value
-            .to_owned()
-            .parse::<u8>()
-            .map(|ascii_code| ascii::Char::from_u8(ascii_code));
-
-        match value_parsed {
-            Ok(ascii_char) => ascii_char.map_or_else(
-                || Err(AsciiParseError::NotValidNumericRangeValue),
-                |value| Ok(ProgramValue(value.to_char())),
-            ),
-            Err(error) if *error.kind() == IntErrorKind::PosOverflow => {
-                Err(AsciiParseError::NotValidNumericRangeValue)
-            }
-            Err(error) if *error.kind() == IntErrorKind::InvalidDigit => {
-                if value.is_ascii() {
-                    let byte_ascii_array = value.as_ascii().unwrap().to_vec();
-                    return Ok(ProgramValue(byte_ascii_array[0].to_char()));
-                }
+        if let Some(sequence) = value.strip_prefix('\\') {
+            return parse_escape_sequence(sequence);
+        }
+
+        let mut characters = value.chars();
 
-                return Err(AsciiParseError::NotValidAsciiCharacter);
+        match (characters.next(), characters.next()) {
+            (None, _) => return Err(AsciiParseError::EmptyValue),
+            (Some(character), None) if character.is_ascii() => {
+                return Ok(ProgramValue(character as u8));
             }
-            Err(_) => Err(AsciiParseError::UnknownError),
+            (Some(_), None) => return Err(AsciiParseError::NotValidAsciiCharacter),
+            _ => (),
+        }
+
+        if let Some(digits) = value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+        {
+            return parse_hex_code(digits);
+        }
+
+        if let Some(digits) = value
+            .strip_prefix('#')
+            .or_else(|| value.strip_prefix("0d"))
+            .or_else(|| value.strip_prefix("0D"))
+        {
+            return parse_decimal_code(digits);
         }
+
+        if value.chars().all(|digit| digit.is_ascii_digit()) {
+            return parse_decimal_code(value);
+        }
+
+        if !value.is_ascii() {
+            return Err(AsciiParseError::NotValidAsciiCharacter);
+        }
+
+        Err(AsciiParseError::MultipleCharacters)
     }
 }
 
-impl Into<u8> for ProgramValue {
-    fn into(self) -> u8 {
-        self.0.as_ascii().unwrap().to_u8()
+impl From<ProgramValue> for u8 {
+    fn from(value: ProgramValue) -> Self {
+        value.0
     }
 }
 
-impl<'a> Default for BasicInput<'a> {
-    fn default() -> Self {
-        let ascii_prompt: CustomType<'a, ProgramValue> = CustomType {
-            message: "Write an ascii character or his code value",
-            starting_input: None,
-            formatter: &|value| value.to_string(),
-            default_value_formatter: &|value| {
-                value.to_string()
-            },
-            default: None,
-            validators: vec![],
-            placeholder: Some("A or 65"),
-            error_message: "Please type a valid ascii character".into(),
-            help_message: "A valid ascii code value is in the range of 0 to 127, or if you want to type a character, those must be uppercase".into(),
-            parser: &|value| ProgramValue::try_from(value).map_err(|_err| ()),
-            render_config: RenderConfig::default(),
-        };
+/**
+ * Writes a line in the transcript with the time passed since the run started, so
+ * the lines can be compared removing the first column.
+ */
+pub fn write_transcript_line(
+    transcript: &mut dyn Write,
+    offset: Duration,
+    event: &str,
+) -> io::Result<()> {
+    writeln!(transcript, "+{:.6}s {}", offset.as_secs_f64(), event)?;
+    transcript.flush()
+}
+
+/**
+ * Input source that records every input event of the inner input in a human
+ * readable transcript. Each line is flushed as soon as it is written, so the
+ * transcript is complete even when the program is killed.
+ */
+pub struct TranscriptInput<I: InputValue, W: Write> {
+    input: I,
+    transcript: W,
+    started: Instant,
+}
 
-        Self {
-            prompt: ascii_prompt,
+impl<I: InputValue, W: Write> TranscriptInput<I, W> {
+    pub fn new(input: I, transcript: W, started: Instant) -> Self {
+        TranscriptInput {
+            input,
+            transcript,
+            started,
         }
     }
 }
 
-impl<'a> InputValue for BasicInput<'a> {
-    fn get_input(&self) -> Result<ProgramValue, Infallible> {
-        Ok(self.prompt.to_owned().prompt().unwrap())
+impl<I: InputValue, W: Write> InputValue for TranscriptInput<I, W> {
+    fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+        self.get_input_with_context(&InputContext::default())
+    }
+
+    fn get_input_with_context(
+        &mut self,
+        context: &InputContext,
+    ) -> Result<ProgramValue, InputError> {
+        let result = self.input.get_input_with_context(context);
+
+        let prompt = self
+            .input
+            .prompt()
+            .map(|prompt| format!(" prompt={:?}", prompt))
+            .unwrap_or_default();
+        let event = match &result {
+            Ok(value) => format!("input{} byte={} char={}", prompt, value.0, value),
+            Err(InputError::EndOfInput) => format!("input{} end_of_input", prompt),
+            Err(
+                InputError::Unknown | InputError::Interrupted | InputError::ReplayDiverged { .. },
+            ) => {
+                format!("input{} failed", prompt)
+            }
+        };
+
+        write_transcript_line(&mut self.transcript, self.started.elapsed(), &event)
+            .map_err(|_| InputError::Unknown)?;
+
+        result
+    }
+
+    fn prompt(&self) -> Option<&str> {
+        self.input.prompt()
     }
 }
 
-pub trait OutputValue {
-    fn print(&self, value: ProgramValue);
+/**
+ * Input source that hands out the bytes queued when it was created, one per read,
+ * and returns EndOfInput once all of them have been consumed.
+ */
+#[derive(Debug, Default)]
+pub struct QueuedInput {
+    bytes: VecDeque<u8>,
 }
 
-#[derive(Copy, Clone)]
-pub struct BasicOutput;
+impl QueuedInput {
+    pub fn new(bytes: impl IntoIterator<Item = u8>) -> Self {
+        QueuedInput {
+            bytes: bytes.into_iter().collect(),
+        }
+    }
+}
 
-impl OutputValue for BasicOutput {
-    fn print(&self, value: ProgramValue) {
-        print!("{:?}", value.0)
+impl From<Vec<u8>> for QueuedInput {
+    fn from(bytes: Vec<u8>) -> Self {
+        QueuedInput {
+            bytes: bytes.into(),
+        }
     }
 }
 
-pub struct BrainfuckMemory {
-    memory: Vec<u8>,
-    position: usize,
+impl From<&str> for QueuedInput {
+    fn from(text: &str) -> Self {
+        QueuedInput::new(text.bytes())
+    }
 }
 
-#[derive(Debug, PartialEq)]
-pub enum MemoryErrors {
-    CellOverflow,
-    CellUnderflow,
-    OutOfRangePosition,
+impl FromIterator<u8> for QueuedInput {
+    fn from_iter<I: IntoIterator<Item = u8>>(bytes: I) -> Self {
+        QueuedInput::new(bytes)
+    }
 }
 
-pub trait MemoryTape<CellType> {
-    fn new(memory_tape_size: usize) -> Self;
-    fn move_pointer_position(&mut self, step: isize) -> Result<(), MemoryErrors>;
-    fn get_current_cell_value(&self) -> CellType;
-    fn get_position(&self) -> usize;
-    fn update_memory_cell_value<F>(&mut self, fn_update: F) -> Result<(), MemoryErrors>
-    where
-        F: FnOnce(CellType) -> Result<CellType, MemoryErrors>;
+impl InputValue for QueuedInput {
+    fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+        self.bytes
+            .pop_front()
+            .map(ProgramValue)
+            .ok_or(InputError::EndOfInput)
+    }
 }
 
-impl MemoryTape<u8> for BrainfuckMemory {
-    fn new(memory_tape_size: usize) -> Self {
-        BrainfuckMemory {
-            memory: vec![0; memory_tape_size],
-            position: 0,
+/**
+ * Reads the input byte by byte from any reader, like a pipe or a file. The end of
+ * the reader is the end of the input.
+ */
+pub struct ReaderInput<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> ReaderInput<R> {
+    pub fn new(reader: R) -> Self {
+        ReaderInput { reader }
+    }
+}
+
+impl<R: Read> InputValue for ReaderInput<R> {
+    fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+        let mut byte = [0; 1];
+
+        match self.reader.read_exact(&mut byte) {
+            Ok(()) => Ok(ProgramValue(byte[0])),
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                Err(InputError::EndOfInput)
+            }
+            Err(_) => Err(InputError::Unknown),
         }
     }
+}
 
-    fn get_current_cell_value(&self) -> u8 {
-        self.memory[self.position]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NewlineMode {
+    Passthrough,
+    Lf,
+    Crlf,
+}
+
+/**
+ * Translates the line endings of the inner input. Lf turns \r\n and a lone \r into
+ * \n, and Crlf turns a \n without \r into \r\n. A line ending can be split over
+ * two reads, so the wrapper remembers the byte that affects the next read instead
+ * of reading ahead, which would block waiting for the user.
+ */
+pub struct NewlineInput<I: InputValue> {
+    input: I,
+    mode: NewlineMode,
+    // Lf: the previous byte was a \r already returned as \n.
+    skip_next_lf: bool,
+    // Crlf: the \n to return after the \r that was added.
+    pending_lf: bool,
+    // Crlf: the previous byte was a \r, so the next \n is already in a pair.
+    previous_cr: bool,
+}
+
+impl<I: InputValue> NewlineInput<I> {
+    pub fn new(input: I, mode: NewlineMode) -> Self {
+        NewlineInput {
+            input,
+            mode,
+            skip_next_lf: false,
+            pending_lf: false,
+            previous_cr: false,
+        }
     }
+}
 
-    fn get_position(&self) -> usize {
-        self.position
+impl<I: InputValue> InputValue for NewlineInput<I> {
+    fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+        self.get_input_with_context(&InputContext::default())
     }
 
-    fn move_pointer_position(&mut self, step: isize) -> Result<(), MemoryErrors> {
-        let new_memory_position = self.position.checked_add_signed(step);
+    fn get_input_with_context(
+        &mut self,
+        context: &InputContext,
+    ) -> Result<ProgramValue, InputError> {
+        match self.mode {
+            NewlineMode::Passthrough => self.input.get_input_with_context(context),
+            NewlineMode::Lf => {
+                let mut value = self.input.get_input_with_context(context);
 
-        match new_memory_position {
-            Some(new_position) if new_position > self.memory.len() => {
-                Err(MemoryErrors::OutOfRangePosition)
+                if self.skip_next_lf && value == Ok(ProgramValue(b'\n')) {
+                    value = self.input.get_input_with_context(context);
+                }
+
+                self.skip_next_lf = value == Ok(ProgramValue(b'\r'));
+
+                match value {
+                    Ok(ProgramValue(b'\r')) => Ok(ProgramValue(b'\n')),
+                    value => value,
+                }
             }
-            Some(new_position) => {
-                self.position = new_position;
-                Ok(())
+            NewlineMode::Crlf => {
+                if self.pending_lf {
+                    self.pending_lf = false;
+                    return Ok(ProgramValue(b'\n'));
+                }
+
+                let value = self.input.get_input_with_context(context);
+                let previous_cr = self.previous_cr;
+                self.previous_cr = value == Ok(ProgramValue(b'\r'));
+
+                match value {
+                    Ok(ProgramValue(b'\n')) if !previous_cr => {
+                        self.pending_lf = true;
+                        Ok(ProgramValue(b'\r'))
+                    }
+                    value => value,
+                }
             }
-            None => Err(MemoryErrors::OutOfRangePosition),
         }
     }
-    fn update_memory_cell_value<F>(&mut self, fn_update: F) -> Result<(), MemoryErrors>
-    where
-        F: FnOnce(u8) -> Result<u8, MemoryErrors>,
-    {
-        if self.memory.get(self.position).is_none() {
-            return Err(MemoryErrors::OutOfRangePosition);
-        }
-        let updated_memory_cell_value = fn_update(self.memory[self.position]);
 
-        match updated_memory_cell_value {
-            Ok(cell_value) => {
-                self.memory[self.position] = cell_value;
-                Ok(())
-            }
-            Err(error) => Err(error),
-        }
+    fn prompt(&self) -> Option<&str> {
+        self.input.prompt()
     }
 }
 
-impl Default for BrainfuckMemory {
-    fn default() -> Self {
-        BrainfuckMemory {
-            memory: vec![0; 3000],
-            position: 0,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordedInput {
+    Byte(u8),
+    EndOfInput,
+    Failed,
+}
+
+/**
+ * The input events of a previous run, read from a transcript written with
+ * --transcript or from a raw file with the input bytes. A raw recording ends with
+ * the end of the input, the same as the file would do if it was the stdin.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    pub events: Vec<RecordedInput>,
+}
+
+fn is_transcript_line(line: &str) -> bool {
+    let mut tokens = line.split(' ');
+
+    let is_offset = tokens
+        .next()
+        .and_then(|offset| offset.strip_prefix('+'))
+        .and_then(|offset| offset.strip_suffix('s'))
+        .is_some_and(|offset| offset.parse::<f64>().is_ok());
+
+    is_offset && matches!(tokens.next(), Some("input" | "outcome"))
+}
+
+fn parse_transcript_event(line: &str) -> Option<RecordedInput> {
+    let (_offset, event) = line.split_once(' ')?;
+    let event = event.strip_prefix("input")?;
+
+    if event.ends_with(" end_of_input") {
+        return Some(RecordedInput::EndOfInput);
+    }
+
+    if event.ends_with(" failed") {
+        return Some(RecordedInput::Failed);
+    }
+
+    // The prompt is written before the byte, so the last byte= is the right one.
+    let (_, byte) = event.rsplit_once(" byte=")?;
+    byte.split(' ')
+        .next()?
+        .parse::<u8>()
+        .ok()
+        .map(RecordedInput::Byte)
+}
+
+impl Recording {
+    pub fn parse(content: &[u8]) -> Self {
+        let transcript = std::str::from_utf8(content).ok().filter(|text| {
+            let mut lines = text.lines().filter(|line| !line.is_empty()).peekable();
+            lines.peek().is_some() && lines.all(is_transcript_line)
+        });
+
+        match transcript {
+            Some(text) => Recording {
+                events: text.lines().filter_map(parse_transcript_event).collect(),
+            },
+            None => Recording {
+                events: content
+                    .iter()
+                    .map(|byte| RecordedInput::Byte(*byte))
+                    .chain([RecordedInput::EndOfInput])
+                    .collect(),
+            },
         }
     }
+
+    /**
+     * The recorded bytes the program didn't read when it requested fewer inputs
+     * than the recording has.
+     */
+    pub fn unused_bytes(&self, inputs_requested: usize) -> usize {
+        self.events
+            .iter()
+            .skip(inputs_requested)
+            .filter(|event| matches!(event, RecordedInput::Byte(_)))
+            .count()
+    }
 }
 
-#[cfg(test)]
-mod conversion_test {
-    use crate::io::*;
+/**
+ * Input source with pseudo random bytes to exercise the programs that read input.
+ * The generator is a splitmix64 written here, so the same seed gives the same
+ * sequence in every run and every version of the dependencies.
+ */
+#[derive(Debug, Clone)]
+pub struct RandomInput {
+    state: u64,
+    range: RangeInclusive<u8>,
+}
 
-    #[test]
-    fn when_string_represent_a_valid_ascii_char_then_return_the_value_parser() {
-        let ascii_char = ProgramValue::try_from("A").unwrap();
+impl RandomInput {
+    /**
+     * By default the bytes are printable ascii characters.
+     */
+    pub fn new(seed: u64) -> Self {
+        RandomInput {
+            state: seed,
+            range: b' '..=b'~',
+        }
+    }
 
-        assert_eq!(ascii_char, ProgramValue('A'))
+    pub fn with_range(mut self, range: RangeInclusive<u8>) -> Self {
+        self.range = range;
+        self
     }
 
-    #[test]
-    fn when_string_represent_a_valid_ascii_char_code_then_return_the_value_as_char() {
-        let ascii_char = ProgramValue::try_from("66").unwrap();
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
 
-        assert_eq!(ascii_char, ProgramValue('B'))
+        let mut value = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        value ^ (value >> 31)
     }
+}
 
-    #[test]
-    fn when_string_have_a_value_non_ascii_complatible_then_return_an_error() {
-        let ascii_char = ProgramValue::try_from("Ñ").unwrap_err();
+impl InputValue for RandomInput {
+    fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+        let (start, end) = (*self.range.start() as u64, *self.range.end() as u64);
 
-        assert_eq!(ascii_char, AsciiParseError::NotValidAsciiCharacter)
+        if start > end {
+            return Err(InputError::EndOfInput);
+        }
+
+        let value = start + self.next_u64() % (end - start + 1);
+
+        Ok(ProgramValue(value as u8))
     }
+}
 
-    #[test]
-    fn when_string_have_a_numeric_value_not_in_range_of_u8_then_return_an_error() {
-        let ascii_char = ProgramValue::try_from("256").unwrap_err();
+/**
+ * Feeds the events of a recording to the program in the same order. Requesting
+ * more inputs than the recording has means the program isn't doing the same as in
+ * the recorded run, so it fails instead of making up the missing input.
+ */
+pub struct ReplayInput {
+    events: VecDeque<RecordedInput>,
+    recorded: usize,
+    requested: usize,
+}
 
-        assert_eq!(ascii_char, AsciiParseError::NotValidNumericRangeValue)
+impl ReplayInput {
+    pub fn new(recording: Recording) -> Self {
+        ReplayInput {
+            recorded: recording.events.len(),
+            events: recording.events.into(),
+            requested: 0,
+        }
     }
+}
 
-    #[test]
-    fn when_string_have_a_numeric_value_greather_than_127_then_return_an_error() {
-        let ascii_char = ProgramValue::try_from("128").unwrap_err();
+impl InputValue for ReplayInput {
+    fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+        self.requested += 1;
 
-        assert_eq!(ascii_char, AsciiParseError::NotValidNumericRangeValue)
+        match self.events.pop_front() {
+            Some(RecordedInput::Byte(byte)) => Ok(ProgramValue(byte)),
+            Some(RecordedInput::EndOfInput) => Err(InputError::EndOfInput),
+            Some(RecordedInput::Failed) => Err(InputError::Unknown),
+            None => Err(InputError::ReplayDiverged {
+                requested: self.requested,
+                recorded: self.recorded,
+            }),
+        }
     }
+}
 
-    #[test]
-    fn given_a_memory_is_at_position_zero_when_moving_minus_one_position_then_return_an_error() {
-        let mut memory = BrainfuckMemory::default();
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StdinMode {
+    Prompt,
+    Bytes,
+}
 
-        let error = memory
-            .move_pointer_position(-1)
-            .expect_err("Expect to get an out of range index");
+#[derive(Debug, PartialEq)]
+pub struct StdinSelection {
+    pub mode: StdinMode,
+    pub warning: Option<&'static str>,
+}
+
+/**
+ * The prompt needs a terminal, so when the stdin is a pipe or a file the input is
+ * read as bytes unless the user asks for the prompt. Reading bytes from a terminal
+ * looks like the program hangs at the first input, so the user is warned.
+ */
+pub fn select_stdin_mode(requested: Option<StdinMode>, stdin_is_terminal: bool) -> StdinSelection {
+    let mode = requested.unwrap_or(if stdin_is_terminal {
+        StdinMode::Prompt
+    } else {
+        StdinMode::Bytes
+    });
+
+    let warning = (mode == StdinMode::Bytes && stdin_is_terminal).then_some(
+        "The input is read as bytes from the terminal, type it and press Ctrl-D to finish it",
+    );
+
+    StdinSelection { mode, warning }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum OutputError {
+    #[error("Unable to write the program output: {0}")]
+    WriteFailed(#[from] PublicError),
+    #[error("The program output reached the limit of {limit} bytes")]
+    LimitReached { limit: u64 },
+    #[error("The program output was closed by the reader")]
+    Closed,
+    #[error("Unable to write the program output in the {sink}: {error}")]
+    SinkFailed {
+        sink: &'static str,
+        error: Box<OutputError>,
+    },
+}
+
+impl From<io::Error> for OutputError {
+    fn from(error: io::Error) -> Self {
+        let limit_reached = error
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<OutputLimitReached>())
+            .map(|reached| reached.limit);
 
-        assert_eq!(error, MemoryErrors::OutOfRangePosition)
+        match limit_reached {
+            Some(limit) => OutputError::LimitReached { limit },
+            None if error.kind() == io::ErrorKind::BrokenPipe => OutputError::Closed,
+            None => OutputError::WriteFailed(PublicError::from(error)),
+        }
     }
+}
 
-    #[test]
-    fn given_a_memory_is_at_position_zero_and_size_2_when_moving_increasing_the_pointer_by_three_steeps_then_return_an_error()
-     {
-        let mut memory = BrainfuckMemory::new(2);
+/**
+ * The output implementations are free to buffer the values printed. The interpreter
+ * calls flush before reading any input and when the program ends, so the user
+ * always sees the output in the right order.
+ */
+pub trait OutputValue {
+    fn print(&self, value: u8) -> Result<(), OutputError>;
+    /**
+     * Prints the whole value of the cell, that is wider than a byte in the memories
+     * of 16 and 32 bits. The outputs of bytes only print its lowest byte.
+     */
+    fn print_cell(&self, value: i64) -> Result<(), OutputError> {
+        self.print(value as u8)
+    }
+    fn flush(&self) -> Result<(), OutputError> {
+        Ok(())
+    }
+    /**
+     * Called once when the program ends, instead of flush, so the implementations
+     * can write what they kept waiting for more values.
+     */
+    fn finish(&self) -> Result<(), OutputError> {
+        self.flush()
+    }
+}
 
-        let error = memory
-            .move_pointer_position(3)
-            .expect_err("Expect to get an out of range index");
+impl<T: OutputValue + ?Sized> OutputValue for Box<T> {
+    fn print(&self, value: u8) -> Result<(), OutputError> {
+        (**self).print(value)
+    }
 
-        assert_eq!(error, MemoryErrors::OutOfRangePosition)
+    fn print_cell(&self, value: i64) -> Result<(), OutputError> {
+        (**self).print_cell(value)
     }
 
-    #[test]
-    fn when_update_cell_value_failed_then_memory_cell_is_not_updated() {
-        let mut memory = BrainfuckMemory::default();
+    fn flush(&self) -> Result<(), OutputError> {
+        (**self).flush()
+    }
 
-        memory.update_memory_cell_value(|_value| Ok(23)).unwrap();
+    fn finish(&self) -> Result<(), OutputError> {
+        (**self).finish()
+    }
+}
 
-        memory
-            .update_memory_cell_value(|_value| Err(MemoryErrors::CellOverflow))
-            .unwrap_err();
+impl<T: OutputValue + ?Sized> OutputValue for &mut T {
+    fn print(&self, value: u8) -> Result<(), OutputError> {
+        (**self).print(value)
+    }
 
-        assert_eq!(memory.get_current_cell_value(), 23)
+    fn print_cell(&self, value: i64) -> Result<(), OutputError> {
+        (**self).print_cell(value)
+    }
+
+    fn flush(&self) -> Result<(), OutputError> {
+        (**self).flush()
+    }
+
+    fn finish(&self) -> Result<(), OutputError> {
+        (**self).finish()
+    }
+}
+
+/**
+ * Keeps in memory every byte printed by the program, so it can be inspected once
+ * the program ends.
+ *
+ * ```
+ * use braincrub::interpreter::{Interpreter, InterpreterConfig};
+ * use braincrub::io::{BrainfuckMemory, CollectingOutput, MemoryTape, QueuedInput};
+ * use braincrub::parser::from_source_to_node_ast;
+ *
+ * let ast = from_source_to_node_ast("++++++++[>++++++++<-]>+.+.").unwrap();
+ * let mut interpreter = Interpreter::new(
+ *     CollectingOutput::default(),
+ *     QueuedInput::default(),
+ *     BrainfuckMemory::default(),
+ *     InterpreterConfig::default(),
+ * );
+ *
+ * interpreter.load_ast_program(&ast);
+ * interpreter.run().unwrap();
+ *
+ * assert_eq!(interpreter.display.into_string_lossy(), "AB");
+ * ```
+ */
+#[derive(Debug, Default)]
+pub struct CollectingOutput {
+    bytes: RefCell<Vec<u8>>,
+}
+
+impl CollectingOutput {
+    pub fn new() -> Self {
+        CollectingOutput::default()
+    }
+
+    pub fn as_bytes(&self) -> Ref<'_, [u8]> {
+        Ref::map(self.bytes.borrow(), |bytes| bytes.as_slice())
+    }
+
+    pub fn into_string_lossy(self) -> String {
+        String::from_utf8_lossy(&self.bytes.into_inner()).into_owned()
+    }
+}
+
+impl OutputValue for CollectingOutput {
+    fn print(&self, value: u8) -> Result<(), OutputError> {
+        self.bytes.borrow_mut().push(value);
+        Ok(())
+    }
+}
+
+/**
+ * Output that discards everything the program prints, for the runs where only the
+ * memory or the summary matter.
+ */
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullOutput;
+
+impl OutputValue for NullOutput {
+    fn print(&self, _value: u8) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputMode {
+    Raw,
+    Debug,
+    Numeric,
+    Hex,
+    Utf8,
+    Hexdump,
+    Csv,
+}
+
+/**
+ * How a value printed by the program is rendered before it reaches the writer.
+ * The formatter doesn't know where the bytes end, so every output mode works with
+ * the stdout, a file or a buffer in memory.
+ */
+pub trait OutputFormatter {
+    fn format(&self, value: u8, writer: &mut dyn Write) -> io::Result<()>;
+    /**
+     * Formats the whole value of the cell. Only the formatters of numbers use more
+     * than its lowest byte.
+     */
+    fn format_cell(&self, value: i64, writer: &mut dyn Write) -> io::Result<()> {
+        self.format(value as u8, writer)
+    }
+    fn finish(&self, _writer: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: OutputFormatter + ?Sized> OutputFormatter for Box<T> {
+    fn format(&self, value: u8, writer: &mut dyn Write) -> io::Result<()> {
+        (**self).format(value, writer)
+    }
+
+    fn format_cell(&self, value: i64, writer: &mut dyn Write) -> io::Result<()> {
+        (**self).format_cell(value, writer)
+    }
+
+    fn finish(&self, writer: &mut dyn Write) -> io::Result<()> {
+        (**self).finish(writer)
+    }
+}
+
+/**
+ * Writes the byte as it is, without quotes or escaping, so the output only
+ * contains what the program has written. Non printable bytes and bytes out of the
+ * ascii range are written too.
+ */
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RawFormatter;
+
+impl OutputFormatter for RawFormatter {
+    fn format(&self, value: u8, writer: &mut dyn Write) -> io::Result<()> {
+        writer.write_all(&[value])
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DebugFormatter;
+
+impl OutputFormatter for DebugFormatter {
+    fn format(&self, value: u8, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, "{}", ProgramValue(value))
+    }
+}
+
+/**
+ * Renders the cell value as a decimal number followed by the separator.
+ */
+#[derive(Debug, Clone)]
+pub struct NumericFormatter {
+    separator: String,
+}
+
+impl NumericFormatter {
+    pub fn new(separator: String) -> Self {
+        NumericFormatter { separator }
+    }
+}
+
+impl OutputFormatter for NumericFormatter {
+    fn format(&self, value: u8, writer: &mut dyn Write) -> io::Result<()> {
+        self.format_cell(value.into(), writer)
+    }
+
+    fn format_cell(&self, value: i64, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, "{}{}", value, self.separator)
+    }
+}
+
+/**
+ * Renders the cell value as two hexadecimal digits followed by the separator.
+ */
+#[derive(Debug, Clone)]
+pub struct HexFormatter {
+    separator: String,
+}
+
+impl HexFormatter {
+    pub fn new(separator: String) -> Self {
+        HexFormatter { separator }
+    }
+}
+
+impl OutputFormatter for HexFormatter {
+    fn format(&self, value: u8, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, "{:02x}{}", value, self.separator)
+    }
+}
+
+/**
+ * Renders the cell values as decimal numbers in a single CSV row. Unlike the
+ * numeric mode the separator is only written between the values, and the row ends
+ * with a new line when the program ends.
+ */
+#[derive(Debug)]
+pub struct CsvFormatter {
+    separator: String,
+    values_written: Cell<bool>,
+}
+
+impl CsvFormatter {
+    pub fn new(separator: String) -> Self {
+        CsvFormatter {
+            separator,
+            values_written: Cell::new(false),
+        }
+    }
+}
+
+impl Default for CsvFormatter {
+    fn default() -> Self {
+        CsvFormatter::new(",".to_string())
+    }
+}
+
+impl OutputFormatter for CsvFormatter {
+    fn format(&self, value: u8, writer: &mut dyn Write) -> io::Result<()> {
+        self.format_cell(value.into(), writer)
+    }
+
+    fn format_cell(&self, value: i64, writer: &mut dyn Write) -> io::Result<()> {
+        if self.values_written.replace(true) {
+            writer.write_all(self.separator.as_bytes())?;
+        }
+
+        write!(writer, "{}", value)
+    }
+
+    fn finish(&self, writer: &mut dyn Write) -> io::Result<()> {
+        if self.values_written.get() {
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * Renders the output as hexdump -C does: 16 bytes per row with the offset, the
+ * hexadecimal values and the printable characters. Every row is written once it is
+ * complete, and the last partial row and the final offset when the program ends.
+ */
+#[derive(Debug, Default)]
+pub struct HexdumpFormatter {
+    row: RefCell<Vec<u8>>,
+    offset: Cell<usize>,
+}
+
+const HEXDUMP_ROW_LENGTH: usize = 16;
+
+impl HexdumpFormatter {
+    pub fn new() -> Self {
+        HexdumpFormatter::default()
+    }
+
+    fn write_row(&self, row: &[u8], writer: &mut dyn Write) -> io::Result<()> {
+        let mut line = format!("{:08x}  ", self.offset.get());
+
+        for index in 0..HEXDUMP_ROW_LENGTH {
+            if index == HEXDUMP_ROW_LENGTH / 2 {
+                line.push(' ');
+            }
+
+            match row.get(index) {
+                Some(byte) => line.push_str(&format!("{:02x} ", byte)),
+                None => line.push_str("   "),
+            }
+        }
+
+        let printable: String = row
+            .iter()
+            .map(|byte| match byte {
+                b' '..=b'~' => *byte as char,
+                _ => '.',
+            })
+            .collect();
+
+        self.offset.set(self.offset.get() + row.len());
+        writeln!(writer, "{} |{}|", line, printable)
+    }
+}
+
+impl OutputFormatter for HexdumpFormatter {
+    fn format(&self, value: u8, writer: &mut dyn Write) -> io::Result<()> {
+        let mut row = self.row.borrow_mut();
+        row.push(value);
+
+        if row.len() == HEXDUMP_ROW_LENGTH {
+            self.write_row(&row, writer)?;
+            row.clear();
+        }
+
+        Ok(())
+    }
+
+    fn finish(&self, writer: &mut dyn Write) -> io::Result<()> {
+        let mut row = self.row.borrow_mut();
+
+        if !row.is_empty() {
+            self.write_row(&row, writer)?;
+            row.clear();
+        }
+
+        writeln!(writer, "{:08x}", self.offset.get())
+    }
+}
+
+/**
+ * Decodes the output as UTF-8, so the programs printing multi byte characters are
+ * shown as characters. The bytes of an incomplete character are kept until the
+ * character is complete. The invalid sequences, and the pending bytes once there
+ * are max_pending of them or the program ends, are written as U+FFFD.
+ */
+#[derive(Debug)]
+pub struct Utf8Formatter {
+    pending: RefCell<Vec<u8>>,
+    max_pending: usize,
+}
+
+impl Utf8Formatter {
+    pub fn new(max_pending: usize) -> Self {
+        Utf8Formatter {
+            pending: RefCell::new(Vec::with_capacity(max_pending)),
+            max_pending,
+        }
+    }
+}
+
+impl Default for Utf8Formatter {
+    fn default() -> Self {
+        Utf8Formatter::new(4)
+    }
+}
+
+impl OutputFormatter for Utf8Formatter {
+    fn format(&self, value: u8, writer: &mut dyn Write) -> io::Result<()> {
+        let mut pending = self.pending.borrow_mut();
+        pending.push(value);
+
+        loop {
+            match std::str::from_utf8(&pending) {
+                Ok(text) => {
+                    writer.write_all(text.as_bytes())?;
+                    pending.clear();
+                    return Ok(());
+                }
+                Err(error) => {
+                    let valid_up_to = error.valid_up_to();
+                    writer.write_all(&pending[..valid_up_to])?;
+                    pending.drain(..valid_up_to);
+
+                    match error.error_len() {
+                        Some(invalid_len) => {
+                            write!(writer, "{}", char::REPLACEMENT_CHARACTER)?;
+                            pending.drain(..invalid_len);
+                        }
+                        None if pending.len() >= self.max_pending => {
+                            write!(writer, "{}", char::REPLACEMENT_CHARACTER)?;
+                            pending.clear();
+                            return Ok(());
+                        }
+                        None => return Ok(()),
+                    }
+                }
+            }
+        }
+    }
+
+    fn finish(&self, writer: &mut dyn Write) -> io::Result<()> {
+        let mut pending = self.pending.borrow_mut();
+
+        if !pending.is_empty() {
+            write!(writer, "{}", char::REPLACEMENT_CHARACTER)?;
+            pending.clear();
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * Output for any io::Write: the stdout, a file, a socket or a Vec<u8>. The writer
+ * is in a RefCell because OutputValue::print only borrows the output.
+ */
+pub struct WriterOutput<W: Write, F: OutputFormatter> {
+    writer: RefCell<W>,
+    formatter: F,
+}
+
+impl<W: Write, F: OutputFormatter> WriterOutput<W, F> {
+    pub fn new(writer: W, formatter: F) -> Self {
+        WriterOutput {
+            writer: RefCell::new(writer),
+            formatter,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl<W: Write, F: OutputFormatter> OutputValue for WriterOutput<W, F> {
+    fn print(&self, value: u8) -> Result<(), OutputError> {
+        Ok(self
+            .formatter
+            .format(value, &mut *self.writer.borrow_mut())?)
+    }
+
+    fn print_cell(&self, value: i64) -> Result<(), OutputError> {
+        Ok(self
+            .formatter
+            .format_cell(value, &mut *self.writer.borrow_mut())?)
+    }
+
+    fn flush(&self) -> Result<(), OutputError> {
+        Ok(self.writer.borrow_mut().flush()?)
+    }
+
+    fn finish(&self) -> Result<(), OutputError> {
+        let mut writer = self.writer.borrow_mut();
+        self.formatter.finish(&mut *writer)?;
+
+        Ok(writer.flush()?)
+    }
+}
+
+/**
+ * Sends every value to both outputs, for example the stdout and a file. Each output
+ * is named, so when one of them fails the error says which one.
+ */
+pub struct TeeOutput<A: OutputValue, B: OutputValue> {
+    first: (&'static str, A),
+    second: (&'static str, B),
+}
+
+impl<A: OutputValue, B: OutputValue> TeeOutput<A, B> {
+    pub fn new(first: (&'static str, A), second: (&'static str, B)) -> Self {
+        TeeOutput { first, second }
+    }
+
+    fn name_sink(sink: &'static str, error: OutputError) -> OutputError {
+        match error {
+            OutputError::LimitReached { limit } => OutputError::LimitReached { limit },
+            OutputError::Closed => OutputError::Closed,
+            error => OutputError::SinkFailed {
+                sink,
+                error: Box::new(error),
+            },
+        }
+    }
+}
+
+impl<A: OutputValue, B: OutputValue> OutputValue for TeeOutput<A, B> {
+    fn print(&self, value: u8) -> Result<(), OutputError> {
+        let (first_name, first) = &self.first;
+        let (second_name, second) = &self.second;
+
+        first
+            .print(value)
+            .map_err(|error| Self::name_sink(first_name, error))?;
+        second
+            .print(value)
+            .map_err(|error| Self::name_sink(second_name, error))
+    }
+
+    fn print_cell(&self, value: i64) -> Result<(), OutputError> {
+        let (first_name, first) = &self.first;
+        let (second_name, second) = &self.second;
+
+        first
+            .print_cell(value)
+            .map_err(|error| Self::name_sink(first_name, error))?;
+        second
+            .print_cell(value)
+            .map_err(|error| Self::name_sink(second_name, error))
+    }
+
+    fn flush(&self) -> Result<(), OutputError> {
+        let (first_name, first) = &self.first;
+        let (second_name, second) = &self.second;
+
+        first
+            .flush()
+            .map_err(|error| Self::name_sink(first_name, error))?;
+        second
+            .flush()
+            .map_err(|error| Self::name_sink(second_name, error))
+    }
+
+    fn finish(&self) -> Result<(), OutputError> {
+        let (first_name, first) = &self.first;
+        let (second_name, second) = &self.second;
+
+        first
+            .finish()
+            .map_err(|error| Self::name_sink(first_name, error))?;
+        second
+            .finish()
+            .map_err(|error| Self::name_sink(second_name, error))
+    }
+}
+
+/**
+ * Writer that can be cloned and still write in the same destination. The output
+ * file needs it because it must be flushed from outside of the interpreter too,
+ * for example when the user press Ctrl-C, so the partial output isn't lost.
+ */
+pub struct SharedWriter<W: Write>(Arc<Mutex<W>>);
+
+impl<W: Write> SharedWriter<W> {
+    pub fn new(writer: W) -> Self {
+        SharedWriter(Arc::new(Mutex::new(writer)))
+    }
+
+    /**
+     * Returns the writer only when this is the last clone alive.
+     */
+    pub fn into_inner(self) -> Option<W> {
+        Arc::try_unwrap(self.0)
+            .ok()
+            .and_then(|writer| writer.into_inner().ok())
+    }
+}
+
+impl<W: Write> Clone for SharedWriter<W> {
+    fn clone(&self) -> Self {
+        SharedWriter(Arc::clone(&self.0))
+    }
+}
+
+impl<W: Write> Write for SharedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .map_err(|_| io::Error::other("The shared writer is poisoned"))?
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0
+            .lock()
+            .map_err(|_| io::Error::other("The shared writer is poisoned"))?
+            .flush()
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("The program output reached the limit of {limit} bytes")]
+pub struct OutputLimitReached {
+    pub limit: u64,
+}
+
+/**
+ * Stops writing once the limit of bytes is reached, so a program printing in an
+ * infinite loop can't fill the disk. The bytes up to the limit are written, and
+ * every write after it fails with OutputLimitReached, whatever the output mode is.
+ */
+pub struct LimitedWriter<W: Write> {
+    writer: W,
+    limit: u64,
+    written: u64,
+}
+
+impl<W: Write> LimitedWriter<W> {
+    pub fn new(writer: W, limit: u64) -> Self {
+        LimitedWriter {
+            writer,
+            limit,
+            written: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Write for LimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let remaining = self.limit - self.written;
+
+        if remaining == 0 && !buf.is_empty() {
+            return Err(io::Error::other(OutputLimitReached { limit: self.limit }));
+        }
+
+        let allowed = buf
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        let written = self.writer.write(&buf[..allowed])?;
+        self.written += written as u64;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/**
+ * The value stored in a memory cell. The interpreter only adds and subtracts one,
+ * the conversions to i64 are for the input, the output and to render the values
+ * of any width.
+ */
+pub trait MemoryCell: Copy + PartialEq + Debug + Display {
+    const ZERO: Self;
+    const ONE: Self;
+    const MAX: Self;
+    /**
+     * Characters of the widest value, the sign included, to align the values.
+     */
+    const WIDTH: usize;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn wrapping_add(self, other: Self) -> Self;
+    fn wrapping_sub(self, other: Self) -> Self;
+    fn saturating_add(self, other: Self) -> Self;
+    fn saturating_sub(self, other: Self) -> Self;
+    fn to_i64(self) -> i64;
+    /**
+     * The values out of range wrap around, as the `as` casts do.
+     */
+    fn from_i64(value: i64) -> Self;
+
+    /**
+     * The value padded to the width of the cell.
+     */
+    fn render(self) -> String {
+        format!("{:>width$}", self, width = Self::WIDTH)
+    }
+}
+
+macro_rules! impl_memory_cell {
+    ($($cell:ty => $width:expr),*) => {
+        $(
+            impl MemoryCell for $cell {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+                const MAX: Self = <$cell>::MAX;
+                const WIDTH: usize = $width;
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$cell>::checked_add(self, other)
+                }
+
+                fn checked_sub(self, other: Self) -> Option<Self> {
+                    <$cell>::checked_sub(self, other)
+                }
+
+                fn wrapping_add(self, other: Self) -> Self {
+                    <$cell>::wrapping_add(self, other)
+                }
+
+                fn wrapping_sub(self, other: Self) -> Self {
+                    <$cell>::wrapping_sub(self, other)
+                }
+
+                fn saturating_add(self, other: Self) -> Self {
+                    <$cell>::saturating_add(self, other)
+                }
+
+                fn saturating_sub(self, other: Self) -> Self {
+                    <$cell>::saturating_sub(self, other)
+                }
+
+                fn to_i64(self) -> i64 {
+                    i64::from(self)
+                }
+
+                fn from_i64(value: i64) -> Self {
+                    value as $cell
+                }
+            }
+        )*
+    };
+}
+
+impl_memory_cell!(u8 => 3, u16 => 5, u32 => 10, i8 => 4, i32 => 11);
+
+/**
+ * The widths of the cells the vec memory can be built with, named as in the
+ * command line: the bits of the unsigned cells, and i8 or i32 for the signed ones.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CellSize {
+    #[default]
+    U8,
+    U16,
+    U32,
+    I8,
+    I32,
+}
+
+impl Display for CellSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CellSize::U8 => "8",
+            CellSize::U16 => "16",
+            CellSize::U32 => "32",
+            CellSize::I8 => "i8",
+            CellSize::I32 => "i32",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/**
+ * Sanity ceiling for the memory size, a GiB of byte cells. The vec memory allocates
+ * all of them before the run, so a typo can't take the whole memory of the machine.
+ */
+pub const MAX_MEMORY_SIZE: usize = 1 << 30;
+
+pub struct BrainfuckMemory<T: MemoryCell = u8> {
+    memory: Vec<T>,
+    position: usize,
+    max_position: usize,
+    watches: MemoryWatches<T>,
+    id: u64,
+    journal: UndoJournal<T>,
+    bounds: BoundsPolicy,
+    pattern: Vec<T>,
+}
+
+/**
+ * What the vec tape does when the pointer leaves it. Wrap goes to the other end
+ * of the tape and Grow adds the cells at the right, but the left of the first
+ * cell is still out of the tape.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BoundsPolicy {
+    #[default]
+    Error,
+    Wrap,
+    Grow,
+}
+
+/**
+ * Source of the ids of the tapes, so a snapshot knows the tape it was taken from.
+ */
+static NEXT_TAPE_ID: AtomicU64 = AtomicU64::new(0);
+
+/**
+ * Point of a vec tape to go back to. It only keeps the length of the undo journal
+ * at the moment it was taken, so it's cheap whatever the size of the tape is.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TapeSnapshot {
+    tape: u64,
+    serial: usize,
+    journal_length: usize,
+    position: usize,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum SnapshotError {
+    #[error("The snapshot was taken from another tape")]
+    ForeignTape,
+    #[error("The snapshot was discarded when an older one was restored")]
+    Discarded,
+}
+
+/**
+ * The old values of the cells written while there are snapshots, newest last, and
+ * the snapshots that can still be restored with the length of the journal when
+ * each one was taken.
+ */
+#[derive(Debug, Clone, PartialEq)]
+struct UndoJournal<T> {
+    entries: Vec<(usize, T)>,
+    snapshots: Vec<(usize, usize)>,
+    next_serial: usize,
+}
+
+impl<T> UndoJournal<T> {
+    fn is_recording(&self) -> bool {
+        !self.snapshots.is_empty()
+    }
+
+    fn record(&mut self, index: usize, old: T) {
+        if self.is_recording() {
+            self.entries.push((index, old));
+        }
+    }
+}
+
+impl<T> Default for UndoJournal<T> {
+    fn default() -> Self {
+        UndoJournal {
+            entries: Vec::new(),
+            snapshots: Vec::new(),
+            next_serial: 0,
+        }
+    }
+}
+
+/**
+ * Handle of a watched region of the memory, to stop watching it.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WatchId(usize);
+
+/**
+ * A write that changed the value of a watched cell. The instruction is the position
+ * in the AST of the instruction that wrote the cell, or None when the cell was
+ * written before the run. The tape is the one of the cell in the memories with
+ * several tapes, and None in the rest.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellChange<CellType> {
+    pub position: isize,
+    pub old: CellType,
+    pub new: CellType,
+    pub instruction: Option<usize>,
+    pub tape: Option<usize>,
+}
+
+/**
+ * Regions watched in a tape and the changes of their cells since the last drain.
+ * The tapes only check the regions on the writes when there is at least one.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryWatches<CellType> {
+    regions: Vec<(WatchId, Range<isize>)>,
+    next_id: usize,
+    instruction: Option<usize>,
+    changes: Vec<CellChange<CellType>>,
+}
+
+impl<CellType: PartialEq> MemoryWatches<CellType> {
+    pub fn watch(&mut self, region: Range<isize>) -> WatchId {
+        let id = WatchId(self.next_id);
+
+        self.next_id += 1;
+        self.regions.push((id, region));
+        id
+    }
+
+    /**
+     * The changes already recorded for the region are kept until the next drain.
+     */
+    pub fn unwatch(&mut self, id: WatchId) {
+        self.regions.retain(|(watch_id, _)| *watch_id != id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    pub fn set_instruction(&mut self, instruction: Option<usize>) {
+        self.instruction = instruction;
+    }
+
+    /**
+     * Records the write when the cell is in a watched region and its value changed.
+     * A cell in several regions is recorded once.
+     */
+    pub fn record(&mut self, position: isize, old: CellType, new: CellType) {
+        if old != new
+            && self
+                .regions
+                .iter()
+                .any(|(_, region)| region.contains(&position))
+        {
+            self.changes.push(CellChange {
+                position,
+                old,
+                new,
+                instruction: self.instruction,
+                tape: None,
+            });
+        }
+    }
+
+    pub fn drain(&mut self) -> Vec<CellChange<CellType>> {
+        std::mem::take(&mut self.changes)
+    }
+}
+
+impl<CellType> Default for MemoryWatches<CellType> {
+    fn default() -> Self {
+        MemoryWatches {
+            regions: Vec::new(),
+            next_id: 0,
+            instruction: None,
+            changes: Vec::new(),
+        }
+    }
+}
+
+/**
+ * The positions are logical ones. The bounds of the tape are i128 as the sparse
+ * tape goes up to usize::MAX, and the target can be one cell past it.
+ */
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum MemoryErrors {
+    #[error("The cell {position} overflows adding {delta}")]
+    CellOverflow { position: isize, delta: i64 },
+    #[error("The cell {position} underflows subtracting {delta}")]
+    CellUnderflow { position: isize, delta: i64 },
+    #[error(
+        "The pointer position {target} is out of range (the tape is {}..{})",
+        bounds.start,
+        bounds.end
+    )]
+    OutOfRangePosition { target: i128, bounds: Range<i128> },
+}
+
+impl MemoryErrors {
+    fn out_of_range(target: i128, bounds: Range<i128>) -> Self {
+        MemoryErrors::OutOfRangePosition { target, bounds }
+    }
+
+    /**
+     * The target of a slice is its first cell outside of the tape.
+     */
+    fn slice_out_of_range(offset: isize, bounds: Range<i128>) -> Self {
+        let start = offset as i128;
+        let target = if bounds.contains(&start) {
+            bounds.end
+        } else {
+            start
+        };
+
+        MemoryErrors::out_of_range(target, bounds)
+    }
+}
+
+pub trait MemoryTape<CellType> {
+    fn new(memory_tape_size: usize) -> Self;
+    fn move_pointer_position(&mut self, step: isize) -> Result<(), MemoryErrors>;
+    fn get_current_cell_value(&self) -> CellType;
+    fn get_position(&self) -> usize;
+    /**
+     * The position shown to the user. It's the same as the position for the tapes
+     * that start in the first cell, and negative for the cells at its left in the
+     * tapes that extend in both directions.
+     */
+    fn get_logical_position(&self) -> isize {
+        isize::try_from(self.get_position()).unwrap_or(isize::MAX)
+    }
+    /**
+     * The value of the cell in the logical position, or None when the position is
+     * outside of the tape.
+     */
+    fn get_cell_at(&self, position: isize) -> Option<CellType>;
+    /**
+     * The logical positions of the cells of the tape, the ones the pointer could
+     * reach.
+     */
+    fn tape_range(&self) -> Range<isize>;
+    /**
+     * Copies the values into the cells starting at the logical position, without
+     * moving the pointer. Nothing is written when any of the values falls outside
+     * of the tape.
+     */
+    fn load_slice(&mut self, offset: isize, values: &[CellType]) -> Result<(), MemoryErrors>;
+    /**
+     * Writes the cell of the logical position without moving the pointer.
+     */
+    fn set_cell_at(&mut self, position: isize, value: CellType) -> Result<(), MemoryErrors> {
+        self.load_slice(position, std::slice::from_ref(&value))
+    }
+    /**
+     * The cells of the range that are inside of the tape, so the range is clamped
+     * at the edges.
+     */
+    fn view(&self, range: Range<isize>) -> Vec<CellType> {
+        range
+            .filter_map(|position| self.get_cell_at(position))
+            .collect()
+    }
+    /**
+     * The rightmost logical position the pointer has reached.
+     */
+    fn max_position_reached(&self) -> isize;
+    /**
+     * The leftmost logical position the pointer has reached, 0 for the tapes that
+     * start in the first cell.
+     */
+    fn min_position_reached(&self) -> isize {
+        0
+    }
+    /**
+     * Number of cells the program has used, by default the cells between the
+     * extremes the pointer has reached.
+     */
+    fn cells_touched(&self) -> usize {
+        self.max_position_reached()
+            .abs_diff(self.min_position_reached())
+            .saturating_add(1)
+    }
+    /**
+     * The cells that aren't 0 with their logical positions. By default the cells
+     * between the extremes the pointer reached are scanned.
+     */
+    fn iter_nonzero(&self) -> impl Iterator<Item = (isize, CellType)>
+    where
+        CellType: MemoryCell,
+    {
+        (self.min_position_reached()..=self.max_position_reached()).filter_map(|position| {
+            self.get_cell_at(position)
+                .filter(|value| *value != CellType::ZERO)
+                .map(|value| (position, value))
+        })
+    }
+    /**
+     * The value the cell of the logical position had before the run, 0 unless the
+     * memory was filled before it.
+     */
+    fn initial_value(&self, _position: isize) -> CellType
+    where
+        CellType: MemoryCell,
+    {
+        CellType::ZERO
+    }
+    /**
+     * Starts recording the changes of the cells of the region. The tapes without
+     * support for watching return an id that never records anything.
+     */
+    fn watch(&mut self, _region: Range<isize>) -> WatchId {
+        WatchId(usize::MAX)
+    }
+    fn unwatch(&mut self, _id: WatchId) {}
+    fn has_watches(&self) -> bool {
+        false
+    }
+    /**
+     * The instruction stamped in the changes recorded from now on.
+     */
+    fn set_current_instruction(&mut self, _instruction: Option<usize>) {}
+    /**
+     * The changes of the watched cells since the last drain, oldest first.
+     */
+    fn drain_changes(&mut self) -> Vec<CellChange<CellType>> {
+        Vec::new()
+    }
+    /**
+     * Moves to another tape, going round. The memories with a single tape stay in
+     * it.
+     */
+    fn switch_tape(&mut self, _step: isize) {}
+    /**
+     * The tape the pointer is in, for the memories with several of them.
+     */
+    fn active_tape(&self) -> Option<usize> {
+        None
+    }
+    fn update_memory_cell_value<F>(&mut self, fn_update: F) -> Result<(), MemoryErrors>
+    where
+        F: FnOnce(CellType) -> Result<CellType, MemoryErrors>;
+}
+
+impl<T: MemoryCell> MemoryTape<T> for BrainfuckMemory<T> {
+    fn new(memory_tape_size: usize) -> Self {
+        BrainfuckMemory {
+            memory: vec![T::ZERO; memory_tape_size],
+            position: 0,
+            max_position: 0,
+            watches: MemoryWatches::default(),
+            id: NEXT_TAPE_ID.fetch_add(1, Ordering::Relaxed),
+            journal: UndoJournal::default(),
+            bounds: BoundsPolicy::default(),
+            pattern: Vec::new(),
+        }
+    }
+
+    fn get_current_cell_value(&self) -> T {
+        self.memory[self.position]
+    }
+
+    /**
+     * The tapes that grow only have the cells they have grown to.
+     */
+    fn tape_range(&self) -> Range<isize> {
+        0..isize::try_from(self.memory.len()).unwrap_or(isize::MAX)
+    }
+
+    fn get_cell_at(&self, position: isize) -> Option<T> {
+        usize::try_from(position)
+            .ok()
+            .and_then(|index| self.memory.get(index))
+            .copied()
+    }
+
+    fn view(&self, range: Range<isize>) -> Vec<T> {
+        let start = usize::try_from(range.start).unwrap_or(0);
+        let end = usize::try_from(range.end).unwrap_or(0);
+
+        self.memory
+            .get(start.min(self.memory.len())..end.min(self.memory.len()))
+            .map_or_else(Vec::new, <[T]>::to_vec)
+    }
+
+    /**
+     * The tapes that grow add the cells the slice needs at the right.
+     */
+    fn load_slice(&mut self, offset: isize, values: &[T]) -> Result<(), MemoryErrors> {
+        if self.bounds == BoundsPolicy::Grow
+            && let Some(end) = usize::try_from(offset)
+                .ok()
+                .and_then(|start| start.checked_add(values.len()))
+            && end > self.memory.len()
+        {
+            self.grow_to(end);
+        }
+
+        let bounds = 0..self.memory.len() as i128;
+        let cells = usize::try_from(offset)
+            .ok()
+            .and_then(|start| Some(start..start.checked_add(values.len())?))
+            .and_then(|range| self.memory.get_mut(range))
+            .ok_or_else(|| MemoryErrors::slice_out_of_range(offset, bounds))?;
+
+        if !self.watches.is_empty() {
+            for ((position, old), new) in (offset..).zip(cells.iter()).zip(values) {
+                self.watches.record(position, *old, *new);
+            }
+        }
+
+        if self.journal.is_recording() {
+            let start = offset as usize;
+            self.journal
+                .entries
+                .extend((start..).zip(cells.iter().copied()));
+        }
+
+        cells.copy_from_slice(values);
+        Ok(())
+    }
+
+    fn max_position_reached(&self) -> isize {
+        isize::try_from(self.max_position).unwrap_or(isize::MAX)
+    }
+
+    /**
+     * The whole vec is scanned, so the cells loaded past the pointer are there too.
+     */
+    fn iter_nonzero(&self) -> impl Iterator<Item = (isize, T)> {
+        self.memory
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| **value != T::ZERO)
+            .map(|(index, value)| (index as isize, *value))
+    }
+
+    fn initial_value(&self, position: isize) -> T {
+        match self.pattern.as_slice() {
+            [] => T::ZERO,
+            pattern => pattern[position.unsigned_abs() % pattern.len()],
+        }
+    }
+
+    fn watch(&mut self, region: Range<isize>) -> WatchId {
+        self.watches.watch(region)
+    }
+
+    fn unwatch(&mut self, id: WatchId) {
+        self.watches.unwatch(id)
+    }
+
+    fn has_watches(&self) -> bool {
+        !self.watches.is_empty()
+    }
+
+    fn set_current_instruction(&mut self, instruction: Option<usize>) {
+        self.watches.set_instruction(instruction)
+    }
+
+    fn drain_changes(&mut self) -> Vec<CellChange<T>> {
+        self.watches.drain()
+    }
+
+    fn get_position(&self) -> usize {
+        self.position
+    }
+
+    /**
+     * The bounds policy is applied here, so the interpreter moves the pointer the
+     * same way whatever the policy is.
+     */
+    fn move_pointer_position(&mut self, step: isize) -> Result<(), MemoryErrors> {
+        let target = self.position as i128 + step as i128;
+        let new_memory_position = match (self.bounds, self.position.checked_add_signed(step)) {
+            (BoundsPolicy::Wrap, _) if !self.memory.is_empty() => {
+                usize::try_from(target.rem_euclid(self.memory.len() as i128)).ok()
+            }
+            (BoundsPolicy::Grow, Some(new_position)) if new_position >= self.memory.len() => {
+                self.grow_to(new_position + 1);
+                Some(new_position)
+            }
+            (_, new_position) => new_position,
+        };
+        let out_of_range = || MemoryErrors::out_of_range(target, 0..self.memory.len() as i128);
+
+        match new_memory_position {
+            Some(new_position) if new_position >= self.memory.len() => Err(out_of_range()),
+            Some(new_position) => {
+                self.position = new_position;
+                self.max_position = self.max_position.max(new_position);
+                Ok(())
+            }
+            None => Err(out_of_range()),
+        }
+    }
+    fn update_memory_cell_value<F>(&mut self, fn_update: F) -> Result<(), MemoryErrors>
+    where
+        F: FnOnce(T) -> Result<T, MemoryErrors>,
+    {
+        if self.memory.get(self.position).is_none() {
+            return Err(MemoryErrors::out_of_range(
+                self.position as i128,
+                0..self.memory.len() as i128,
+            ));
+        }
+        let updated_memory_cell_value = fn_update(self.memory[self.position]);
+
+        match updated_memory_cell_value {
+            Ok(cell_value) => {
+                if !self.watches.is_empty() {
+                    self.watches.record(
+                        self.get_logical_position(),
+                        self.memory[self.position],
+                        cell_value,
+                    );
+                }
+
+                self.journal
+                    .record(self.position, self.memory[self.position]);
+                self.memory[self.position] = cell_value;
+                Ok(())
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/**
+ * The default tape has bytes, like most of the brainfuck interpreters.
+ */
+impl Default for BrainfuckMemory {
+    fn default() -> Self {
+        BrainfuckMemory::new(3000)
+    }
+}
+
+impl<T: MemoryCell> BrainfuckMemory<T> {
+    /**
+     * A tape whose cells start with the pattern repeated instead of 0, the last
+     * repetition cut when the size isn't a multiple of it. The cells added when
+     * the tape grows follow the pattern too.
+     */
+    pub fn with_pattern(memory_tape_size: usize, pattern: &[T]) -> Self {
+        let mut memory = <Self as MemoryTape<T>>::new(0);
+
+        memory.pattern = pattern.to_vec();
+        memory.grow_to(memory_tape_size);
+        memory
+    }
+
+    fn grow_to(&mut self, size: usize) {
+        let start = self.memory.len();
+
+        match self.pattern.as_slice() {
+            [] => self.memory.resize(size, T::ZERO),
+            pattern => self
+                .memory
+                .extend((start..size).map(|index| pattern[index % pattern.len()])),
+        }
+    }
+
+    pub fn with_bounds_policy(mut self, bounds: BoundsPolicy) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /**
+     * Number of cells of the tape, which changes when it grows.
+     */
+    pub fn size(&self) -> usize {
+        self.memory.len()
+    }
+
+    /**
+     * From now on the writes keep the old value of the cell, until the snapshots
+     * are released.
+     */
+    pub fn snapshot(&mut self) -> TapeSnapshot {
+        let serial = self.journal.next_serial;
+
+        self.journal.next_serial += 1;
+        self.journal
+            .snapshots
+            .push((serial, self.journal.entries.len()));
+
+        TapeSnapshot {
+            tape: self.id,
+            serial,
+            journal_length: self.journal.entries.len(),
+            position: self.position,
+        }
+    }
+
+    /**
+     * Undoes the writes done after the snapshot and moves the pointer back. The
+     * snapshot can be restored again, but the ones taken after it are discarded.
+     */
+    pub fn restore(&mut self, snapshot: &TapeSnapshot) -> Result<(), SnapshotError> {
+        if snapshot.tape != self.id {
+            return Err(SnapshotError::ForeignTape);
+        }
+
+        let live_index = self
+            .journal
+            .snapshots
+            .iter()
+            .position(|(serial, _)| *serial == snapshot.serial)
+            .ok_or(SnapshotError::Discarded)?;
+
+        for (index, old) in self.journal.entries.drain(snapshot.journal_length..).rev() {
+            self.memory[index] = old;
+        }
+
+        self.journal.snapshots.truncate(live_index + 1);
+        self.position = snapshot.position;
+        Ok(())
+    }
+
+    /**
+     * Forgets every snapshot and stops recording the writes.
+     */
+    pub fn release_snapshots(&mut self) {
+        self.journal.entries.clear();
+        self.journal.snapshots.clear();
+    }
+
+    /**
+     * Number of old values kept to restore the snapshots.
+     */
+    pub fn journal_length(&self) -> usize {
+        self.journal.entries.len()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemoryModel {
+    Vec,
+    Bidirectional,
+    Sparse,
+}
+
+/**
+ * Tape unbounded in both directions, for the programs that move to the left of
+ * the first cell. The cells at the right of the origin, the origin included, are
+ * in one vec and the cells at its left in another one, so the cell -1 is the
+ * first of the left vec. Both vecs grow when the pointer reaches new cells, up to
+ * the caps.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct BidirectionalMemory {
+    right: Vec<u8>,
+    left: Vec<u8>,
+    position: isize,
+    right_cap: usize,
+    left_cap: usize,
+    max_position: isize,
+    min_position: isize,
+}
+
+impl BidirectionalMemory {
+    /**
+     * The right cap counts the origin, so the positions go from -left_cap to
+     * right_cap - 1.
+     */
+    pub fn with_caps(left_cap: usize, right_cap: usize) -> Self {
+        BidirectionalMemory {
+            right: vec![0],
+            left: vec![],
+            position: 0,
+            right_cap: right_cap.max(1),
+            left_cap,
+            max_position: 0,
+            min_position: 0,
+        }
+    }
+
+    fn bounds(&self) -> Range<i128> {
+        -(self.left_cap as i128)..self.right_cap as i128
+    }
+
+    fn cell(&self, position: isize) -> Option<&u8> {
+        match usize::try_from(position) {
+            Ok(index) => self.right.get(index),
+            Err(_) => self.left.get(position.unsigned_abs() - 1),
+        }
+    }
+
+    fn cell_mut(&mut self, position: isize) -> Option<&mut u8> {
+        match usize::try_from(position) {
+            Ok(index) => self.right.get_mut(index),
+            Err(_) => self.left.get_mut(position.unsigned_abs() - 1),
+        }
+    }
+}
+
+impl MemoryTape<u8> for BidirectionalMemory {
+    /**
+     * The size is the cap in each direction.
+     */
+    fn new(memory_tape_size: usize) -> Self {
+        BidirectionalMemory::with_caps(memory_tape_size, memory_tape_size)
+    }
+
+    fn get_current_cell_value(&self) -> u8 {
+        self.cell(self.position).copied().unwrap_or(0)
+    }
+
+    /**
+     * The index counted from the leftmost cell the pointer has reached.
+     */
+    fn get_position(&self) -> usize {
+        self.position.saturating_add_unsigned(self.left.len()) as usize
+    }
+
+    fn get_logical_position(&self) -> isize {
+        self.position
+    }
+
+    fn max_position_reached(&self) -> isize {
+        self.max_position
+    }
+
+    fn min_position_reached(&self) -> isize {
+        self.min_position
+    }
+
+    fn tape_range(&self) -> Range<isize> {
+        let cap = |cap: usize| isize::try_from(cap).unwrap_or(isize::MAX);
+
+        -cap(self.left_cap)..cap(self.right_cap)
+    }
+
+    fn iter_nonzero(&self) -> impl Iterator<Item = (isize, u8)> {
+        let right = (0..).zip(self.right.iter().copied());
+        let left = (1..)
+            .map(|index: isize| -index)
+            .zip(self.left.iter().copied());
+
+        right.chain(left).filter(|(_, value)| *value != 0)
+    }
+
+    /**
+     * The cells the pointer hasn't reached yet are 0 while they are inside of the
+     * caps.
+     */
+    fn get_cell_at(&self, position: isize) -> Option<u8> {
+        let inside_caps = match usize::try_from(position) {
+            Ok(index) => index < self.right_cap,
+            Err(_) => position.unsigned_abs() <= self.left_cap,
+        };
+
+        inside_caps.then(|| self.cell(position).copied().unwrap_or(0))
+    }
+
+    fn load_slice(&mut self, offset: isize, values: &[u8]) -> Result<(), MemoryErrors> {
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        let out_of_range = || MemoryErrors::slice_out_of_range(offset, self.bounds());
+        let last = isize::try_from(values.len())
+            .ok()
+            .and_then(|length| offset.checked_add(length - 1))
+            .ok_or_else(out_of_range)?;
+
+        if self.get_cell_at(offset).is_none() || self.get_cell_at(last).is_none() {
+            return Err(out_of_range());
+        }
+
+        if let Ok(index) = usize::try_from(last)
+            && index >= self.right.len()
+        {
+            self.right.resize(index + 1, 0);
+        }
+        if offset < 0 && offset.unsigned_abs() > self.left.len() {
+            self.left.resize(offset.unsigned_abs(), 0);
+        }
+
+        for (position, value) in (offset..=last).zip(values) {
+            *self
+                .cell_mut(position)
+                .expect("The cells were allocated before") = *value;
+        }
+
+        Ok(())
+    }
+
+    fn move_pointer_position(&mut self, step: isize) -> Result<(), MemoryErrors> {
+        let out_of_range =
+            || MemoryErrors::out_of_range(self.position as i128 + step as i128, self.bounds());
+        let new_position = self.position.checked_add(step).ok_or_else(out_of_range)?;
+
+        match usize::try_from(new_position) {
+            Ok(index) if index >= self.right_cap => return Err(out_of_range()),
+            Ok(index) if index >= self.right.len() => self.right.resize(index + 1, 0),
+            Ok(_) => (),
+            Err(_) => {
+                let index = new_position.unsigned_abs() - 1;
+
+                if index >= self.left_cap {
+                    return Err(out_of_range());
+                }
+                if index >= self.left.len() {
+                    self.left.resize(index + 1, 0);
+                }
+            }
+        }
+
+        self.position = new_position;
+        self.max_position = self.max_position.max(new_position);
+        self.min_position = self.min_position.min(new_position);
+        Ok(())
+    }
+
+    fn update_memory_cell_value<F>(&mut self, fn_update: F) -> Result<(), MemoryErrors>
+    where
+        F: FnOnce(u8) -> Result<u8, MemoryErrors>,
+    {
+        let (position, bounds) = (self.position, self.bounds());
+        let cell = self
+            .cell_mut(position)
+            .ok_or_else(|| MemoryErrors::out_of_range(position as i128, bounds))?;
+
+        *cell = fn_update(*cell)?;
+        Ok(())
+    }
+}
+
+impl Default for BidirectionalMemory {
+    fn default() -> Self {
+        BidirectionalMemory::new(3000)
+    }
+}
+
+/**
+ * Tape for the programs that use a few cells far from each other. Only the cells
+ * that aren't 0 are stored, so the memory used depends on the cells written and
+ * not on the size of the tape, at the cost of a lookup on every access.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMemory {
+    cells: HashMap<usize, u8>,
+    position: usize,
+    size: usize,
+    max_position: usize,
+}
+
+impl SparseMemory {
+    /**
+     * A tape where the pointer can go up to usize::MAX.
+     */
+    pub fn unbounded() -> Self {
+        SparseMemory::new(usize::MAX)
+    }
+
+    /**
+     * Number of cells kept in the map, which are the ones that aren't 0.
+     */
+    pub fn stored_cells(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+impl MemoryTape<u8> for SparseMemory {
+    fn new(memory_tape_size: usize) -> Self {
+        SparseMemory {
+            cells: HashMap::new(),
+            position: 0,
+            size: memory_tape_size,
+            max_position: 0,
+        }
+    }
+
+    fn get_current_cell_value(&self) -> u8 {
+        self.cells.get(&self.position).copied().unwrap_or(0)
+    }
+
+    fn get_cell_at(&self, position: isize) -> Option<u8> {
+        usize::try_from(position)
+            .ok()
+            .filter(|index| *index < self.size)
+            .map(|index| self.cells.get(&index).copied().unwrap_or(0))
+    }
+
+    fn tape_range(&self) -> Range<isize> {
+        0..isize::try_from(self.size).unwrap_or(isize::MAX)
+    }
+
+    fn load_slice(&mut self, offset: isize, values: &[u8]) -> Result<(), MemoryErrors> {
+        let out_of_range = || MemoryErrors::slice_out_of_range(offset, 0..self.size as i128);
+        let start = usize::try_from(offset).map_err(|_| out_of_range())?;
+
+        match start.checked_add(values.len()) {
+            Some(end) if end <= self.size => (),
+            _ => return Err(out_of_range()),
+        }
+
+        for (index, value) in (start..).zip(values) {
+            match value {
+                0 => self.cells.remove(&index),
+                value => self.cells.insert(index, *value),
+            };
+        }
+
+        Ok(())
+    }
+
+    fn get_position(&self) -> usize {
+        self.position
+    }
+
+    fn max_position_reached(&self) -> isize {
+        isize::try_from(self.max_position).unwrap_or(isize::MAX)
+    }
+
+    /**
+     * The cells kept in the map, as counting the span of the pointer would give
+     * the whole tape for the programs that jump far.
+     */
+    fn cells_touched(&self) -> usize {
+        self.stored_cells()
+    }
+
+    /**
+     * Only the map is walked, so the cells come in no particular order.
+     */
+    fn iter_nonzero(&self) -> impl Iterator<Item = (isize, u8)> {
+        self.cells
+            .iter()
+            .map(|(index, value)| (*index as isize, *value))
+    }
+
+    fn move_pointer_position(&mut self, step: isize) -> Result<(), MemoryErrors> {
+        match self.position.checked_add_signed(step) {
+            Some(new_position) if new_position < self.size => {
+                self.position = new_position;
+                self.max_position = self.max_position.max(new_position);
+                Ok(())
+            }
+            _ => Err(MemoryErrors::out_of_range(
+                self.position as i128 + step as i128,
+                0..self.size as i128,
+            )),
+        }
+    }
+
+    fn update_memory_cell_value<F>(&mut self, fn_update: F) -> Result<(), MemoryErrors>
+    where
+        F: FnOnce(u8) -> Result<u8, MemoryErrors>,
+    {
+        match fn_update(self.get_current_cell_value())? {
+            0 => self.cells.remove(&self.position),
+            cell_value => self.cells.insert(self.position, cell_value),
+        };
+
+        Ok(())
+    }
+}
+
+impl Default for SparseMemory {
+    fn default() -> Self {
+        SparseMemory::unbounded()
+    }
+}
+
+/**
+ * Several independent tapes with a pointer each, for the multitape dialect. Every
+ * operation goes to the active tape, so the positions, the figures and the views
+ * are the ones of that tape.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiTape<Memory> {
+    tapes: Vec<Memory>,
+    active: usize,
+}
+
+/**
+ * Tapes of the memory built by MultiTape::new.
+ */
+pub const MULTITAPE_TAPES: usize = 2;
+
+impl<Memory> MultiTape<Memory> {
+    /**
+     * The program starts in the first tape.
+     */
+    pub fn from_tapes(tapes: Vec<Memory>) -> Self {
+        assert!(
+            !tapes.is_empty(),
+            "A multitape memory needs at least one tape"
+        );
+
+        MultiTape { tapes, active: 0 }
+    }
+
+    pub fn tapes(&self) -> &[Memory] {
+        &self.tapes
+    }
+
+    fn tape(&self) -> &Memory {
+        &self.tapes[self.active]
+    }
+
+    fn tape_mut(&mut self) -> &mut Memory {
+        &mut self.tapes[self.active]
+    }
+}
+
+impl<Cell, Memory: MemoryTape<Cell>> MemoryTape<Cell> for MultiTape<Memory> {
+    fn new(memory_tape_size: usize) -> Self {
+        MultiTape::from_tapes(
+            (0..MULTITAPE_TAPES)
+                .map(|_| Memory::new(memory_tape_size))
+                .collect(),
+        )
+    }
+
+    fn move_pointer_position(&mut self, step: isize) -> Result<(), MemoryErrors> {
+        self.tape_mut().move_pointer_position(step)
+    }
+
+    fn get_current_cell_value(&self) -> Cell {
+        self.tape().get_current_cell_value()
+    }
+
+    fn get_position(&self) -> usize {
+        self.tape().get_position()
+    }
+
+    fn get_logical_position(&self) -> isize {
+        self.tape().get_logical_position()
+    }
+
+    fn get_cell_at(&self, position: isize) -> Option<Cell> {
+        self.tape().get_cell_at(position)
+    }
+
+    fn load_slice(&mut self, offset: isize, values: &[Cell]) -> Result<(), MemoryErrors> {
+        self.tape_mut().load_slice(offset, values)
+    }
+
+    fn view(&self, range: Range<isize>) -> Vec<Cell> {
+        self.tape().view(range)
+    }
+
+    fn max_position_reached(&self) -> isize {
+        self.tape().max_position_reached()
+    }
+
+    fn min_position_reached(&self) -> isize {
+        self.tape().min_position_reached()
+    }
+
+    fn cells_touched(&self) -> usize {
+        self.tape().cells_touched()
+    }
+
+    fn tape_range(&self) -> Range<isize> {
+        self.tape().tape_range()
+    }
+
+    fn iter_nonzero(&self) -> impl Iterator<Item = (isize, Cell)>
+    where
+        Cell: MemoryCell,
+    {
+        self.tape().iter_nonzero()
+    }
+
+    fn initial_value(&self, position: isize) -> Cell
+    where
+        Cell: MemoryCell,
+    {
+        self.tape().initial_value(position)
+    }
+
+    /**
+     * The region is watched in every tape. They get the same watches in the same
+     * order, so the id of each tape is the same.
+     */
+    fn watch(&mut self, region: Range<isize>) -> WatchId {
+        let mut id = WatchId(usize::MAX);
+
+        for tape in &mut self.tapes {
+            id = tape.watch(region.clone());
+        }
+
+        id
+    }
+
+    fn unwatch(&mut self, id: WatchId) {
+        self.tapes.iter_mut().for_each(|tape| tape.unwatch(id))
+    }
+
+    fn has_watches(&self) -> bool {
+        self.tapes.iter().any(|tape| tape.has_watches())
+    }
+
+    fn set_current_instruction(&mut self, instruction: Option<usize>) {
+        self.tapes
+            .iter_mut()
+            .for_each(|tape| tape.set_current_instruction(instruction))
+    }
+
+    /**
+     * The changes of each tape are labeled with it, and come tape after tape.
+     */
+    fn drain_changes(&mut self) -> Vec<CellChange<Cell>> {
+        self.tapes
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(index, tape)| {
+                tape.drain_changes()
+                    .into_iter()
+                    .map(move |change| CellChange {
+                        tape: Some(index),
+                        ..change
+                    })
+            })
+            .collect()
+    }
+
+    fn switch_tape(&mut self, step: isize) {
+        let tapes = self.tapes.len() as isize;
+
+        self.active = (self.active as isize + step).rem_euclid(tapes) as usize;
+    }
+
+    fn active_tape(&self) -> Option<usize> {
+        Some(self.active)
+    }
+
+    fn update_memory_cell_value<F>(&mut self, fn_update: F) -> Result<(), MemoryErrors>
+    where
+        F: FnOnce(Cell) -> Result<Cell, MemoryErrors>,
+    {
+        self.tape_mut().update_memory_cell_value(fn_update)
+    }
+}
+
+#[cfg(test)]
+mod conversion_test {
+    use crate::io::testing::{ClosedWriter, NullOutput};
+    use crate::io::*;
+
+    #[test]
+    fn when_string_represent_a_valid_ascii_char_then_return_the_value_parser() {
+        let ascii_char = ProgramValue::try_from("A").unwrap();
+
+        assert_eq!(ascii_char, ProgramValue(b'A'))
+    }
+
+    #[test]
+    fn when_string_represent_a_valid_ascii_char_code_then_return_the_value_as_char() {
+        let ascii_char = ProgramValue::try_from("66").unwrap();
+
+        assert_eq!(ascii_char, ProgramValue(b'B'))
+    }
+
+    #[test]
+    fn when_string_is_a_single_lowercase_digit_punctuation_or_space_then_return_the_character() {
+        for (input, expected) in [
+            ("a", b'a'),
+            ("z", b'z'),
+            ("6", b'6'),
+            ("0", b'0'),
+            (" ", b' '),
+            ("#", b'#'),
+        ] {
+            assert_eq!(
+                ProgramValue::try_from(input),
+                Ok(ProgramValue(expected)),
+                "{:?}",
+                input
+            )
+        }
+    }
+
+    #[test]
+    fn when_string_is_a_prefixed_decimal_code_then_return_the_code() {
+        for (input, expected) in [
+            ("#6", 6),
+            ("0d6", 6),
+            ("0D65", 65),
+            ("#255", 255),
+            ("#0", 0),
+            ("07", 7),
+        ] {
+            assert_eq!(
+                ProgramValue::try_from(input),
+                Ok(ProgramValue(expected)),
+                "{:?}",
+                input
+            )
+        }
+    }
+
+    #[test]
+    fn when_string_is_a_malformed_prefixed_decimal_code_then_return_an_error() {
+        assert_eq!(ProgramValue::try_from("#"), Ok(ProgramValue(b'#')));
+        assert_eq!(
+            ProgramValue::try_from("#x"),
+            Err(AsciiParseError::NotValidDecimalValue)
+        );
+        assert_eq!(
+            ProgramValue::try_from("0d"),
+            Err(AsciiParseError::NotValidDecimalValue)
+        );
+        assert_eq!(
+            ProgramValue::try_from("#256"),
+            Err(AsciiParseError::NotValidNumericRangeValue)
+        )
+    }
+
+    #[test]
+    fn when_string_has_several_characters_that_arent_a_code_then_return_an_error() {
+        for input in ["ab", "A ", "hello", "6a"] {
+            assert_eq!(
+                ProgramValue::try_from(input),
+                Err(AsciiParseError::MultipleCharacters),
+                "{:?}",
+                input
+            )
+        }
+    }
+
+    #[test]
+    fn when_string_is_empty_then_return_an_error() {
+        assert_eq!(ProgramValue::try_from(""), Err(AsciiParseError::EmptyValue))
+    }
+
+    #[test]
+    fn when_the_examples_of_the_input_grammar_are_parsed_then_all_of_them_are_valid() {
+        for (form, example) in INPUT_GRAMMAR {
+            assert!(ProgramValue::try_from(*example).is_ok(), "{}", form);
+            assert!(input_help_message(false).contains(form))
+        }
+    }
+
+    #[test]
+    fn when_the_hex_input_example_is_parsed_then_it_is_a_hexadecimal_code() {
+        let (form, example) = HEX_INPUT_FORM;
+
+        assert_eq!(
+            ProgramValue::from_input(example, true),
+            Ok(ProgramValue(0x41))
+        );
+        assert!(input_help_message(true).contains(form))
+    }
+
+    #[test]
+    fn given_the_input_mode_when_the_placeholder_is_built_then_it_has_the_examples_of_the_grammar()
+    {
+        assert_eq!(input_placeholder(false), "A or 65");
+        assert_eq!(input_placeholder(true), "A or 41")
+    }
+
+    #[test]
+    fn when_string_have_a_value_non_ascii_complatible_then_return_an_error() {
+        let ascii_char = ProgramValue::try_from("Ñ").unwrap_err();
+
+        assert_eq!(ascii_char, AsciiParseError::NotValidAsciiCharacter)
+    }
+
+    #[test]
+    fn when_string_have_a_numeric_value_not_in_range_of_u8_then_return_an_error() {
+        let ascii_char = ProgramValue::try_from("256").unwrap_err();
+
+        assert_eq!(ascii_char, AsciiParseError::NotValidNumericRangeValue)
+    }
+
+    #[test]
+    fn when_string_have_a_numeric_value_between_128_and_255_then_return_the_value_as_byte() {
+        for (input, expected) in [("128", 128), ("200", 200), ("255", 255)] {
+            let value = ProgramValue::try_from(input).unwrap();
+
+            assert_eq!(value, ProgramValue(expected));
+            assert_eq!(u8::from(value), expected)
+        }
+    }
+
+    #[test]
+    fn when_string_represent_a_valid_hex_code_then_return_the_value_as_byte() {
+        for (input, expected) in [("0x41", b'A'), ("0X7f", 127), ("0xA", 10), ("0xFF", 255)] {
+            assert_eq!(
+                ProgramValue::try_from(input),
+                Ok(ProgramValue(expected)),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn when_string_have_a_hex_value_not_in_range_of_u8_then_return_an_error() {
+        for input in ["0x100", "0x0041"] {
+            assert_eq!(
+                ProgramValue::try_from(input),
+                Err(AsciiParseError::NotValidNumericRangeValue),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn when_string_have_a_malformed_hex_value_then_return_an_error() {
+        for input in ["0x1G", "0x", "0x-1"] {
+            assert_eq!(
+                ProgramValue::try_from(input),
+                Err(AsciiParseError::NotValidHexValue),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn given_the_hex_input_preference_when_string_have_two_hex_digits_then_return_the_value_as_hex()
+    {
+        assert_eq!(ProgramValue::from_input("41", true), Ok(ProgramValue(b'A')));
+        assert_eq!(ProgramValue::from_input("7F", true), Ok(ProgramValue(127)));
+        assert_eq!(ProgramValue::from_input("41", false), Ok(ProgramValue(41)));
+        assert_eq!(ProgramValue::from_input("A", true), Ok(ProgramValue(b'A')));
+        assert_eq!(
+            ProgramValue::from_input("0x42", true),
+            Ok(ProgramValue(b'B'))
+        )
+    }
+
+    #[test]
+    fn when_string_is_an_escape_sequence_then_return_the_byte_it_represents() {
+        let escapes = [
+            ("\\n", b'\n'),
+            ("\\t", b'\t'),
+            ("\\r", b'\r'),
+            ("\\0", b'\0'),
+            ("\\\\", b'\\'),
+            ("\\x41", b'A'),
+            ("\\x7F", 127),
+            ("\\x7f", 127),
+            ("\\x80", 128),
+            ("\\xFF", 255),
+        ];
+
+        for (input, expected) in escapes {
+            assert_eq!(
+                ProgramValue::try_from(input),
+                Ok(ProgramValue(expected)),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn when_string_is_a_lone_backslash_then_return_an_error_suggesting_the_escaped_backslash() {
+        let error = ProgramValue::try_from("\\").unwrap_err();
+
+        assert_eq!(error, AsciiParseError::IncompleteEscapeSequence);
+        assert!(error.to_string().contains("\\\\"))
+    }
+
+    #[test]
+    fn when_string_is_an_unknown_or_malformed_escape_sequence_then_return_an_error() {
+        for input in ["\\q", "\\x", "\\x7", "\\x7G", "\\x100", "\\nn"] {
+            assert_eq!(
+                ProgramValue::try_from(input),
+                Err(AsciiParseError::NotValidEscapeSequence),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn when_a_program_value_is_displayed_then_only_ascii_values_are_shown_as_characters() {
+        assert_eq!(ProgramValue(b'A').to_string(), "'A'");
+        assert_eq!(ProgramValue(b'\n').to_string(), "'\\n'");
+        assert_eq!(ProgramValue(128).to_string(), "'\\x80'");
+        assert_eq!(ProgramValue(255).to_string(), "'\\xFF'")
+    }
+
+    #[test]
+    fn given_a_memory_is_at_position_zero_when_moving_minus_one_position_then_return_an_error() {
+        let mut memory = BrainfuckMemory::default();
+
+        let error = memory
+            .move_pointer_position(-1)
+            .expect_err("Expect to get an out of range index");
+
+        assert_eq!(
+            error,
+            MemoryErrors::OutOfRangePosition {
+                target: -1,
+                bounds: 0..3000
+            }
+        )
+    }
+
+    #[test]
+    fn when_a_memory_error_is_displayed_then_it_has_the_position_and_the_bounds_of_the_tape() {
+        let mut memory = BrainfuckMemory::default();
+
+        assert_eq!(
+            memory.move_pointer_position(-1).unwrap_err().to_string(),
+            "The pointer position -1 is out of range (the tape is 0..3000)"
+        );
+        assert_eq!(
+            MemoryErrors::CellUnderflow {
+                position: 4,
+                delta: 1
+            }
+            .to_string(),
+            "The cell 4 underflows subtracting 1"
+        )
+    }
+
+    #[test]
+    fn given_a_memory_is_at_position_zero_and_size_2_when_moving_increasing_the_pointer_by_three_steeps_then_return_an_error()
+     {
+        let mut memory = BrainfuckMemory::<u8>::new(2);
+
+        let error = memory
+            .move_pointer_position(3)
+            .expect_err("Expect to get an out of range index");
+
+        assert_eq!(
+            error,
+            MemoryErrors::OutOfRangePosition {
+                target: 3,
+                bounds: 0..2
+            }
+        )
+    }
+
+    fn run_the_tape_scenario<T: MemoryCell>() {
+        let mut memory = BrainfuckMemory::<T>::new(4);
+
+        memory
+            .update_memory_cell_value(|value| {
+                value.checked_add(T::ONE).ok_or(MemoryErrors::CellOverflow {
+                    position: 0,
+                    delta: 1,
+                })
+            })
+            .unwrap();
+        memory.move_pointer_position(2).unwrap();
+        memory
+            .update_memory_cell_value(|value| Ok(value.wrapping_sub(T::ONE)))
+            .unwrap();
+
+        assert_eq!(
+            memory.get_current_cell_value().wrapping_add(T::ONE),
+            T::ZERO
+        );
+        assert_eq!(memory.get_position(), 2);
+
+        memory
+            .update_memory_cell_value(|_value| Ok(T::MAX.saturating_add(T::ONE)))
+            .unwrap();
+
+        assert_eq!(memory.get_current_cell_value(), T::MAX);
+        assert_eq!(T::MAX.checked_add(T::ONE), None);
+
+        memory.move_pointer_position(-2).unwrap();
+
+        assert_eq!(memory.get_current_cell_value().to_i64(), 1);
+        assert_eq!(
+            memory
+                .get_current_cell_value()
+                .saturating_sub(T::ONE)
+                .checked_sub(T::ONE),
+            T::ZERO.checked_sub(T::ONE)
+        );
+        assert_eq!(T::from_i64(T::MAX.to_i64()), T::MAX);
+        assert_eq!(
+            memory.move_pointer_position(-1),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: -1,
+                bounds: 0..4
+            })
+        )
+    }
+
+    #[test]
+    fn given_a_memory_of_each_cell_width_when_the_cells_are_updated_then_all_behave_the_same() {
+        run_the_tape_scenario::<u8>();
+        run_the_tape_scenario::<u16>();
+        run_the_tape_scenario::<u32>();
+        run_the_tape_scenario::<i8>();
+        run_the_tape_scenario::<i32>()
+    }
+
+    #[test]
+    fn when_a_cell_value_is_rendered_then_it_is_padded_to_the_width_of_the_cell() {
+        assert_eq!(255_u8.render(), "255");
+        assert_eq!(7_u16.render(), "    7");
+        assert_eq!((-1_i8).render(), "  -1");
+        assert_eq!(i32::MIN.render(), "-2147483648");
+        assert_eq!(u32::MAX.render().len(), u32::WIDTH)
+    }
+
+    #[test]
+    fn when_a_value_out_of_range_is_converted_to_a_cell_then_it_wraps_around() {
+        assert_eq!(u8::from_i64(256), 0);
+        assert_eq!(u8::from_i64(-1), 255);
+        assert_eq!(i8::from_i64(128), -128);
+        assert_eq!(u16::from_i64(65), 65)
+    }
+
+    #[test]
+    fn when_update_cell_value_failed_then_memory_cell_is_not_updated() {
+        let mut memory = BrainfuckMemory::default();
+
+        memory.update_memory_cell_value(|_value| Ok(23)).unwrap();
+
+        memory
+            .update_memory_cell_value(|_value| {
+                Err(MemoryErrors::CellOverflow {
+                    position: 0,
+                    delta: 1,
+                })
+            })
+            .unwrap_err();
+
+        assert_eq!(memory.get_current_cell_value(), 23)
+    }
+
+    #[test]
+    fn when_formatting_a_value_in_debug_mode_then_ascii_values_are_quoted_and_the_rest_escaped() {
+        let mut output: Vec<u8> = vec![];
+
+        DebugFormatter.format(65, &mut output).unwrap();
+        DebugFormatter.format(200, &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "'A''\\xC8'")
+    }
+
+    #[test]
+    fn when_formatting_a_value_in_numeric_mode_then_write_the_number_and_the_separator() {
+        let mut output: Vec<u8> = vec![];
+        let formatter = NumericFormatter::new(",".to_string());
+
+        formatter.format(7, &mut output).unwrap();
+        formatter.format(200, &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "7,200,")
+    }
+
+    #[test]
+    fn when_a_cell_wider_than_a_byte_is_printed_then_only_the_numeric_formatters_keep_its_whole_value()
+     {
+        let numeric = WriterOutput::new(vec![], NumericFormatter::new(" ".to_string()));
+        let csv = WriterOutput::new(vec![], CsvFormatter::default());
+        let raw = WriterOutput::new(vec![], RawFormatter);
+
+        for output in [&numeric as &dyn OutputValue, &csv, &raw] {
+            output.print_cell(300).unwrap();
+            output.print_cell(65).unwrap();
+            output.finish().unwrap();
+        }
+
+        assert_eq!(numeric.into_inner(), b"300 65 ");
+        assert_eq!(csv.into_inner(), b"300,65\n");
+        assert_eq!(raw.into_inner(), [44, 65])
+    }
+
+    #[test]
+    fn when_formatting_a_value_in_hex_mode_then_write_two_hex_digits_and_the_separator() {
+        let mut output: Vec<u8> = vec![];
+        let formatter = HexFormatter::new(" ".to_string());
+
+        formatter.format(7, &mut output).unwrap();
+        formatter.format(200, &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "07 c8 ")
+    }
+
+    #[test]
+    fn when_a_writer_output_prints_then_the_formatted_value_is_written_in_the_writer() {
+        let output = WriterOutput::new(vec![], NumericFormatter::new(" ".to_string()));
+
+        output.print(65).unwrap();
+        output.flush().unwrap();
+
+        assert_eq!(output.into_inner(), b"65 ")
+    }
+
+    #[test]
+    fn when_a_collecting_output_is_used_behind_a_mutable_reference_then_the_owner_keeps_the_bytes()
+    {
+        let mut output = CollectingOutput::new();
+
+        {
+            let borrowed_output = &mut output;
+            borrowed_output.print(72).unwrap();
+            borrowed_output.print(105).unwrap();
+        }
+
+        assert_eq!(*output.as_bytes(), *b"Hi");
+        assert_eq!(output.into_string_lossy(), "Hi")
+    }
+
+    #[test]
+    fn when_a_queued_input_is_read_then_returns_the_bytes_in_order_and_end_of_input_when_empty() {
+        let inputs = [
+            QueuedInput::from("ab"),
+            QueuedInput::from(vec![97, 98]),
+            "ab".bytes().collect::<QueuedInput>(),
+        ];
+
+        for mut input in inputs {
+            assert_eq!(input.get_input(), Ok(ProgramValue(b'a')));
+            assert_eq!(input.get_input(), Ok(ProgramValue(b'b')));
+            assert_eq!(input.get_input(), Err(InputError::EndOfInput));
+        }
+    }
+
+    #[test]
+    fn when_a_reader_input_is_read_then_returns_every_byte_and_end_of_input_at_the_end() {
+        let mut input = ReaderInput::new(&[104, 200][..]);
+
+        assert_eq!(input.get_input(), Ok(ProgramValue(104)));
+        assert_eq!(input.get_input(), Ok(ProgramValue(200)));
+        assert_eq!(input.get_input(), Err(InputError::EndOfInput))
+    }
+
+    #[test]
+    fn given_no_stdin_mode_requested_when_stdin_is_not_a_terminal_then_read_bytes_without_warning()
+    {
+        assert_eq!(
+            select_stdin_mode(None, false),
+            StdinSelection {
+                mode: StdinMode::Bytes,
+                warning: None
+            }
+        );
+        assert_eq!(
+            select_stdin_mode(None, true),
+            StdinSelection {
+                mode: StdinMode::Prompt,
+                warning: None
+            }
+        )
+    }
+
+    #[test]
+    fn given_a_stdin_mode_requested_then_it_is_used_and_bytes_on_a_terminal_shows_a_warning() {
+        assert_eq!(
+            select_stdin_mode(Some(StdinMode::Prompt), false).mode,
+            StdinMode::Prompt
+        );
+
+        let selection = select_stdin_mode(Some(StdinMode::Bytes), true);
+
+        assert_eq!(selection.mode, StdinMode::Bytes);
+        assert!(selection.warning.is_some())
+    }
+
+    #[test]
+    fn given_a_limited_writer_when_a_write_crosses_the_limit_then_only_the_bytes_up_to_the_limit_are_written()
+     {
+        let mut writer = LimitedWriter::new(vec![], 3);
+
+        let error = writer.write_all(b"abcd").unwrap_err();
+
+        assert_eq!(
+            OutputError::from(error),
+            OutputError::LimitReached { limit: 3 }
+        );
+        assert_eq!(writer.into_inner(), b"abc")
+    }
+
+    #[test]
+    fn when_a_write_fails_because_the_reader_closed_the_pipe_then_the_output_is_closed() {
+        assert_eq!(
+            OutputError::from(io::Error::from(io::ErrorKind::BrokenPipe)),
+            OutputError::Closed
+        );
+        assert!(matches!(
+            OutputError::from(io::Error::other("The disk is full")),
+            OutputError::WriteFailed(_)
+        ))
+    }
+
+    #[test]
+    fn given_a_tee_output_when_one_of_the_outputs_is_closed_then_the_error_is_not_named() {
+        let tee = TeeOutput::new(
+            ("stdout", WriterOutput::new(ClosedWriter, RawFormatter)),
+            ("output file", NullOutput),
+        );
+
+        assert_eq!(tee.print(b'a'), Err(OutputError::Closed))
+    }
+
+    #[test]
+    fn given_the_utf8_formatter_when_a_character_is_split_in_several_values_then_it_is_written_once_complete()
+     {
+        let formatter = Utf8Formatter::default();
+        let mut output: Vec<u8> = vec![];
+
+        formatter.format(0xC3, &mut output).unwrap();
+        assert!(output.is_empty());
+
+        for value in [0xA9, 0xE2, 0x82, 0xAC] {
+            formatter.format(value, &mut output).unwrap();
+        }
+
+        assert_eq!(String::from_utf8(output).unwrap(), "é€")
+    }
+
+    #[test]
+    fn given_the_utf8_formatter_when_there_are_invalid_or_dangling_bytes_then_they_are_written_as_replacement_characters()
+     {
+        let formatter = Utf8Formatter::default();
+        let mut output: Vec<u8> = vec![];
+
+        for value in [b'a', 0xFF, b'b', 0xE2, 0x82] {
+            formatter.format(value, &mut output).unwrap();
+        }
+        formatter.finish(&mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "a\u{FFFD}b\u{FFFD}")
+    }
+
+    #[test]
+    fn given_the_utf8_formatter_with_a_lower_threshold_when_the_pending_bytes_reach_it_then_they_are_replaced()
+     {
+        let formatter = Utf8Formatter::new(2);
+        let mut output: Vec<u8> = vec![];
+
+        for value in [0xE2, 0x82, b'c'] {
+            formatter.format(value, &mut output).unwrap();
+        }
+
+        assert_eq!(String::from_utf8(output).unwrap(), "\u{FFFD}c")
+    }
+
+    #[test]
+    fn given_a_transcript_input_when_the_inputs_are_read_then_every_event_is_written_in_a_line() {
+        let mut transcript: Vec<u8> = vec![];
+        let mut input = TranscriptInput::new(
+            QueuedInput::from(vec![b'a', b'\n']),
+            &mut transcript,
+            Instant::now(),
+        );
+
+        assert_eq!(input.get_input(), Ok(ProgramValue(b'a')));
+        assert_eq!(input.get_input(), Ok(ProgramValue(b'\n')));
+        assert_eq!(input.get_input(), Err(InputError::EndOfInput));
+
+        drop(input);
+
+        let events: Vec<String> = String::from_utf8(transcript)
+            .unwrap()
+            .lines()
+            .map(|line| line.split_once(' ').unwrap().1.to_string())
+            .collect();
+
+        assert_eq!(
+            events,
+            [
+                "input byte=97 char='a'",
+                "input byte=10 char='\\n'",
+                "input end_of_input"
+            ]
+        )
+    }
+
+    #[test]
+    fn given_a_transcript_when_it_is_parsed_then_the_input_events_are_replayed_in_order() {
+        let transcript = "+0.000010s input prompt=\"Write a byte=\" byte=97 char='a'\n\
+                          +0.000020s input byte=32 char=' '\n\
+                          +0.000030s input end_of_input\n\
+                          +0.000040s outcome success\n";
+
+        let recording = Recording::parse(transcript.as_bytes());
+        let mut input = ReplayInput::new(recording);
+
+        assert_eq!(input.get_input(), Ok(ProgramValue(b'a')));
+        assert_eq!(input.get_input(), Ok(ProgramValue(b' ')));
+        assert_eq!(input.get_input(), Err(InputError::EndOfInput));
+        assert_eq!(
+            input.get_input(),
+            Err(InputError::ReplayDiverged {
+                requested: 4,
+                recorded: 3
+            })
+        )
+    }
+
+    #[test]
+    fn given_a_file_that_isnt_a_transcript_when_it_is_parsed_then_it_is_read_as_raw_bytes() {
+        let recording = Recording::parse(b"hi\n");
+
+        assert_eq!(
+            recording.events,
+            [
+                RecordedInput::Byte(b'h'),
+                RecordedInput::Byte(b'i'),
+                RecordedInput::Byte(b'\n'),
+                RecordedInput::EndOfInput
+            ]
+        )
+    }
+
+    #[test]
+    fn given_a_recording_when_the_program_requested_fewer_inputs_then_the_unused_bytes_are_counted()
+    {
+        let recording = Recording::parse(b"abc");
+
+        assert_eq!(recording.unused_bytes(1), 2);
+        assert_eq!(recording.unused_bytes(3), 0);
+        assert_eq!(recording.unused_bytes(4), 0)
+    }
+
+    #[test]
+    fn when_a_position_is_written_as_ordinal_then_it_has_the_english_suffix() {
+        let ordinals: Vec<String> = [1, 2, 3, 4, 11, 12, 13, 21, 102].map(ordinal).to_vec();
+
+        assert_eq!(
+            ordinals,
+            [
+                "1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "102nd"
+            ]
+        )
+    }
+
+    fn read_all(input: &mut impl InputValue) -> Vec<u8> {
+        let mut bytes = vec![];
+        while let Ok(ProgramValue(byte)) = input.get_input() {
+            bytes.push(byte);
+        }
+        bytes
+    }
+
+    #[test]
+    fn given_mixed_line_endings_when_translated_to_lf_then_every_line_ends_with_a_single_lf() {
+        let mut input = NewlineInput::new(QueuedInput::from("a\r\nb\rc\n\r\r\n"), NewlineMode::Lf);
+
+        assert_eq!(read_all(&mut input), b"a\nb\nc\n\n\n")
+    }
+
+    #[test]
+    fn given_mixed_line_endings_when_translated_to_crlf_then_every_lf_has_a_cr_before() {
+        let mut input = NewlineInput::new(QueuedInput::from("a\r\nb\nc\r"), NewlineMode::Crlf);
+
+        assert_eq!(read_all(&mut input), b"a\r\nb\r\nc\r")
+    }
+
+    #[test]
+    fn given_a_crlf_split_in_two_reads_when_translated_to_lf_then_the_end_of_input_is_kept() {
+        let mut input = NewlineInput::new(QueuedInput::from("\r\n"), NewlineMode::Lf);
+
+        assert_eq!(input.get_input(), Ok(ProgramValue(b'\n')));
+        assert_eq!(input.get_input(), Err(InputError::EndOfInput))
+    }
+
+    #[test]
+    fn given_passthrough_when_the_input_is_read_then_the_bytes_are_not_changed() {
+        let mut input = NewlineInput::new(QueuedInput::from("a\r\nb\r"), NewlineMode::Passthrough);
+
+        assert_eq!(read_all(&mut input), b"a\r\nb\r")
+    }
+
+    fn take_bytes(input: &mut impl InputValue, count: usize) -> Vec<u8> {
+        (0..count).map(|_| input.get_input().unwrap().0).collect()
+    }
+
+    #[test]
+    fn given_two_random_inputs_with_the_same_seed_when_read_then_the_sequences_are_identical() {
+        let mut first = RandomInput::new(42);
+        let mut second = RandomInput::new(42);
+
+        assert_eq!(take_bytes(&mut first, 64), take_bytes(&mut second, 64))
+    }
+
+    #[test]
+    fn given_two_random_inputs_with_different_seeds_when_read_then_the_sequences_differ() {
+        let mut first = RandomInput::new(1);
+        let mut second = RandomInput::new(2);
+
+        assert_ne!(take_bytes(&mut first, 64), take_bytes(&mut second, 64))
+    }
+
+    #[test]
+    fn given_a_random_input_when_read_then_every_byte_is_in_the_range() {
+        let mut printable = RandomInput::new(7);
+        let mut digits = RandomInput::new(7).with_range(b'0'..=b'9');
+
+        assert!(
+            take_bytes(&mut printable, 256)
+                .iter()
+                .all(|byte| (b' '..=b'~').contains(byte))
+        );
+        assert!(take_bytes(&mut digits, 256).iter().all(u8::is_ascii_digit))
+    }
+
+    #[test]
+    fn given_a_40_bytes_output_when_formatted_as_hexdump_then_the_rows_have_the_offsets_and_the_printable_characters()
+     {
+        let mut output: Vec<u8> = vec![];
+        let display = WriterOutput::new(&mut output, HexdumpFormatter::new());
+        let bytes: Vec<u8> = b"Hello World!\n\0\x01\x7f"
+            .iter()
+            .copied()
+            .chain(b'A'..=b'T')
+            .chain([0xff, 0x80, b' ', b'~'])
+            .collect();
+
+        assert_eq!(bytes.len(), 40);
+        bytes.iter().for_each(|byte| display.print(*byte).unwrap());
+        display.finish().unwrap();
+        drop(display);
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "00000000  48 65 6c 6c 6f 20 57 6f  72 6c 64 21 0a 00 01 7f  |Hello World!....|\n\
+             00000010  41 42 43 44 45 46 47 48  49 4a 4b 4c 4d 4e 4f 50  |ABCDEFGHIJKLMNOP|\n\
+             00000020  51 52 53 54 ff 80 20 7e                           |QRST.. ~|\n\
+             00000028\n"
+        )
+    }
+
+    #[test]
+    fn given_a_hexdump_output_when_a_row_is_complete_then_it_is_written_before_the_end() {
+        let mut output: Vec<u8> = vec![];
+        let display = WriterOutput::new(&mut output, HexdumpFormatter::new());
+
+        (0..20).for_each(|byte| display.print(byte).unwrap());
+        drop(display);
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "00000000  00 01 02 03 04 05 06 07  08 09 0a 0b 0c 0d 0e 0f  |................|\n"
+        )
+    }
+
+    #[test]
+    fn given_the_csv_formatter_when_the_program_ends_then_the_values_are_a_single_row() {
+        let mut output: Vec<u8> = vec![];
+        let display = WriterOutput::new(&mut output, CsvFormatter::default());
+
+        [0, 127, 255]
+            .iter()
+            .for_each(|value| display.print(*value).unwrap());
+        display.finish().unwrap();
+        drop(display);
+
+        assert_eq!(output, b"0,127,255\n")
+    }
+
+    #[test]
+    fn given_the_csv_formatter_without_output_when_the_program_ends_then_nothing_is_written() {
+        let mut output: Vec<u8> = vec![];
+        let display = WriterOutput::new(&mut output, CsvFormatter::new(";".to_string()));
+
+        display.finish().unwrap();
+        drop(display);
+
+        assert_eq!(output, b"")
+    }
+
+    #[test]
+    fn given_the_last_output_of_the_program_when_it_isnt_a_newline_then_the_output_line_is_pending()
+    {
+        let context = |last_output| InputContext {
+            last_output,
+            ..InputContext::default()
+        };
+
+        assert!(context(Some(b'?')).output_line_pending());
+        assert!(!context(Some(b'\n')).output_line_pending());
+        assert!(!context(None).output_line_pending())
+    }
+
+    #[test]
+    fn given_a_bidirectional_memory_when_the_pointer_moves_left_from_the_start_then_the_cell_minus_one_is_updated()
+     {
+        let mut memory = BidirectionalMemory::default();
+
+        memory.move_pointer_position(-1).unwrap();
+        memory
+            .update_memory_cell_value(|value| Ok(value + 1))
+            .unwrap();
+
+        assert_eq!(memory.get_logical_position(), -1);
+        assert_eq!(memory.get_position(), 0);
+        assert_eq!(memory.get_current_cell_value(), 1);
+
+        memory.move_pointer_position(1).unwrap();
+
+        assert_eq!(memory.get_logical_position(), 0);
+        assert_eq!(memory.get_position(), 1);
+        assert_eq!(memory.get_current_cell_value(), 0)
+    }
+
+    #[test]
+    fn given_a_bidirectional_memory_when_the_pointer_goes_back_to_a_cell_then_its_value_is_kept() {
+        let mut memory = BidirectionalMemory::default();
+
+        for step in [-3, 5, -2] {
+            memory.move_pointer_position(step).unwrap();
+            memory.update_memory_cell_value(|_| Ok(7)).unwrap();
+        }
+        memory.move_pointer_position(-3).unwrap();
+
+        assert_eq!(memory.get_logical_position(), -3);
+        assert_eq!(memory.get_current_cell_value(), 7)
+    }
+
+    #[test]
+    fn given_a_bidirectional_memory_with_caps_when_the_pointer_crosses_them_then_return_an_error() {
+        let mut memory = BidirectionalMemory::with_caps(2, 3);
+
+        assert_eq!(memory.move_pointer_position(-2), Ok(()));
+        assert_eq!(
+            memory.move_pointer_position(-1),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: -3,
+                bounds: -2..3
+            })
+        );
+        assert_eq!(memory.move_pointer_position(4), Ok(()));
+        assert_eq!(
+            memory.move_pointer_position(1),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: 3,
+                bounds: -2..3
+            })
+        );
+        assert_eq!(memory.get_logical_position(), 2)
+    }
+
+    #[test]
+    fn given_a_sparse_memory_when_cells_far_from_each_other_are_written_then_only_they_are_stored()
+    {
+        let mut memory = SparseMemory::default();
+
+        for (step, value) in [(0, 1), (1_000_000, 2), (1_000_000, 3)] {
+            memory.move_pointer_position(step).unwrap();
+            memory.update_memory_cell_value(|_| Ok(value)).unwrap();
+        }
+
+        assert_eq!(memory.get_position(), 2_000_000);
+        assert_eq!(memory.get_current_cell_value(), 3);
+        memory.move_pointer_position(-1_000_000).unwrap();
+        assert_eq!(memory.get_current_cell_value(), 2);
+        memory.move_pointer_position(-1_000_000).unwrap();
+        assert_eq!(memory.get_current_cell_value(), 1);
+        assert_eq!(memory.stored_cells(), 3)
+    }
+
+    #[test]
+    fn given_a_sparse_memory_when_a_cell_is_written_back_to_zero_then_it_is_removed() {
+        let mut memory = SparseMemory::default();
+
+        memory.move_pointer_position(42).unwrap();
+        memory.update_memory_cell_value(|_| Ok(5)).unwrap();
+        memory.update_memory_cell_value(|_| Ok(0)).unwrap();
+
+        assert_eq!(memory.get_current_cell_value(), 0);
+        assert_eq!(memory.stored_cells(), 0)
+    }
+
+    #[test]
+    fn given_a_memory_when_the_pointer_moves_to_the_end_of_the_tape_then_return_an_error() {
+        let mut memory = BrainfuckMemory::<u8>::new(2);
+
+        assert_eq!(
+            memory.move_pointer_position(2),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: 2,
+                bounds: 0..2
+            })
+        );
+        assert_eq!(memory.move_pointer_position(1), Ok(()));
+        assert_eq!(
+            memory.move_pointer_position(1),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: 2,
+                bounds: 0..2
+            })
+        );
+        assert_eq!(memory.get_position(), 1);
+        assert_eq!(memory.get_current_cell_value(), 0)
+    }
+
+    #[test]
+    fn given_a_sparse_memory_when_the_pointer_leaves_the_tape_then_return_an_error() {
+        let mut memory = SparseMemory::new(10);
+
+        assert_eq!(
+            memory.move_pointer_position(-1),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: -1,
+                bounds: 0..10
+            })
+        );
+        assert_eq!(
+            memory.move_pointer_position(10),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: 10,
+                bounds: 0..10
+            })
+        );
+        assert_eq!(memory.move_pointer_position(9), Ok(()));
+
+        let mut unbounded = SparseMemory::unbounded();
+
+        assert_eq!(unbounded.move_pointer_position(isize::MAX), Ok(()));
+        assert_eq!(unbounded.move_pointer_position(isize::MAX), Ok(()));
+        assert_eq!(
+            unbounded.move_pointer_position(1),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: usize::MAX as i128,
+                bounds: 0..usize::MAX as i128
+            })
+        )
+    }
+
+    #[test]
+    fn given_each_memory_when_a_cell_is_read_by_position_then_the_cells_outside_of_the_tape_are_none()
+     {
+        let mut vec_memory = BrainfuckMemory::<u8>::new(3);
+        vec_memory.move_pointer_position(2).unwrap();
+        vec_memory.update_memory_cell_value(|_| Ok(9)).unwrap();
+
+        assert_eq!(vec_memory.get_cell_at(2), Some(9));
+        assert_eq!(vec_memory.get_cell_at(0), Some(0));
+        assert_eq!(vec_memory.get_cell_at(3), None);
+        assert_eq!(vec_memory.get_cell_at(-1), None);
+
+        let mut bidirectional_memory = BidirectionalMemory::with_caps(2, 2);
+        bidirectional_memory.move_pointer_position(-2).unwrap();
+        bidirectional_memory
+            .update_memory_cell_value(|_| Ok(4))
+            .unwrap();
+
+        assert_eq!(bidirectional_memory.get_cell_at(-2), Some(4));
+        assert_eq!(bidirectional_memory.get_cell_at(1), Some(0));
+        assert_eq!(bidirectional_memory.get_cell_at(2), None);
+        assert_eq!(bidirectional_memory.get_cell_at(-3), None);
+
+        let mut sparse_memory = SparseMemory::new(100);
+        sparse_memory.move_pointer_position(50).unwrap();
+        sparse_memory.update_memory_cell_value(|_| Ok(1)).unwrap();
+
+        assert_eq!(sparse_memory.get_cell_at(50), Some(1));
+        assert_eq!(sparse_memory.get_cell_at(99), Some(0));
+        assert_eq!(sparse_memory.get_cell_at(100), None)
+    }
+
+    #[test]
+    fn given_each_memory_when_a_slice_is_loaded_then_the_cells_have_its_values() {
+        let mut vec_memory = BrainfuckMemory::<u8>::new(8);
+        vec_memory.load_slice(5, b"HI").unwrap();
+
+        assert_eq!(vec_memory.get_cell_at(5), Some(b'H'));
+        assert_eq!(vec_memory.get_cell_at(6), Some(b'I'));
+        assert_eq!(vec_memory.get_position(), 0);
+
+        let mut bidirectional_memory = BidirectionalMemory::with_caps(2, 2);
+        bidirectional_memory.load_slice(-2, b"abc").unwrap();
+
+        assert_eq!(bidirectional_memory.get_cell_at(-2), Some(b'a'));
+        assert_eq!(bidirectional_memory.get_cell_at(0), Some(b'c'));
+        bidirectional_memory.move_pointer_position(-1).unwrap();
+        assert_eq!(bidirectional_memory.get_current_cell_value(), b'b');
+
+        let mut sparse_memory = SparseMemory::default();
+        sparse_memory.load_slice(1_000_000, &[1, 0, 2]).unwrap();
+
+        assert_eq!(sparse_memory.get_cell_at(1_000_002), Some(2));
+        assert_eq!(sparse_memory.stored_cells(), 2)
+    }
+
+    #[test]
+    fn given_each_memory_when_a_slice_does_not_fit_then_return_an_error_and_nothing_is_written() {
+        let mut vec_memory = BrainfuckMemory::<u8>::new(4);
+
+        assert_eq!(
+            vec_memory.load_slice(3, b"ab"),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: 4,
+                bounds: 0..4
+            })
+        );
+        assert_eq!(
+            vec_memory.load_slice(-1, b"a"),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: -1,
+                bounds: 0..4
+            })
+        );
+        assert_eq!(vec_memory.get_cell_at(3), Some(0));
+
+        let mut bidirectional_memory = BidirectionalMemory::with_caps(1, 2);
+
+        assert_eq!(
+            bidirectional_memory.load_slice(-2, b"ab"),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: -2,
+                bounds: -1..2
+            })
+        );
+        assert_eq!(
+            bidirectional_memory.load_slice(0, b"abc"),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: 2,
+                bounds: -1..2
+            })
+        );
+        assert_eq!(bidirectional_memory.get_cell_at(-1), Some(0));
+
+        let mut sparse_memory = SparseMemory::new(10);
+
+        assert_eq!(
+            sparse_memory.load_slice(8, b"abc"),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: 10,
+                bounds: 0..10
+            })
+        );
+        assert_eq!(sparse_memory.stored_cells(), 0)
+    }
+
+    #[test]
+    fn given_a_watched_region_when_cells_are_written_then_only_the_changes_inside_it_are_drained() {
+        let mut memory = BrainfuckMemory::<u8>::new(8);
+        memory.watch(2..4);
+
+        for (position, value) in [(1, 1), (2, 2), (2, 2), (3, 3), (4, 4)] {
+            let step = position - memory.get_logical_position();
+            memory.move_pointer_position(step).unwrap();
+            memory.set_current_instruction(Some(position as usize));
+            memory.update_memory_cell_value(|_| Ok(value)).unwrap();
+        }
+
+        assert_eq!(
+            memory.drain_changes(),
+            [
+                CellChange {
+                    position: 2,
+                    old: 0,
+                    new: 2,
+                    instruction: Some(2),
+                    tape: None
+                },
+                CellChange {
+                    position: 3,
+                    old: 0,
+                    new: 3,
+                    instruction: Some(3),
+                    tape: None
+                }
+            ]
+        );
+        assert_eq!(memory.drain_changes(), [])
+    }
+
+    #[test]
+    fn given_an_unwatched_region_when_its_cells_are_written_then_nothing_is_recorded() {
+        let mut memory = BrainfuckMemory::<u8>::new(4);
+        let id = memory.watch(0..4);
+
+        memory.unwatch(id);
+        memory.load_slice(0, b"abcd").unwrap();
+
+        assert!(!memory.has_watches());
+        assert_eq!(memory.drain_changes(), [])
+    }
+
+    #[test]
+    fn given_a_tape_without_watching_when_a_region_is_watched_then_nothing_is_recorded() {
+        let mut memory = SparseMemory::default();
+
+        memory.watch(0..10);
+        memory.update_memory_cell_value(|_| Ok(1)).unwrap();
+
+        assert!(!memory.has_watches());
+        assert_eq!(memory.drain_changes(), [])
+    }
+
+    #[test]
+    fn given_a_vec_memory_when_the_pointer_goes_and_comes_back_then_the_high_water_mark_is_kept() {
+        let mut memory = BrainfuckMemory::<u8>::new(10);
+
+        for step in [3, -2, 5, -6] {
+            memory.move_pointer_position(step).unwrap();
+            memory.update_memory_cell_value(|_| Ok(1)).unwrap();
+        }
+        let _ = memory.move_pointer_position(20);
+
+        assert_eq!(memory.max_position_reached(), 6);
+        assert_eq!(memory.min_position_reached(), 0);
+        assert_eq!(memory.cells_touched(), 7)
+    }
+
+    #[test]
+    fn given_a_bidirectional_memory_when_the_pointer_moves_both_ways_then_both_extremes_are_kept() {
+        let mut memory = BidirectionalMemory::with_caps(5, 5);
+
+        for step in [2, -5, 1] {
+            memory.move_pointer_position(step).unwrap();
+            memory.update_memory_cell_value(|_| Ok(1)).unwrap();
+        }
+        let _ = memory.move_pointer_position(-4);
+
+        assert_eq!(memory.max_position_reached(), 2);
+        assert_eq!(memory.min_position_reached(), -3);
+        assert_eq!(memory.cells_touched(), 6)
+    }
+
+    #[test]
+    fn given_a_sparse_memory_when_far_cells_are_written_then_the_cells_touched_are_the_stored_ones()
+    {
+        let mut memory = SparseMemory::default();
+
+        for (step, value) in [(10, 1), (1_000_000, 2), (-500_000, 0)] {
+            memory.move_pointer_position(step).unwrap();
+            memory.update_memory_cell_value(|_| Ok(value)).unwrap();
+        }
+
+        assert_eq!(memory.max_position_reached(), 1_000_010);
+        assert_eq!(memory.min_position_reached(), 0);
+        assert_eq!(memory.cells_touched(), 2)
+    }
+
+    #[test]
+    fn given_each_memory_when_the_nonzero_cells_are_iterated_then_only_the_written_ones_are_returned()
+     {
+        let mut vec_memory = BrainfuckMemory::<u8>::new(10);
+        vec_memory.load_slice(2, &[7, 0, 9]).unwrap();
+        vec_memory.move_pointer_position(5).unwrap();
+
+        assert_eq!(
+            vec_memory.iter_nonzero().collect::<Vec<_>>(),
+            [(2, 7), (4, 9)]
+        );
+
+        let mut bidirectional_memory = BidirectionalMemory::default();
+        bidirectional_memory.move_pointer_position(-2).unwrap();
+        bidirectional_memory
+            .update_memory_cell_value(|_| Ok(3))
+            .unwrap();
+
+        assert_eq!(
+            bidirectional_memory.iter_nonzero().collect::<Vec<_>>(),
+            [(-2, 3)]
+        );
+
+        let mut sparse_memory = SparseMemory::default();
+        sparse_memory.load_slice(1_000_000, &[1, 0, 2]).unwrap();
+        let mut sparse_cells = sparse_memory.iter_nonzero().collect::<Vec<_>>();
+        sparse_cells.sort();
+
+        assert_eq!(sparse_cells, [(1_000_000, 1), (1_000_002, 2)])
+    }
+
+    #[test]
+    fn given_a_vec_memory_when_cells_are_accessed_by_position_then_the_pointer_does_not_move() {
+        let mut memory = BrainfuckMemory::<u16>::new(5);
+
+        memory.set_cell_at(4, 300).unwrap();
+        memory.set_cell_at(0, 1).unwrap();
+
+        assert_eq!(memory.get_cell_at(4), Some(300));
+        assert_eq!(memory.get_position(), 0);
+        assert_eq!(
+            memory.set_cell_at(5, 1),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: 5,
+                bounds: 0..5
+            })
+        );
+        assert_eq!(memory.view(-2..2), [1, 0]);
+        assert_eq!(memory.view(3..9), [0, 300]);
+        assert!(memory.view(7..9).is_empty())
+    }
+
+    #[test]
+    fn given_a_bidirectional_memory_when_cells_are_accessed_by_position_then_both_sides_are_reachable()
+     {
+        let mut memory = BidirectionalMemory::with_caps(3, 3);
+
+        memory.set_cell_at(-3, 7).unwrap();
+        memory.set_cell_at(2, 9).unwrap();
+
+        assert_eq!(memory.get_cell_at(-3), Some(7));
+        assert_eq!(memory.get_logical_position(), 0);
+        assert_eq!(
+            memory.set_cell_at(-4, 1),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: -4,
+                bounds: -3..3
+            })
+        );
+        assert_eq!(memory.view(-5..5), [7, 0, 0, 0, 0, 9]);
+        memory.move_pointer_position(-3).unwrap();
+        assert_eq!(memory.get_current_cell_value(), 7)
+    }
+
+    #[test]
+    fn given_a_sparse_memory_when_cells_are_accessed_by_position_then_only_the_nonzero_ones_are_stored()
+     {
+        let mut memory = SparseMemory::new(1_000_000);
+
+        memory.set_cell_at(999_999, 5).unwrap();
+        memory.set_cell_at(10, 0).unwrap();
+
+        assert_eq!(memory.get_cell_at(999_999), Some(5));
+        assert_eq!(memory.stored_cells(), 1);
+        assert_eq!(
+            memory.set_cell_at(1_000_000, 1),
+            Err(MemoryErrors::OutOfRangePosition {
+                target: 1_000_000,
+                bounds: 0..1_000_000
+            })
+        );
+        assert_eq!(memory.view(999_998..1_000_002), [0, 5])
+    }
+
+    #[test]
+    fn given_a_snapshot_when_many_cells_are_written_and_it_is_restored_then_the_tape_is_the_same() {
+        let mut memory = BrainfuckMemory::<u8>::new(30_000);
+        memory.load_slice(0, b"Hello").unwrap();
+        let original = memory.view(0..30_000);
+
+        let snapshot = memory.snapshot();
+        for position in 0..1000 {
+            memory
+                .set_cell_at(position * 7, (position % 251) as u8 + 1)
+                .unwrap();
+        }
+        memory.move_pointer_position(42).unwrap();
+        memory.update_memory_cell_value(|_| Ok(9)).unwrap();
+
+        assert_eq!(memory.restore(&snapshot), Ok(()));
+        assert_eq!(memory.view(0..30_000), original);
+        assert_eq!(memory.get_position(), 0)
+    }
+
+    #[test]
+    fn given_a_snapshot_when_cells_are_written_then_the_journal_grows_with_the_writes_and_not_the_tape()
+     {
+        let mut memory = BrainfuckMemory::<u8>::new(1_000_000);
+
+        memory.set_cell_at(1, 1).unwrap();
+        assert_eq!(memory.journal_length(), 0);
+
+        memory.snapshot();
+        for _ in 0..10 {
+            memory
+                .update_memory_cell_value(|value| Ok(value + 1))
+                .unwrap();
+        }
+
+        assert_eq!(memory.journal_length(), 10);
+
+        memory.release_snapshots();
+        memory.update_memory_cell_value(|_| Ok(0)).unwrap();
+
+        assert_eq!(memory.journal_length(), 0)
+    }
+
+    #[test]
+    fn given_nested_snapshots_when_the_older_one_is_restored_then_the_newer_one_is_discarded() {
+        let mut memory = BrainfuckMemory::<u8>::new(4);
+
+        let older = memory.snapshot();
+        memory.set_cell_at(0, 1).unwrap();
+        let newer = memory.snapshot();
+        memory.set_cell_at(1, 2).unwrap();
+
+        assert_eq!(memory.restore(&newer), Ok(()));
+        assert_eq!(memory.view(0..4), [1, 0, 0, 0]);
+        assert_eq!(memory.restore(&older), Ok(()));
+        assert_eq!(memory.view(0..4), [0, 0, 0, 0]);
+        assert_eq!(memory.restore(&newer), Err(SnapshotError::Discarded));
+
+        memory.set_cell_at(3, 3).unwrap();
+
+        assert_eq!(memory.restore(&older), Ok(()));
+        assert_eq!(memory.view(0..4), [0, 0, 0, 0])
+    }
+
+    #[test]
+    fn given_a_snapshot_of_another_tape_when_it_is_restored_then_return_an_error() {
+        let mut memory = BrainfuckMemory::<u8>::new(4);
+        let mut other = BrainfuckMemory::<u8>::new(4);
+
+        let snapshot = other.snapshot();
+
+        assert_eq!(memory.restore(&snapshot), Err(SnapshotError::ForeignTape))
+    }
+
+    #[test]
+    fn given_each_bounds_policy_when_the_pointer_leaves_the_tape_then_it_fails_wraps_or_grows() {
+        let mut error_memory = BrainfuckMemory::<u8>::new(4);
+
+        assert!(error_memory.move_pointer_position(5).is_err());
+        assert!(error_memory.move_pointer_position(-1).is_err());
+
+        let mut wrap_memory = BrainfuckMemory::<u8>::new(4).with_bounds_policy(BoundsPolicy::Wrap);
+
+        assert_eq!(wrap_memory.move_pointer_position(5), Ok(()));
+        assert_eq!(wrap_memory.get_position(), 1);
+        assert_eq!(wrap_memory.move_pointer_position(-2), Ok(()));
+        assert_eq!(wrap_memory.get_position(), 3);
+
+        let mut grow_memory = BrainfuckMemory::<u8>::new(4).with_bounds_policy(BoundsPolicy::Grow);
+
+        assert_eq!(grow_memory.move_pointer_position(5), Ok(()));
+        assert_eq!(grow_memory.get_position(), 5);
+        assert_eq!(grow_memory.size(), 6);
+        assert_eq!(grow_memory.update_memory_cell_value(|_| Ok(1)), Ok(()));
+        assert!(grow_memory.move_pointer_position(-6).is_err());
+        grow_memory.load_slice(8, b"ab").unwrap();
+        assert_eq!(grow_memory.size(), 10)
+    }
+
+    #[test]
+    fn given_a_pattern_when_the_memory_is_built_then_it_fills_alternating_cells_up_to_an_odd_size()
+    {
+        let mut memory =
+            BrainfuckMemory::<u8>::with_pattern(5, &[1, 2]).with_bounds_policy(BoundsPolicy::Grow);
+
+        assert_eq!(memory.view(0..5), [1, 2, 1, 2, 1]);
+        assert_eq!(memory.cells_touched(), 1);
+
+        memory.move_pointer_position(6).unwrap();
+
+        assert_eq!(memory.view(4..7), [1, 2, 1])
+    }
+
+    #[test]
+    fn given_an_init_value_when_the_memory_is_built_then_every_cell_has_it() {
+        let memory = BrainfuckMemory::<u16>::with_pattern(3, &[300]);
+
+        assert_eq!(memory.view(0..3), [300, 300, 300]);
+        assert_eq!(memory.max_position_reached(), 0)
+    }
+
+    #[test]
+    fn given_a_watched_multitape_memory_when_both_tapes_are_written_then_the_changes_are_labeled_with_their_tape()
+     {
+        let mut memory: MultiTape<BrainfuckMemory> = MultiTape::new(4);
+        let id = memory.watch(0..2);
+
+        assert!(memory.has_watches());
+
+        memory.update_memory_cell_value(|_| Ok(1)).unwrap();
+        memory.switch_tape(1);
+        memory.set_current_instruction(Some(3));
+        memory.update_memory_cell_value(|_| Ok(2)).unwrap();
+
+        assert_eq!(
+            memory.drain_changes(),
+            [
+                CellChange {
+                    position: 0,
+                    old: 0,
+                    new: 1,
+                    instruction: None,
+                    tape: Some(0)
+                },
+                CellChange {
+                    position: 0,
+                    old: 0,
+                    new: 2,
+                    instruction: Some(3),
+                    tape: Some(1)
+                }
+            ]
+        );
+
+        memory.unwatch(id);
+
+        assert!(!memory.has_watches())
+    }
+
+    #[test]
+    fn given_a_multitape_memory_when_the_tape_is_switched_then_each_tape_keeps_its_cells_and_pointer()
+     {
+        let mut memory: MultiTape<BrainfuckMemory> = MultiTape::new(4);
+
+        memory.move_pointer_position(2).unwrap();
+        memory.update_memory_cell_value(|_| Ok(7)).unwrap();
+        memory.switch_tape(1);
+
+        assert_eq!(memory.active_tape(), Some(1));
+        assert_eq!(memory.get_logical_position(), 0);
+        assert_eq!(memory.get_cell_at(2), Some(0));
+
+        memory.switch_tape(1);
+
+        assert_eq!(memory.active_tape(), Some(0));
+        assert_eq!(memory.get_current_cell_value(), 7);
+
+        memory.switch_tape(-1);
+
+        assert_eq!(memory.active_tape(), Some(1));
+        assert_eq!(memory.tapes()[0].get_cell_at(2), Some(7))
+    }
+
+    #[test]
+    fn given_a_vec_memory_when_the_logical_position_is_asked_then_it_is_the_position() {
+        let mut memory = BrainfuckMemory::default();
+
+        memory.move_pointer_position(5).unwrap();
+
+        assert_eq!(memory.get_logical_position(), 5)
     }
 }
@@ -0,0 +1,197 @@
+/*!
+ * Input and output doubles to test the code that runs the interpreter, so every
+ * test doesn't need to write its own.
+ */
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    io::{self, Write},
+};
+
+pub use super::{CollectingOutput, NullOutput, QueuedInput};
+use super::{InputError, InputValue, OutputError, OutputValue, ProgramValue};
+
+/**
+ * Input for the programs that must not read anything. The test fails as soon as
+ * the program asks for an input.
+ */
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PanicInput;
+
+impl InputValue for PanicInput {
+    fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+        panic!("No input expected for this test")
+    }
+}
+
+/**
+ * Output that accepts the given number of values and fails on the next ones, like
+ * a closed pipe.
+ */
+#[derive(Debug)]
+pub struct FailingOutput {
+    remaining_prints: Cell<usize>,
+}
+
+impl FailingOutput {
+    pub fn new(allowed_prints: usize) -> Self {
+        FailingOutput {
+            remaining_prints: Cell::new(allowed_prints),
+        }
+    }
+}
+
+impl OutputValue for FailingOutput {
+    fn print(&self, _value: u8) -> Result<(), OutputError> {
+        match self.remaining_prints.get() {
+            0 => Err(io::Error::other("The output is closed").into()),
+            remaining => {
+                self.remaining_prints.set(remaining - 1);
+                Ok(())
+            }
+        }
+    }
+}
+
+/**
+ * Input that hands out the given bytes and fails on the next read, instead of
+ * returning the end of the input as QueuedInput does.
+ */
+#[derive(Debug, Default)]
+pub struct FailingInput {
+    bytes: VecDeque<u8>,
+}
+
+impl FailingInput {
+    pub fn new(bytes: impl IntoIterator<Item = u8>) -> Self {
+        FailingInput {
+            bytes: bytes.into_iter().collect(),
+        }
+    }
+}
+
+impl InputValue for FailingInput {
+    fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+        self.bytes
+            .pop_front()
+            .map(ProgramValue)
+            .ok_or(InputError::Unknown)
+    }
+}
+
+/**
+ * Writer that accepts the given number of bytes and fails on the next writes, to
+ * test the outputs built on top of a writer.
+ */
+#[derive(Debug)]
+pub struct FailingWriter {
+    remaining_bytes: usize,
+}
+
+impl FailingWriter {
+    pub fn new(allowed_bytes: usize) -> Self {
+        FailingWriter {
+            remaining_bytes: allowed_bytes,
+        }
+    }
+}
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.remaining_bytes == 0 {
+            return Err(io::Error::other("The writer is closed"));
+        }
+
+        let written = buf.len().min(self.remaining_bytes);
+        self.remaining_bytes -= written;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/**
+ * Writer of a pipe whose reader is gone, like the stdout piped to a `head` that
+ * already ended.
+ */
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ClosedWriter;
+
+impl Write for ClosedWriter {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+}
+
+#[cfg(test)]
+mod testing_test {
+    use super::*;
+
+    #[test]
+    fn given_a_null_output_when_values_are_printed_then_all_of_them_are_accepted() {
+        let output = NullOutput;
+
+        assert!((0..=255).all(|value| output.print(value).is_ok()))
+    }
+
+    #[test]
+    #[should_panic(expected = "No input expected")]
+    fn given_a_panic_input_when_an_input_is_read_then_it_panics() {
+        let _ = PanicInput.get_input();
+    }
+
+    #[test]
+    fn given_a_failing_output_when_it_prints_more_than_allowed_then_it_fails() {
+        let output = FailingOutput::new(2);
+
+        assert!(output.print(b'a').is_ok());
+        assert!(output.print(b'b').is_ok());
+        assert_eq!(
+            output.print(b'c'),
+            Err(OutputError::from(io::Error::other("The output is closed")))
+        )
+    }
+
+    #[test]
+    fn given_a_failing_input_when_its_bytes_are_consumed_then_it_fails_instead_of_ending() {
+        let mut input = FailingInput::new(*b"a");
+
+        assert_eq!(input.get_input(), Ok(ProgramValue(b'a')));
+        assert_eq!(input.get_input(), Err(InputError::Unknown))
+    }
+
+    #[test]
+    fn given_a_failing_writer_when_it_writes_more_than_allowed_then_it_writes_partially_and_fails()
+    {
+        let mut writer = FailingWriter::new(3);
+
+        assert_eq!(writer.write(b"abcd").unwrap(), 3);
+        assert!(writer.write(b"d").is_err())
+    }
+
+    #[test]
+    fn given_a_closed_writer_when_it_writes_then_it_fails_with_a_broken_pipe() {
+        let error = ClosedWriter.write(b"a").unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe)
+    }
+
+    #[test]
+    fn given_the_reexported_doubles_when_used_together_then_the_input_is_collected() {
+        let mut input = QueuedInput::from("hi");
+        let output = CollectingOutput::new();
+
+        while let Ok(ProgramValue(value)) = input.get_input() {
+            output.print(value).unwrap();
+        }
+
+        assert_eq!(output.into_string_lossy(), "hi")
+    }
+}
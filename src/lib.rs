@@ -0,0 +1,44 @@
+/*!
+ * The parser and the interpreter of braincrub, to run brainfuck from other
+ * programs. The CLI is a binary on top of it, with the rest of the modules.
+ *
+ * ```
+ * use braincrub::{
+ *     BrainfuckMemory, CollectingOutput, Interpreter, InterpreterConfig, MemoryTape, QueuedInput,
+ *     from_source_to_node_ast,
+ * };
+ *
+ * let ast = from_source_to_node_ast("++++++++[>++++++++<-]>+.").unwrap();
+ * let mut interpreter = Interpreter::new(
+ *     CollectingOutput::new(),
+ *     QueuedInput::from(vec![]),
+ *     BrainfuckMemory::<u8>::new(30),
+ *     InterpreterConfig::new(10_000),
+ * );
+ * interpreter.load_ast_program(&ast);
+ * interpreter.run().unwrap();
+ *
+ * assert_eq!(&*interpreter.display.as_bytes(), b"A");
+ * ```
+ */
+pub mod file;
+pub mod interpreter;
+pub mod io;
+pub mod parser;
+pub mod run;
+
+/*
+ * What a program needs to parse and run brainfuck. The rest is in the modules.
+ */
+pub use crate::interpreter::{
+    EofBehavior, ExecutionTracer, Interpreter, InterpreterConfig, InterpreterErrors,
+    OverflowPolicy, RunSummary,
+};
+pub use crate::io::{
+    BrainfuckMemory, CollectingOutput, InputValue, MemoryCell, MemoryTape, OutputValue, QueuedInput,
+};
+pub use crate::parser::{
+    BrainfuckNodeAST, Dialect, ParserErrors, from_source_to_node_ast,
+    from_source_to_node_ast_in_dialect,
+};
+pub use crate::run::{BraincrubError, RunConfig, RunResult, run_ast, run_source};
@@ -0,0 +1,209 @@
+use std::fmt::Write;
+
+use crate::parser::{
+    BrainfuckNodeAST, BrainfuckOperations, CommandInformation, Dialect, LoopInformation,
+    source_positions_of_nodes,
+};
+
+pub fn symbol_and_name(operation: BrainfuckOperations) -> (char, &'static str) {
+    match operation {
+        BrainfuckOperations::MovePointerRight => ('>', "move right"),
+        BrainfuckOperations::MovePointerLeft => ('<', "move left"),
+        BrainfuckOperations::IncrementByOneCurrentCell => ('+', "increment"),
+        BrainfuckOperations::DecrementByOneCurrentCell => ('-', "decrement"),
+        BrainfuckOperations::InputCommand => (',', "input"),
+        BrainfuckOperations::OutputCommand => ('.', "output"),
+        BrainfuckOperations::LoopStart => ('[', "loop"),
+        BrainfuckOperations::LoopEnd => (']', "end loop"),
+        BrainfuckOperations::SwitchTapeNext => ('}', "next tape"),
+        BrainfuckOperations::SwitchTapePrev => ('{', "previous tape"),
+    }
+}
+
+/**
+ * The line and the column of every offset, counting from one. The offsets must be
+ * sorted, as the ones of the nodes are.
+ */
+fn lines_and_columns(source_code: &str, offsets: &[usize]) -> Vec<String> {
+    let mut positions = Vec::with_capacity(offsets.len());
+    let (mut line, mut line_start, mut scanned) = (1, 0, 0);
+
+    for offset in offsets {
+        for (index, character) in source_code[scanned..*offset].char_indices() {
+            if character == '\n' {
+                line += 1;
+                line_start = scanned + index + 1;
+            }
+        }
+        scanned = *offset;
+
+        let column = source_code[line_start..*offset].chars().count() + 1;
+        positions.push(format!("{}:{}", line, column));
+    }
+
+    positions
+}
+
+/**
+ * The line and the column of an offset of the source code, counting from one.
+ */
+pub fn line_and_column(source_code: &str, offset: usize) -> (usize, usize) {
+    let before = &source_code[..offset];
+    let line_start = before.rfind('\n').map_or(0, |new_line| new_line + 1);
+
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/**
+ * The line and the column of every node of the program, like 3:7.
+ */
+pub fn node_lines_and_columns(source_code: &str, dialect: Dialect) -> Vec<String> {
+    lines_and_columns(
+        source_code,
+        &source_positions_of_nodes(source_code, dialect),
+    )
+}
+
+/**
+ * Lists the nodes of the program, one per line, with the index of the node, its
+ * line and column in the source code, the command and where the program goes next.
+ * The loops start with where they go when the cell isn't 0 and when it is, and the
+ * nodes inside of them are indented. The commands are padded after the indent, so
+ * where they go is in the same column at every depth.
+ */
+pub fn render_ast_listing(ast: &[BrainfuckNodeAST], source_code: &str, dialect: Dialect) -> String {
+    let positions = node_lines_and_columns(source_code, dialect);
+    let index_width = ast.len().saturating_sub(1).to_string().len();
+    let position_width = positions.iter().map(String::len).max().unwrap_or(0);
+    let max_depth = loop_depths(ast).max().unwrap_or(0);
+    let command_width = 2 * max_depth + 15;
+    let mut listing = String::new();
+
+    for (index, (node, depth)) in ast.iter().zip(loop_depths(ast)).enumerate() {
+        let (operation, targets) = match node {
+            BrainfuckNodeAST::Command(CommandInformation {
+                operation,
+                next_position,
+            }) => (Some(*operation), format!("-> {}", next_position)),
+            BrainfuckNodeAST::Loop(LoopInformation {
+                operation,
+                next_position_as_true,
+                next_position_as_false,
+            }) => (
+                Some(*operation),
+                format!(
+                    "-> {} if the cell isn't 0, {} if it is",
+                    next_position_as_true, next_position_as_false
+                ),
+            ),
+            BrainfuckNodeAST::NoOp => (None, String::new()),
+        };
+
+        let (symbol, name) = operation.map_or((' ', "no-op"), symbol_and_name);
+        let position = positions.get(index).map_or("", String::as_str);
+        let command = format!("{}{} {}", "  ".repeat(depth), symbol, name);
+        let line = format!(
+            "{:>index_width$}  {:<position_width$}  {:<command_width$} {}",
+            index, position, command, targets
+        );
+        let _ = writeln!(listing, "{}", line.trim_end());
+    }
+
+    listing
+}
+
+/**
+ * How many loops every node is inside of, with the brackets of a loop outside of it.
+ */
+fn loop_depths(ast: &[BrainfuckNodeAST]) -> impl Iterator<Item = usize> {
+    ast.iter().scan(0usize, |depth, node| {
+        let operation = match node {
+            BrainfuckNodeAST::Command(CommandInformation { operation, .. })
+            | BrainfuckNodeAST::Loop(LoopInformation { operation, .. }) => Some(*operation),
+            BrainfuckNodeAST::NoOp => None,
+        };
+
+        Some(match operation {
+            Some(BrainfuckOperations::LoopStart) => {
+                *depth += 1;
+                *depth - 1
+            }
+            Some(BrainfuckOperations::LoopEnd) => {
+                *depth = depth.saturating_sub(1);
+                *depth
+            }
+            _ => *depth,
+        })
+    })
+}
+
+#[cfg(test)]
+mod listing_test {
+    use super::*;
+    use crate::parser::from_source_to_node_ast_in_dialect;
+
+    fn listing_of(source_code: &str, dialect: Dialect) -> String {
+        let ast = from_source_to_node_ast_in_dialect(source_code, dialect).unwrap();
+
+        render_ast_listing(&ast, source_code, dialect)
+    }
+
+    #[test]
+    fn given_the_nested_loops_fixture_when_it_is_listed_then_every_node_has_its_line() {
+        assert_eq!(
+            listing_of(
+                include_str!("../resources/test/nested_loops.bf"),
+                Dialect::Standard
+            ),
+            concat!(
+                " 0  2:1   + increment         -> 1\n",
+                " 1  2:2   + increment         -> 2\n",
+                " 2  2:3   [ loop              -> 3 if the cell isn't 0, 11 if it is\n",
+                " 3  2:4     > move right      -> 4\n",
+                " 4  2:5     + increment       -> 5\n",
+                " 5  2:6     [ loop            -> 6 if the cell isn't 0, 8 if it is\n",
+                " 6  2:7       - decrement     -> 7\n",
+                " 7  2:8     ] end loop        -> 5\n",
+                " 8  2:9     < move left       -> 9\n",
+                " 9  2:10    - decrement       -> 10\n",
+                "10  2:11  ] end loop          -> 2\n",
+                "11  3:1   > move right        -> 12\n",
+                "12  3:2   . output            -> 13\n",
+            )
+        )
+    }
+
+    #[test]
+    fn given_the_multitape_dialect_when_it_is_listed_then_the_tape_switches_are_named() {
+        assert_eq!(
+            listing_of("}{", Dialect::Multitape),
+            concat!(
+                "0  1:1  } next tape     -> 1\n",
+                "1  1:2  { previous tape -> 2\n"
+            )
+        )
+    }
+
+    #[test]
+    fn given_a_program_after_a_shebang_and_wide_characters_when_it_is_listed_then_the_columns_count_characters()
+     {
+        assert_eq!(
+            listing_of("#!/usr/bin/env braincrab run\né+", Dialect::Standard),
+            "0  2:2  + increment     -> 1\n"
+        )
+    }
+
+    #[test]
+    fn given_an_offset_after_wide_characters_when_its_line_and_column_are_found_then_they_count_characters()
+     {
+        assert_eq!(line_and_column("+\né é[-]", 7), (2, 4))
+    }
+
+    #[test]
+    fn given_an_empty_program_when_it_is_listed_then_the_listing_is_empty() {
+        assert_eq!(listing_of("no commands", Dialect::Standard), "")
+    }
+}
@@ -1,41 +1,651 @@
-#![feature(ascii_char)]
+mod cache;
+mod cli;
+mod config;
+mod diagnostic;
+mod diff;
+mod dump;
+mod listing;
+mod man;
+mod pipe;
+mod profile;
+mod prompt;
+mod repl;
+mod report;
+mod suite;
+mod trace;
+
+/*
+ * The modules of the library, reachable from the ones of the binary as crate::io and
+ * the rest, like they were before the split.
+ */
+use braincrub::{file, interpreter, io, parser, run};
+
+use std::env;
+use std::fs::File;
+use std::io::{BufWriter, ErrorKind, IsTerminal, Read, Write, stderr, stdin, stdout};
+use std::iter;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command, ValueHint};
+use clap_complete::{Shell, generate};
+use signal_hook::consts::SIGINT;
+use signal_hook::iterator::Signals;
+
+use crate::cache::{CacheLookup, cache_path, load_cached_ast, store_cached_ast};
+use crate::cli::{
+    DataFile, bounds_policy_parser, byte_limit_parser, cell_size_parser, color_mode_parser,
+    data_file_parser, dialect_parser, dump_range_parser, eof_value_parser, exclude_pattern_parser,
+    input_newlines_parser, input_value_parser, limit_read_instructions_parser, lint_format_parser,
+    memory_model_parser, memory_size_parser, output_mode_parser, overflow_policy_parser,
+    path_parser, profile_format_parser, stdin_mode_parser, trace_format_parser,
+};
+use crate::config::{
+    CliDefaults, ENVIRONMENT_MAN_SECTION, find_config, load_config, with_defaults,
+};
+use crate::diagnostic::{ColorMode, Diagnostic, DiagnosticRenderer, Severity};
+use crate::diff::{render_difference, run_program};
+use crate::dump::{render_memory_dump, render_postmortem};
+use crate::file::{
+    DEFAULT_MAX_SOURCE_SIZE, ExpandedSource, FileError, INLINE_PROGRAM, SOURCE_EXTENSIONS,
+    STDIN_PATH, SourceFiles, SourceMap, UnreadableDirectory, create_output_file, expand_includes,
+    expand_paths, has_source_extension, read_binary_file, read_source, unexpected_error,
+};
+use crate::interpreter::{
+    EofBehavior, ExecutionTracer, Interpreter, InterpreterConfig, InterpreterErrors, MemoryStats,
+    OverflowPolicy, RunSummary,
+};
+use crate::io::{
+    BidirectionalMemory, BoundsPolicy, BrainfuckMemory, CellSize, CsvFormatter, DebugFormatter,
+    HexFormatter, HexdumpFormatter, InputValue, LimitedWriter, MULTITAPE_TAPES, MemoryCell,
+    MemoryModel, MemoryTape, MultiTape, NewlineInput, NewlineMode, NumericFormatter,
+    OutputFormatter, OutputMode, OutputValue, QueuedInput, RandomInput, RawFormatter, ReaderInput,
+    Recording, ReplayInput, SharedWriter, SparseMemory, StdinMode, TeeOutput, TranscriptInput,
+    Utf8Formatter, WriterOutput, select_stdin_mode, write_transcript_line,
+};
+use crate::listing::{line_and_column, node_lines_and_columns, render_ast_listing};
+use crate::man::{render_man_page, render_man_pages};
+use crate::parser::{
+    BrainfuckNodeAST, Dialect, from_source_to_node_ast_in_dialect, looks_like_prose, reads_input,
+    source_position_of_node_in_dialect,
+};
+use crate::pipe::{PipeConfig, Stage, run_pipe};
+use crate::profile::{ProfileFormat, Profiler};
+use crate::prompt::BasicInput;
+use crate::repl::{Repl, run_repl};
+use crate::report::{
+    EXIT_CODES_MAN_SECTION, ErrorReport, FILE_EXIT_CODE, INTERRUPTED_EXIT_CODE, LIMIT_EXIT_CODE,
+    LintFormat, OUTPUT_CLOSED_EXIT_CODE, PARSE_EXIT_CODE, Phase, RUNTIME_EXIT_CODE, Report,
+    SarifLog, SarifResult, Timing, USAGE_EXIT_CODE, format_verbose_report,
+};
+use crate::run::RunConfig;
+use crate::suite::{CaseOutcome, SuiteConfig, SuiteSummary, find_cases, run_case};
+use crate::trace::{ChromeTrace, DEFAULT_TRACE_SAMPLE, TraceFormat};
+
+/**
+ * Why the CLI stops before finishing. The errors are shown where they happen, with
+ * what is known there, and main only exits with their code.
+ */
+#[derive(Debug)]
+enum CliError {
+    /**
+     * A failure of the programs or their files, with the exit code of its kind.
+     */
+    Failed(ErrorReport),
+    Usage,
+    /**
+     * A file that isn't a program can't be read or written, like the config.
+     */
+    File,
+    /**
+     * The programs tested or compared didn't do what was expected.
+     */
+    Runtime,
+    /**
+     * The run asked to exit with the value of its cell, with --exit-cell.
+     */
+    ExitCell(i32),
+    Interrupted,
+    OutputClosed,
+}
+
+impl CliError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Failed(error) => error.exit_code,
+            CliError::Usage => USAGE_EXIT_CODE,
+            CliError::File => FILE_EXIT_CODE,
+            CliError::Runtime => RUNTIME_EXIT_CODE,
+            CliError::ExitCell(value) => *value,
+            CliError::Interrupted => INTERRUPTED_EXIT_CODE,
+            CliError::OutputClosed => OUTPUT_CLOSED_EXIT_CODE,
+        }
+    }
+}
+
+/**
+ * The SIGINT kills the process without running the destructors, so the buffered
+ * output would be lost. We listen the signal in another thread to flush the output
+ * file before exiting with the conventional exit code. It's the only exit outside
+ * of main, as the main thread is still running the program.
+ */
+fn flush_output_file_on_interrupt(mut writer: SharedWriter<BufWriter<File>>) {
+    let mut signals = Signals::new([SIGINT]).expect("Unable to listen the interrupt signal");
+
+    thread::spawn(move || {
+        if signals.forever().next().is_some() {
+            let _ = writer.flush();
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+    });
+}
+
 /**
- * The previous line is mandatory to be able to use the experimental ascii handle api
+ * println panics when the stdout is closed, so the report and the rest of the
+ * lines are written by hand.
  */
-pub mod file;
-pub mod interpreter;
-pub mod io;
-pub mod parser;
+fn print_report(report: &Report) -> Result<(), CliError> {
+    print_line(&report.to_json())
+}
 
-use std::path::PathBuf;
+fn print_line(line: &str) -> Result<(), CliError> {
+    print_text(&format!("{}\n", line))
+}
 
-use clap::{Arg, ArgAction, Command};
+fn print_text(text: &str) -> Result<(), CliError> {
+    match stdout().write_all(text.as_bytes()) {
+        Err(error) if error.kind() == ErrorKind::BrokenPipe => Err(CliError::OutputClosed),
+        _ => Ok(()),
+    }
+}
 
-use crate::file::read_source_code_file;
-use crate::interpreter::{Interpreter, InterpreterConfig};
-use crate::io::{BasicInput, BasicOutput, BrainfuckMemory, MemoryTape};
-use crate::parser::from_source_to_node_ast;
+/**
+ * With --json the failures are reported in the stdout as a JSON object, otherwise
+ * the diagnostic is rendered in the stderr. Both give the error back to exit with
+ * its code.
+ */
+fn fail(
+    report: Report,
+    json: bool,
+    renderer: &DiagnosticRenderer,
+    diagnostic: Diagnostic,
+) -> CliError {
+    match json {
+        true => {
+            if let Err(error) = print_report(&report) {
+                return error;
+            }
+        }
+        false => {
+            let _ = renderer.render(&diagnostic, &mut stderr());
+        }
+    }
+
+    report.error.map_or(CliError::Runtime, CliError::Failed)
+}
+
+/**
+ * What is left of a run once the interpreter is dropped. The views of the memory
+ * are rendered before.
+ */
+struct MemoryRun {
+    result: Result<(), InterpreterErrors>,
+    summary: RunSummary,
+    execution_time: Duration,
+    dump: Option<String>,
+    postmortem: Option<String>,
+}
 
-pub fn path_parser(path_string: &str) -> Result<PathBuf, String> {
-    Ok(PathBuf::from(path_string))
+/**
+ * Settings of a run that depend on the memory: the data copied into it before the
+ * run, and the views and the figures of it rendered after. The tracer follows the
+ * interpreter built for the memory.
+ */
+struct MemoryOptions<'a> {
+    data: &'a [(DataFile, Vec<u8>)],
+    dump_range: Option<Option<Range<isize>>>,
+    postmortem: bool,
+    stats: bool,
+    dialect: Dialect,
+    cell_size: CellSize,
+    tracer: Option<&'a mut (dyn ExecutionTracer + Send)>,
 }
 
-pub fn memory_size_parser(memory_size: &str) -> Result<usize, String> {
-    match memory_size.to_owned().parse::<usize>() {
-        Ok(value) if value > 30_000 => Err("Maximum value accepted is 30_000".to_string()),
-        Ok(value) if value < 1 => Err("Minimum value accepted is 1".to_string()),
-        Ok(value) => Ok(value),
-        Err(err) => Err(err.to_string()),
+/**
+ * The multitape dialect runs on several tapes of the memory model, built with the
+ * same settings.
+ */
+fn run_with_memory<Cell: MemoryCell, Memory: MemoryTape<Cell>>(
+    display: Box<dyn OutputValue>,
+    input: Box<dyn InputValue>,
+    new_memory: impl Fn() -> Memory,
+    config: InterpreterConfig,
+    ast: &Vec<BrainfuckNodeAST>,
+    options: MemoryOptions,
+) -> Result<MemoryRun, String> {
+    match options.dialect {
+        Dialect::Standard => run_on_tape(display, input, new_memory(), config, ast, options),
+        Dialect::Multitape => {
+            let tapes = (0..MULTITAPE_TAPES).map(|_| new_memory()).collect();
+
+            run_on_tape::<Cell, _>(
+                display,
+                input,
+                MultiTape::from_tapes(tapes),
+                config,
+                ast,
+                options,
+            )
+        }
     }
 }
 
-pub fn limit_read_instructions_parser(limit_read_instructions: &str) -> Result<usize, String> {
-    match limit_read_instructions.to_owned().parse::<usize>() {
-        Ok(value) if value > 100_000 => Err("Maximum value accepted is 100_000".to_string()),
-        Ok(value) if value < 1 => Err("Minimum value accepted is 1".to_string()),
-        Ok(value) => Ok(value),
-        Err(err) => Err(err.to_string()),
+/**
+ * The interpreter is generic over the memory, so each memory model needs its own
+ * interpreter. The interpreter is dropped before returning, so the outputs are
+ * flushed when the caller reports the result. The memory is dumped before, when
+ * asked, as the range by default goes between the extremes the pointer reached.
+ * The data files are copied first, and the run fails when one of them doesn't fit.
+ */
+fn run_on_tape<Cell: MemoryCell, Memory: MemoryTape<Cell>>(
+    display: Box<dyn OutputValue>,
+    input: Box<dyn InputValue>,
+    mut memory: Memory,
+    config: InterpreterConfig,
+    ast: &Vec<BrainfuckNodeAST>,
+    options: MemoryOptions,
+) -> Result<MemoryRun, String> {
+    for (data_file, content) in options.data {
+        let cells: Vec<Cell> = content
+            .iter()
+            .map(|byte| Cell::from_i64(i64::from(*byte)))
+            .collect();
+
+        memory.load_slice(data_file.offset, &cells).map_err(|_| {
+            format!(
+                "The data file {} doesn't fit in the memory from the cell {}, it has {} bytes",
+                data_file.path.display(),
+                data_file.offset,
+                content.len()
+            )
+        })?;
+    }
+
+    let mut interpreter = Interpreter::new(display, input, memory, config);
+
+    interpreter.load_ast_program(ast);
+    if let Some(tracer) = options.tracer {
+        interpreter.set_tracer(tracer);
     }
+
+    let run_started = Instant::now();
+    let result = interpreter.run();
+    let execution_time = run_started.elapsed();
+
+    if options.stats {
+        interpreter.summary.memory = Some(MemoryStats::collect(&interpreter.memory));
+    }
+
+    let dump = options.dump_range.map(|range| {
+        let range = range.unwrap_or_else(|| {
+            interpreter.memory.min_position_reached()
+                ..interpreter.memory.max_position_reached().saturating_add(1)
+        });
+
+        // The dumps of the cells wider than a byte say so, as the tapes do.
+        match options.cell_size {
+            CellSize::U8 => render_memory_dump(&interpreter.memory, range),
+            cell_size => format!(
+                "Cell size {}\n{}",
+                cell_size,
+                render_memory_dump(&interpreter.memory, range)
+            ),
+        }
+    });
+    let postmortem = match &result {
+        Err(error) if options.postmortem && error.is_program_fault() => {
+            Some(render_postmortem(&interpreter.memory))
+        }
+        _ => None,
+    };
+
+    Ok(MemoryRun {
+        result,
+        summary: interpreter.summary,
+        execution_time,
+        dump,
+        postmortem,
+    })
+}
+
+/**
+ * The vec memory with the cells of the width asked, filled with the pattern.
+ */
+fn vec_memory<Cell: MemoryCell>(
+    memory_tape_size: usize,
+    pattern: &[u8],
+    bounds: BoundsPolicy,
+) -> BrainfuckMemory<Cell> {
+    let pattern: Vec<Cell> = pattern
+        .iter()
+        .map(|byte| Cell::from_i64(i64::from(*byte)))
+        .collect();
+
+    BrainfuckMemory::with_pattern(memory_tape_size, &pattern).with_bounds_policy(bounds)
+}
+
+fn dialect_arg() -> Arg {
+    Arg::new("dialect")
+        .long("dialect")
+        .action(ArgAction::Set)
+        .value_name("DIALECT")
+        .num_args(1)
+        .value_parser(dialect_parser)
+        .default_value("standard")
+        .help("The commands of the language: standard, or multitape to switch between two tapes with } and {")
+}
+
+fn ext_arg() -> Arg {
+    Arg::new("ext")
+        .long("ext")
+        .action(ArgAction::Set)
+        .value_name("EXTENSIONS")
+        .num_args(1)
+        .value_delimiter(',')
+        .default_values(SOURCE_EXTENSIONS)
+        .help("Extensions of the source files, separated by commas. The other files get a warning, and the recursive lint skips them")
+}
+
+fn strict_arg() -> Arg {
+    Arg::new("strict")
+        .long("strict")
+        .action(ArgAction::SetTrue)
+        .help("Fail instead of warning when the source code doesn't look like brainfuck")
+}
+
+fn allow_includes_arg() -> Arg {
+    Arg::new("allow-includes")
+        .long("allow-includes")
+        .action(ArgAction::SetTrue)
+        .help("Replace the lines like @include \"path.bf\" with the file, relative to the file with the line. Without it they are comments")
+}
+
+fn max_source_size_arg() -> Arg {
+    Arg::new("max-source-size")
+        .long("max-source-size")
+        .action(ArgAction::Set)
+        .value_name("BYTES")
+        .num_args(1)
+        .value_parser(byte_limit_parser)
+        .default_value("64Mi")
+        .help("Refuse to read a source code bigger than the number of bytes. Accepts the same suffixes as --max-output")
+}
+
+/**
+ * The files are given with -f or as positional arguments, but not mixing both, or
+ * the source code itself with --eval.
+ */
+fn source_files_group() -> ArgGroup {
+    ArgGroup::new("source-files")
+        .args(["file", "files", "eval"])
+        .required(true)
+}
+
+fn eval_arg() -> Arg {
+    Arg::new("eval")
+        .short('e')
+        .long("eval")
+        .action(ArgAction::Set)
+        .value_name("PROGRAM")
+        .num_args(1)
+        .help("Use the given source code instead of a file, like '++++++++[>++++++++<-]>+.'")
+}
+
+fn json_arg() -> Arg {
+    Arg::new("json")
+        .long("json")
+        .action(ArgAction::SetTrue)
+        .help("Print the result as a JSON object in the stdout, hiding the human readable messages")
+}
+
+fn quiet_arg() -> Arg {
+    Arg::new("quiet")
+        .short('q')
+        .long("quiet")
+        .action(ArgAction::SetTrue)
+        .help("Hide the status messages, only the program output and the errors are shown")
+}
+
+fn verbose_arg() -> Arg {
+    Arg::new("verbose")
+        .short('v')
+        .long("verbose")
+        .action(ArgAction::Count)
+        .conflicts_with("quiet")
+        .help("Show how long every phase took and the run figures in the stderr. Repeat it (-vv) for more details")
+}
+
+/**
+ * The warnings about a source code that may not be a program: a file without the
+ * extension of one, and a big file with too few commands, which is an error with
+ * --strict. The warning of the extension isn't shown with --quiet or --json.
+ */
+fn source_diagnostics(path: &str, source_code: &str, sub_matches: &ArgMatches) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let extensions: Vec<String> = sub_matches
+        .get_many::<String>("ext")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    let silent = sub_matches.get_flag("quiet") || sub_matches.get_flag("json");
+
+    let is_file = path != STDIN_PATH && path != INLINE_PROGRAM;
+
+    if is_file && !silent && !has_source_extension(Path::new(path), &extensions) {
+        diagnostics.push(
+            Diagnostic::warning(format!(
+                "The file {} doesn't have the extension of a source code ({}), is it the right file?",
+                path,
+                extensions.join(", ")
+            ))
+            .with_code("UnknownExtension"),
+        );
+    }
+
+    let dialect = *sub_matches.get_one::<Dialect>("dialect").unwrap();
+
+    if let Some(ratio) = looks_like_prose(source_code, dialect) {
+        let message = format!(
+            "The file {} doesn't look like brainfuck, only {:.2}% of it are commands",
+            path,
+            ratio * 100.0
+        );
+
+        diagnostics.push(
+            match sub_matches.get_flag("strict") {
+                true => Diagnostic::error(message),
+                false => Diagnostic::warning(message),
+            }
+            .with_code("NotBrainfuck"),
+        );
+    }
+
+    diagnostics
+}
+
+/**
+ * The error of clap for a run without a program, when the stdin is the terminal
+ * and waiting for the program there would look like a hang.
+ */
+fn missing_program_error() -> CliError {
+    let mut cli = braincrub_cli();
+    cli.build();
+    let run = cli
+        .find_subcommand_mut("run")
+        .expect("Expected the run subcommand to exist");
+    let error = run.error(
+        clap::error::ErrorKind::MissingRequiredArgument,
+        "the program is missing, give a file with -f or as FILES, the source code with -e, or pipe it to the stdin",
+    );
+
+    let _ = error.print();
+    CliError::Usage
+}
+
+/**
+ * Reads the source code for lint and run, expanding the includes when they are
+ * allowed. The includes of an inline program are relative to the current directory.
+ */
+fn read_program(path: &str, sub_matches: &ArgMatches) -> Result<ExpandedSource, FileError> {
+    let limit = *sub_matches.get_one::<u64>("max-source-size").unwrap();
+    let source_code = match sub_matches.get_one::<String>("eval") {
+        Some(inline_program) if path == INLINE_PROGRAM => inline_program.clone(),
+        _ => read_source(path, stdin(), stdin().is_terminal(), limit)?,
+    };
+
+    if sub_matches.get_flag("allow-includes") {
+        expand_includes(path, &source_code, limit)
+    } else {
+        Ok(ExpandedSource::from(source_code))
+    }
+}
+
+fn lint_format(sub_matches: &ArgMatches) -> LintFormat {
+    match sub_matches.get_flag("json") {
+        true => LintFormat::Json,
+        false => *sub_matches.get_one::<LintFormat>("format").unwrap(),
+    }
+}
+
+/**
+ * The SARIF result of a diagnostic, at the file and the line where the line of the
+ * expanded source code comes from.
+ */
+fn sarif_result(
+    rule_id: &str,
+    diagnostic: &Diagnostic,
+    path: &str,
+    source: Option<(&str, &SourceMap)>,
+) -> SarifResult {
+    let location = match (source, diagnostic.position) {
+        (Some((source_code, source_map)), Some(position)) => {
+            let (line, column) = line_and_column(source_code, position);
+
+            match source_map.locate(line) {
+                Some((included_path, included_line)) => {
+                    (included_path, Some((included_line, column)))
+                }
+                None => (path, Some((line, column))),
+            }
+        }
+        _ => (path, None),
+    };
+
+    SarifResult::new(
+        rule_id,
+        diagnostic.severity,
+        &diagnostic.message,
+        location.0,
+        location.1,
+    )
+}
+
+/**
+ * Lints a file from the result of its read, rendering its error unless a report
+ * is asked. With several files the errors start with the path. The phases are the
+ * ones shown with --verbose. The errors and the warnings are added to the SARIF
+ * results too.
+ */
+fn lint_file(
+    path: &str,
+    source: Result<ExpandedSource, FileError>,
+    sub_matches: &ArgMatches,
+    color: bool,
+    several_files: bool,
+    sarif_results: &mut Vec<SarifResult>,
+) -> Result<[Phase; 2], ErrorReport> {
+    let format = lint_format(sub_matches);
+    let json = format == LintFormat::Json;
+    let started = Instant::now();
+    let renderer = DiagnosticRenderer::new(color);
+    let prefixed = |message: String| match several_files {
+        true => format!("{}: {}", path, message),
+        false => message,
+    };
+
+    let ExpandedSource {
+        source_code,
+        source_map,
+        ..
+    } = source.map_err(|error| {
+        let error_report = ErrorReport::from(&error);
+
+        sarif_results.push(sarif_result(
+            &error_report.kind,
+            &Diagnostic::error(&error),
+            path,
+            None,
+        ));
+        if format == LintFormat::Text {
+            let diagnostic = Diagnostic::error(prefixed(error.to_string()));
+            let _ = renderer.render(&diagnostic, &mut stderr());
+        }
+        error_report
+    })?;
+    let renderer = renderer
+        .with_source(path, &source_code)
+        .with_source_map(&source_map);
+
+    for diagnostic in source_diagnostics(path, &source_code, sub_matches) {
+        sarif_results.push(sarif_result(
+            diagnostic.code.unwrap_or("NotBrainfuck"),
+            &diagnostic,
+            path,
+            Some((&source_code, &source_map)),
+        ));
+
+        if format == LintFormat::Text || (json && diagnostic.severity == Severity::Error) {
+            let _ = renderer.render(&diagnostic, &mut stderr());
+        }
+
+        if diagnostic.severity == Severity::Error {
+            return Err(ErrorReport::new(
+                "NotBrainfuck",
+                diagnostic.message,
+                PARSE_EXIT_CODE,
+            ));
+        }
+    }
+
+    let read_phase = Phase::new("read", started.elapsed());
+
+    let parse_started = Instant::now();
+    let dialect = *sub_matches.get_one::<Dialect>("dialect").unwrap();
+    let ast = from_source_to_node_ast_in_dialect(&source_code, dialect).map_err(|error| {
+        let error_report = ErrorReport::from(&error);
+
+        sarif_results.push(sarif_result(
+            &error_report.kind,
+            &Diagnostic::error(&error).at(Some(error.position())),
+            path,
+            Some((&source_code, &source_map)),
+        ));
+        if format == LintFormat::Text {
+            let diagnostic =
+                Diagnostic::error(prefixed(error.to_string())).at(Some(error.position()));
+            let _ = renderer.render(&diagnostic, &mut stderr());
+        }
+        error_report
+    })?;
+
+    Ok([
+        read_phase,
+        Phase::new("parse", parse_started.elapsed()).with_nodes(ast.len()),
+    ])
 }
 
 fn braincrub_cli() -> Command {
@@ -43,121 +653,1863 @@ fn braincrub_cli() -> Command {
         .about("A Brainfuck interperter to lint, run brainfuck source code files.")
         .subcommand_required(true)
         .arg_required_else_help(true)
+        .after_help("The options can also be given in the environment, like BRAINCRUB_MEMORY_SIZE or BRAINCRUB_LIMIT, and in a braincrub.toml config file. A flag wins over the environment, the environment over the config file, and the config file over the default")
+        .arg(
+            Arg::new("color")
+                .long("color")
+                .action(ArgAction::Set)
+                .value_name("WHEN")
+                .num_args(1)
+                .value_parser(color_mode_parser)
+                .default_value("auto")
+                .global(true)
+                .help("When to color the errors and warnings: auto, always or never. auto respects NO_COLOR and only colors a terminal")
+        )
+        .arg(
+            Arg::new("no-config")
+                .long("no-config")
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .help("Ignore the braincrub.toml of the current directory and the config.toml in the config directory of the user")
+        )
+        .subcommand(
+            Command::new("lint")
+                .about("Check if the file provided have correct brainfuck syntax. It will fail if the loops aren't balanced. Non valid characters are ignored")
+                .arg(json_arg())
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .action(ArgAction::Set)
+                        .value_name("FORMAT")
+                        .num_args(1)
+                        .value_parser(lint_format_parser)
+                        .default_value("text")
+                        .conflicts_with("json")
+                        .help("How the result is printed: text for the messages, json for a report of every file like --json, or sarif for a SARIF 2.1.0 document with the errors and warnings of all the files, for the code scanning tools")
+                )
+                .arg(eval_arg())
+                .arg(dialect_arg())
+                .arg(max_source_size_arg())
+                .arg(allow_includes_arg())
+                .arg(ext_arg())
+                .arg(strict_arg())
+                .arg(quiet_arg())
+                .arg(verbose_arg())
+                .arg(
+                    Arg::new("file")
+                        .short('f')
+                        .long("file")
+                        .action(ArgAction::Append)
+                        .value_name("PATH")
+                        .help("File path to the file to be processed, or - to read it from the stdin. Patterns like 'src/**/*.bf' are expanded")
+                        .num_args(1..)
+                        .value_parser(path_parser)
+                        .value_hint(ValueHint::AnyPath)
+                )
+                .arg(
+                    Arg::new("files")
+                        .action(ArgAction::Append)
+                        .value_name("PATHS")
+                        .help("More files or patterns to lint, each one reported with its path")
+                        .num_args(1..)
+                        .value_parser(path_parser)
+                        .value_hint(ValueHint::AnyPath)
+                )
+                .group(source_files_group())
+                .arg(
+                    Arg::new("recursive")
+                        .short('r')
+                        .long("recursive")
+                        .action(ArgAction::SetTrue)
+                        .help("Lint the source files inside of the directories given, skipping the hidden directories")
+                )
+                .arg(
+                    Arg::new("exclude")
+                        .long("exclude")
+                        .action(ArgAction::Append)
+                        .value_name("PATTERN")
+                        .num_args(1)
+                        .value_parser(exclude_pattern_parser)
+                        .requires("recursive")
+                        .help("Skip the files and directories whose path or name match the pattern, like vendor or '*.min.bf'. Repeat it for more patterns")
+                )
+                .arg_required_else_help(true),
+        )
+        .subcommand(
+            Command::new("run")
+                .about("Check and run a brainfuck source code file. Non valid characters are ignored")
+                .arg(eval_arg())
+                .arg(dialect_arg())
+                .arg(max_source_size_arg())
+                .arg(allow_includes_arg())
+                .arg(ext_arg())
+                .arg(strict_arg())
+                .arg(
+                    Arg::new("cache")
+                        .long("cache")
+                        .action(ArgAction::SetTrue)
+                        .help("Keep the parsed program next to the source code, like prog.bf.bfc for prog.bf, and reuse it while the source code doesn't change")
+                )
+                .arg(
+                    Arg::new("memory-size")
+                        .short('m')
+                        .long("memory-size")
+                        .action(ArgAction::Set)
+                        .required(false)
+                        .num_args(1)
+                        .default_value("3000")
+                        .help("Size of the vec to simulate the memory to save the data. Accepts the k and M suffixes and the binary Ki and Mi ones, like 30k. The maximum size is 1Gi memory cells")
+                        .value_parser(memory_size_parser)
+                )
+                .arg(
+                    Arg::new("memory")
+                        .long("memory")
+                        .action(ArgAction::Set)
+                        .value_name("MODEL")
+                        .num_args(1)
+                        .value_parser(memory_model_parser)
+                        .default_value("vec")
+                        .help("How the memory is simulated. vec starts in the first cell, bidirectional extends to the left too, up to the memory size in each direction, and sparse only stores the cells that aren't 0, without limit unless the memory size is given")
+                )
+                .arg(
+                    Arg::new("init-value")
+                        .long("init-value")
+                        .action(ArgAction::Set)
+                        .value_name("BYTE")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(u8))
+                        .conflicts_with("init-pattern")
+                        .help("Value of the cells of the vec memory before the run, instead of 0")
+                )
+                .arg(
+                    Arg::new("init-pattern")
+                        .long("init-pattern")
+                        .action(ArgAction::Set)
+                        .value_name("BYTES")
+                        .num_args(1)
+                        .value_delimiter(',')
+                        .value_parser(clap::value_parser!(u8))
+                        .help("Fill the vec memory before the run repeating the values separated by commas, like 1,2")
+                )
+                .arg(
+                    Arg::new("memory-bounds")
+                        .long("memory-bounds")
+                        .action(ArgAction::Set)
+                        .value_name("POLICY")
+                        .num_args(1)
+                        .value_parser(bounds_policy_parser)
+                        .default_value("error")
+                        .help("What the vec memory does when the pointer leaves it. error stops the program, wrap goes to the other end and grow adds cells at the right")
+                )
+                .arg(
+                    Arg::new("cell-size")
+                        .long("cell-size")
+                        .action(ArgAction::Set)
+                        .value_name("SIZE")
+                        .num_args(1)
+                        .value_parser(cell_size_parser)
+                        .default_value("8")
+                        .help("Width of the cells of the vec memory: 8, 16 or 32 bits, or i8 and i32 for signed cells. The numeric and csv outputs write the whole value, the others its lowest byte")
+                )
+                .arg(
+                    Arg::new("cell-overflow")
+                        .long("cell-overflow")
+                        .action(ArgAction::Set)
+                        .value_name("POLICY")
+                        .num_args(1)
+                        .value_parser(overflow_policy_parser)
+                        .default_value("wrap")
+                        .help("What an increment of the highest value of a cell or a decrement of the lowest one does. wrap goes to the other end, saturate leaves the cell as it is and error stops the program")
+                )
+                .arg(
+                    Arg::new("wrap-cells")
+                        .long("wrap-cells")
+                        .action(ArgAction::SetTrue)
+                        .help("Shorthand of --cell-overflow wrap, it wins over the policy of the config or the environment")
+                )
+                .arg(
+                    Arg::new("limit-read-instructions")
+                        .short('l')
+                        .long("limit")
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .default_value("60000")
+                        .value_parser(limit_read_instructions_parser)
+                        .help("Number of instructions the cli can process before to consider we are on a infinite loop. Accepts the k, M and G suffixes, like 10M, and 0 runs without limit")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("no-limit")
+                        .long("no-limit")
+                        .action(ArgAction::SetTrue)
+                        .help("Run without limit of instructions, the same as --limit 0, even when a limit is given. A program in an infinite loop runs until it is interrupted")
+                )
+                .arg(
+                    Arg::new("stdin-mode")
+                        .long("stdin-mode")
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .value_parser(stdin_mode_parser)
+                        .help("How the program input is read. prompt asks for every value, bytes reads the stdin as it is. By default bytes is used when the stdin isn't a terminal")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("eof-value")
+                        .long("eof-value")
+                        .action(ArgAction::Set)
+                        .value_name("VALUE")
+                        .num_args(1)
+                        .allow_negative_numbers(true)
+                        .value_parser(eof_value_parser)
+                        .default_value("0")
+                        .help("What a read writes in the cell when the input ended: 0, as most programs expect, 255 (or -1), the highest value of the cell, or unchanged to leave the cell as it is")
+                )
+                .arg(
+                    Arg::new("hex-input")
+                        .long("hex-input")
+                        .action(ArgAction::SetTrue)
+                        .help("Read the two digits input values, like 41, as hexadecimal codes instead of decimal ones")
+                )
+                .arg(
+                    Arg::new("input-newlines")
+                        .long("input-newlines")
+                        .action(ArgAction::Set)
+                        .value_name("MODE")
+                        .num_args(1)
+                        .value_parser(input_newlines_parser)
+                        .default_value("passthrough")
+                        .help("Translate the line endings of the input. lf turns \\r\\n and \\r into \\n, crlf turns \\n into \\r\\n")
+                )
+                .arg(
+                    Arg::new("output-mode")
+                        .long("output-mode")
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .default_value("raw")
+                        .value_parser(output_mode_parser)
+                        .help("How the program output is rendered. raw writes the characters as they are, debug quotes every character, numeric and hex write the cell value as a number, utf8 decodes the multi byte characters, hexdump shows 16 bytes per row as hexdump -C, csv writes the cell values in a comma separated row")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("output-separator")
+                        .long("output-separator")
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .default_value("\n")
+                        .help("Text written after every value in the numeric and hex output modes, and between the values in the csv mode, where it is a comma by default")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("output-file")
+                        .long("output-file")
+                        .action(ArgAction::Set)
+                        .value_name("PATH")
+                        .num_args(1)
+                        .value_parser(path_parser)
+                        .value_hint(ValueHint::FilePath)
+                        .help("Write the program output in the file instead of the stdout")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("max-output")
+                        .long("max-output")
+                        .action(ArgAction::Set)
+                        .value_name("BYTES")
+                        .num_args(1)
+                        .value_parser(byte_limit_parser)
+                        .help("Abort the program when the output reaches the number of bytes. Accepts the k, M and G suffixes and the binary Ki, Mi and Gi ones, by default there isn't limit")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("transcript")
+                        .long("transcript")
+                        .action(ArgAction::Set)
+                        .value_name("PATH")
+                        .num_args(1)
+                        .value_parser(path_parser)
+                        .value_hint(ValueHint::FilePath)
+                        .help("Write a human readable transcript of every input read and the outcome of the run in the file")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("exit-cell")
+                        .long("exit-cell")
+                        .action(ArgAction::SetTrue)
+                        .help("Exit with the value of the cell of the pointer when the program succeeds, modulo 256. The value is used even when it is one of the exit codes of the errors, from 1 to 5, and the errors still exit with their own codes")
+                )
+                .arg(
+                    Arg::new("time")
+                        .long("time")
+                        .action(ArgAction::SetTrue)
+                        .help("Print how long the program ran, without reading and parsing it, the instructions executed and the instructions per second. With --json they are added to the report")
+                )
+                .arg(
+                    Arg::new("trace-file")
+                        .long("trace-file")
+                        .action(ArgAction::Set)
+                        .value_name("PATH")
+                        .num_args(1)
+                        .value_parser(path_parser)
+                        .value_hint(ValueHint::FilePath)
+                        .help("Write a trace of the execution in the file, to open it in chrome://tracing or Perfetto. It has the loops, the outputs, the inputs and the pointer position")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("trace-format")
+                        .long("trace-format")
+                        .action(ArgAction::Set)
+                        .value_name("FORMAT")
+                        .num_args(1)
+                        .value_parser(trace_format_parser)
+                        .default_value("chrome")
+                        .requires("trace-file")
+                        .help("Format of the trace file. chrome is the Trace Event Format")
+                )
+                .arg(
+                    Arg::new("trace-sample")
+                        .long("trace-sample")
+                        .action(ArgAction::Set)
+                        .value_name("INSTRUCTIONS")
+                        .num_args(1)
+                        .value_parser(clap::builder::RangedU64ValueParser::<usize>::new().range(1..))
+                        .default_value(DEFAULT_TRACE_SAMPLE.to_string())
+                        .requires("trace-file")
+                        .help("Instructions between the samples of the pointer position in the trace")
+                )
+                .arg(
+                    Arg::new("profile")
+                        .long("profile")
+                        .action(ArgAction::Set)
+                        .value_name("PATH")
+                        .num_args(1)
+                        .value_parser(path_parser)
+                        .value_hint(ValueHint::FilePath)
+                        .conflicts_with("trace-file")
+                        .help("Write a profile of the run in the file: the loops with the most instructions, how many times each command ran, how far the memory went and how long it took")
+                )
+                .arg(
+                    Arg::new("profile-format")
+                        .long("profile-format")
+                        .action(ArgAction::Set)
+                        .value_name("FORMAT")
+                        .num_args(1)
+                        .value_parser(profile_format_parser)
+                        .default_value("text")
+                        .requires("profile")
+                        .help("Format of the profile file: text or json")
+                )
+                .arg(
+                    Arg::new("in")
+                        .long("in")
+                        .action(ArgAction::Append)
+                        .value_name("VALUE")
+                        .num_args(1)
+                        .value_parser(input_value_parser)
+                        .conflicts_with_all(["stdin-mode", "hex-input", "random-input", "replay"])
+                        .help("Queue a value as input, written as in the prompt: a character, a code, 0xNN or an escape sequence. Repeat it for every value")
+                )
+                .arg(
+                    Arg::new("dump-memory")
+                        .long("dump-memory")
+                        .action(ArgAction::Set)
+                        .value_name("RANGE")
+                        .num_args(0..=1)
+                        .value_parser(dump_range_parser)
+                        .help("Print the memory in the stderr when the program ends, even if it fails. By default the cells between the leftmost and the rightmost ones reached, or the cells of a range like 0..32")
+                )
+                .arg(
+                    Arg::new("data")
+                        .long("data")
+                        .action(ArgAction::Append)
+                        .value_name("PATH[@OFFSET]")
+                        .num_args(1)
+                        .value_parser(data_file_parser)
+                        .value_hint(ValueHint::FilePath)
+                        .help("Copy the bytes of the file into the memory before the run, from the cell of the offset or the first one. Repeat it to load several files")
+                )
+                .arg(
+                    Arg::new("no-postmortem")
+                        .long("no-postmortem")
+                        .action(ArgAction::SetTrue)
+                        .help("Don't print the pointer and the cells around it when the program fails accessing the memory or running out of instructions")
+                )
+                .arg(
+                    Arg::new("random-input")
+                        .long("random-input")
+                        .action(ArgAction::Set)
+                        .value_name("SEED")
+                        .num_args(0..=1)
+                        .value_parser(clap::value_parser!(u64))
+                        .conflicts_with_all(["stdin-mode", "hex-input"])
+                        .help("Feed the program with pseudo random printable characters. The same seed gives the same input, without a seed one is generated and shown. Combine it with -l or --max-output so the run ends")
+                )
+                .arg(
+                    Arg::new("replay")
+                        .long("replay")
+                        .action(ArgAction::Set)
+                        .value_name("PATH")
+                        .num_args(1)
+                        .value_parser(path_parser)
+                        .value_hint(ValueHint::FilePath)
+                        .conflicts_with_all(["stdin-mode", "hex-input", "random-input"])
+                        .help("Feed the program with the input of a transcript or a raw file, failing if the program asks for more input than the recording has")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("tee")
+                        .long("tee")
+                        .action(ArgAction::SetTrue)
+                        .requires("output-file")
+                        .help("Write the program output in the stdout too. The output file always gets the raw bytes")
+                )
+                .arg(json_arg().conflicts_with("output-file"))
+                .arg(quiet_arg())
+                .arg(verbose_arg())
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .action(ArgAction::SetTrue)
+                        .help("Overwrite the output, transcript, trace and profile files if they already exist")
+                )
+                .arg(
+                    Arg::new("file")
+                        .short('f')
+                        .long("file")
+                        .action(ArgAction::Append)
+                        .value_name("PATH")
+                        .help("Path to the file to be processed, or - to read it from the stdin. Repeat it to run several files as one program, one after the other. Without a file or -e, the program is read from the stdin when it is piped")
+                        .num_args(1)
+                        .value_parser(path_parser)
+                        .value_hint(ValueHint::FilePath)
+                )
+                .arg(
+                    Arg::new("files")
+                        .action(ArgAction::Append)
+                        .value_name("PATHS")
+                        .help("Files of the program, as an alternative to -f")
+                        .num_args(1..)
+                        .value_parser(path_parser)
+                        .value_hint(ValueHint::FilePath)
+                )
+                // The program can come from the piped stdin, which clap can't tell.
+                .group(source_files_group().required(false)),
+        )
         .subcommand(
-            Command::new("lint")
-                .about("Check if the file provided have correct brainfuck syntax. It will fail if the loops aren't balanced. Non valid characters are ignored")
+            Command::new("ast")
+                .about("Print the parsed program without running it, one node per line with where it goes next and its line and column in the source code")
+                .arg(json_arg())
+                .arg(eval_arg())
+                .arg(dialect_arg())
+                .arg(max_source_size_arg())
+                .arg(allow_includes_arg())
                 .arg(
                     Arg::new("file")
                         .short('f')
+                        .long("file")
                         .action(ArgAction::Set)
                         .value_name("PATH")
-                        .help("File path to the file to be processed")
+                        .help("Path to the file to be processed, or - to read it from the stdin")
                         .num_args(1)
                         .value_parser(path_parser)
+                        .value_hint(ValueHint::FilePath)
+                )
+                .group(
+                    ArgGroup::new("source-files")
+                        .args(["file", "eval"])
+                        .required(true)
+                ),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Print the completion script of the shell, to complete the subcommands, the flags and the paths")
+                .arg(
+                    Arg::new("shell")
+                        .action(ArgAction::Set)
+                        .value_name("SHELL")
                         .required(true)
+                        .value_parser(clap::value_parser!(Shell))
+                        .help("Shell of the script: bash, elvish, fish, powershell or zsh")
+                ),
+        )
+        .subcommand(
+            Command::new("repl")
+                .about("Run brainfuck line by line, keeping the memory between lines. The commands :reset, :dump [start..end], :load <file>, :mem <size> and :quit manage the session")
+                .arg(
+                    Arg::new("memory-size")
+                        .short('m')
+                        .long("memory-size")
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .default_value("3000")
+                        .help("Size of the memory of the session, with the same suffixes as in run")
+                        .value_parser(memory_size_parser)
                 )
-                .arg_required_else_help(true),
+                .arg(
+                    Arg::new("limit-read-instructions")
+                        .short('l')
+                        .long("limit")
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .default_value("60000")
+                        .value_parser(limit_read_instructions_parser)
+                        .help("Number of instructions a line can run before to consider it an infinite loop")
+                ),
         )
         .subcommand(
-            Command::new("run")
-                .about("Check and run a brainfuck source code file. Non valid characters are ignored")
+            Command::new("test")
+                .about("Run every foo.bf of the directory with foo.in as its input, and compare the output with foo.out, or the error with foo.err")
+                .arg(
+                    Arg::new("directory")
+                        .action(ArgAction::Set)
+                        .value_name("DIRECTORY")
+                        .required(true)
+                        .value_hint(ValueHint::DirPath)
+                        .help("Directory with the programs and their expectations")
+                )
+                .arg(
+                    Arg::new("update")
+                        .long("update")
+                        .action(ArgAction::SetTrue)
+                        .help("Write the output of the programs in their .out files instead of comparing it")
+                )
                 .arg(
                     Arg::new("memory-size")
                         .short('m')
+                        .long("memory-size")
                         .action(ArgAction::Set)
-                        .required(false)
                         .num_args(1)
                         .default_value("3000")
-                        .help("Size of the vec to simulate the memory to save the data. The maximum size is 30_000 memory cells")
+                        .help("Size of the memory of each program, with the same suffixes as in run")
                         .value_parser(memory_size_parser)
                 )
                 .arg(
                     Arg::new("limit-read-instructions")
                         .short('l')
+                        .long("limit")
                         .action(ArgAction::Set)
                         .num_args(1)
                         .default_value("60000")
                         .value_parser(limit_read_instructions_parser)
-                        .help("Number of instructions the cli can process before to consider we are on a infinite loop")
-                        .required(false)
+                        .help("Number of instructions each program can run before to consider it an infinite loop")
+                ),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Run two programs with the same input and compare their outputs byte by byte and how they ended")
+                .arg(
+                    Arg::new("a")
+                        .short('a')
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .required(true)
+                        .value_name("FILE")
+                        .value_hint(ValueHint::FilePath)
+                        .help("The first program, like the one before a change")
                 )
                 .arg(
-                    Arg::new("file")
-                        .short('f')
+                    Arg::new("b")
+                        .short('b')
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .required(true)
+                        .value_name("FILE")
+                        .value_hint(ValueHint::FilePath)
+                        .help("The second program, like the one after a change")
+                )
+                .arg(
+                    Arg::new("input-file")
+                        .long("input-file")
                         .action(ArgAction::Set)
+                        .num_args(1)
                         .value_name("PATH")
-                        .help("Path to the file to be processed")
+                        .value_hint(ValueHint::FilePath)
+                        .help("File with the bytes both programs read as their input. Without it they read nothing")
+                )
+                .arg(dialect_arg())
+                .arg(
+                    Arg::new("memory-size")
+                        .short('m')
+                        .long("memory-size")
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .default_value("3000")
+                        .help("Size of the memory of each program, with the same suffixes as in run")
+                        .value_parser(memory_size_parser)
+                )
+                .arg(
+                    Arg::new("limit-read-instructions")
+                        .short('l')
+                        .long("limit")
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .default_value("60000")
+                        .value_parser(limit_read_instructions_parser)
+                        .help("Number of instructions each program can run before to consider it an infinite loop")
+                ),
+        )
+        .subcommand(
+            Command::new("man")
+                .about("Print the manual page of the CLI in roff, or write it as braincrab.1 in a directory, with a page for every subcommand")
+                .hide(true)
+                .arg(
+                    Arg::new("output-directory")
+                        .short('o')
+                        .long("output")
+                        .action(ArgAction::Set)
                         .num_args(1)
+                        .value_name("DIRECTORY")
                         .value_parser(path_parser)
+                        .value_hint(ValueHint::DirPath)
+                        .help("Directory where braincrab.1 and the pages of the subcommands are written, instead of the stdout")
+                ),
+        )
+        .subcommand(
+            Command::new("pipe")
+                .about("Run the programs one after another, the output of each one being the input of the next one")
+                .arg(
+                    Arg::new("file")
+                        .short('f')
+                        .long("file")
+                        .action(ArgAction::Append)
+                        .num_args(1)
                         .required(true)
+                        .value_name("FILE")
+                        .value_hint(ValueHint::FilePath)
+                        .help("A program of the pipe, repeat it to add the next ones. The first one reads the piped stdin and the output of the last one goes to the stdout")
                 )
-                .arg_required_else_help(true),
+                .arg(dialect_arg())
+                .arg(
+                    Arg::new("memory-size")
+                        .short('m')
+                        .long("memory-size")
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .default_value("3000")
+                        .help("Size of the memory of each program, with the same suffixes as in run")
+                        .value_parser(memory_size_parser)
+                )
+                .arg(
+                    Arg::new("limit-read-instructions")
+                        .short('l')
+                        .long("limit")
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .default_value("60000")
+                        .value_parser(limit_read_instructions_parser)
+                        .help("Number of instructions each program can run before to consider it an infinite loop")
+                ),
         )
 }
 
 fn main() {
-    let matches = braincrub_cli().get_matches();
+    if let Err(error) = run_cli() {
+        process::exit(error.exit_code());
+    }
+}
+
+fn run_cli() -> Result<(), CliError> {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+
+    // The config gives the defaults of the flags, so it has to be read before they
+    // are parsed.
+    let config_path = match env::args_os().any(|arg| arg == "--no-config") {
+        true => None,
+        false => find_config(),
+    };
+    let defaults = match &config_path {
+        Some(path) => load_config(path).map_err(|message| {
+            let renderer = DiagnosticRenderer::new(
+                ColorMode::Auto.use_color(stderr().is_terminal(), no_color),
+            );
+            let _ = renderer.render(&Diagnostic::error(message), &mut stderr());
+            CliError::File
+        })?,
+        None => CliDefaults::default(),
+    };
+
+    // The usage errors exit with their own code instead of the 2 of clap.
+    let cli = with_defaults(braincrub_cli(), &defaults);
+    let matches = match cli.try_get_matches() {
+        Ok(matches) => matches,
+        Err(error) => {
+            let _ = error.print();
+            // The help and the version are errors for clap too.
+            return match error.use_stderr() {
+                true => Err(CliError::Usage),
+                false => Ok(()),
+            };
+        }
+    };
+
+    let color = matches
+        .get_one::<ColorMode>("color")
+        .unwrap()
+        .use_color(stderr().is_terminal(), no_color);
+    let renderer = DiagnosticRenderer::new(color);
+
+    if let Some(path) = &config_path {
+        for key in defaults.unknown_keys() {
+            let diagnostic = Diagnostic::warning(format!(
+                "Unknown key {} in the config file {:?}, it is ignored",
+                key, path
+            ));
+            let _ = renderer.render(&diagnostic, &mut stderr());
+        }
+    }
 
     match matches.subcommand() {
         Some(("lint", sub_matches)) => {
-            let path = sub_matches
-                .get_one::<PathBuf>("file")
-                .unwrap()
-                .to_str()
-                .expect("Expected a valid path string as it was parsed before");
+            let format = lint_format(sub_matches);
+            let json = format == LintFormat::Json;
+            let verbosity = sub_matches.get_count("verbose");
+            let quiet = sub_matches.get_flag("quiet");
+
+            let patterns = sub_matches
+                .get_many::<PathBuf>("file")
+                .unwrap_or_default()
+                .chain(sub_matches.get_many::<PathBuf>("files").unwrap_or_default())
+                .map(|path| {
+                    path.to_str()
+                        .expect("Expected a valid path string as it was parsed before")
+                });
+
+            // The walk of the directories goes file by file, so the results are
+            // shown while the tree is walked.
+            let (paths, several_files): (
+                Box<dyn Iterator<Item = Result<String, UnreadableDirectory>>>,
+                bool,
+            ) = if sub_matches.get_flag("recursive") {
+                let files = SourceFiles::new(
+                    expand_paths(patterns).iter().map(String::as_str),
+                    sub_matches
+                        .get_many::<String>("ext")
+                        .unwrap_or_default()
+                        .cloned()
+                        .collect(),
+                    sub_matches
+                        .get_many::<glob::Pattern>("exclude")
+                        .unwrap_or_default()
+                        .cloned()
+                        .collect(),
+                );
+
+                (Box::new(files), true)
+            } else if sub_matches.contains_id("eval") {
+                (Box::new(iter::once(Ok(INLINE_PROGRAM.to_string()))), false)
+            } else {
+                let paths = expand_paths(patterns);
+                let several_files = paths.len() > 1;
+
+                (Box::new(paths.into_iter().map(Ok)), several_files)
+            };
+            let mut checked_files = 0;
+            let mut failed_files = 0;
+            let mut first_error = None;
+            let mut sarif_results = vec![];
+
+            for path in paths {
+                checked_files += 1;
+
+                let started = Instant::now();
+                // The directories that can't be read are failed files too.
+                let (path, source) = match path {
+                    Ok(path) => {
+                        let source = read_program(&path, sub_matches);
+                        (path, source)
+                    }
+                    Err(UnreadableDirectory { path, error }) => (path, Err(error)),
+                };
+                let path = &path;
+                let result = lint_file(
+                    path,
+                    source,
+                    sub_matches,
+                    color,
+                    several_files,
+                    &mut sarif_results,
+                );
+
+                if let Ok(phases) = &result
+                    && verbosity > 0
+                {
+                    eprintln!("{}", format_verbose_report(phases, None, verbosity));
+                }
+
+                let report = match result {
+                    Ok(_) => Report::new("lint", None, started.elapsed()),
+                    Err(error) => {
+                        failed_files += 1;
+                        // With several files the exit code is the one of the first error.
+                        first_error.get_or_insert_with(|| error.clone());
+                        Report::new("lint", Some(error), started.elapsed())
+                    }
+                };
 
-            let source_code = read_source_code_file(path)
-                .map_err(|error| panic!("{:?}", error.to_string()))
-                .unwrap();
+                if json {
+                    print_report(&match several_files {
+                        true => report.with_path(path),
+                        false => report,
+                    })?;
+                } else if report.error.is_none() && !quiet && format == LintFormat::Text {
+                    match several_files {
+                        true => print_line(&format!("{}: All good!", path))?,
+                        false => print_line("All good!")?,
+                    }
+                }
+            }
 
-            from_source_to_node_ast(&source_code)
-                .map_err(|error| panic!("{:?}", error.to_string()))
-                .unwrap();
+            if several_files && format == LintFormat::Text && !quiet {
+                print_line(&format!(
+                    "{} files checked, {} with errors",
+                    checked_files, failed_files
+                ))?;
+            }
 
-            println!("All good!");
+            // The findings of all the files go in a single SARIF document.
+            if format == LintFormat::Sarif {
+                print_line(&SarifLog::new(sarif_results).to_json())?;
+            }
+
+            if let Some(error) = first_error {
+                return Err(CliError::Failed(error));
+            }
         }
         Some(("run", sub_matches)) => {
-            let path = sub_matches
-                .get_one::<PathBuf>("file")
-                .unwrap()
-                .to_str()
-                .expect("Expected a valid path string as it was parsed before");
+            let mut paths: Vec<&str> = match sub_matches.contains_id("eval") {
+                true => vec![INLINE_PROGRAM],
+                false => sub_matches
+                    .get_many::<PathBuf>("file")
+                    .unwrap_or_default()
+                    .chain(sub_matches.get_many::<PathBuf>("files").unwrap_or_default())
+                    .map(|path| {
+                        path.to_str()
+                            .expect("Expected a valid path string as it was parsed before")
+                    })
+                    .collect(),
+            };
+            // Without a program in the arguments it comes from the stdin, like with
+            // -f -, unless nothing is piped.
+            if paths.is_empty() {
+                match stdin().is_terminal() {
+                    true => return Err(missing_program_error()),
+                    false => paths.push(STDIN_PATH),
+                }
+            }
+            let path = paths[0];
 
             let memory_tape_size = sub_matches
                 .get_one::<usize>("memory-size")
                 .expect("Expecte a valid memory tape size");
 
-            let limit_read_instructions = sub_matches
-                .get_one::<usize>("limit-read-instructions")
-                .unwrap();
+            let limit_read_instructions = match sub_matches.get_flag("no-limit") {
+                true => &0,
+                false => sub_matches
+                    .get_one::<u64>("limit-read-instructions")
+                    .unwrap(),
+            };
+
+            let output_mode = sub_matches.get_one::<OutputMode>("output-mode").unwrap();
+
+            let output_file = sub_matches.get_one::<PathBuf>("output-file");
+
+            let output_separator = sub_matches.get_one::<String>("output-separator").unwrap();
+
+            let json = sub_matches.get_flag("json");
+            let verbosity = sub_matches.get_count("verbose");
+            let started = Instant::now();
+
+            // Only the vec memory is generic over the width of the cells.
+            let cell_size = *sub_matches.get_one::<CellSize>("cell-size").unwrap();
+            let overflow_policy = match sub_matches.get_flag("wrap-cells") {
+                true => OverflowPolicy::Wrap,
+                false => *sub_matches
+                    .get_one::<OverflowPolicy>("cell-overflow")
+                    .unwrap(),
+            };
+            let memory_model = *sub_matches.get_one::<MemoryModel>("memory").unwrap();
+
+            if cell_size != CellSize::U8 && memory_model != MemoryModel::Vec {
+                let message = format!(
+                    "The cell size {} is only available with the vec memory",
+                    cell_size
+                );
+                let report = Report::new(
+                    "run",
+                    Some(ErrorReport::new(
+                        "UnsupportedCellSize",
+                        message.clone(),
+                        USAGE_EXIT_CODE,
+                    )),
+                    started.elapsed(),
+                );
+                return Err(fail(report, json, &renderer, Diagnostic::error(message)));
+            }
+
+            // The flags of the vec memory would be ignored by the other memories.
+            if memory_model != MemoryModel::Vec
+                && let Some(flag) = ["memory-bounds", "init-value", "init-pattern"]
+                    .into_iter()
+                    .find(|flag| {
+                        matches!(
+                            sub_matches.value_source(flag),
+                            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+                        )
+                    })
+            {
+                let message = format!("The flag --{} is only available with the vec memory", flag);
+                let report = Report::new(
+                    "run",
+                    Some(ErrorReport::new(
+                        "UnsupportedMemoryFlag",
+                        message.clone(),
+                        USAGE_EXIT_CODE,
+                    )),
+                    started.elapsed(),
+                );
+                return Err(fail(report, json, &renderer, Diagnostic::error(message)));
+            }
+
+            let mut diagnostics = vec![];
+            let program = match paths.as_slice() {
+                [path] => read_program(path, sub_matches).inspect(|program| {
+                    diagnostics = source_diagnostics(path, &program.source_code, sub_matches)
+                }),
+                _ => paths
+                    .iter()
+                    .try_fold(ExpandedSource::default(), |mut program, path| {
+                        let part = read_program(path, sub_matches)?;
+                        diagnostics.extend(source_diagnostics(
+                            path,
+                            &part.source_code,
+                            sub_matches,
+                        ));
+                        program.append(path, &part);
+                        Ok(program)
+                    }),
+            };
+            let ExpandedSource {
+                source_code,
+                source_map,
+                ..
+            } = program.map_err(|error| {
+                let report = Report::new("run", Some(ErrorReport::from(&error)), started.elapsed());
+                fail(report, json, &renderer, Diagnostic::error(&error))
+            })?;
+            let renderer = renderer
+                .with_source(path, &source_code)
+                .with_source_map(&source_map);
+
+            for diagnostic in diagnostics {
+                if diagnostic.severity == Severity::Error {
+                    let report = Report::new(
+                        "run",
+                        Some(ErrorReport::new(
+                            "NotBrainfuck",
+                            diagnostic.message.clone(),
+                            PARSE_EXIT_CODE,
+                        )),
+                        started.elapsed(),
+                    );
+                    return Err(fail(report, json, &renderer, diagnostic));
+                }
+
+                let _ = renderer.render(&diagnostic, &mut stderr());
+            }
+
+            let read_phase = Phase::new("read", started.elapsed());
+
+            let parse_started = Instant::now();
+            let dialect = *sub_matches.get_one::<Dialect>("dialect").unwrap();
+            // The cache is kept next to the source code, so it's only for single files.
+            let cache = (sub_matches.get_flag("cache")
+                && paths.len() == 1
+                && path != STDIN_PATH
+                && path != INLINE_PROGRAM)
+                .then(|| cache_path(path));
+            let cached_ast = cache.as_ref().and_then(|cache| {
+                let lookup = load_cached_ast(cache, &source_code, dialect);
+
+                if verbosity > 0 {
+                    let outcome = match lookup {
+                        CacheLookup::Hit(_) => "hit",
+                        _ => "miss",
+                    };
+                    eprintln!("cache {}: {}", outcome, cache.display());
+                }
+
+                match lookup {
+                    CacheLookup::Hit(ast) => Some(ast),
+                    CacheLookup::Miss => None,
+                    CacheLookup::Ignored(warning) => {
+                        let _ = renderer.render(&Diagnostic::warning(warning), &mut stderr());
+                        None
+                    }
+                }
+            });
+            let cache_hit = cached_ast.is_some();
+            let ast = match cached_ast {
+                Some(ast) => ast,
+                None => {
+                    from_source_to_node_ast_in_dialect(&source_code, dialect).map_err(|error| {
+                        let report =
+                            Report::new("run", Some(ErrorReport::from(&error)), started.elapsed());
+                        let diagnostic = Diagnostic::error(&error).at(Some(error.position()));
+                        fail(report, json, &renderer, diagnostic)
+                    })?
+                }
+            };
+
+            // A cache that can't be written only makes the next run parse again.
+            if let Some(cache) = cache.filter(|_| !cache_hit)
+                && let Err(error) = store_cached_ast(&cache, &source_code, dialect, &ast)
+            {
+                let warning = Diagnostic::warning(format!(
+                    "Unable to write the cache file {}: {}",
+                    cache.display(),
+                    error
+                ));
+                let _ = renderer.render(&warning, &mut stderr());
+            }
+
+            let parse_phase = Phase::new(
+                if cache_hit { "cache" } else { "parse" },
+                parse_started.elapsed(),
+            )
+            .with_nodes(ast.len());
+
+            // With --json the output is captured to be included in the report.
+            let captured_output = SharedWriter::new(Vec::new());
+
+            let output_file_writer = output_file
+                .map(|output_path| {
+                    let file = create_output_file(
+                        output_path
+                            .to_str()
+                            .expect("Expected a valid path string as it was parsed before"),
+                        sub_matches.get_flag("force"),
+                    )
+                    .map_err(|error| {
+                        let report =
+                            Report::new("run", Some(ErrorReport::from(&error)), started.elapsed());
+                        fail(report, json, &renderer, Diagnostic::error(&error))
+                    })?;
+
+                    let file_writer = SharedWriter::new(BufWriter::new(file));
+                    flush_output_file_on_interrupt(file_writer.clone());
+
+                    Ok(file_writer)
+                })
+                .transpose()?;
+
+            let max_output = sub_matches.get_one::<u64>("max-output").copied();
+            let limited = |writer: Box<dyn Write>| -> Box<dyn Write> {
+                match max_output {
+                    Some(limit) => Box::new(LimitedWriter::new(writer, limit)),
+                    None => writer,
+                }
+            };
+
+            let formatter: Box<dyn OutputFormatter> = match output_mode {
+                OutputMode::Raw => Box::new(RawFormatter),
+                OutputMode::Debug => Box::new(DebugFormatter),
+                OutputMode::Numeric => Box::new(NumericFormatter::new(output_separator.to_owned())),
+                OutputMode::Hex => Box::new(HexFormatter::new(output_separator.to_owned())),
+                OutputMode::Utf8 => Box::new(Utf8Formatter::default()),
+                OutputMode::Hexdump => Box::new(HexdumpFormatter::new()),
+                OutputMode::Csv => match sub_matches.value_source("output-separator") {
+                    Some(ValueSource::CommandLine | ValueSource::EnvVariable) => {
+                        Box::new(CsvFormatter::new(output_separator.to_owned()))
+                    }
+                    _ => Box::new(CsvFormatter::default()),
+                },
+            };
+
+            let tee = sub_matches.get_flag("tee");
+
+            // With --tee the output mode only applies to the stdout, the file always
+            // gets the raw bytes.
+            let display: Box<dyn OutputValue> = match output_file_writer {
+                Some(file_writer) if tee => Box::new(TeeOutput::new(
+                    (
+                        "stdout",
+                        WriterOutput::new(limited(Box::new(BufWriter::new(stdout()))), formatter),
+                    ),
+                    (
+                        "output file",
+                        WriterOutput::new(limited(Box::new(file_writer)), RawFormatter),
+                    ),
+                )),
+                Some(file_writer) => {
+                    Box::new(WriterOutput::new(limited(Box::new(file_writer)), formatter))
+                }
+                None if json => Box::new(WriterOutput::new(
+                    limited(Box::new(captured_output.clone())),
+                    formatter,
+                )),
+                None => Box::new(WriterOutput::new(
+                    limited(Box::new(BufWriter::new(stdout()))),
+                    formatter,
+                )),
+            };
+
+            let recording = sub_matches
+                .get_one::<PathBuf>("replay")
+                .map(|replay_path| {
+                    let content = read_binary_file(
+                        replay_path
+                            .to_str()
+                            .expect("Expected a valid path string as it was parsed before"),
+                    )
+                    .map_err(|error| {
+                        let report =
+                            Report::new("run", Some(ErrorReport::from(&error)), started.elapsed());
+                        fail(report, json, &renderer, Diagnostic::error(&error))
+                    })?;
+
+                    Ok(Recording::parse(&content))
+                })
+                .transpose()?;
+
+            let random_seed = sub_matches.contains_id("random-input").then(|| {
+                sub_matches
+                    .get_one::<u64>("random-input")
+                    .copied()
+                    .unwrap_or_else(|| {
+                        let seed = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .map_or(0, |time| time.as_nanos() as u64);
+
+                        if !sub_matches.get_flag("quiet") {
+                            eprintln!("Random input seed: {}", seed);
+                        }
+                        seed
+                    })
+            });
+
+            let queued_values = sub_matches.get_many::<u8>("in");
+
+            let input: Box<dyn InputValue> = match (&recording, random_seed, queued_values) {
+                (Some(recording), _, _) => Box::new(ReplayInput::new(recording.clone())),
+                (None, Some(seed), _) => Box::new(RandomInput::new(seed)),
+                (None, None, Some(values)) => Box::new(QueuedInput::new(values.copied())),
+                (None, None, None) => {
+                    // The stdin was read to the end to get the source code.
+                    if paths.contains(&STDIN_PATH) && reads_input(&ast) {
+                        let message = "The source code was read from the stdin, so the program can't read its input from it. Give the input with --in, --replay or --random-input";
+                        let report = Report::new(
+                            "run",
+                            Some(ErrorReport::new(
+                                "StdinAlreadyUsed",
+                                message.to_string(),
+                                USAGE_EXIT_CODE,
+                            )),
+                            started.elapsed(),
+                        );
+                        return Err(fail(report, json, &renderer, Diagnostic::error(message)));
+                    }
 
-            let source_code = read_source_code_file(path)
-                .map_err(|error| panic!("{:?}", error.to_string()))
-                .unwrap();
+                    let stdin_selection = select_stdin_mode(
+                        sub_matches.get_one::<StdinMode>("stdin-mode").copied(),
+                        stdin().is_terminal(),
+                    );
 
-            let ast = from_source_to_node_ast(&source_code)
-                .map_err(|error| panic!("{:?}", error.to_string()))
-                .unwrap();
+                    if let Some(warning) = stdin_selection.warning {
+                        let _ = renderer.render(&Diagnostic::warning(warning), &mut stderr());
+                    }
 
-            let mut interpreter = Interpreter::new(
-                BasicOutput,
-                BasicInput::default(),
-                BrainfuckMemory::new(*memory_tape_size),
-                InterpreterConfig::new(*limit_read_instructions),
+                    match stdin_selection.mode {
+                        StdinMode::Prompt => Box::new(
+                            BasicInput::default().with_hex_input(sub_matches.get_flag("hex-input")),
+                        ),
+                        StdinMode::Bytes => Box::new(ReaderInput::new(stdin())),
+                    }
+                }
+            };
+
+            let input: Box<dyn InputValue> = match sub_matches
+                .get_one::<NewlineMode>("input-newlines")
+                .unwrap()
+            {
+                NewlineMode::Passthrough => input,
+                mode => Box::new(NewlineInput::new(input, *mode)),
+            };
+
+            let transcript_writer = sub_matches
+                .get_one::<PathBuf>("transcript")
+                .map(|transcript_path| {
+                    let file = create_output_file(
+                        transcript_path
+                            .to_str()
+                            .expect("Expected a valid path string as it was parsed before"),
+                        sub_matches.get_flag("force"),
+                    )
+                    .map_err(|error| {
+                        let report =
+                            Report::new("run", Some(ErrorReport::from(&error)), started.elapsed());
+                        fail(report, json, &renderer, Diagnostic::error(&error))
+                    })?;
+
+                    Ok(SharedWriter::new(file))
+                })
+                .transpose()?;
+
+            let input: Box<dyn InputValue> = match &transcript_writer {
+                Some(transcript) => {
+                    Box::new(TranscriptInput::new(input, transcript.clone(), started))
+                }
+                None => input,
+            };
+
+            let config = InterpreterConfig::new(*limit_read_instructions)
+                .with_overflow_policy(overflow_policy)
+                .with_eof_behavior(*sub_matches.get_one::<EofBehavior>("eof-value").unwrap());
+            let data: Vec<(DataFile, Vec<u8>)> = sub_matches
+                .get_many::<DataFile>("data")
+                .unwrap_or_default()
+                .map(|data_file| {
+                    let content = read_binary_file(
+                        data_file
+                            .path
+                            .to_str()
+                            .expect("Expected a valid path string as it was parsed before"),
+                    )
+                    .map_err(|error| {
+                        let report =
+                            Report::new("run", Some(ErrorReport::from(&error)), started.elapsed());
+                        fail(report, json, &renderer, Diagnostic::error(&error))
+                    })?;
+
+                    Ok((data_file.clone(), content))
+                })
+                .collect::<Result<_, CliError>>()?;
+            let mut trace = sub_matches
+                .get_one::<PathBuf>("trace-file")
+                .map(|trace_path| {
+                    let file = create_output_file(
+                        trace_path
+                            .to_str()
+                            .expect("Expected a valid path string as it was parsed before"),
+                        sub_matches.get_flag("force"),
+                    )
+                    .map_err(|error| {
+                        let report =
+                            Report::new("run", Some(ErrorReport::from(&error)), started.elapsed());
+                        fail(report, json, &renderer, Diagnostic::error(&error))
+                    })?;
+
+                    Ok(
+                        match sub_matches.get_one::<TraceFormat>("trace-format").unwrap() {
+                            TraceFormat::Chrome => ChromeTrace::new(
+                                BufWriter::new(file),
+                                node_lines_and_columns(&source_code, dialect),
+                                *sub_matches.get_one::<usize>("trace-sample").unwrap(),
+                            ),
+                        },
+                    )
+                })
+                .transpose()?;
+
+            // The profile counts every instruction, so it only follows the runs that ask
+            // for it.
+            let mut profiler = sub_matches
+                .contains_id("profile")
+                .then(|| Profiler::new(&ast));
+
+            let options = MemoryOptions {
+                data: &data,
+                dump_range: sub_matches
+                    .contains_id("dump-memory")
+                    .then(|| sub_matches.get_one::<Range<isize>>("dump-memory").cloned()),
+                postmortem: !json && !sub_matches.get_flag("no-postmortem"),
+                stats: json || verbosity > 0,
+                dialect,
+                cell_size,
+                tracer: match (trace.as_mut(), profiler.as_mut()) {
+                    (Some(trace), _) => Some(trace as &mut (dyn ExecutionTracer + Send)),
+                    (None, Some(profiler)) => Some(profiler as &mut (dyn ExecutionTracer + Send)),
+                    (None, None) => None,
+                },
+            };
+
+            let init_pattern: Vec<u8> = sub_matches
+                .get_many::<u8>("init-value")
+                .or_else(|| sub_matches.get_many::<u8>("init-pattern"))
+                .unwrap_or_default()
+                .copied()
+                .collect();
+
+            let execute_started = Instant::now();
+            let memory_run = match memory_model {
+                MemoryModel::Vec => {
+                    let bounds = *sub_matches
+                        .get_one::<BoundsPolicy>("memory-bounds")
+                        .unwrap();
+                    let size = *memory_tape_size;
+                    let pattern = &init_pattern;
+
+                    // Each width is its own instantiation of the memory and the interpreter.
+                    match cell_size {
+                        CellSize::U8 => run_with_memory(
+                            display,
+                            input,
+                            || vec_memory::<u8>(size, pattern, bounds),
+                            config,
+                            &ast,
+                            options,
+                        ),
+                        CellSize::U16 => run_with_memory(
+                            display,
+                            input,
+                            || vec_memory::<u16>(size, pattern, bounds),
+                            config,
+                            &ast,
+                            options,
+                        ),
+                        CellSize::U32 => run_with_memory(
+                            display,
+                            input,
+                            || vec_memory::<u32>(size, pattern, bounds),
+                            config,
+                            &ast,
+                            options,
+                        ),
+                        CellSize::I8 => run_with_memory(
+                            display,
+                            input,
+                            || vec_memory::<i8>(size, pattern, bounds),
+                            config,
+                            &ast,
+                            options,
+                        ),
+                        CellSize::I32 => run_with_memory(
+                            display,
+                            input,
+                            || vec_memory::<i32>(size, pattern, bounds),
+                            config,
+                            &ast,
+                            options,
+                        ),
+                    }
+                }
+                MemoryModel::Bidirectional => run_with_memory(
+                    display,
+                    input,
+                    || BidirectionalMemory::new(*memory_tape_size),
+                    config,
+                    &ast,
+                    options,
+                ),
+                MemoryModel::Sparse => {
+                    let memory = || match sub_matches.value_source("memory-size") {
+                        Some(ValueSource::CommandLine | ValueSource::EnvVariable) => {
+                            SparseMemory::new(*memory_tape_size)
+                        }
+                        _ => SparseMemory::unbounded(),
+                    };
+
+                    run_with_memory(display, input, memory, config, &ast, options)
+                }
+            }
+            .map_err(|message| {
+                let report = Report::new(
+                    "run",
+                    Some(ErrorReport::new(
+                        "DataDoesNotFit",
+                        message.clone(),
+                        LIMIT_EXIT_CODE,
+                    )),
+                    started.elapsed(),
+                );
+                fail(report, json, &renderer, Diagnostic::error(message))
+            })?;
+            let execute_phase = Phase::new("execute", execute_started.elapsed());
+            let MemoryRun {
+                result,
+                summary,
+                execution_time,
+                dump,
+                postmortem,
+            } = memory_run;
+
+            // The trace is finished even when the run failed, so it stays valid JSON.
+            if let Some(trace) = trace
+                && let Err(error) = trace.finish()
+            {
+                let trace_path = sub_matches.get_one::<PathBuf>("trace-file").unwrap();
+                let error = unexpected_error(&trace_path.to_string_lossy(), error);
+                let report = Report::new("run", Some(ErrorReport::from(&error)), started.elapsed());
+                return Err(fail(report, json, &renderer, Diagnostic::error(&error)));
+            }
+
+            // Like the trace, the profile is written when the run failed too.
+            if let Some(profiler) = profiler {
+                let profile_path = sub_matches
+                    .get_one::<PathBuf>("profile")
+                    .unwrap()
+                    .to_str()
+                    .expect("Expected a valid path string as it was parsed before");
+                let profile = profiler.profile(
+                    &node_lines_and_columns(&source_code, dialect),
+                    &summary,
+                    execution_time,
+                );
+                let content = match sub_matches
+                    .get_one::<ProfileFormat>("profile-format")
+                    .unwrap()
+                {
+                    ProfileFormat::Text => profile.to_string(),
+                    ProfileFormat::Json => format!("{}\n", profile.to_json()),
+                };
+
+                if let Err(error) = create_output_file(profile_path, sub_matches.get_flag("force"))
+                    .and_then(|mut file| {
+                        file.write_all(content.as_bytes())
+                            .map_err(|error| unexpected_error(profile_path, error))
+                    })
+                {
+                    let report =
+                        Report::new("run", Some(ErrorReport::from(&error)), started.elapsed());
+                    return Err(fail(report, json, &renderer, Diagnostic::error(&error)));
+                }
+            }
+
+            if let Some(dump) = dump {
+                eprint!("{}", dump);
+            }
+
+            let timing = sub_matches
+                .get_flag("time")
+                .then(|| Timing::new(execution_time, summary.instructions_executed));
+
+            if let Some(timing) = timing
+                && !json
+            {
+                eprintln!("{}", timing);
+            }
+
+            if verbosity > 0 {
+                eprintln!(
+                    "{}",
+                    format_verbose_report(
+                        &[read_phase, parse_phase, execute_phase],
+                        Some(&summary),
+                        verbosity
+                    )
+                );
+                eprintln!("cells: size {}, {} on overflow", cell_size, overflow_policy);
+            }
+
+            if let Some(mut transcript) = transcript_writer {
+                let outcome = match &result {
+                    Ok(()) => "outcome success".to_string(),
+                    Err(error) => format!("outcome failure {:?}", error.to_string()),
+                };
+
+                if let Err(error) =
+                    write_transcript_line(&mut transcript, started.elapsed(), &outcome)
+                {
+                    let transcript_path = sub_matches.get_one::<PathBuf>("transcript").unwrap();
+                    let error = unexpected_error(&transcript_path.to_string_lossy(), error);
+                    let report =
+                        Report::new("run", Some(ErrorReport::from(&error)), started.elapsed());
+                    return Err(fail(report, json, &renderer, Diagnostic::error(&error)));
+                }
+            }
+
+            if let Some(recording) = &recording {
+                let unused_bytes = recording.unused_bytes(summary.inputs_requested);
+
+                if unused_bytes > 0 {
+                    let warning = Diagnostic::warning(format!(
+                        "The program didn't read {} bytes of the recorded input",
+                        unused_bytes
+                    ));
+                    let _ = renderer.render(&warning, &mut stderr());
+                }
+            }
+
+            // A pointer outside of the memory has no cell, which exits with 0.
+            let cell_exit = match sub_matches.get_flag("exit-cell") {
+                true => match summary.final_cell.map_or(0, |value| value.rem_euclid(256)) {
+                    0 => Ok(()),
+                    value => Err(CliError::ExitCell(value as i32)),
+                },
+                false => Ok(()),
+            };
+
+            // Ctrl-C at the prompt is caught by inquire instead of killing the process,
+            // so it exits as the SIGINT would.
+            if result == Err(InterpreterErrors::Interrupted) {
+                return Err(CliError::Interrupted);
+            }
+
+            if json {
+                let error = result
+                    .as_ref()
+                    .err()
+                    .map(|error| ErrorReport::from_runtime_error(error, &summary));
+                let output = captured_output.into_inner().unwrap_or_default();
+                let failure = error.clone().map(CliError::Failed);
+                let report = Report::new("run", error, started.elapsed())
+                    .with_run(summary, &output)
+                    .with_cells(cell_size, overflow_policy);
+                let report = match timing {
+                    Some(timing) => report.with_timing(timing),
+                    None => report,
+                };
+
+                print_report(&report)?;
+
+                return match failure {
+                    Some(failure) => Err(failure),
+                    None => cell_exit,
+                };
+            }
+
+            // Stopping when the reader doesn't want more output isn't an error.
+            if result == Err(InterpreterErrors::OutputClosed) {
+                return Err(CliError::OutputClosed);
+            }
+
+            if let Err(error) = result {
+                // The runtime errors happen at the last instruction executed, but when
+                // the limit is reached the loop that used it tells more.
+                let position = summary.last_instruction.and_then(|node| {
+                    source_position_of_node_in_dialect(&source_code, node, dialect)
+                });
+                let diagnostic = match summary.busiest_loop.and_then(|busiest_loop| {
+                    source_position_of_node_in_dialect(&source_code, busiest_loop.node, dialect)
+                        .map(|loop_position| (busiest_loop, loop_position))
+                }) {
+                    Some((busiest_loop, loop_position)) => {
+                        let (line, column) = line_and_column(&source_code, loop_position);
+                        let message = format!(
+                            "{}. The limit of {} instructions was reached while the loop at line {}, column {} ran {} iterations, with {}% of the last {} instructions",
+                            error,
+                            limit_read_instructions,
+                            line,
+                            column,
+                            busiest_loop.iterations,
+                            busiest_loop.instructions * 100
+                                / busiest_loop.instructions_counted.max(1),
+                            busiest_loop.instructions_counted
+                        );
+
+                        Diagnostic::error(message).at(Some(loop_position))
+                    }
+                    None => Diagnostic::error(&error).at(position),
+                };
+                let error_report = ErrorReport::from_runtime_error(&error, &summary);
+
+                // The state of the memory goes after the error, which says where the
+                // program failed.
+                if let Some(postmortem) = postmortem {
+                    let _ = renderer.render(&diagnostic, &mut stderr());
+                    eprint!("{}", postmortem);
+                    return Err(CliError::Failed(error_report));
+                }
+
+                let report = Report::new("run", Some(error_report), started.elapsed());
+                return Err(fail(report, false, &renderer, diagnostic));
+            }
+
+            if sub_matches.get_flag("quiet") {
+                return cell_exit;
+            }
+
+            // The stdout only carries what the program printed, every status message
+            // goes to the stderr so the output can be redirected.
+            if (output_file.is_none() || tee) && *output_mode == OutputMode::Debug {
+                eprintln!();
+            }
+
+            eprintln!("Program executed succesfully");
+
+            cell_exit?;
+        }
+        Some(("ast", sub_matches)) => {
+            let json = sub_matches.get_flag("json");
+            let started = Instant::now();
+            let path = match sub_matches.get_one::<PathBuf>("file") {
+                Some(path) => path
+                    .to_str()
+                    .expect("Expected a valid path string as it was parsed before"),
+                None => INLINE_PROGRAM,
+            };
+
+            let ExpandedSource {
+                source_code,
+                source_map,
+                ..
+            } = read_program(path, sub_matches).map_err(|error| {
+                let report = Report::new("ast", Some(ErrorReport::from(&error)), started.elapsed());
+                fail(report, json, &renderer, Diagnostic::error(&error))
+            })?;
+            let renderer = renderer
+                .with_source(path, &source_code)
+                .with_source_map(&source_map);
+
+            let dialect = *sub_matches.get_one::<Dialect>("dialect").unwrap();
+            let ast =
+                from_source_to_node_ast_in_dialect(&source_code, dialect).map_err(|error| {
+                    let report =
+                        Report::new("ast", Some(ErrorReport::from(&error)), started.elapsed());
+                    let diagnostic = Diagnostic::error(&error).at(Some(error.position()));
+                    fail(report, json, &renderer, diagnostic)
+                })?;
+
+            let printed = match json {
+                true => writeln!(
+                    stdout(),
+                    "{}",
+                    serde_json::to_string(&ast).expect("The AST only contains serializable values")
+                ),
+                false => write!(
+                    stdout(),
+                    "{}",
+                    render_ast_listing(&ast, &source_code, dialect)
+                ),
+            };
+
+            if printed.is_err_and(|error| error.kind() == ErrorKind::BrokenPipe) {
+                return Err(CliError::OutputClosed);
+            }
+        }
+        Some(("completions", sub_matches)) => {
+            let shell = *sub_matches.get_one::<Shell>("shell").unwrap();
+
+            let mut script = Vec::new();
+
+            // The script completes the name of the binary, not the one of the CLI.
+            generate(
+                shell,
+                &mut braincrub_cli(),
+                env!("CARGO_BIN_NAME"),
+                &mut script,
+            );
+
+            // generate panics when it can't write, so the script is written here.
+            if stdout()
+                .write_all(&script)
+                .is_err_and(|error| error.kind() == ErrorKind::BrokenPipe)
+            {
+                return Err(CliError::OutputClosed);
+            }
+        }
+        Some(("man", sub_matches)) => {
+            // The page shows the environment variables of the flags, but not the
+            // defaults of the config file of whoever generates it. It's named after
+            // the binary, which is what the readers type.
+            let cli = with_defaults(braincrub_cli(), &CliDefaults::default())
+                .name(env!("CARGO_BIN_NAME"));
+            let sections = [ENVIRONMENT_MAN_SECTION, EXIT_CODES_MAN_SECTION];
+
+            match sub_matches.get_one::<PathBuf>("output-directory") {
+                Some(directory) => {
+                    for (file_name, page) in render_man_pages(&cli, &sections) {
+                        let path = directory.join(file_name);
+                        let path = path.to_string_lossy();
+
+                        if let Err(error) = create_output_file(&path, true).and_then(|mut file| {
+                            file.write_all(page.as_bytes())
+                                .map_err(|error| unexpected_error(&path, error))
+                        }) {
+                            let _ = renderer.render(&Diagnostic::error(&error), &mut stderr());
+                            return Err(CliError::Failed(ErrorReport::from(&error)));
+                        }
+                    }
+                }
+                None => print_text(&render_man_page(&cli, &sections))?,
+            }
+        }
+        Some(("repl", sub_matches)) => {
+            let repl = Repl::new(
+                *sub_matches.get_one::<usize>("memory-size").unwrap(),
+                *sub_matches
+                    .get_one::<u64>("limit-read-instructions")
+                    .unwrap(),
             );
 
-            interpreter.load_ast_program(&ast);
+            if let Err(error) = run_repl(repl, stdin().lock(), &mut stdout()) {
+                if error.kind() == ErrorKind::BrokenPipe {
+                    return Err(CliError::OutputClosed);
+                }
+
+                let _ = renderer.render(&Diagnostic::error(&error), &mut stderr());
+                return Err(CliError::Runtime);
+            }
+        }
+        Some(("test", sub_matches)) => {
+            let directory = sub_matches.get_one::<String>("directory").unwrap();
+            let config = SuiteConfig {
+                memory_size: *sub_matches.get_one::<usize>("memory-size").unwrap(),
+                number_of_reads: *sub_matches
+                    .get_one::<u64>("limit-read-instructions")
+                    .unwrap(),
+                update: sub_matches.get_flag("update"),
+            };
+            let cases = find_cases(directory).map_err(|error| {
+                let _ = renderer.render(&Diagnostic::error(&error), &mut stderr());
+                CliError::Failed(ErrorReport::from(&error))
+            })?;
+            let mut summary = SuiteSummary::default();
+
+            for case in &cases {
+                let outcome = run_case(case, config);
 
-            interpreter
-                .run()
-                .map_err(|error| panic!("{:?}", error.to_string()))
-                .unwrap();
+                match &outcome {
+                    CaseOutcome::Failed(message) | CaseOutcome::Errored(message) => {
+                        print_line(&format!("{} {}: {}", outcome, case.name, message))?
+                    }
+                    _ => print_line(&format!("{} {}", outcome, case.name))?,
+                }
+                summary.add(&outcome);
+            }
 
-            println!("");
-            println!("Program executed succesfully");
+            print_line(&summary.to_string())?;
+            if !summary.is_success() {
+                return Err(CliError::Runtime);
+            }
+        }
+        Some(("diff", sub_matches)) => {
+            let started = Instant::now();
+            let config = RunConfig {
+                memory_size: *sub_matches.get_one::<usize>("memory-size").unwrap(),
+                number_of_reads: *sub_matches
+                    .get_one::<u64>("limit-read-instructions")
+                    .unwrap(),
+                dialect: *sub_matches.get_one::<Dialect>("dialect").unwrap(),
+            };
+            let file_failure = |error: FileError| {
+                let report =
+                    Report::new("diff", Some(ErrorReport::from(&error)), started.elapsed());
+                fail(report, false, &renderer, Diagnostic::error(&error))
+            };
+            // Only the programs failing are differences, the files that can't be read
+            // stop the comparison.
+            let [a, b] = ["a", "b"].map(|side| {
+                let path = sub_matches.get_one::<String>(side).unwrap();
+                read_source(path, stdin(), true, DEFAULT_MAX_SOURCE_SIZE)
+            });
+            let a = a.map_err(file_failure)?;
+            let b = b.map_err(file_failure)?;
+            let input = match sub_matches.get_one::<String>("input-file") {
+                Some(path) => read_binary_file(path).map_err(file_failure)?,
+                None => vec![],
+            };
+
+            let a = run_program(&a, &input, &config);
+            let b = run_program(&b, &input, &config);
+
+            match render_difference(&a, &b) {
+                Some(difference) => {
+                    print_text(&difference)?;
+                    return Err(CliError::Runtime);
+                }
+                None => print_line(&format!("outputs identical ({} bytes)", a.output.len()))?,
+            }
+        }
+        Some(("pipe", sub_matches)) => {
+            let started = Instant::now();
+            let dialect = *sub_matches.get_one::<Dialect>("dialect").unwrap();
+            let config = PipeConfig {
+                memory_size: *sub_matches.get_one::<usize>("memory-size").unwrap(),
+                number_of_reads: *sub_matches
+                    .get_one::<u64>("limit-read-instructions")
+                    .unwrap(),
+            };
+            let paths: Vec<&String> = sub_matches.get_many::<String>("file").unwrap().collect();
+            let sources: Vec<String> = paths
+                .iter()
+                .map(|path| {
+                    read_source(path, stdin(), true, DEFAULT_MAX_SOURCE_SIZE).map_err(|error| {
+                        let report =
+                            Report::new("pipe", Some(ErrorReport::from(&error)), started.elapsed());
+                        fail(report, false, &renderer, Diagnostic::error(&error))
+                    })
+                })
+                .collect::<Result<_, CliError>>()?;
+            let stages: Vec<Stage> = paths
+                .iter()
+                .zip(&sources)
+                .enumerate()
+                .map(|(index, (path, source_code))| {
+                    let ast = from_source_to_node_ast_in_dialect(source_code, dialect).map_err(
+                        |error| {
+                            let report = Report::new(
+                                "pipe",
+                                Some(ErrorReport::from(&error)),
+                                started.elapsed(),
+                            );
+                            let diagnostic = Diagnostic::error(format!(
+                                "Stage {} ({}): {}",
+                                index + 1,
+                                path,
+                                error
+                            ))
+                            .at(Some(error.position()));
+                            let renderer =
+                                DiagnosticRenderer::new(color).with_source(path, source_code);
+                            fail(report, false, &renderer, diagnostic)
+                        },
+                    )?;
+
+                    Ok(Stage {
+                        path: path.to_string(),
+                        ast,
+                    })
+                })
+                .collect::<Result<_, CliError>>()?;
+
+            // Only the first stage reads what was piped, the terminal isn't waited on.
+            let mut input = vec![];
+            if !stdin().is_terminal()
+                && let Err(error) = stdin().read_to_end(&mut input)
+            {
+                let _ = renderer.render(&Diagnostic::error(error.to_string()), &mut stderr());
+                return Err(CliError::File);
+            }
+
+            match run_pipe(&stages, input, config) {
+                Ok(output) => {
+                    let mut stdout = stdout();
+                    if let Err(error) = stdout.write_all(&output).and_then(|()| stdout.flush()) {
+                        if error.kind() == ErrorKind::BrokenPipe {
+                            return Err(CliError::OutputClosed);
+                        }
+                        let _ =
+                            renderer.render(&Diagnostic::error(error.to_string()), &mut stderr());
+                        return Err(CliError::Runtime);
+                    }
+                }
+                Err(failure) => {
+                    let source_code = &sources[failure.stage - 1];
+                    let position = failure.summary.last_instruction.and_then(|node| {
+                        source_position_of_node_in_dialect(source_code, node, dialect)
+                    });
+                    let report = Report::new(
+                        "pipe",
+                        Some(ErrorReport::from_run_error(
+                            &failure.error,
+                            &failure.summary,
+                        )),
+                        started.elapsed(),
+                    );
+                    let renderer =
+                        DiagnosticRenderer::new(color).with_source(&failure.path, source_code);
+                    return Err(fail(
+                        report,
+                        false,
+                        &renderer,
+                        Diagnostic::error(&failure).at(position),
+                    ));
+                }
+            }
         }
         _ => {
-            panic!("command doesn't exist")
+            unreachable!("clap requires one of the subcommands above")
         }
     }
+
+    Ok(())
 }
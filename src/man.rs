@@ -0,0 +1,174 @@
+use std::io::{self, Write};
+
+use clap::Command;
+use clap_mangen::Man;
+
+/**
+ * The flags of a subcommand, in a section named after it, like RUN OPTIONS. The
+ * help flag is the same in all of them, so it's left out.
+ */
+fn write_subcommand_options(page: &mut Vec<u8>, subcommand: &Command) -> io::Result<()> {
+    let heading = format!("{} options", subcommand.get_name());
+    let subcommand = subcommand
+        .clone()
+        .disable_help_flag(true)
+        .mut_args(|arg| arg.help_heading(heading.clone()));
+
+    Man::new(subcommand).render_options_section(page)
+}
+
+fn write_man_page(page: &mut Vec<u8>, command: &Command, sections: &[&str]) -> io::Result<()> {
+    // The text after the help goes in the description, after the about.
+    let description = [command.get_about(), command.get_after_help()]
+        .into_iter()
+        .flatten()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join("\n\n");
+    let man = Man::new(
+        command
+            .clone()
+            .long_about(description)
+            .after_help(None::<&str>),
+    )
+    .title(command.get_name().to_uppercase())
+    .source(source(command));
+
+    man.render(page)?;
+    for subcommand in visible_subcommands(command) {
+        write_subcommand_options(page, subcommand)?;
+    }
+
+    for section in sections {
+        page.write_all(section.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/**
+ * The name and the version of the CLI, in the footer of the pages.
+ */
+fn source(command: &Command) -> String {
+    format!("{} {}", command.get_name(), env!("CARGO_PKG_VERSION"))
+}
+
+fn visible_subcommands(command: &Command) -> impl Iterator<Item = &Command> {
+    command
+        .get_subcommands()
+        .filter(|subcommand| !subcommand.is_hide_set())
+}
+
+fn into_text(page: Vec<u8>) -> String {
+    String::from_utf8(page).expect("The page is made of the strings of the command")
+}
+
+/**
+ * The manual page of the CLI in roff, with the flags of every subcommand that
+ * isn't hidden. The sections that clap doesn't know about, like the exit codes,
+ * are appended as they are given, already in roff.
+ */
+pub fn render_man_page(command: &Command, sections: &[&str]) -> String {
+    let mut page = vec![];
+
+    write_man_page(&mut page, &prepare(command), sections).expect("Writing in memory doesn't fail");
+    into_text(page)
+}
+
+/**
+ * The pages written in a directory, by their file name: the one of the CLI, and one
+ * for every subcommand, like braincrab-run.1, which the page of the CLI points to.
+ */
+pub fn render_man_pages(command: &Command, sections: &[&str]) -> Vec<(String, String)> {
+    let command = prepare(command);
+    let mut pages = vec![(
+        format!("{}.1", command.get_name()),
+        render_man_page(&command, sections),
+    )];
+
+    for subcommand in visible_subcommands(&command) {
+        let name = subcommand
+            .get_display_name()
+            .unwrap_or(subcommand.get_name());
+        let man = Man::new(subcommand.clone())
+            .title(name.to_uppercase())
+            .source(source(&command));
+        let mut page = vec![];
+
+        man.render(&mut page)
+            .expect("Writing in memory doesn't fail");
+        pages.push((man.get_filename(), into_text(page)));
+    }
+
+    pages
+}
+
+/**
+ * Built, the subcommands are named after the CLI, like braincrab-run, and the help
+ * subcommand has no page.
+ */
+fn prepare(command: &Command) -> Command {
+    let mut command = command.clone().disable_help_subcommand(true);
+    command.build();
+    command
+}
+
+#[cfg(test)]
+mod man_test {
+    use super::*;
+    use clap::{Arg, ArgAction};
+
+    fn command() -> Command {
+        Command::new("tool")
+            .about("A tool.")
+            .after_help("Read the flags from the environment too.")
+            .arg(
+                Arg::new("memory-size")
+                    .short('m')
+                    .long("memory-size")
+                    .value_name("SIZE")
+                    .default_value("3000")
+                    .help("Cells of the memory"),
+            )
+            .subcommand(
+                Command::new("run").about("Run it").arg(
+                    Arg::new("quiet")
+                        .long("quiet")
+                        .action(ArgAction::SetTrue)
+                        .help("Hide the messages"),
+                ),
+            )
+            .subcommand(Command::new("secret").hide(true))
+    }
+
+    #[test]
+    fn given_a_command_when_its_page_is_rendered_then_the_flags_and_the_subcommands_are_listed() {
+        let page = render_man_page(&command(), &[".SH EXIT STATUS\n0 on success\n"]);
+
+        assert!(page.contains(".TH TOOL 1"));
+        assert!(
+            page.contains(
+                ".SH DESCRIPTION\nA tool.\n.PP\nRead the flags from the environment too.\n"
+            )
+        );
+        assert!(page.contains(
+            ".TP\n\\fB\\-m\\fR, \\fB\\-\\-memory\\-size\\fR \\fI<SIZE>\\fR [default: 3000]\nCells of the memory\n"
+        ));
+        assert!(page.contains("tool\\-run(1)\nRun it\n"));
+        assert!(
+            page.contains(".SH \"RUN OPTIONS\"\n.TP\n\\fB\\-\\-quiet\\fR\nHide the messages\n")
+        );
+        assert!(!page.contains("secret"));
+        assert!(!page.contains("help(1)"));
+        assert!(page.ends_with(".SH EXIT STATUS\n0 on success\n"))
+    }
+
+    #[test]
+    fn given_a_command_when_its_pages_are_rendered_then_every_visible_subcommand_has_one() {
+        let pages = render_man_pages(&command(), &[]);
+        let names: Vec<&str> = pages.iter().map(|(name, _)| name.as_str()).collect();
+
+        assert_eq!(names, ["tool.1", "tool-run.1"]);
+        assert!(pages[1].1.contains(".TH TOOL-RUN 1"))
+    }
+}
@@ -1,9 +1,15 @@
 #[cfg(test)]
 use std::iter::repeat_n;
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+/**
+ * The AST is serialized for the cache and the `ast --json` subcommand, with the
+ * names of the types and their fields. Renaming one of them changes the format,
+ * which the tests check against the files of resources/serde.
+ */
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum BrainfuckOperations {
     MovePointerRight,
     MovePointerLeft,
@@ -13,22 +19,38 @@ pub enum BrainfuckOperations {
     OutputCommand,
     LoopStart,
     LoopEnd,
+    SwitchTapeNext,
+    SwitchTapePrev,
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+/**
+ * The commands the parser understands. The multitape dialect adds } to move to the
+ * next tape and { to move to the previous one, both going round. In the standard
+ * dialect they are comments, like any other character.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Dialect {
+    #[default]
+    Standard,
+    Multitape,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CommandInformation {
     pub operation: BrainfuckOperations,
     pub next_position: usize, // Change to Option
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct LoopInformation {
     pub operation: BrainfuckOperations,
     pub next_position_as_true: usize,  // Change to Option
     pub next_position_as_false: usize, // Change to Option
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum BrainfuckNodeAST {
     Command(CommandInformation),
     Loop(LoopInformation),
@@ -40,6 +62,13 @@ pub struct BrainfuckASTBuilder {
     pub ast: Vec<BrainfuckNodeAST>,
 }
 
+#[cfg(test)]
+impl Default for BrainfuckASTBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 impl BrainfuckASTBuilder {
     pub fn new() -> Self {
@@ -91,16 +120,31 @@ impl BrainfuckASTBuilder {
     }
 }
 
+/**
+ * The position is the byte offset in the source code of the bracket without a
+ * match, so the error can point to it.
+ */
 #[derive(Debug, PartialEq, Error)]
 pub enum ParserErrors {
     #[error("The source code have more open loop brackets than closing loop brackets.")]
-    MissingTerminantedLoop,
+    MissingTerminantedLoop { position: usize },
     #[error("The source code have more closing loop brackets than open loop brackets.")]
-    MissingOpenLoop,
+    MissingOpenLoop { position: usize },
 }
 
-fn map_char_to_brainfuck_operation(token: char) -> Option<BrainfuckOperations> {
+impl ParserErrors {
+    pub fn position(&self) -> usize {
+        match self {
+            ParserErrors::MissingTerminantedLoop { position }
+            | ParserErrors::MissingOpenLoop { position } => *position,
+        }
+    }
+}
+
+fn map_char_to_brainfuck_operation(token: char, dialect: Dialect) -> Option<BrainfuckOperations> {
     match token {
+        '}' if dialect == Dialect::Multitape => Some(BrainfuckOperations::SwitchTapeNext),
+        '{' if dialect == Dialect::Multitape => Some(BrainfuckOperations::SwitchTapePrev),
         '>' => Some(BrainfuckOperations::MovePointerRight),
         '<' => Some(BrainfuckOperations::MovePointerLeft),
         '+' => Some(BrainfuckOperations::IncrementByOneCurrentCell),
@@ -113,22 +157,45 @@ fn map_char_to_brainfuck_operation(token: char) -> Option<BrainfuckOperations> {
     }
 }
 
+/**
+ * The source code with the offsets of its characters, without the shebang line of
+ * the executable files, like #!/usr/bin/env -S braincrab run -f. The offsets stay
+ * the ones of the whole source code, so the lines after it keep their numbers.
+ */
+fn source_characters(source_code: &str) -> impl Iterator<Item = (usize, char)> {
+    let commands_start = match source_code.starts_with("#!") {
+        true => source_code.find('\n').unwrap_or(source_code.len()),
+        false => 0,
+    };
+
+    source_code[commands_start..]
+        .char_indices()
+        .map(move |(offset, token)| (commands_start + offset, token))
+}
+
 pub fn from_source_to_node_ast(source_code: &str) -> Result<Vec<BrainfuckNodeAST>, ParserErrors> {
-    let mut loop_start_position: Vec<usize> = vec![];
+    from_source_to_node_ast_in_dialect(source_code, Dialect::Standard)
+}
+
+pub fn from_source_to_node_ast_in_dialect(
+    source_code: &str,
+    dialect: Dialect,
+) -> Result<Vec<BrainfuckNodeAST>, ParserErrors> {
+    // Every open loop keeps its node position and its offset in the source code.
+    let mut loop_start_position: Vec<(usize, usize)> = vec![];
     let mut program_ast_vec: Vec<BrainfuckNodeAST> = vec![];
-    let mut iter_chars = source_code.chars().into_iter();
 
-    while let Some(token) = iter_chars.next() {
-        match map_char_to_brainfuck_operation(token) {
+    for (offset, token) in source_characters(source_code) {
+        match map_char_to_brainfuck_operation(token, dialect) {
             Some(BrainfuckOperations::LoopStart) => {
-                loop_start_position.push(program_ast_vec.len());
+                loop_start_position.push((program_ast_vec.len(), offset));
                 program_ast_vec.push(BrainfuckNodeAST::Command(CommandInformation {
                     operation: BrainfuckOperations::LoopStart,
                     next_position: program_ast_vec.len() + 1,
                 }));
             }
             Some(BrainfuckOperations::LoopEnd) => match loop_start_position.pop() {
-                Some(last_position_recorded) => {
+                Some((last_position_recorded, _)) => {
                     program_ast_vec.push(BrainfuckNodeAST::Command(CommandInformation {
                         operation: BrainfuckOperations::LoopEnd,
                         next_position: last_position_recorded,
@@ -140,7 +207,7 @@ pub fn from_source_to_node_ast(source_code: &str) -> Result<Vec<BrainfuckNodeAST
                             next_position_as_false: program_ast_vec.len(),
                         })
                 }
-                None => return Err(ParserErrors::MissingOpenLoop),
+                None => return Err(ParserErrors::MissingOpenLoop { position: offset }),
             },
             Some(value) => {
                 program_ast_vec.push(BrainfuckNodeAST::Command(CommandInformation {
@@ -152,13 +219,96 @@ pub fn from_source_to_node_ast(source_code: &str) -> Result<Vec<BrainfuckNodeAST
         }
     }
 
-    if loop_start_position.len() > 0 {
-        return Err(ParserErrors::MissingTerminantedLoop);
+    if let Some((_, offset)) = loop_start_position.first() {
+        return Err(ParserErrors::MissingTerminantedLoop { position: *offset });
     }
 
     Ok(program_ast_vec)
 }
 
+/**
+ * Source codes smaller than this aren't checked by looks_like_prose, they have too
+ * few characters to tell.
+ */
+pub const PROSE_CHECK_MIN_SIZE: usize = 4 << 10;
+
+/**
+ * Least share of the bytes of a program that are commands, leaving out the . , and
+ * - that any text has.
+ */
+pub const MIN_COMMAND_RATIO: f64 = 0.01;
+
+/**
+ * The share of the bytes that are commands, without the punctuation commands, as
+ * they are as common in the comments as in the code.
+ */
+pub fn command_ratio(source_code: &str, dialect: Dialect) -> f64 {
+    let commands = source_characters(source_code)
+        .filter(|(_, token)| !matches!(token, '.' | ',' | '-'))
+        .filter(|(_, token)| map_char_to_brainfuck_operation(*token, dialect).is_some())
+        .count();
+
+    commands as f64 / source_code.len().max(1) as f64
+}
+
+/**
+ * The command ratio of a big source code that has too few commands to be a
+ * program, like a file of notes or a binary given by mistake.
+ */
+pub fn looks_like_prose(source_code: &str, dialect: Dialect) -> Option<f64> {
+    if source_code.len() < PROSE_CHECK_MIN_SIZE {
+        return None;
+    }
+
+    let ratio = command_ratio(source_code, dialect);
+
+    (ratio < MIN_COMMAND_RATIO).then_some(ratio)
+}
+
+/**
+ * Whether the program has any , so it may read from the input.
+ */
+pub fn reads_input(ast: &[BrainfuckNodeAST]) -> bool {
+    ast.iter().any(|node| {
+        matches!(
+            node,
+            BrainfuckNodeAST::Command(CommandInformation {
+                operation: BrainfuckOperations::InputCommand,
+                ..
+            })
+        )
+    })
+}
+
+/**
+ * Every operation in the source code is a node in the AST, so the node position
+ * is the number of operations found before it.
+ */
+pub fn source_position_of_node(source_code: &str, node_position: usize) -> Option<usize> {
+    source_position_of_node_in_dialect(source_code, node_position, Dialect::Standard)
+}
+
+pub fn source_position_of_node_in_dialect(
+    source_code: &str,
+    node_position: usize,
+    dialect: Dialect,
+) -> Option<usize> {
+    source_characters(source_code)
+        .filter(|(_, token)| map_char_to_brainfuck_operation(*token, dialect).is_some())
+        .nth(node_position)
+        .map(|(offset, _)| offset)
+}
+
+/**
+ * The offsets in the source code of all the nodes, in order.
+ */
+pub fn source_positions_of_nodes(source_code: &str, dialect: Dialect) -> Vec<usize> {
+    source_characters(source_code)
+        .filter(|(_, token)| map_char_to_brainfuck_operation(*token, dialect).is_some())
+        .map(|(offset, _)| offset)
+        .collect()
+}
+
 #[cfg(test)]
 mod parser_source_code_test {
     use super::*;
@@ -224,7 +374,7 @@ mod parser_source_code_test {
         let result = from_source_to_node_ast(input)
             .expect_err("Expected an error as the first bracked found is close loop");
 
-        assert_eq!(result, ParserErrors::MissingOpenLoop)
+        assert_eq!(result, ParserErrors::MissingOpenLoop { position: 1 })
     }
 
     #[test]
@@ -234,7 +384,7 @@ mod parser_source_code_test {
         let result = from_source_to_node_ast(input)
             .expect_err("Expected an error as we have unbalanced brackets in the loop");
 
-        assert_eq!(result, ParserErrors::MissingOpenLoop)
+        assert_eq!(result, ParserErrors::MissingOpenLoop { position: 13 })
     }
 
     #[test]
@@ -245,6 +395,139 @@ mod parser_source_code_test {
             "Expected an error as the parsed ended before could find a matched bracket",
         );
 
-        assert_eq!(result, ParserErrors::MissingTerminantedLoop)
+        assert_eq!(result, ParserErrors::MissingTerminantedLoop { position: 0 })
+    }
+
+    #[test]
+    fn given_nested_loops_without_closing_the_outer_one_then_the_error_points_to_the_outer_bracket()
+    {
+        let result = from_source_to_node_ast("+[[-]").unwrap_err();
+
+        assert_eq!(result, ParserErrors::MissingTerminantedLoop { position: 1 })
+    }
+
+    #[test]
+    fn given_a_node_position_when_looking_for_it_in_the_source_code_then_the_comments_are_skipped()
+    {
+        let source_code = "a+ b\n>.";
+
+        assert_eq!(source_position_of_node(source_code, 0), Some(1));
+        assert_eq!(source_position_of_node(source_code, 2), Some(6));
+        assert_eq!(source_position_of_node(source_code, 3), None)
+    }
+
+    #[test]
+    fn given_a_shebang_line_when_parsed_then_its_characters_are_not_commands() {
+        let source_code = "#!/usr/bin/env -S braincrab run -f ./a+b.bf\n+.";
+        let mut builder = BrainfuckASTBuilder::new();
+
+        assert_eq!(
+            from_source_to_node_ast(source_code).unwrap(),
+            *builder
+                .add_command_node(BrainfuckOperations::IncrementByOneCurrentCell, 1)
+                .add_command_node(BrainfuckOperations::OutputCommand, 2)
+                .build()
+        );
+        assert_eq!(source_position_of_node(source_code, 0), Some(44));
+        assert_eq!(from_source_to_node_ast("#!+."), Ok(vec![]));
+        assert_eq!(from_source_to_node_ast(" #!+.").unwrap().len(), 2)
+    }
+
+    // Only works in Linux and Mac
+    macro_rules! source_test_case {
+        ($fname:expr) => {
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/resources/test/",
+                $fname
+            ))
+        };
+    }
+
+    #[test]
+    fn given_a_file_of_prose_when_checked_then_it_doesnt_look_like_a_program() {
+        let prose = source_test_case!("prose.txt");
+
+        assert!(prose.len() > PROSE_CHECK_MIN_SIZE);
+        assert!(looks_like_prose(prose, Dialect::Standard).is_some_and(|ratio| ratio < 0.001))
+    }
+
+    #[test]
+    fn given_a_heavily_commented_program_when_checked_then_it_looks_like_a_program() {
+        let program = source_test_case!("commented_hello_world.bf");
+
+        assert!(program.len() > PROSE_CHECK_MIN_SIZE);
+        assert_eq!(looks_like_prose(program, Dialect::Standard), None)
+    }
+
+    #[test]
+    fn given_a_small_source_code_without_commands_when_checked_then_it_isnt_judged() {
+        assert_eq!(looks_like_prose("Some notes.", Dialect::Standard), None);
+        assert_eq!(command_ratio("a+b.", Dialect::Standard), 0.25)
+    }
+
+    #[test]
+    fn given_programs_with_and_without_input_when_checked_then_only_the_one_with_a_comma_reads() {
+        assert!(reads_input(&from_source_to_node_ast("+[,.]").unwrap()));
+        assert!(!reads_input(&from_source_to_node_ast("+[-.]").unwrap()))
+    }
+
+    #[test]
+    fn given_the_tape_switch_commands_when_parsed_in_each_dialect_then_only_multitape_has_them() {
+        assert_eq!(
+            from_source_to_node_ast("{+}"),
+            Ok(vec![BrainfuckNodeAST::Command(CommandInformation {
+                operation: BrainfuckOperations::IncrementByOneCurrentCell,
+                next_position: 1,
+            })])
+        );
+
+        let mut builder = BrainfuckASTBuilder::new();
+
+        assert_eq!(
+            from_source_to_node_ast_in_dialect("{+}", Dialect::Multitape).unwrap(),
+            *builder
+                .add_command_node(BrainfuckOperations::SwitchTapePrev, 1)
+                .add_command_node(BrainfuckOperations::IncrementByOneCurrentCell, 2)
+                .add_command_node(BrainfuckOperations::SwitchTapeNext, 3)
+                .build()
+        );
+        assert_eq!(
+            source_position_of_node_in_dialect("a{+}", 2, Dialect::Multitape),
+            Some(3)
+        )
+    }
+
+    #[test]
+    fn given_every_kind_of_node_when_it_is_serialized_then_it_reads_back_the_same() {
+        let ast = from_source_to_node_ast_in_dialect("+-<>[.,]{}", Dialect::Multitape).unwrap();
+        let with_noop = [ast.clone(), vec![BrainfuckNodeAST::NoOp]].concat();
+
+        let json = serde_json::to_string(&with_noop).unwrap();
+        let bytes = postcard::to_stdvec(&with_noop).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<Vec<BrainfuckNodeAST>>(&json).unwrap(),
+            with_noop
+        );
+        assert_eq!(
+            postcard::from_bytes::<Vec<BrainfuckNodeAST>>(&bytes).unwrap(),
+            with_noop
+        );
+        assert!(json.ends_with(r#""NoOp"]"#))
+    }
+
+    #[test]
+    fn given_a_node_with_an_unknown_field_when_it_is_read_then_it_is_refused() {
+        let result = serde_json::from_str::<BrainfuckNodeAST>(
+            r#"{"Command": {"operation": "OutputCommand", "next_position": 1, "cell": 0}}"#,
+        );
+
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .starts_with("unknown field `cell`")
+        )
     }
 }
@@ -0,0 +1,125 @@
+use std::fmt::{self, Display};
+
+use crate::{
+    interpreter::RunSummary,
+    parser::{BrainfuckNodeAST, Dialect},
+    run::{BraincrubError, RunConfig, run_ast},
+};
+
+/**
+ * A program of the pipe, with the path it was read from to name it when it fails.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stage {
+    pub path: String,
+    pub ast: Vec<BrainfuckNodeAST>,
+}
+
+/**
+ * The limits of every stage. Each stage runs with a memory of its own, but all of
+ * them get the same size and the same limit of instructions.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct PipeConfig {
+    pub memory_size: usize,
+    pub number_of_reads: u64,
+}
+
+/**
+ * The stage that failed, counted from 1, with the error and how far it went.
+ */
+#[derive(Debug, PartialEq)]
+pub struct StageFailure {
+    pub stage: usize,
+    pub path: String,
+    pub error: BraincrubError,
+    pub summary: RunSummary,
+}
+
+impl Display for StageFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Stage {} ({}): {}", self.stage, self.path, self.error)
+    }
+}
+
+fn run_stage(
+    number: usize,
+    stage: &Stage,
+    input: Vec<u8>,
+    config: PipeConfig,
+) -> Result<Vec<u8>, Box<StageFailure>> {
+    let config = RunConfig {
+        memory_size: config.memory_size,
+        number_of_reads: config.number_of_reads,
+        // The stages are already parsed.
+        dialect: Dialect::Standard,
+    };
+    let (result, ran) = run_ast(&stage.ast, &input, &config);
+
+    match ran {
+        Ok(()) => Ok(result.output),
+        Err(error) => Err(Box::new(StageFailure {
+            stage: number,
+            path: stage.path.clone(),
+            error,
+            summary: result.summary,
+        })),
+    }
+}
+
+/**
+ * Runs the stages from the first to the last, the whole output of a stage being
+ * the input of the next one. The input goes to the first stage and the output of
+ * the last stage is returned.
+ */
+pub fn run_pipe(
+    stages: &[Stage],
+    input: Vec<u8>,
+    config: PipeConfig,
+) -> Result<Vec<u8>, Box<StageFailure>> {
+    stages
+        .iter()
+        .enumerate()
+        .try_fold(input, |input, (index, stage)| {
+            run_stage(index + 1, stage, input, config)
+        })
+}
+
+#[cfg(test)]
+mod pipe_test {
+    use super::*;
+    use crate::parser::from_source_to_node_ast;
+
+    const CONFIG: PipeConfig = PipeConfig {
+        memory_size: 30,
+        number_of_reads: 10_000,
+    };
+
+    fn stage(path: &str, source_code: &str) -> Stage {
+        Stage {
+            path: path.to_string(),
+            ast: from_source_to_node_ast(source_code).unwrap(),
+        }
+    }
+
+    #[test]
+    fn given_stages_when_the_pipe_runs_then_each_output_is_the_input_of_the_next_one() {
+        let stages = [
+            stage("cat.bf", ",[.,]"),
+            stage("upper.bf", ",[--------------------------------.,]"),
+            stage("cat.bf", ",[.,]"),
+        ];
+
+        assert_eq!(run_pipe(&stages, b"abc".to_vec(), CONFIG).unwrap(), b"ABC")
+    }
+
+    #[test]
+    fn given_a_stage_that_never_ends_when_the_pipe_runs_then_the_failure_names_the_stage() {
+        let stages = [stage("cat.bf", ",[.,]"), stage("runaway.bf", "+[]")];
+
+        let failure = run_pipe(&stages, b"a".to_vec(), CONFIG).unwrap_err();
+
+        assert_eq!(failure.stage, 2);
+        assert!(failure.to_string().starts_with("Stage 2 (runaway.bf): "))
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    cmp::Reverse,
+    fmt::{self, Display},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use crate::{
+    interpreter::{ExecutionTracer, RunSummary},
+    listing::symbol_and_name,
+    parser::{BrainfuckNodeAST, BrainfuckOperations},
+    report::Timing,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileFormat {
+    Text,
+    Json,
+}
+
+/**
+ * Loops listed in the profile, the ones with the most instructions.
+ */
+pub const PROFILE_TOP_LOOPS: usize = 10;
+
+/**
+ * Counts how many times every node runs and the instructions executed inside of
+ * every loop, its nested loops included. It follows the execution as a tracer, so
+ * the runs without --profile don't count anything.
+ */
+pub struct Profiler {
+    operations: Vec<Option<BrainfuckOperations>>,
+    executions: Vec<u64>,
+    loop_instructions: Vec<u64>,
+    loop_runs_finished: Vec<u64>,
+    running_loops: Vec<usize>,
+}
+
+impl Profiler {
+    pub fn new(ast: &[BrainfuckNodeAST]) -> Self {
+        let operations = ast
+            .iter()
+            .map(|node| match node {
+                BrainfuckNodeAST::Command(command) => Some(command.operation),
+                BrainfuckNodeAST::Loop(loop_node) => Some(loop_node.operation),
+                BrainfuckNodeAST::NoOp => None,
+            })
+            .collect();
+
+        Profiler {
+            operations,
+            executions: vec![0; ast.len()],
+            loop_instructions: vec![0; ast.len()],
+            loop_runs_finished: vec![0; ast.len()],
+            running_loops: vec![],
+        }
+    }
+
+    /**
+     * The figures of the run, with the loops named by the line and the column of
+     * their node.
+     */
+    pub fn profile(
+        &self,
+        positions: &[String],
+        summary: &RunSummary,
+        execution_time: Duration,
+    ) -> Profile {
+        let total = summary.instructions_executed;
+        let percentage = |count: u64| count as f64 * 100.0 / total.max(1) as f64;
+
+        let mut loops: Vec<LoopProfile> = self
+            .operations
+            .iter()
+            .enumerate()
+            .filter(|(node, operation)| {
+                **operation == Some(BrainfuckOperations::LoopStart) && self.executions[*node] > 0
+            })
+            .map(|(node, _)| LoopProfile {
+                position: positions.get(node).cloned().unwrap_or_else(|| "?".into()),
+                // Every run of the loop tests its cell once more than it iterates,
+                // but the run still going when the program stopped.
+                iterations: self.executions[node] - self.loop_runs_finished[node],
+                instructions: self.loop_instructions[node],
+                percentage: percentage(self.loop_instructions[node]),
+            })
+            .collect();
+        // The sort is stable, so the first loop of the source wins the ties.
+        loops.sort_by_key(|loop_profile| Reverse(loop_profile.instructions));
+        loops.truncate(PROFILE_TOP_LOOPS);
+
+        let mut opcodes: Vec<OpcodeProfile> = vec![];
+        for (operation, executions) in self.operations.iter().zip(&self.executions) {
+            let Some(operation) = operation else { continue };
+            let (symbol, _) = symbol_and_name(*operation);
+
+            match opcodes.iter_mut().find(|opcode| opcode.opcode == symbol) {
+                Some(opcode) => opcode.executions += executions,
+                None => opcodes.push(OpcodeProfile {
+                    opcode: symbol,
+                    executions: *executions,
+                    percentage: 0.0,
+                }),
+            }
+        }
+        opcodes.retain(|opcode| opcode.executions > 0);
+        opcodes.sort_by_key(|opcode| Reverse(opcode.executions));
+        for opcode in opcodes.iter_mut() {
+            opcode.percentage = percentage(opcode.executions);
+        }
+
+        Profile {
+            timing: Timing::new(execution_time, total),
+            max_memory_position: summary.max_memory_position,
+            cells_touched: summary.cells_touched,
+            loops,
+            opcodes,
+        }
+    }
+}
+
+impl ExecutionTracer for Profiler {
+    fn loop_started(&mut self, node: usize) {
+        self.running_loops.push(node);
+    }
+
+    fn loop_finished(&mut self, node: usize) {
+        self.running_loops.pop();
+        self.loop_runs_finished[node] += 1;
+    }
+
+    fn output(&mut self, _node: usize, _value: u8) {}
+
+    fn input(&mut self, _node: usize, _value: Option<u8>) {}
+
+    fn instruction(&mut self, node: usize, _instructions_executed: u64, _pointer: isize) {
+        self.executions[node] += 1;
+
+        for running_loop in &self.running_loops {
+            self.loop_instructions[*running_loop] += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoopProfile {
+    pub position: String,
+    pub iterations: u64,
+    pub instructions: u64,
+    pub percentage: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpcodeProfile {
+    pub opcode: char,
+    pub executions: u64,
+    pub percentage: f64,
+}
+
+/**
+ * What --profile writes after the run: the loops that used the most instructions,
+ * how many times each command ran, how far the memory went and how long it took.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Profile {
+    pub timing: Timing,
+    pub max_memory_position: usize,
+    pub cells_touched: usize,
+    pub loops: Vec<LoopProfile>,
+    pub opcodes: Vec<OpcodeProfile>,
+}
+
+impl Profile {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("The profile only contains serializable values")
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.timing)?;
+        writeln!(
+            f,
+            "memory: {} cells touched, up to the cell {}",
+            self.cells_touched, self.max_memory_position
+        )?;
+
+        writeln!(f, "\nloops by instructions:")?;
+        if self.loops.is_empty() {
+            writeln!(f, "  no loop ran")?;
+        }
+        for profile in &self.loops {
+            writeln!(
+                f,
+                "  loop at {}: {} instructions ({:.2}%), {} iterations",
+                profile.position, profile.instructions, profile.percentage, profile.iterations
+            )?;
+        }
+
+        writeln!(f, "\ncommands:")?;
+        for profile in &self.opcodes {
+            writeln!(
+                f,
+                "  {} {} ({:.2}%)",
+                profile.opcode, profile.executions, profile.percentage
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod profile_test {
+    use super::*;
+    use crate::{
+        interpreter::{Interpreter, InterpreterConfig},
+        io::{BrainfuckMemory, CollectingOutput, MemoryTape, QueuedInput},
+        listing::node_lines_and_columns,
+        parser::{Dialect, from_source_to_node_ast},
+    };
+
+    fn profile(source_code: &str) -> Profile {
+        let ast = from_source_to_node_ast(source_code).unwrap();
+        let mut profiler = Profiler::new(&ast);
+        let mut interpreter = Interpreter::new(
+            CollectingOutput::new(),
+            QueuedInput::from(vec![]),
+            BrainfuckMemory::<u8>::new(30),
+            InterpreterConfig::new(10_000),
+        );
+        interpreter.load_ast_program(&ast);
+        interpreter.set_tracer(&mut profiler);
+        let _ = interpreter.run();
+        let summary = interpreter.summary.clone();
+        drop(interpreter);
+
+        profiler.profile(
+            &node_lines_and_columns(source_code, Dialect::Standard),
+            &summary,
+            Duration::ZERO,
+        )
+    }
+
+    #[test]
+    fn given_nested_loops_when_the_program_is_profiled_then_the_outer_loop_counts_the_inner_one() {
+        let profile = profile("++[>+++[-]<-]");
+
+        assert_eq!(
+            profile
+                .loops
+                .iter()
+                .map(|loop_profile| (
+                    loop_profile.position.as_str(),
+                    loop_profile.iterations,
+                    loop_profile.instructions
+                ))
+                .collect::<Vec<_>>(),
+            [("1:3", 2, 36), ("1:8", 6, 18)]
+        );
+        assert_eq!(
+            profile
+                .opcodes
+                .iter()
+                .map(|opcode| (opcode.opcode, opcode.executions))
+                .collect::<Vec<_>>(),
+            [('[', 11), ('+', 8), ('-', 8), (']', 8), ('>', 2), ('<', 2)]
+        )
+    }
+
+    #[test]
+    fn given_a_program_that_never_ends_when_it_is_profiled_then_the_running_loop_is_counted() {
+        let profile = profile("+[]");
+
+        assert_eq!(profile.loops[0].iterations, 5_000);
+        assert!((profile.loops[0].percentage - 99.98).abs() < 0.01)
+    }
+}
@@ -0,0 +1,246 @@
+use inquire::{
+    CustomUserError, InquireError, Text,
+    autocompletion::{Autocomplete, Replacement},
+    ui::RenderConfig,
+    validator::Validation,
+};
+
+use crate::io::{
+    InputContext, InputError, InputValue, ProgramValue, input_help_message, input_placeholder,
+};
+
+const PROMPT_MESSAGE: &str = "Write an ascii character or his code value";
+
+/**
+ * Asks the user for every input with an inquire prompt. inquire consumes the prompt
+ * when it is shown, so only the render configuration is kept and a new prompt is
+ * built on each read. The values entered during the run are kept to suggest them
+ * in the next reads.
+ */
+#[derive(Default)]
+pub struct BasicInput<'a> {
+    render_config: RenderConfig<'a>,
+    hex_input: bool,
+    history: InputHistory,
+}
+
+const REPEAT_LAST_INPUT: &str = "!!";
+
+/**
+ * The values entered in the prompt, as they were typed. They are the suggestions
+ * of the prompt, the most recent first, so the arrows recall them.
+ */
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct InputHistory {
+    entries: Vec<String>,
+}
+
+impl InputHistory {
+    pub fn push(&mut self, entry: &str) {
+        self.entries.retain(|previous| previous != entry);
+        self.entries.push(entry.to_string());
+    }
+
+    pub fn last(&self) -> Option<&str> {
+        self.entries.last().map(String::as_str)
+    }
+
+    /**
+     * Replaces !! by the last value entered, the rest of the answers are kept.
+     */
+    pub fn resolve<'b>(&'b self, answer: &'b str) -> Option<&'b str> {
+        if answer == REPEAT_LAST_INPUT {
+            self.last()
+        } else {
+            Some(answer)
+        }
+    }
+}
+
+impl Autocomplete for InputHistory {
+    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, CustomUserError> {
+        Ok(self
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.starts_with(input) && entry.as_str() != input)
+            .cloned()
+            .collect())
+    }
+
+    fn get_completion(
+        &mut self,
+        _input: &str,
+        highlighted_suggestion: Option<String>,
+    ) -> Result<Replacement, CustomUserError> {
+        Ok(highlighted_suggestion)
+    }
+}
+
+impl<'a> BasicInput<'a> {
+    pub fn with_hex_input(mut self, hex_input: bool) -> Self {
+        self.hex_input = hex_input;
+        self
+    }
+
+    fn parse_answer(&self, answer: &str) -> Result<ProgramValue, String> {
+        match self.history.resolve(answer) {
+            Some(answer) => {
+                ProgramValue::from_input(answer, self.hex_input).map_err(|error| error.to_string())
+            }
+            None => Err("There isn't a previous value to repeat".to_string()),
+        }
+    }
+
+    fn ask(&mut self, message: &str) -> Result<ProgramValue, InputError> {
+        let formatter = |answer: &str| {
+            self.parse_answer(answer)
+                .map_or(answer.to_string(), |value| value.to_string())
+        };
+        let validator = |answer: &str| {
+            Ok(match self.parse_answer(answer) {
+                Ok(_) => Validation::Valid,
+                Err(error) => Validation::Invalid(error.into()),
+            })
+        };
+
+        let help_message = format!(
+            "{}. Type !! or use the arrows to repeat a previous value",
+            input_help_message(self.hex_input)
+        );
+
+        let answer = Text::new(message)
+            .with_placeholder(&input_placeholder(self.hex_input))
+            .with_help_message(&help_message)
+            .with_formatter(&formatter)
+            .with_validator(validator)
+            .with_autocomplete(self.history.clone())
+            .with_render_config(self.render_config)
+            .prompt();
+
+        match answer {
+            Ok(answer) => {
+                let value = self
+                    .parse_answer(&answer)
+                    .map_err(|_| InputError::Unknown)?;
+                let entry = self.history.resolve(&answer).unwrap_or(&answer).to_string();
+                self.history.push(&entry);
+                Ok(value)
+            }
+            Err(InquireError::OperationCanceled) => Err(InputError::EndOfInput),
+            Err(InquireError::OperationInterrupted) => Err(InputError::Interrupted),
+            Err(_) => Err(InputError::Unknown),
+        }
+    }
+}
+
+/**
+ * The prompt message with the cell where the value is going to be stored.
+ */
+fn prompt_message_with_context(context: &InputContext) -> String {
+    format!(
+        "{} for the cell {} (currently {})",
+        PROMPT_MESSAGE, context.cell_index, context.cell_value
+    )
+}
+
+impl<'a> InputValue for BasicInput<'a> {
+    fn get_input(&mut self) -> Result<ProgramValue, InputError> {
+        self.ask(PROMPT_MESSAGE)
+    }
+
+    fn get_input_with_context(
+        &mut self,
+        context: &InputContext,
+    ) -> Result<ProgramValue, InputError> {
+        // The prompt redraws its lines from the first column, so it starts in a new
+        // one to keep the program output visible.
+        if context.output_line_pending() {
+            eprintln!();
+        }
+
+        self.ask(&prompt_message_with_context(context))
+    }
+
+    fn prompt(&self) -> Option<&str> {
+        Some(PROMPT_MESSAGE)
+    }
+}
+
+#[cfg(test)]
+mod prompt_test {
+    use crate::io::{InputContext, ProgramValue};
+    use inquire::autocompletion::Autocomplete;
+
+    use super::*;
+
+    #[test]
+    fn given_an_input_context_when_the_prompt_message_is_built_then_it_has_the_cell_and_its_value()
+    {
+        let context = InputContext {
+            cell_index: 4,
+            cell_value: 7,
+            reads_so_far: 2,
+            last_output: None,
+        };
+
+        assert_eq!(
+            prompt_message_with_context(&context),
+            "Write an ascii character or his code value for the cell 4 (currently 7)"
+        )
+    }
+
+    #[test]
+    fn given_an_input_history_when_a_value_is_repeated_then_it_is_moved_to_the_most_recent() {
+        let mut history = InputHistory::default();
+
+        history.push("65");
+        history.push("B");
+        history.push("65");
+
+        assert_eq!(
+            history.get_suggestions("").unwrap(),
+            ["65".to_string(), "B".to_string()]
+        );
+        assert_eq!(history.last(), Some("65"))
+    }
+
+    #[test]
+    fn given_an_input_history_when_the_user_types_then_only_the_entries_starting_with_it_are_suggested()
+     {
+        let mut history = InputHistory::default();
+
+        history.push("0x41");
+        history.push("65");
+        history.push("0x4");
+
+        assert_eq!(
+            history.get_suggestions("0x4").unwrap(),
+            ["0x41".to_string()]
+        )
+    }
+
+    #[test]
+    fn given_the_repeat_shortcut_when_it_is_resolved_then_it_is_the_last_value_entered() {
+        let mut history = InputHistory::default();
+
+        assert_eq!(history.resolve("!!"), None);
+
+        history.push("\\n");
+
+        assert_eq!(history.resolve("!!"), Some("\\n"));
+        assert_eq!(history.resolve("A"), Some("A"))
+    }
+
+    #[test]
+    fn given_a_basic_input_with_history_when_the_repeat_shortcut_is_parsed_then_the_last_value_is_returned()
+     {
+        let mut input = BasicInput::default();
+
+        assert!(input.parse_answer("!!").is_err());
+
+        input.history.push("66");
+
+        assert_eq!(input.parse_answer("!!"), Ok(ProgramValue(b'B')))
+    }
+}
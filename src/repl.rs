@@ -0,0 +1,306 @@
+use std::{
+    io::{self, BufRead, Write},
+    mem,
+    ops::Range,
+};
+
+use crate::{
+    cli::{dump_range_parser, memory_size_parser},
+    dump::{POSTMORTEM_RADIUS, render_memory_dump},
+    file::{DEFAULT_MAX_SOURCE_SIZE, read_source_code_file},
+    interpreter::{Interpreter, InterpreterConfig},
+    io::{BrainfuckMemory, CollectingOutput, MemoryTape, QueuedInput},
+    parser::{BrainfuckNodeAST, ParserErrors, from_source_to_node_ast},
+};
+
+pub const PROMPT: &str = "bf> ";
+
+/**
+ * Shown while a loop opened in the previous lines isn't closed yet.
+ */
+pub const CONTINUATION_PROMPT: &str = "..> ";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplStatus {
+    Ready,
+    Continuation,
+    Quit,
+}
+
+/**
+ * An interactive session where every line is run against the same memory, so the
+ * tape and the pointer are kept between lines. The programs have no input, a read
+ * behaves as the end of the input.
+ */
+pub struct Repl {
+    memory: BrainfuckMemory,
+    memory_size: usize,
+    number_of_reads: u64,
+    pending: String,
+}
+
+impl Repl {
+    pub fn new(memory_size: usize, number_of_reads: u64) -> Self {
+        Repl {
+            memory: BrainfuckMemory::new(memory_size),
+            memory_size,
+            number_of_reads,
+            pending: String::new(),
+        }
+    }
+
+    pub fn prompt(&self) -> &'static str {
+        match self.pending.is_empty() {
+            true => PROMPT,
+            false => CONTINUATION_PROMPT,
+        }
+    }
+
+    /**
+     * Runs a line, or keeps it until the loops opened in it are closed. The lines
+     * starting with a colon are commands of the session, like :quit.
+     */
+    pub fn feed_line(&mut self, line: &str, output: &mut impl Write) -> io::Result<ReplStatus> {
+        if self.pending.is_empty()
+            && let Some(command) = line.trim().strip_prefix(':')
+        {
+            return self.run_command(command, output);
+        }
+
+        self.pending.push_str(line);
+
+        match from_source_to_node_ast(&self.pending) {
+            Err(ParserErrors::MissingTerminantedLoop { .. }) => {
+                return Ok(ReplStatus::Continuation);
+            }
+            Err(error) => writeln!(output, "error: {}", error)?,
+            Ok(ast) => self.execute(&ast, output)?,
+        }
+
+        self.pending.clear();
+        Ok(ReplStatus::Ready)
+    }
+
+    fn run_command(&mut self, command: &str, output: &mut impl Write) -> io::Result<ReplStatus> {
+        let (name, argument) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+
+        match (name, argument) {
+            ("quit", _) => return Ok(ReplStatus::Quit),
+            ("reset", _) => {
+                self.memory = BrainfuckMemory::new(self.memory_size);
+                self.write_state(output)?
+            }
+            ("dump", "") => write!(
+                output,
+                "{}",
+                render_memory_dump(&self.memory, self.pointer_window())
+            )?,
+            ("dump", range) => match dump_range_parser(range) {
+                Ok(range) => write!(output, "{}", render_memory_dump(&self.memory, range))?,
+                Err(error) => writeln!(output, "error: {}", error)?,
+            },
+            ("load", path) if !path.is_empty() => {
+                match read_source_code_file(path, DEFAULT_MAX_SOURCE_SIZE) {
+                    Ok(source_code) => match from_source_to_node_ast(&source_code) {
+                        Ok(ast) => self.execute(&ast, output)?,
+                        Err(error) => writeln!(output, "error: {}", error)?,
+                    },
+                    Err(error) => writeln!(output, "error: {}", error)?,
+                }
+            }
+            ("mem", size) => match memory_size_parser(size) {
+                Ok(size) => {
+                    self.memory_size = size;
+                    self.memory = BrainfuckMemory::new(size);
+                    self.write_state(output)?
+                }
+                Err(error) => writeln!(output, "error: {}", error)?,
+            },
+            _ => writeln!(
+                output,
+                "error: Unknown command :{}, the commands are :reset, :dump [start..end], :load <file>, :mem <size> and :quit",
+                command
+            )?,
+        }
+
+        Ok(ReplStatus::Ready)
+    }
+
+    /**
+     * The lines without commands don't change anything, so they don't print the
+     * state either.
+     */
+    fn execute(&mut self, ast: &Vec<BrainfuckNodeAST>, output: &mut impl Write) -> io::Result<()> {
+        if ast.is_empty() {
+            return Ok(());
+        }
+
+        let mut interpreter = Interpreter::new(
+            CollectingOutput::new(),
+            QueuedInput::new([]),
+            mem::take(&mut self.memory),
+            InterpreterConfig::new(self.number_of_reads),
+        );
+        interpreter.load_ast_program(ast);
+
+        let result = interpreter.run();
+        let printed = interpreter.display.as_bytes().to_vec();
+        self.memory = interpreter.memory;
+
+        output.write_all(&printed)?;
+        if printed.last().is_some_and(|byte| *byte != b'\n') {
+            writeln!(output)?;
+        }
+
+        if let Err(error) = result {
+            writeln!(output, "error: {}", error)?;
+        }
+
+        self.write_state(output)
+    }
+
+    /**
+     * The pointer stays outside of the memory after moving out of it, until a
+     * :reset.
+     */
+    fn write_state(&self, output: &mut impl Write) -> io::Result<()> {
+        let pointer = self.memory.get_logical_position();
+
+        match self.memory.get_cell_at(pointer) {
+            Some(value) => writeln!(output, "[pointer {}, cell {}]", pointer, value),
+            None => writeln!(output, "[pointer {}, outside of the memory]", pointer),
+        }
+    }
+
+    fn pointer_window(&self) -> Range<isize> {
+        let pointer = self.memory.get_logical_position();
+
+        pointer.saturating_sub(POSTMORTEM_RADIUS)..pointer.saturating_add(POSTMORTEM_RADIUS)
+    }
+}
+
+/**
+ * Reads the lines until :quit or the end of the input, showing the prompt before
+ * each one.
+ */
+pub fn run_repl(
+    mut repl: Repl,
+    mut input: impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut line = String::new();
+
+    loop {
+        write!(output, "{}", repl.prompt())?;
+        output.flush()?;
+
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            // The shell prompt goes to its own line after a Ctrl-D.
+            return writeln!(output);
+        }
+
+        if repl.feed_line(&line, output)? == ReplStatus::Quit {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod repl_test {
+    use super::*;
+
+    fn session(lines: &str) -> String {
+        let mut output = vec![];
+
+        run_repl(Repl::new(30, 10_000), lines.as_bytes(), &mut output).unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn given_several_lines_when_they_are_run_then_the_tape_and_the_pointer_are_kept() {
+        assert_eq!(
+            session("+++\n>++\n<.\n"),
+            concat!(
+                "bf> [pointer 0, cell 3]\n",
+                "bf> [pointer 1, cell 2]\n",
+                "bf> \u{3}\n[pointer 0, cell 3]\n",
+                "bf> \n"
+            )
+        )
+    }
+
+    #[test]
+    fn given_a_loop_split_in_several_lines_when_they_are_run_then_it_waits_until_it_is_closed() {
+        assert_eq!(
+            session("++++++++[>++++++++\n<-]>+.\n:quit\n"),
+            "bf> ..> A\n[pointer 1, cell 65]\nbf> "
+        )
+    }
+
+    #[test]
+    fn given_a_closing_bracket_without_loop_when_it_is_run_then_the_error_is_shown_and_the_line_dropped()
+     {
+        assert_eq!(
+            session("+]\n+\n:quit\n"),
+            concat!(
+                "bf> error: The source code have more closing loop brackets than open loop brackets.\n",
+                "bf> [pointer 0, cell 1]\n",
+                "bf> "
+            )
+        )
+    }
+
+    #[test]
+    fn when_the_session_is_reset_then_the_memory_is_empty_again() {
+        assert_eq!(
+            session(">+\n:reset\n:quit\n"),
+            "bf> [pointer 1, cell 1]\nbf> [pointer 0, cell 0]\nbf> "
+        )
+    }
+
+    #[test]
+    fn when_the_memory_is_dumped_then_the_cells_of_the_range_are_shown() {
+        let output = session("+>++\n:dump 0..2\n:dump 1\n:quit\n");
+
+        assert!(output.contains("bf>        0  dec   1   2\n"));
+        assert!(output.contains("bf> error: The range must be written as start..end"))
+    }
+
+    #[test]
+    fn when_the_memory_size_changes_then_the_pointer_is_bounded_by_the_new_size() {
+        let output = session(":mem 2\n>>\n.\n:mem 0\n:quit\n");
+
+        assert!(output.starts_with("bf> [pointer 0, cell 0]\n"));
+        assert!(output.contains(concat!(
+            "error: The pointer position 2 is out of range (the tape is 0..2)\n",
+            "[pointer 1, cell 0]\n"
+        )));
+        assert!(output.contains("bf> \u{0}\n[pointer 1, cell 0]\n"));
+        assert!(output.contains("bf> error: Minimum value accepted is 1\n"))
+    }
+
+    #[test]
+    fn when_a_file_is_loaded_then_it_runs_against_the_memory_of_the_session() {
+        let output = session(&format!(
+            "+\n:load {}\n:load missing.bf\n:quit\n",
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/resources/test/include_print.bf"
+            )
+        ));
+
+        assert!(output.contains("bf> \u{1}\u{1}\n[pointer 0, cell 1]\n"));
+        assert!(output.contains("bf> error: "))
+    }
+
+    #[test]
+    fn when_an_unknown_command_is_given_then_the_commands_are_listed() {
+        assert!(
+            session(":help\n").starts_with("bf> error: Unknown command :help, the commands are")
+        )
+    }
+}
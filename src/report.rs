@@ -0,0 +1,794 @@
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diagnostic::Severity,
+    file::FileError,
+    interpreter::{BuilderErrors, InterpreterErrors, OverflowPolicy, RunSummary},
+    io::{CellSize, MemoryErrors},
+    parser::ParserErrors,
+    run::BraincrubError,
+};
+
+/**
+ * Bumped every time a field is removed or changes its meaning, so the tools reading
+ * the report can check they understand it.
+ */
+pub const REPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Success,
+    Failure,
+}
+
+/**
+ * The exit code is decided with the kind, from the error it comes from, so the
+ * tools reading the report get the same code as the shell.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub kind: String,
+    pub message: String,
+    pub position: Option<usize>,
+    pub exit_code: i32,
+}
+
+impl From<&FileError> for ErrorReport {
+    fn from(error: &FileError) -> Self {
+        let (kind, exit_code) = match error {
+            FileError::FileNotFound { .. } => ("FileNotFound", FILE_EXIT_CODE),
+            FileError::FilePathMalformed { .. } => ("FilePathMalformed", FILE_EXIT_CODE),
+            FileError::IsADirectory { .. } => ("IsADirectory", FILE_EXIT_CODE),
+            FileError::DirectoryNotFound { .. } => ("DirectoryNotFound", FILE_EXIT_CODE),
+            FileError::NotEnoughPermission { .. } => ("NotEnoughPermission", FILE_EXIT_CODE),
+            FileError::FileAlreadyExists { .. } => ("FileAlreadyExists", FILE_EXIT_CODE),
+            FileError::NothingPiped => ("NothingPiped", USAGE_EXIT_CODE),
+            FileError::FileTooLarge { .. } => ("FileTooLarge", LIMIT_EXIT_CODE),
+            FileError::IncludeCycle { .. } => ("IncludeCycle", FILE_EXIT_CODE),
+            FileError::IncludeTooDeep { .. } => ("IncludeTooDeep", FILE_EXIT_CODE),
+            FileError::DecompressionFailed { .. } => ("DecompressionFailed", FILE_EXIT_CODE),
+            FileError::UnexpectedError { .. } => ("UnexpectedError", FILE_EXIT_CODE),
+        };
+
+        ErrorReport {
+            kind: kind.to_string(),
+            message: error.to_string(),
+            position: None,
+            exit_code,
+        }
+    }
+}
+
+impl From<&ParserErrors> for ErrorReport {
+    fn from(error: &ParserErrors) -> Self {
+        let kind = match error {
+            ParserErrors::MissingTerminantedLoop { .. } => "MissingTerminantedLoop",
+            ParserErrors::MissingOpenLoop { .. } => "MissingOpenLoop",
+        };
+
+        ErrorReport {
+            kind: kind.to_string(),
+            message: error.to_string(),
+            position: Some(error.position()),
+            exit_code: PARSE_EXIT_CODE,
+        }
+    }
+}
+
+/**
+ * Exit codes of the failures, by their kind of error. The CLI exits with 0 when
+ * everything went well, with 1 for a wrong use of the CLI, 2 when a file can't be
+ * read or written, 3 when the source code isn't a valid program, 4 when the program
+ * fails while running and 5 when a limit is reached.
+ */
+pub const USAGE_EXIT_CODE: i32 = 1;
+pub const FILE_EXIT_CODE: i32 = 2;
+pub const PARSE_EXIT_CODE: i32 = 3;
+pub const RUNTIME_EXIT_CODE: i32 = 4;
+pub const LIMIT_EXIT_CODE: i32 = 5;
+
+/**
+ * The runs that don't fail by themselves but are stopped exit with the codes a
+ * shell gives to the processes killed by the signal: 130 for SIGINT and 141 for
+ * SIGPIPE, when the reader of the stdout closed it before the program finished,
+ * like `head` does.
+ */
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+pub const OUTPUT_CLOSED_EXIT_CODE: i32 = 141;
+
+/**
+ * The exit codes for the manual page, in roff. Keep it in sync with the codes
+ * above.
+ */
+pub const EXIT_CODES_MAN_SECTION: &str = r#".SH "EXIT STATUS"
+.TP
+\fB0\fR
+Everything went well. A run with \fB\-\-exit\-cell\fR exits with the value of the cell instead.
+.TP
+\fB1\fR
+A wrong use of the CLI, like an unknown flag or a program missing.
+.TP
+\fB2\fR
+A file can't be read or written.
+.TP
+\fB3\fR
+The source code isn't a valid program.
+.TP
+\fB4\fR
+The program failed while running, or the programs compared or tested didn't do what was expected.
+.TP
+\fB5\fR
+A limit was reached, like the instructions, the memory or the output.
+.TP
+\fB130\fR
+The run was interrupted with SIGINT.
+.TP
+\fB141\fR
+The reader of the stdout closed it before the program finished.
+"#;
+
+impl ErrorReport {
+    /**
+     * The errors of the CLI itself, which aren't of the library, like a flag given
+     * with a memory that doesn't have it.
+     */
+    pub fn new(kind: &str, message: String, exit_code: i32) -> Self {
+        ErrorReport {
+            kind: kind.to_string(),
+            message,
+            position: None,
+            exit_code,
+        }
+    }
+
+    /**
+     * The runtime errors happen at the last instruction the interpreter executed,
+     * which is the position in the AST reported.
+     */
+    pub fn from_runtime_error(error: &InterpreterErrors, summary: &RunSummary) -> Self {
+        let (kind, exit_code) = match error {
+            InterpreterErrors::EmptyAST => ("EmptyAST", RUNTIME_EXIT_CODE),
+            InterpreterErrors::UnknownASTNode { .. } => ("UnknownASTNode", RUNTIME_EXIT_CODE),
+            InterpreterErrors::Memory(MemoryErrors::OutOfRangePosition { .. }) => {
+                ("OutOfRangeMemoryAccess", RUNTIME_EXIT_CODE)
+            }
+            InterpreterErrors::Memory(MemoryErrors::CellOverflow { .. }) => {
+                ("CellOverflow", RUNTIME_EXIT_CODE)
+            }
+            InterpreterErrors::Memory(MemoryErrors::CellUnderflow { .. }) => {
+                ("CellUnderflow", RUNTIME_EXIT_CODE)
+            }
+            InterpreterErrors::UnableToCompleteTheProgram => {
+                ("UnableToCompleteTheProgram", LIMIT_EXIT_CODE)
+            }
+            InterpreterErrors::InputFailed => ("InputFailed", RUNTIME_EXIT_CODE),
+            InterpreterErrors::ReplayDiverged(_) => ("ReplayDiverged", RUNTIME_EXIT_CODE),
+            InterpreterErrors::OutputFailed(_) => ("OutputFailed", RUNTIME_EXIT_CODE),
+            InterpreterErrors::OutputLimitReached { .. } => ("OutputLimitReached", LIMIT_EXIT_CODE),
+            InterpreterErrors::OutputClosed => ("OutputClosed", OUTPUT_CLOSED_EXIT_CODE),
+            InterpreterErrors::Interrupted => ("Interrupted", INTERRUPTED_EXIT_CODE),
+        };
+
+        ErrorReport {
+            kind: kind.to_string(),
+            message: error.to_string(),
+            position: summary.last_instruction,
+            exit_code,
+        }
+    }
+
+    /**
+     * The errors of run_source, with the summary of the run for the runtime ones.
+     */
+    pub fn from_run_error(error: &BraincrubError, summary: &RunSummary) -> Self {
+        let (kind, exit_code) = match error {
+            BraincrubError::Parse(error) => return ErrorReport::from(error),
+            BraincrubError::Runtime(error) => {
+                return ErrorReport::from_runtime_error(error, summary);
+            }
+            BraincrubError::Build(BuilderErrors::MissingProgram) => {
+                ("MissingProgram", RUNTIME_EXIT_CODE)
+            }
+            BraincrubError::Build(BuilderErrors::EmptyMemory) => ("EmptyMemory", USAGE_EXIT_CODE),
+            BraincrubError::Build(BuilderErrors::MemoryTooBig { .. }) => {
+                ("MemoryTooBig", LIMIT_EXIT_CODE)
+            }
+            BraincrubError::Build(BuilderErrors::MemorySizeWithCustomMemory) => {
+                ("MemorySizeWithCustomMemory", RUNTIME_EXIT_CODE)
+            }
+        };
+
+        ErrorReport::new(kind, error.to_string(), exit_code)
+    }
+}
+
+/**
+ * Result of a lint or a run printed with --json. The lint reports don't have the
+ * summary nor the output, as no program is executed. The path is only there when
+ * several files are linted, with a report for each one.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    pub version: u32,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub outcome: Outcome,
+    pub error: Option<ErrorReport>,
+    pub summary: Option<RunSummary>,
+    pub output_base64: Option<String>,
+    pub duration_micros: u128,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<Timing>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell_size: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell_overflow: Option<String>,
+}
+
+impl Report {
+    pub fn new(command: &str, error: Option<ErrorReport>, duration: Duration) -> Self {
+        Report {
+            version: REPORT_VERSION,
+            command: command.to_string(),
+            path: None,
+            outcome: match error {
+                Some(_) => Outcome::Failure,
+                None => Outcome::Success,
+            },
+            error,
+            summary: None,
+            output_base64: None,
+            duration_micros: duration.as_micros(),
+            timing: None,
+            cell_size: None,
+            cell_overflow: None,
+        }
+    }
+
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    pub fn with_run(mut self, summary: RunSummary, output: &[u8]) -> Self {
+        self.summary = Some(summary);
+        self.output_base64 = Some(STANDARD.encode(output));
+        self
+    }
+
+    pub fn with_timing(mut self, timing: Timing) -> Self {
+        self.timing = Some(timing);
+        self
+    }
+
+    /**
+     * The width of the cells of a run and what they do when they overflow, written
+     * as in the --cell-size and --cell-overflow flags, to run it again the same way.
+     */
+    pub fn with_cells(mut self, cell_size: CellSize, overflow_policy: OverflowPolicy) -> Self {
+        self.cell_size = Some(cell_size.to_string());
+        self.cell_overflow = Some(overflow_policy.to_string());
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("The report only contains serializable values")
+    }
+}
+
+/**
+ * How long the program ran, without reading and parsing it, shown with --time.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Timing {
+    pub execution_micros: u128,
+    pub instructions_executed: u64,
+    pub instructions_per_second: f64,
+}
+
+impl Timing {
+    pub fn new(duration: Duration, instructions_executed: u64) -> Self {
+        // A run too short to be measured counts as one nanosecond.
+        let seconds = duration.as_secs_f64().max(1e-9);
+
+        Timing {
+            execution_micros: duration.as_micros(),
+            instructions_executed,
+            instructions_per_second: instructions_executed as f64 / seconds,
+        }
+    }
+}
+
+fn format_duration(micros: u128) -> String {
+    match micros {
+        0..1_000 => format!("{}µs", micros),
+        1_000..1_000_000 => format!("{:.2}ms", micros as f64 / 1_000.0),
+        _ => format!("{:.2}s", micros as f64 / 1_000_000.0),
+    }
+}
+
+fn format_rate(rate: f64) -> String {
+    match rate {
+        ..1e3 => format!("{:.0}", rate),
+        ..1e6 => format!("{:.1}k", rate / 1e3),
+        ..1e9 => format!("{:.1}M", rate / 1e6),
+        _ => format!("{:.1}G", rate / 1e9),
+    }
+}
+
+impl Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "executed in {}, {} instructions, {} instructions/s",
+            format_duration(self.execution_micros),
+            self.instructions_executed,
+            format_rate(self.instructions_per_second)
+        )
+    }
+}
+
+/**
+ * How lint prints its result: the messages for a human, a JSON report for every
+ * file, or a SARIF document with the findings of all the files for the code
+ * scanning tools.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LintFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+pub const SARIF_VERSION: &str = "2.1.0";
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/**
+ * The subset of SARIF 2.1.0 lint needs: a single run of the CLI with a result for
+ * every error or warning found.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    pub schema: String,
+    pub version: String,
+    pub runs: Vec<SarifRun>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SarifRun {
+    pub tool: SarifTool,
+    pub results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SarifTool {
+    pub driver: SarifDriver,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SarifDriver {
+    pub name: String,
+    pub version: String,
+}
+
+/**
+ * The rule is the kind of the error or the warning, like MissingOpenLoop.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    pub rule_id: String,
+    pub level: String,
+    pub message: SarifMessage,
+    pub locations: Vec<SarifLocation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SarifMessage {
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifLocation {
+    pub physical_location: SarifPhysicalLocation,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifPhysicalLocation {
+    pub artifact_location: SarifArtifactLocation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<SarifRegion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SarifArtifactLocation {
+    pub uri: String,
+}
+
+/**
+ * The lines and the columns start at 1, as SARIF requires.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifRegion {
+    pub start_line: usize,
+    pub start_column: usize,
+}
+
+impl SarifResult {
+    /**
+     * The region is the line and the column of the finding, when it has one. The
+     * paths of Windows are written with slashes, as the URIs are.
+     */
+    pub fn new(
+        rule_id: &str,
+        severity: Severity,
+        message: &str,
+        path: &str,
+        region: Option<(usize, usize)>,
+    ) -> Self {
+        SarifResult {
+            rule_id: rule_id.to_string(),
+            level: match severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            }
+            .to_string(),
+            message: SarifMessage {
+                text: message.to_string(),
+            },
+            locations: vec![SarifLocation {
+                physical_location: SarifPhysicalLocation {
+                    artifact_location: SarifArtifactLocation {
+                        uri: path.replace('\\', "/"),
+                    },
+                    region: region.map(|(start_line, start_column)| SarifRegion {
+                        start_line,
+                        start_column,
+                    }),
+                },
+            }],
+        }
+    }
+}
+
+impl SarifLog {
+    pub fn new(results: Vec<SarifResult>) -> Self {
+        SarifLog {
+            schema: SARIF_SCHEMA.to_string(),
+            version: SARIF_VERSION.to_string(),
+            runs: vec![SarifRun {
+                tool: SarifTool {
+                    driver: SarifDriver {
+                        name: "braincrub".to_string(),
+                        version: env!("CARGO_PKG_VERSION").to_string(),
+                    },
+                },
+                results,
+            }],
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("The SARIF log only contains serializable values")
+    }
+}
+
+/**
+ * Wall time of one of the steps done by the CLI, shown with --verbose. The number
+ * of nodes is the size of the AST after the step, when it produces one.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phase {
+    pub name: &'static str,
+    pub duration: Duration,
+    pub nodes: Option<usize>,
+}
+
+impl Phase {
+    pub fn new(name: &'static str, duration: Duration) -> Self {
+        Phase {
+            name,
+            duration,
+            nodes: None,
+        }
+    }
+
+    pub fn with_nodes(mut self, nodes: usize) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+}
+
+/**
+ * Human readable breakdown printed with --verbose. The verbosity 2 (-vv) adds the
+ * size of the AST after each phase, the cells touched and the memory high-water
+ * mark.
+ */
+pub fn format_verbose_report(
+    phases: &[Phase],
+    summary: Option<&RunSummary>,
+    verbosity: u8,
+) -> String {
+    let mut lines: Vec<String> = phases
+        .iter()
+        .map(|phase| {
+            let timing = format!(
+                "phase {:<8} {:>10.3}ms",
+                phase.name,
+                phase.duration.as_secs_f64() * 1000.0
+            );
+
+            match phase.nodes {
+                Some(nodes) if verbosity > 1 => format!("{} ({} nodes)", timing, nodes),
+                _ => timing,
+            }
+        })
+        .collect();
+
+    if let Some(summary) = summary {
+        lines.push(format!(
+            "instructions executed: {}",
+            summary.instructions_executed
+        ));
+        lines.push(format!("bytes written: {}", summary.bytes_written));
+        lines.push(format!("bytes read: {}", summary.bytes_read));
+        lines.push(format!("inputs requested: {}", summary.inputs_requested));
+
+        if let Some(memory) = &summary.memory {
+            lines.push(format!(
+                "memory: {} cells touched, {} non-zero at exit, values range {}–{}",
+                summary.cells_touched, memory.nonzero_cells, memory.min_value, memory.max_value
+            ));
+        }
+
+        if verbosity > 1 {
+            lines.push(format!("cells touched: {}", summary.cells_touched));
+            lines.push(format!(
+                "memory high-water mark: {}",
+                summary.max_memory_position
+            ));
+        }
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod report_test {
+    use super::*;
+    use crate::interpreter::MemoryStats;
+
+    #[test]
+    fn given_runs_of_several_lengths_when_the_timing_is_formatted_then_it_uses_the_fitting_unit() {
+        assert_eq!(
+            Timing::new(Duration::from_micros(250), 1_000).to_string(),
+            "executed in 250µs, 1000 instructions, 4.0M instructions/s"
+        );
+        assert_eq!(
+            Timing::new(Duration::from_micros(12_345), 617).to_string(),
+            "executed in 12.35ms, 617 instructions, 50.0k instructions/s"
+        );
+        assert_eq!(
+            Timing::new(Duration::from_secs(2), 300).to_string(),
+            "executed in 2.00s, 300 instructions, 150 instructions/s"
+        )
+    }
+
+    #[test]
+    fn given_a_timing_when_the_report_is_serialized_then_it_has_the_timing_fields() {
+        let report = Report::new("run", None, Duration::from_micros(10))
+            .with_timing(Timing::new(Duration::from_micros(4), 8));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+
+        assert_eq!(json["timing"]["execution_micros"], 4);
+        assert_eq!(json["timing"]["instructions_executed"], 8);
+        assert_eq!(json["timing"]["instructions_per_second"], 2_000_000.0);
+        assert!(
+            serde_json::from_str::<serde_json::Value>(
+                &Report::new("run", None, Duration::ZERO).to_json()
+            )
+            .unwrap()
+            .get("timing")
+            .is_none()
+        )
+    }
+
+    #[test]
+    fn given_a_successful_run_when_the_report_is_serialized_then_the_output_is_encoded_in_base64() {
+        let summary = RunSummary {
+            instructions_executed: 3,
+            bytes_written: 2,
+            ..RunSummary::default()
+        };
+
+        let report =
+            Report::new("run", None, Duration::from_micros(42)).with_run(summary, &[0, 255]);
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+
+        assert_eq!(json["version"], REPORT_VERSION);
+        assert_eq!(json["outcome"], "success");
+        assert_eq!(json["error"], serde_json::Value::Null);
+        assert_eq!(json["summary"]["instructions_executed"], 3);
+        assert_eq!(json["output_base64"], "AP8=");
+        assert_eq!(json["duration_micros"], 42)
+    }
+
+    #[test]
+    fn given_an_error_of_each_category_when_the_exit_code_is_asked_then_it_is_the_one_of_the_category()
+     {
+        let file_error = ErrorReport::from(&FileError::NothingPiped);
+        let parse_error = ErrorReport::from(&ParserErrors::MissingOpenLoop { position: 0 });
+        let runtime_error = |error: InterpreterErrors| {
+            ErrorReport::from_runtime_error(&error, &RunSummary::default())
+        };
+
+        assert_eq!(file_error.exit_code, USAGE_EXIT_CODE);
+        assert_eq!(
+            ErrorReport::from(&FileError::IncludeCycle { chain: vec![] }).exit_code,
+            FILE_EXIT_CODE
+        );
+        assert_eq!(parse_error.exit_code, PARSE_EXIT_CODE);
+        assert_eq!(
+            runtime_error(InterpreterErrors::InputFailed).exit_code,
+            RUNTIME_EXIT_CODE
+        );
+        assert_eq!(
+            runtime_error(InterpreterErrors::OutputLimitReached { limit: 1 }).exit_code,
+            LIMIT_EXIT_CODE
+        );
+        assert_eq!(
+            runtime_error(InterpreterErrors::Interrupted).exit_code,
+            INTERRUPTED_EXIT_CODE
+        );
+        assert_eq!(
+            runtime_error(InterpreterErrors::OutputClosed).exit_code,
+            OUTPUT_CLOSED_EXIT_CODE
+        );
+        assert_eq!(
+            ErrorReport::from_run_error(
+                &BraincrubError::Build(BuilderErrors::MemoryTooBig { size: 2, max: 1 }),
+                &RunSummary::default()
+            )
+            .exit_code,
+            LIMIT_EXIT_CODE
+        )
+    }
+
+    #[test]
+    fn given_an_error_report_when_is_serialized_then_it_has_the_exit_code() {
+        let json: serde_json::Value =
+            serde_json::to_value(ErrorReport::from(&FileError::NothingPiped)).unwrap();
+
+        assert_eq!(json["exit_code"], USAGE_EXIT_CODE)
+    }
+
+    #[test]
+    fn given_a_runtime_error_when_is_reported_then_the_position_is_the_last_instruction_executed() {
+        let summary = RunSummary {
+            last_instruction: Some(7),
+            ..RunSummary::default()
+        };
+
+        let error = ErrorReport::from_runtime_error(
+            &InterpreterErrors::Memory(MemoryErrors::OutOfRangePosition {
+                target: -1,
+                bounds: 0..3000,
+            }),
+            &summary,
+        );
+
+        assert_eq!(error.kind, "OutOfRangeMemoryAccess");
+        assert_eq!(
+            error.message,
+            "The pointer position -1 is out of range (the tape is 0..3000)"
+        );
+        assert_eq!(error.position, Some(7))
+    }
+
+    #[test]
+    fn when_a_report_is_serialized_and_deserialized_then_it_is_the_same_report() {
+        let report = Report::new(
+            "lint",
+            Some(ErrorReport::from(&ParserErrors::MissingOpenLoop {
+                position: 0,
+            })),
+            Duration::from_micros(1),
+        );
+
+        let parsed: Report = serde_json::from_str(&report.to_json()).unwrap();
+
+        assert_eq!(parsed, report);
+        assert_eq!(parsed.outcome, Outcome::Failure)
+    }
+
+    #[test]
+    fn given_the_phases_of_a_run_when_the_verbose_report_is_formatted_then_every_phase_is_shown() {
+        let phases = [
+            Phase::new("read", Duration::from_micros(1500)),
+            Phase::new("parse", Duration::from_micros(20)).with_nodes(4),
+        ];
+        let summary = RunSummary {
+            instructions_executed: 7,
+            max_memory_position: 3,
+            ..RunSummary::default()
+        };
+
+        let report = format_verbose_report(&phases, Some(&summary), 1);
+
+        assert_eq!(
+            report,
+            "phase read          1.500ms\n\
+             phase parse         0.020ms\n\
+             instructions executed: 7\n\
+             bytes written: 0\n\
+             bytes read: 0\n\
+             inputs requested: 0"
+        )
+    }
+
+    #[test]
+    fn given_a_summary_with_memory_stats_when_the_verbose_report_is_formatted_then_they_are_shown()
+    {
+        let summary = RunSummary {
+            cells_touched: 37,
+            memory: Some(MemoryStats {
+                nonzero_cells: 12,
+                min_value: 0,
+                max_value: 200,
+            }),
+            ..RunSummary::default()
+        };
+
+        let report = format_verbose_report(&[], Some(&summary), 1);
+
+        assert!(
+            report.ends_with("memory: 37 cells touched, 12 non-zero at exit, values range 0–200")
+        )
+    }
+
+    #[test]
+    fn given_a_verbosity_of_two_when_the_verbose_report_is_formatted_then_the_details_are_included()
+    {
+        let phases = [Phase::new("parse", Duration::ZERO).with_nodes(4)];
+        let summary = RunSummary {
+            max_memory_position: 3,
+            ..RunSummary::default()
+        };
+
+        let report = format_verbose_report(&phases, Some(&summary), 2);
+
+        assert!(report.starts_with("phase parse         0.000ms (4 nodes)\n"));
+        assert!(report.ends_with("memory high-water mark: 3"))
+    }
+
+    #[test]
+    fn given_a_finding_without_position_when_it_is_written_as_sarif_then_it_has_no_region() {
+        let result = SarifResult::new(
+            "UnknownExtension",
+            Severity::Warning,
+            "message",
+            "dir\\notes.txt",
+            None,
+        );
+        let json = serde_json::to_value(&result).unwrap();
+
+        assert_eq!(json["ruleId"], "UnknownExtension");
+        assert_eq!(json["level"], "warning");
+        assert_eq!(
+            json["locations"][0]["physicalLocation"],
+            serde_json::json!({ "artifactLocation": { "uri": "dir/notes.txt" } })
+        )
+    }
+}
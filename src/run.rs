@@ -0,0 +1,171 @@
+use thiserror::Error;
+
+use crate::{
+    interpreter::{
+        BuilderErrors, InterpreterBuilder, InterpreterConfig, InterpreterErrors, RunSummary,
+    },
+    io::{CollectingOutput, MemoryTape, QueuedInput},
+    parser::{BrainfuckNodeAST, Dialect, ParserErrors, from_source_to_node_ast_in_dialect},
+};
+
+/**
+ * How a program runs from a string: the cells of its memory, the limit of
+ * instructions and the dialect of its source code.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunConfig {
+    pub memory_size: usize,
+    pub number_of_reads: u64,
+    pub dialect: Dialect,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        RunConfig {
+            memory_size: 3000,
+            number_of_reads: 60000,
+            dialect: Dialect::Standard,
+        }
+    }
+}
+
+/**
+ * What the program did: the bytes it printed, the summary of the run and the
+ * cells of the memory when it stopped.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunResult {
+    pub output: Vec<u8>,
+    pub summary: RunSummary,
+    pub tape: Vec<u8>,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum BraincrubError {
+    #[error(transparent)]
+    Parse(#[from] ParserErrors),
+    #[error(transparent)]
+    Build(#[from] BuilderErrors),
+    #[error(transparent)]
+    Runtime(#[from] InterpreterErrors),
+}
+
+/**
+ * Runs the parsed program with the input. The result is there even when the run
+ * fails, with what the program printed before failing.
+ */
+pub fn run_ast(
+    ast: &[BrainfuckNodeAST],
+    input: &[u8],
+    config: &RunConfig,
+) -> (RunResult, Result<(), BraincrubError>) {
+    let built = InterpreterBuilder::new()
+        .input(QueuedInput::from(input.to_vec()))
+        .output(CollectingOutput::new())
+        .memory_size(config.memory_size)
+        .config(InterpreterConfig::new(config.number_of_reads))
+        .program(ast)
+        .build();
+    let mut interpreter = match built {
+        Ok(interpreter) => interpreter,
+        Err(error) => return (RunResult::default(), Err(error.into())),
+    };
+
+    let ran = interpreter.run();
+    let result = RunResult {
+        output: interpreter.display.as_bytes().to_vec(),
+        summary: interpreter.summary.clone(),
+        tape: (0..config.memory_size as isize)
+            .map_while(|position| interpreter.memory.get_cell_at(position))
+            .collect(),
+    };
+
+    (result, ran.map_err(BraincrubError::from))
+}
+
+/**
+ * Parses and runs the source code with the input, for the programs that only want
+ * its output.
+ *
+ * ```
+ * use braincrub::{RunConfig, run_source};
+ *
+ * let result = run_source("++++++++[>++++++++<-]>+.", b"", &RunConfig::default()).unwrap();
+ *
+ * assert_eq!(result.output, b"A");
+ * ```
+ */
+pub fn run_source(
+    source_code: &str,
+    input: &[u8],
+    config: &RunConfig,
+) -> Result<RunResult, BraincrubError> {
+    let ast = from_source_to_node_ast_in_dialect(source_code, config.dialect)?;
+    let (result, ran) = run_ast(&ast, input, config);
+
+    ran.map(|()| result)
+}
+
+#[cfg(test)]
+mod run_test {
+    use super::*;
+
+    const CONFIG: RunConfig = RunConfig {
+        memory_size: 4,
+        number_of_reads: 10_000,
+        dialect: Dialect::Standard,
+    };
+
+    #[test]
+    fn given_a_program_when_its_source_is_run_then_return_its_output_and_its_memory() {
+        let result = run_source(",[.,]>+++", b"hi", &CONFIG).unwrap();
+
+        assert_eq!(result.output, b"hi");
+        assert_eq!(result.tape, [0, 3, 0, 0]);
+        assert_eq!(result.summary.bytes_read, 2)
+    }
+
+    #[test]
+    fn given_an_unbalanced_program_when_its_source_is_run_then_return_the_parse_error() {
+        assert!(matches!(
+            run_source("+[", b"", &CONFIG),
+            Err(BraincrubError::Parse(
+                ParserErrors::MissingTerminantedLoop { .. }
+            ))
+        ))
+    }
+
+    #[test]
+    fn given_a_program_that_never_ends_when_it_is_run_then_the_output_before_is_kept() {
+        let ast = from_source_to_node_ast_in_dialect("+.[]", Dialect::Standard).unwrap();
+
+        let (result, ran) = run_ast(&ast, b"", &CONFIG);
+
+        assert_eq!(
+            ran,
+            Err(BraincrubError::Runtime(
+                InterpreterErrors::UnableToCompleteTheProgram
+            ))
+        );
+        assert_eq!(result.output, [1]);
+        assert_eq!(
+            run_source("+.[]", b"", &CONFIG),
+            Err(BraincrubError::Runtime(
+                InterpreterErrors::UnableToCompleteTheProgram
+            ))
+        )
+    }
+
+    #[test]
+    fn given_a_memory_without_cells_when_the_program_is_run_then_return_the_build_error() {
+        let config = RunConfig {
+            memory_size: 0,
+            ..CONFIG
+        };
+
+        assert_eq!(
+            run_source("+", b"", &config),
+            Err(BraincrubError::Build(BuilderErrors::EmptyMemory))
+        )
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    file::{
+        DEFAULT_MAX_SOURCE_SIZE, FileError, map_read_error, read_binary_file, read_source_code_file,
+    },
+    parser::Dialect,
+    run::{RunConfig, run_source},
+};
+
+/**
+ * A program of the suite, foo.bf, with the files next to it: foo.in with the input
+ * bytes, foo.out with the output expected and foo.err with the error expected
+ * instead of an output.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    pub program: PathBuf,
+}
+
+impl TestCase {
+    fn sibling(&self, extension: &str) -> PathBuf {
+        self.program.with_extension(extension)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaseOutcome {
+    Passed,
+    Updated,
+    Failed(String),
+    Errored(String),
+}
+
+impl Display for CaseOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaseOutcome::Passed => write!(f, "PASS"),
+            CaseOutcome::Updated => write!(f, "UPDATED"),
+            CaseOutcome::Failed(_) => write!(f, "FAIL"),
+            CaseOutcome::Errored(_) => write!(f, "ERROR"),
+        }
+    }
+}
+
+/**
+ * Every case runs with its own memory and its own limit of instructions, so a
+ * program that never ends only fails its case.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct SuiteConfig {
+    pub memory_size: usize,
+    pub number_of_reads: u64,
+    pub update: bool,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SuiteSummary {
+    pub passed: usize,
+    pub updated: usize,
+    pub failed: usize,
+    pub errored: usize,
+}
+
+impl SuiteSummary {
+    pub fn add(&mut self, outcome: &CaseOutcome) {
+        match outcome {
+            CaseOutcome::Passed => self.passed += 1,
+            CaseOutcome::Updated => self.updated += 1,
+            CaseOutcome::Failed(_) => self.failed += 1,
+            CaseOutcome::Errored(_) => self.errored += 1,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed == 0 && self.errored == 0
+    }
+}
+
+impl Display for SuiteSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cases = self.passed + self.updated + self.failed + self.errored;
+
+        write!(
+            f,
+            "{} {}: {} passed, {} failed, {} with errors",
+            cases,
+            if cases == 1 { "case" } else { "cases" },
+            self.passed,
+            self.failed,
+            self.errored
+        )?;
+
+        if self.updated > 0 {
+            write!(f, ", {} updated", self.updated)?;
+        }
+
+        Ok(())
+    }
+}
+
+/**
+ * The .bf files of the directory, without entering the subdirectories, in the
+ * order of their names.
+ */
+pub fn find_cases(directory: &str) -> Result<Vec<TestCase>, FileError> {
+    let entries = fs::read_dir(directory).map_err(|error| map_read_error(directory, error))?;
+    let mut cases: Vec<TestCase> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file() && path.extension().is_some_and(|extension| extension == "bf")
+        })
+        .map(|program| TestCase {
+            name: program
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            program,
+        })
+        .collect();
+
+    cases.sort_by(|left, right| left.name.cmp(&right.name));
+    Ok(cases)
+}
+
+fn read_optional(path: &Path) -> Result<Option<Vec<u8>>, FileError> {
+    match path.is_file() {
+        true => read_binary_file(&path.to_string_lossy()).map(Some),
+        false => Ok(None),
+    }
+}
+
+/**
+ * Runs the program with the input of the case, giving the output or the message
+ * of the parse or runtime error.
+ */
+fn run_program(source_code: &str, input: &[u8], config: SuiteConfig) -> Result<Vec<u8>, String> {
+    let config = RunConfig {
+        memory_size: config.memory_size,
+        number_of_reads: config.number_of_reads,
+        dialect: Dialect::Standard,
+    };
+
+    run_source(source_code, input, &config)
+        .map(|result| result.output)
+        .map_err(|error| error.to_string())
+}
+
+fn lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+pub fn run_case(case: &TestCase, config: SuiteConfig) -> CaseOutcome {
+    let expectations = (|| {
+        let source_code =
+            read_source_code_file(&case.program.to_string_lossy(), DEFAULT_MAX_SOURCE_SIZE)?;
+        let input = read_optional(&case.sibling("in"))?.unwrap_or_default();
+        let output = read_optional(&case.sibling("out"))?;
+        let error = read_optional(&case.sibling("err"))?;
+
+        Ok::<_, FileError>((source_code, input, output, error))
+    })();
+    let (source_code, input, expected_output, expected_error) = match expectations {
+        Ok(expectations) => expectations,
+        Err(error) => return CaseOutcome::Errored(error.to_string()),
+    };
+    let result = run_program(&source_code, &input, config);
+
+    if let Some(expected_error) = expected_error {
+        let expected_error = lossy(&expected_error).trim_end().to_string();
+
+        return match result {
+            Err(error) if error == expected_error => CaseOutcome::Passed,
+            Err(error) => CaseOutcome::Failed(format!(
+                "the error is {:?} instead of {:?}",
+                error, expected_error
+            )),
+            Ok(_) => CaseOutcome::Failed(format!(
+                "the program succeeded instead of failing with {:?}",
+                expected_error
+            )),
+        };
+    }
+
+    let output = match result {
+        Ok(output) => output,
+        Err(error) => return CaseOutcome::Errored(error),
+    };
+
+    match expected_output {
+        Some(expected) if expected == output => CaseOutcome::Passed,
+        _ if config.update => match fs::write(case.sibling("out"), &output) {
+            Ok(()) => CaseOutcome::Updated,
+            Err(error) => CaseOutcome::Errored(error.to_string()),
+        },
+        Some(expected) => CaseOutcome::Failed(format!(
+            "the output is {:?} instead of {:?}",
+            lossy(&output),
+            lossy(&expected)
+        )),
+        None => CaseOutcome::Errored(format!(
+            "there isn't {}.out or {}.err with what the program should do, use --update to write the output",
+            case.name, case.name
+        )),
+    }
+}
+
+#[cfg(test)]
+mod suite_test {
+    use super::*;
+
+    const CONFIG: SuiteConfig = SuiteConfig {
+        memory_size: 30,
+        number_of_reads: 10_000,
+        update: false,
+    };
+
+    fn case(name: &str) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            program: PathBuf::from(format!(
+                "{}/resources/suite/{}.bf",
+                env!("CARGO_MANIFEST_DIR"),
+                name
+            )),
+        }
+    }
+
+    #[test]
+    fn given_the_suite_directory_when_the_cases_are_listed_then_they_are_the_bf_files_by_name() {
+        let cases = find_cases(concat!(env!("CARGO_MANIFEST_DIR"), "/resources/suite")).unwrap();
+
+        assert_eq!(
+            cases
+                .iter()
+                .map(|case| case.name.as_str())
+                .collect::<Vec<_>>(),
+            ["echo", "hello", "runaway", "unbalanced", "wrong"]
+        )
+    }
+
+    #[test]
+    fn given_a_program_with_its_input_when_the_case_is_run_then_the_output_is_compared() {
+        assert_eq!(run_case(&case("echo"), CONFIG), CaseOutcome::Passed);
+        assert_eq!(run_case(&case("hello"), CONFIG), CaseOutcome::Passed);
+        assert!(matches!(
+            run_case(&case("wrong"), CONFIG),
+            CaseOutcome::Failed(message) if message.starts_with("the output is \"A\" instead of \"B\"")
+        ))
+    }
+
+    #[test]
+    fn given_an_expected_error_when_the_case_is_run_then_the_message_is_compared() {
+        assert_eq!(run_case(&case("unbalanced"), CONFIG), CaseOutcome::Passed)
+    }
+
+    #[test]
+    fn given_a_program_that_never_ends_when_the_case_is_run_then_only_the_case_errors() {
+        assert!(matches!(
+            run_case(&case("runaway"), CONFIG),
+            CaseOutcome::Errored(message) if message.starts_with("Not enought reads")
+        ))
+    }
+
+    #[test]
+    fn given_the_outcomes_when_they_are_summarized_then_they_are_counted() {
+        let mut summary = SuiteSummary::default();
+
+        for outcome in [
+            CaseOutcome::Passed,
+            CaseOutcome::Passed,
+            CaseOutcome::Failed(String::new()),
+            CaseOutcome::Updated,
+        ] {
+            summary.add(&outcome);
+        }
+
+        assert_eq!(
+            summary.to_string(),
+            "4 cases: 2 passed, 1 failed, 0 with errors, 1 updated"
+        );
+        assert!(!summary.is_success())
+    }
+}
@@ -0,0 +1,232 @@
+use std::{
+    io::{self, Write},
+    time::Instant,
+};
+
+use serde_json::{Value, json};
+
+use crate::interpreter::ExecutionTracer;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    Chrome,
+}
+
+/**
+ * Instructions between the samples of the pointer position.
+ */
+pub const DEFAULT_TRACE_SAMPLE: usize = 1000;
+
+/**
+ * Writes the execution in the Trace Event Format of chrome://tracing and Perfetto:
+ * a duration event for every execution of a loop, nested as the loops are, an
+ * instant event for every output and input, and a counter with the pointer
+ * position. The events are written while the program runs, so the array is only
+ * valid JSON after finishing the trace.
+ */
+pub struct ChromeTrace<W: Write> {
+    writer: W,
+    started: Instant,
+    // The line and the column of every node, to name the loops.
+    positions: Vec<String>,
+    sample_every: usize,
+    events_written: usize,
+    running_loops: usize,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> ChromeTrace<W> {
+    pub fn new(writer: W, positions: Vec<String>, sample_every: usize) -> Self {
+        ChromeTrace {
+            writer,
+            started: Instant::now(),
+            positions,
+            sample_every: sample_every.max(1),
+            events_written: 0,
+            running_loops: 0,
+            error: None,
+        }
+    }
+
+    fn position(&self, node: usize) -> &str {
+        self.positions.get(node).map_or("?", String::as_str)
+    }
+
+    /**
+     * The tracer can't stop the program, so the first error writing is kept and
+     * returned when the trace finishes.
+     */
+    fn write_event(&mut self, phase: &str, name: &str, args: Option<Value>) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let mut event = json!({
+            "name": name,
+            "ph": phase,
+            "ts": self.started.elapsed().as_secs_f64() * 1_000_000.0,
+            "pid": 1,
+            "tid": 1,
+        });
+        if phase == "i" {
+            event["s"] = json!("t");
+        }
+        if let Some(args) = args {
+            event["args"] = args;
+        }
+
+        let separator = match self.events_written {
+            0 => "[\n",
+            _ => ",\n",
+        };
+        self.events_written += 1;
+
+        if let Err(error) = write!(self.writer, "{}{}", separator, event) {
+            self.error = Some(error);
+        }
+    }
+
+    /**
+     * Closes the loops still running, when the program was aborted inside of them,
+     * and the array of events.
+     */
+    pub fn finish(mut self) -> io::Result<W> {
+        while self.running_loops > 0 {
+            self.running_loops -= 1;
+            self.write_event("E", "", None);
+        }
+
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        match self.events_written {
+            0 => writeln!(self.writer, "[]")?,
+            _ => writeln!(self.writer, "\n]")?,
+        }
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> ExecutionTracer for ChromeTrace<W> {
+    fn loop_started(&mut self, node: usize) {
+        let name = format!("loop at {}", self.position(node));
+
+        self.running_loops += 1;
+        self.write_event("B", &name, None);
+    }
+
+    fn loop_finished(&mut self, node: usize) {
+        let name = format!("loop at {}", self.position(node));
+
+        self.running_loops = self.running_loops.saturating_sub(1);
+        self.write_event("E", &name, None);
+    }
+
+    fn output(&mut self, node: usize, value: u8) {
+        let args = json!({ "position": self.position(node), "value": value });
+
+        self.write_event("i", "output", Some(args));
+    }
+
+    fn input(&mut self, node: usize, value: Option<u8>) {
+        let args = json!({ "position": self.position(node), "value": value });
+
+        self.write_event("i", "input", Some(args));
+    }
+
+    fn instruction(&mut self, _node: usize, instructions_executed: u64, pointer: isize) {
+        if instructions_executed
+            .saturating_sub(1)
+            .is_multiple_of(self.sample_every as u64)
+        {
+            self.write_event("C", "pointer", Some(json!({ "position": pointer })));
+        }
+    }
+}
+
+#[cfg(test)]
+mod trace_test {
+    use super::*;
+
+    fn events(trace: ChromeTrace<Vec<u8>>) -> Vec<Value> {
+        let written = trace.finish().unwrap();
+
+        serde_json::from_slice::<Value>(&written)
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn given_nested_loops_when_they_are_traced_then_the_events_are_nested() {
+        let mut trace = ChromeTrace::new(vec![], vec!["1:1".into(), "1:2".into()], 100);
+
+        trace.loop_started(0);
+        trace.loop_started(1);
+        trace.loop_finished(1);
+        trace.output(1, 65);
+        trace.loop_finished(0);
+
+        let phases: Vec<(String, String)> = events(trace)
+            .iter()
+            .map(|event| {
+                (
+                    event["ph"].as_str().unwrap().to_string(),
+                    event["name"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            phases,
+            [
+                ("B".to_string(), "loop at 1:1".to_string()),
+                ("B".to_string(), "loop at 1:2".to_string()),
+                ("E".to_string(), "loop at 1:2".to_string()),
+                ("i".to_string(), "output".to_string()),
+                ("E".to_string(), "loop at 1:1".to_string()),
+            ]
+        )
+    }
+
+    #[test]
+    fn given_loops_still_running_when_the_trace_finishes_then_they_are_closed() {
+        let mut trace = ChromeTrace::new(vec![], vec!["1:1".into()], 100);
+
+        trace.loop_started(0);
+        trace.input(0, None);
+
+        let events = events(trace);
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[1]["args"]["value"], Value::Null);
+        assert_eq!(events[2]["ph"], "E")
+    }
+
+    #[test]
+    fn given_a_sample_interval_when_the_instructions_are_traced_then_the_pointer_is_sampled() {
+        let mut trace = ChromeTrace::new(vec![], vec![], 3);
+
+        for instruction in 1..=7 {
+            trace.instruction(0, instruction, instruction as isize);
+        }
+
+        let positions: Vec<i64> = events(trace)
+            .iter()
+            .map(|event| event["args"]["position"].as_i64().unwrap())
+            .collect();
+
+        assert_eq!(positions, [1, 4, 7])
+    }
+
+    #[test]
+    fn given_nothing_traced_when_the_trace_finishes_then_it_is_an_empty_array() {
+        let trace = ChromeTrace::new(vec![], vec![], 1);
+
+        assert_eq!(trace.finish().unwrap(), b"[]\n")
+    }
+}
@@ -0,0 +1,115 @@
+use braincrub::{
+    BrainfuckMemory, BrainfuckNodeAST, CollectingOutput, EofBehavior, Interpreter,
+    InterpreterConfig, InterpreterErrors, MemoryTape, OverflowPolicy, ParserErrors, QueuedInput,
+    RunConfig, RunSummary, from_source_to_node_ast, run_source,
+};
+
+macro_rules! serde_test_case {
+    ($fname:expr) => {
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/resources/serde/",
+            $fname
+        ))
+    };
+}
+
+#[test]
+fn when_a_program_is_run_through_the_library_then_its_output_is_collected() {
+    let ast = from_source_to_node_ast(",[.,]").unwrap();
+    let mut interpreter = Interpreter::new(
+        CollectingOutput::new(),
+        QueuedInput::from(b"abc".to_vec()),
+        BrainfuckMemory::<u8>::new(30),
+        InterpreterConfig::new(10_000),
+    );
+    interpreter.load_ast_program(&ast);
+
+    interpreter.run().unwrap();
+
+    assert_eq!(&*interpreter.display.as_bytes(), b"abc");
+    assert_eq!(interpreter.summary.bytes_read, 3)
+}
+
+#[test]
+fn when_an_unbalanced_program_is_parsed_through_the_library_then_the_error_is_returned() {
+    assert!(matches!(
+        from_source_to_node_ast("+]"),
+        Err(ParserErrors::MissingOpenLoop { .. })
+    ))
+}
+
+#[test]
+fn when_a_program_never_ends_through_the_library_then_the_limit_stops_it() {
+    let ast = from_source_to_node_ast("+[]").unwrap();
+    let mut interpreter = Interpreter::new(
+        CollectingOutput::new(),
+        QueuedInput::from(vec![]),
+        BrainfuckMemory::<u8>::new(30),
+        InterpreterConfig::new(100),
+    );
+    interpreter.load_ast_program(&ast);
+
+    assert_eq!(
+        interpreter.run(),
+        Err(InterpreterErrors::UnableToCompleteTheProgram)
+    )
+}
+
+/*
+ * The files of resources/serde are the format of the serialized types. When one of
+ * these tests fails, the format changed and the tools reading it break.
+ */
+#[test]
+fn given_the_ast_of_a_program_when_it_is_serialized_then_it_is_the_checked_in_format() {
+    let ast = from_source_to_node_ast("+[-]>.,").unwrap();
+
+    assert_eq!(
+        serde_json::to_string_pretty(&ast).unwrap() + "\n",
+        serde_test_case!("ast.json")
+    );
+    assert_eq!(
+        serde_json::from_str::<Vec<BrainfuckNodeAST>>(serde_test_case!("ast.json")).unwrap(),
+        ast
+    );
+    assert_eq!(
+        postcard::to_stdvec(&ast).unwrap(),
+        include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/resources/serde/ast.postcard"
+        ))
+    )
+}
+
+#[test]
+fn given_a_config_when_it_is_serialized_then_it_is_the_checked_in_format() {
+    let config = InterpreterConfig::new(1000)
+        .with_eof_behavior(EofBehavior::SetMax)
+        .with_overflow_policy(OverflowPolicy::Saturate)
+        .with_flush_every(64);
+
+    assert_eq!(
+        serde_json::to_string_pretty(&config).unwrap() + "\n",
+        serde_test_case!("config.json")
+    );
+    assert_eq!(
+        serde_json::from_str::<InterpreterConfig>(serde_test_case!("config.json")).unwrap(),
+        config
+    )
+}
+
+#[test]
+fn given_the_summary_of_a_run_when_it_is_serialized_then_it_is_the_checked_in_format() {
+    let summary = run_source(",[.,]", b"ab", &RunConfig::default())
+        .unwrap()
+        .summary;
+
+    assert_eq!(
+        serde_json::to_string_pretty(&summary).unwrap() + "\n",
+        serde_test_case!("summary.json")
+    );
+    assert_eq!(
+        serde_json::from_str::<RunSummary>(serde_test_case!("summary.json")).unwrap(),
+        summary
+    )
+}
@@ -1,5 +1,9 @@
+use std::io::pipe;
+use std::process;
+
 use assert_cmd::Command;
 use predicates::prelude::*;
+use serde_json::Value;
 
 macro_rules! file_test_case {
     ($fname:expr) => {
@@ -21,12 +25,29 @@ fn when_linting_code_well_written_then_render_succesfully_linted() {
         .stdout(predicate::str::contains("All good!"));
 }
 
+#[test]
+fn when_linting_code_well_written_quietly_then_nothing_is_shown() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["lint", "-q", "-f", file_test_case!("test_hello_world.txt")])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::is_empty());
+}
+
 #[test]
 fn when_linting_a_source_code_with_lack_of_open_brackets_then_render_error_of_unable_to_complete_the_program()
  {
     Command::cargo_bin("braincrab")
         .unwrap()
-        .args(["run", "-f", file_test_case!("test_lack_open_loop.txt")])
+        .args([
+            "lint",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_lack_open_loop.txt"),
+        ])
         .assert()
         .failure()
         .stderr(predicate::str::contains(
@@ -39,10 +60,255 @@ fn when_linting_a_source_code_with_lack_of_closing_brackets_then_render_error_of
  {
     Command::cargo_bin("braincrab")
         .unwrap()
-        .args(["run", "-f", file_test_case!("test_lack_close_loop.txt")])
+        .args([
+            "lint",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_lack_close_loop.txt"),
+        ])
         .assert()
         .failure()
         .stderr(predicate::str::contains(
             "The source code have more open loop brackets than closing loop brackets.",
         ));
 }
+
+#[test]
+fn when_linting_code_well_written_with_json_then_render_a_success_report() {
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "lint",
+            "--json",
+            "-f",
+            file_test_case!("test_hello_world.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All good!").not());
+
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+    assert_eq!(report["command"], "lint");
+    assert_eq!(report["outcome"], "success");
+    assert_eq!(report["output_base64"], Value::Null);
+}
+
+#[test]
+fn when_linting_a_source_code_with_lack_of_open_brackets_then_the_error_points_to_the_bracket() {
+    let path = file_test_case!("test_lack_open_loop.txt");
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["--color", "never", "lint", "--ext", "txt", "-f", path])
+        .assert()
+        .failure()
+        .stderr(predicate::eq(format!(
+            "error: The source code have more closing loop brackets than open loop brackets.\n \
+             --> {}:1:21\n  \
+             |\n\
+             1 | ...[+++[++++]---]---].\n  \
+             |                     ^\n",
+            path
+        )));
+}
+
+#[test]
+fn when_linting_a_wrong_source_code_with_the_color_forced_then_the_error_is_colored() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "lint",
+            "--color",
+            "always",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_lack_close_loop.txt"),
+        ])
+        .env_remove("NO_COLOR")
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with("\x1b[1m\x1b[31merror\x1b[0m"));
+}
+
+#[test]
+fn given_the_file_flag_or_a_positional_file_when_linting_then_the_result_is_the_same() {
+    let path = file_test_case!("test_hello_world.txt");
+
+    for args in [
+        vec!["lint", "--json", "-f", path],
+        vec!["lint", "--json", "--file", path],
+        vec!["lint", "--json", path],
+    ] {
+        let assert = Command::cargo_bin("braincrab")
+            .unwrap()
+            .args(&args)
+            .assert()
+            .success();
+
+        let report: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+        assert_eq!(report["outcome"], "success", "{:?}", args);
+    }
+}
+
+#[test]
+fn given_the_file_flag_and_a_positional_file_when_linting_then_the_cli_refuses_to_mix_them() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "lint",
+            file_test_case!("test_cat.txt"),
+            "-f",
+            file_test_case!("test_hello_world.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn when_linting_without_files_then_the_cli_asks_for_them() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["lint", "-q"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the following required arguments were not provided",
+        ));
+}
+
+#[test]
+fn when_linting_a_file_with_a_parse_error_then_the_exit_code_is_the_one_of_the_parse_errors() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "lint",
+            "-q",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_lack_close_loop.txt"),
+        ])
+        .assert()
+        .code(3);
+}
+
+#[test]
+fn when_linting_an_inline_program_then_it_is_checked_without_a_file() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["lint", "-e", "+[-]"])
+        .assert()
+        .success()
+        .stdout("All good!\n");
+}
+
+#[test]
+fn given_a_file_with_an_error_and_two_warnings_when_linting_with_sarif_then_the_document_has_the_three_results()
+ {
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "lint",
+            "--format",
+            "sarif",
+            "-f",
+            file_test_case!("test_prose_unbalanced.txt"),
+        ])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::is_empty());
+
+    let sarif: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+    assert_eq!(sarif["version"], "2.1.0");
+    assert_eq!(sarif["runs"].as_array().unwrap().len(), 1);
+    assert_eq!(sarif["runs"][0]["tool"]["driver"]["name"], "braincrub");
+
+    let results = sarif["runs"][0]["results"].as_array().unwrap();
+    let rules: Vec<(&str, &str)> = results
+        .iter()
+        .map(|result| {
+            (
+                result["ruleId"].as_str().unwrap(),
+                result["level"].as_str().unwrap(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        rules,
+        [
+            ("UnknownExtension", "warning"),
+            ("NotBrainfuck", "warning"),
+            ("MissingOpenLoop", "error"),
+        ]
+    );
+
+    let location = &results[2]["locations"][0]["physicalLocation"];
+
+    assert!(
+        location["artifactLocation"]["uri"]
+            .as_str()
+            .unwrap()
+            .ends_with("resources/integration/test_prose_unbalanced.txt")
+    );
+    assert_eq!(location["region"]["startLine"], 43);
+    assert_eq!(location["region"]["startColumn"], 3);
+    assert!(
+        results[2]["message"]["text"]
+            .as_str()
+            .unwrap()
+            .contains("more closing loop brackets")
+    );
+}
+
+#[test]
+fn given_several_files_when_linting_with_sarif_then_their_results_are_in_one_document() {
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "lint",
+            "--format",
+            "sarif",
+            "--ext",
+            "txt",
+            file_test_case!("test_hello_world.txt"),
+            file_test_case!("test_lack_open_loop.txt"),
+            file_test_case!("test_lack_close_loop.txt"),
+        ])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains("files checked").not());
+
+    let sarif: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    let results = sarif["runs"][0]["results"].as_array().unwrap();
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["ruleId"], "MissingTerminantedLoop");
+    assert_eq!(results[1]["ruleId"], "MissingOpenLoop");
+}
+
+#[test]
+fn given_a_closed_stdout_when_linting_several_files_then_exit_without_panicking() {
+    let (reader, writer) = pipe().unwrap();
+    drop(reader);
+
+    let output = process::Command::new(env!("CARGO_BIN_EXE_braincrab"))
+        .args([
+            "lint",
+            file_test_case!("test_hello_world.txt"),
+            file_test_case!("test_cat.txt"),
+        ])
+        .stdout(writer)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(!stderr.contains("panicked"), "{}", stderr)
+}
@@ -1,6 +1,8 @@
 use std::{
-    fs::{File, remove_file},
+    fs::{File, create_dir_all, read_to_string, remove_dir_all, remove_file},
+    io::pipe,
     os::unix::fs::PermissionsExt,
+    process,
 };
 
 use assert_cmd::Command;
@@ -21,7 +23,7 @@ fn given_user_with_lack_of_permission_when_user_try_to_read_a_file_without_permi
  {
     let path_file = concat!(env!("CARGO_TARGET_TMPDIR"), "no_permission.txt");
     let file = File::create(path_file)
-        .expect(format!("Unable to create file in CARGO_TARGET_TMPDIR ${path_file}").as_str());
+        .unwrap_or_else(|_| panic!("Unable to create file in CARGO_TARGET_TMPDIR ${path_file}"));
     let mut permission = file.metadata().unwrap().permissions();
 
     permission.set_mode(0o000);
@@ -34,9 +36,127 @@ fn given_user_with_lack_of_permission_when_user_try_to_read_a_file_without_permi
         .args(["run", "-f", path_file])
         .assert()
         .failure()
+        .stderr(predicate::str::contains(format!(
+            "Unable to read the file {:?} due lack of permission\n",
+            path_file
+        )));
+
+    remove_file(path_file).expect("File to be deleted")
+}
+
+#[test]
+fn when_the_bash_completions_are_asked_then_the_script_completes_the_subcommands_and_flags() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("_braincrab()"))
+        .stdout(predicate::str::contains("complete -F _braincrab"))
+        .stdout(predicate::str::contains("lint run ast completions repl"))
+        .stdout(predicate::str::contains("--max-source-size"))
+        .stdout(predicate::str::contains("--memory-size"));
+}
+
+#[test]
+fn given_a_closed_stdout_when_the_completions_are_asked_then_exit_without_panicking() {
+    let (reader, writer) = pipe().unwrap();
+    drop(reader);
+
+    let output = process::Command::new(env!("CARGO_BIN_EXE_braincrab"))
+        .args(["completions", "bash"])
+        .stdout(writer)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(!stderr.contains("panicked"), "{}", stderr)
+}
+
+#[test]
+fn when_the_completions_of_an_unknown_shell_are_asked_then_the_shells_are_listed() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["completions", "tcsh"])
+        .assert()
+        .code(1)
         .stderr(predicate::str::contains(
-            "Unable to read the file due lack of permission",
+            "[possible values: bash, elvish, fish, powershell, zsh]",
         ));
+}
 
-    remove_file(path_file).expect("File to be deleted")
+#[test]
+fn when_the_manual_page_is_asked_then_it_has_the_flags_and_the_exit_codes() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .arg("man")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".TH BRAINCRAB 1"))
+        .stdout(predicate::str::contains(".SH \"RUN OPTIONS\""))
+        .stdout(predicate::str::contains(
+            "\\fB\\-m\\fR, \\fB\\-\\-memory\\-size\\fR",
+        ))
+        .stdout(predicate::str::contains(
+            "May also be specified with the \\fBBRAINCRUB_MEMORY_SIZE\\fR environment variable.",
+        ))
+        .stdout(predicate::str::contains(".SH ENVIRONMENT"))
+        .stdout(predicate::str::contains(
+            ".SH \"EXIT STATUS\"\n.TP\n\\fB0\\fR",
+        ))
+        .stdout(predicate::str::contains("\\fB141\\fR"))
+        .stdout(predicate::str::contains("MAN OPTIONS").not());
+}
+
+#[test]
+fn given_a_closed_stdout_when_the_manual_page_is_asked_then_exit_without_panicking() {
+    let (reader, writer) = pipe().unwrap();
+    drop(reader);
+
+    let output = process::Command::new(env!("CARGO_BIN_EXE_braincrab"))
+        .arg("man")
+        .stdout(writer)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(!stderr.contains("panicked"), "{}", stderr)
+}
+
+#[test]
+fn when_the_manual_page_is_written_in_a_directory_then_it_is_braincrab_1_with_the_subcommands() {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/man");
+    create_dir_all(directory).unwrap();
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["man", "-o", directory])
+        .assert()
+        .success()
+        .stdout("");
+
+    let page = read_to_string(format!("{}/braincrab.1", directory)).unwrap();
+    let run_page = read_to_string(format!("{}/braincrab-run.1", directory)).unwrap();
+    remove_dir_all(directory).unwrap();
+
+    assert!(page.contains(".SH \"LINT OPTIONS\""));
+    assert!(page.contains("braincrab\\-run(1)"));
+    assert!(run_page.contains(".TH BRAINCRAB-RUN 1"));
+}
+
+#[test]
+fn when_the_manual_page_is_written_in_a_missing_directory_then_the_directory_is_reported() {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/missing/man");
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["man", "-o", directory])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(format!(
+            "The directory {:?} doesn't exist",
+            directory
+        )));
 }
@@ -1,6 +1,18 @@
+use std::fs::{
+    Permissions, create_dir_all, read, remove_dir_all, remove_file, set_permissions, write,
+};
+use std::io::{Read, Write, pipe};
+use std::os::unix::fs::PermissionsExt;
+use std::process::{self, Stdio};
+use std::time::Duration;
+
 use assert_cmd::Command;
-use expectrl::{Any, Expect, spawn};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use expectrl::process::unix::WaitStatus;
+use expectrl::{Any, Expect, Session, spawn};
+use flate2::{Compression, write::GzEncoder};
 use predicates::prelude::*;
+use serde_json::Value;
 
 macro_rules! file_test_case {
     ($fname:expr) => {
@@ -14,8 +26,9 @@ macro_rules! file_test_case {
 
 #[test]
 fn when_user_enter_a_numeric_ascii_code_value_then_cli_do_not_show_an_error() {
-    let mut session = spawn("cargo run -- run -f ./resources/integration/test_input.txt")
-        .expect("Error runing the run subcommand");
+    let mut session =
+        spawn("cargo run -- run --output-mode debug -f ./resources/integration/test_input.txt")
+            .expect("Error runing the run subcommand");
 
     session
         .expect(Any::boxed(vec![Box::new(
@@ -43,10 +56,115 @@ fn when_user_enter_a_numeric_ascii_code_value_then_cli_do_not_show_an_error() {
 }
 
 #[test]
-fn when_user_enter_an_ascii_char_then_cli_do_not_show_an_error() {
-    let mut session = spawn("cargo run -- run -f ./resources/integration/test_input.txt")
+fn when_the_program_prints_a_question_before_reading_then_the_question_is_shown_before_the_prompt()
+{
+    let mut session = spawn("cargo run -- run -f ./resources/integration/test_question.txt")
         .expect("Error runing the run subcommand");
 
+    let prompt = session
+        .expect(Any::boxed(vec![Box::new(
+            "Write an ascii character or his code value",
+        )]))
+        .expect("Message waiting to the input not rendered");
+
+    assert!(String::from_utf8_lossy(prompt.before()).contains("X?\r\n"));
+
+    session
+        .send_line("A")
+        .expect("Failed to write the ascii character");
+
+    session
+        .expect(Any::boxed(vec![Box::new("Program executed succesfully")]))
+        .expect("CLI not ended succesfully");
+}
+
+#[test]
+fn when_the_program_asks_for_an_input_then_the_prompt_shows_the_cell_and_its_value() {
+    let mut session = spawn(
+        "cargo run -- run --output-mode debug -f ./resources/integration/test_input_second_cell.txt",
+    )
+    .expect("Error runing the run subcommand");
+
+    session
+        .expect(Any::boxed(vec![Box::new(
+            "Write an ascii character or his code value for the cell 1 (currently 3)",
+        )]))
+        .expect("Prompt with the cell information not rendered");
+
+    session
+        .send_line("A")
+        .expect("Failed to write the character");
+
+    session
+        .expect(Any::boxed(vec![Box::new("'A'")]))
+        .expect("Display cell value not redered");
+}
+
+#[test]
+fn when_the_user_repeats_the_previous_value_then_both_cells_get_the_same_byte() {
+    let mut session = spawn(
+        "cargo run -- run --output-mode debug -f ./resources/integration/test_input_two_cells.txt",
+    )
+    .expect("Error runing the run subcommand");
+
+    session
+        .expect(Any::boxed(vec![Box::new("for the cell 0")]))
+        .expect("First prompt not rendered");
+    session
+        .send_line("B")
+        .expect("Failed to write the character");
+
+    session
+        .expect(Any::boxed(vec![Box::new("for the cell 1")]))
+        .expect("Second prompt not rendered");
+    session
+        .send_line("!!")
+        .expect("Failed to repeat the previous value");
+
+    session
+        .expect(Any::boxed(vec![Box::new("'B''B'")]))
+        .expect("Both cells don't have the same value");
+}
+
+#[test]
+fn when_the_user_moves_through_the_previous_values_with_the_arrows_then_the_highlighted_one_is_read()
+ {
+    let mut session = spawn(
+        "cargo run -- run --output-mode debug -f ./resources/integration/test_input_three_cells.txt",
+    )
+    .expect("Error runing the run subcommand");
+
+    for (cell, value) in [(0, "A"), (1, "B")] {
+        session
+            .expect(Any::boxed(vec![Box::new(format!("for the cell {}", cell))]))
+            .expect("Prompt not rendered");
+        session
+            .send_line(value)
+            .expect("Failed to write the character");
+    }
+
+    session
+        .expect(Any::boxed(vec![Box::new("for the cell 2")]))
+        .expect("Third prompt not rendered");
+    // The down arrow goes to the older values and the up arrow back to the newer ones.
+    session
+        .send("\x1b[B\x1b[B\x1b[A")
+        .expect("Failed to move through the previous values");
+    session
+        .send_line("")
+        .expect("Failed to read the highlighted value");
+
+    session
+        .expect(Any::boxed(vec![Box::new("'A''B''B'")]))
+        .expect("The third cell doesn't have the recalled value");
+}
+
+#[test]
+fn when_user_enter_an_ascii_char_then_cli_do_not_show_an_error() {
+    let mut session =
+        spawn("cargo run -- run --output-mode debug -f ./resources/integration/test_input.txt")
+            .expect("Error runing the run subcommand");
+
     session
         .expect(Any::boxed(vec![Box::new(
             "Write an ascii character or his code value",
@@ -72,10 +190,31 @@ fn when_user_enter_an_ascii_char_then_cli_do_not_show_an_error() {
     );
 }
 
+#[test]
+fn when_the_user_presses_ctrl_c_at_the_prompt_then_the_run_exits_as_interrupted() {
+    let mut session =
+        spawn("cargo run -- run --output-mode debug -f ./resources/integration/test_input.txt")
+            .expect("Error runing the run subcommand");
+
+    session
+        .expect(Any::boxed(vec![Box::new(
+            "Write an ascii character or his code value",
+        )]))
+        .expect("Message waiting to the input not rendered");
+
+    session.send("\x03").expect("Failed to press Ctrl-C");
+
+    assert!(matches!(
+        session.get_process().wait(),
+        Ok(WaitStatus::Exited(_, 130))
+    ));
+}
+
 #[test]
 fn when_user_enter_an_invalid_ascii_char_code_then_cli_show_invalid_error() {
-    let mut session = spawn("cargo run -- run -f ./resources/integration/test_input.txt")
-        .expect("Error runing the run subcommand");
+    let mut session =
+        spawn("cargo run -- run --output-mode debug -f ./resources/integration/test_input.txt")
+            .expect("Error runing the run subcommand");
 
     session
         .expect(Any::boxed(vec![Box::new(
@@ -84,25 +223,26 @@ fn when_user_enter_an_invalid_ascii_char_code_then_cli_show_invalid_error() {
         .expect("Message waiting to the input not rendered");
 
     session
-        .send_line("128")
+        .send_line("256")
         .expect("Failed to write the numeric ascii code");
 
     let error_message = session
         .expect(Any::boxed(vec![Box::new(
-            "Please type a valid ascii character",
+            "The value must be in the range of 0 to 255",
         )]))
         .expect("Error message not rendered");
 
     assert_eq!(
         String::from_utf8_lossy(error_message.get(0).unwrap()),
-        "Please type a valid ascii character"
+        "The value must be in the range of 0 to 255"
     );
 }
 
 #[test]
 fn when_user_enter_an_invalid_ascii_char_then_cli_show_invalid_error() {
-    let mut session = spawn("cargo run -- run -f ./resources/integration/test_input.txt")
-        .expect("Error runing the run subcommand");
+    let mut session =
+        spawn("cargo run -- run --output-mode debug -f ./resources/integration/test_input.txt")
+            .expect("Error runing the run subcommand");
 
     session
         .expect(Any::boxed(vec![Box::new(
@@ -116,27 +256,232 @@ fn when_user_enter_an_invalid_ascii_char_then_cli_show_invalid_error() {
 
     let error_message = session
         .expect(Any::boxed(vec![Box::new(
-            "Please type a valid ascii character",
+            "The value isn't an ascii character",
         )]))
         .expect("Error message not rendered");
 
     assert_eq!(
         String::from_utf8_lossy(error_message.get(0).unwrap()),
-        "Please type a valid ascii character"
+        "The value isn't an ascii character"
     );
 }
 
 #[test]
-fn when_running_hello_world_source_code_then_render_hello_world_and_complete_successfully() {
+fn when_running_hello_world_source_code_in_debug_mode_then_render_hello_world_quoted_and_complete_successfully()
+ {
     Command::cargo_bin("braincrab")
         .unwrap()
-        .args(["run", "-f", file_test_case!("test_hello_world.txt")])
+        .args([
+            "run",
+            "--output-mode",
+            "debug",
+            "-f",
+            file_test_case!("test_hello_world.txt"),
+        ])
         .assert()
         .success()
-        .stdout(
-            predicate::str::contains("'H''e''l''l''o'' ''W''o''r''l''d''!'")
-                .and(predicate::str::contains("Program executed succesfully")),
-        );
+        .stdout(predicate::str::contains(
+            "'H''e''l''l''o'' ''W''o''r''l''d''!'",
+        ))
+        .stderr(predicate::str::contains("Program executed succesfully"));
+}
+
+#[test]
+fn when_running_hello_world_source_code_in_raw_mode_then_stdout_only_contains_the_program_output() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-f", file_test_case!("test_hello_world_newline.txt")])
+        .assert()
+        .success()
+        .stdout(predicate::eq("Hello World!\n"))
+        .stderr(predicate::str::contains("Program executed succesfully"));
+}
+
+#[test]
+fn when_running_hello_world_source_code_quietly_then_only_the_program_output_is_shown() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--quiet",
+            "--output-mode",
+            "debug",
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("'H''e''l''l''o'' ''W''o''r''l''d''!''\\n'"))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn when_running_a_wrong_source_code_quietly_then_the_error_is_still_shown() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "-q",
+            "-f",
+            file_test_case!("test_lack_open_loop.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The source code have more closing loop brackets than open loop brackets.",
+        ));
+}
+
+#[test]
+fn when_running_hello_world_source_code_in_debug_mode_then_stdout_only_contains_the_quoted_program_output()
+ {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--output-mode",
+            "debug",
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("'H''e''l''l''o'' ''W''o''r''l''d''!''\\n'"));
+}
+
+#[test]
+fn when_running_a_source_code_that_outputs_non_printable_bytes_in_raw_mode_then_stdout_has_the_exact_bytes()
+ {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "-f",
+            file_test_case!("test_output_non_printable.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq(&[65_u8, 7, 200, 98, 10] as &[u8]));
+}
+
+#[test]
+fn when_running_hello_world_with_an_output_file_then_the_file_has_the_program_output_and_stdout_is_empty()
+ {
+    let output_path = concat!(env!("CARGO_TARGET_TMPDIR"), "/hello_world_output.txt");
+    let _ = remove_file(output_path);
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--output-file",
+            output_path,
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("Program executed succesfully"));
+
+    assert_eq!(read(output_path).unwrap(), b"Hello World!\n");
+
+    remove_file(output_path).expect("File to be deleted")
+}
+
+#[test]
+fn given_an_output_file_that_already_exists_when_running_without_force_then_render_an_error_and_keep_the_file()
+ {
+    let output_path = concat!(env!("CARGO_TARGET_TMPDIR"), "/existing_output.txt");
+    write(output_path, "previous content").unwrap();
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--output-file",
+            output_path,
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("panicked").not())
+        .stderr(predicate::str::contains(
+            "already exists, use --force to overwrite it",
+        ));
+
+    assert_eq!(read(output_path).unwrap(), b"previous content");
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--output-file",
+            output_path,
+            "--force",
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .success();
+
+    assert_eq!(read(output_path).unwrap(), b"Hello World!\n");
+
+    remove_file(output_path).expect("File to be deleted")
+}
+
+#[test]
+fn when_running_a_counter_in_numeric_mode_then_render_every_value_as_a_number() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--output-mode",
+            "numeric",
+            "-f",
+            file_test_case!("test_count_to_five.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("0\n1\n2\n3\n4\n5\n"));
+}
+
+#[test]
+fn when_running_a_counter_in_numeric_mode_with_a_custom_separator_then_render_the_numbers_with_the_separator()
+ {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--output-mode",
+            "numeric",
+            "--output-separator",
+            " ",
+            "-f",
+            file_test_case!("test_count_to_five.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("0 1 2 3 4 5 "));
+}
+
+#[test]
+fn when_running_a_counter_in_hex_mode_then_render_every_value_as_hex_digits() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--output-mode",
+            "hex",
+            "--output-separator",
+            " ",
+            "-f",
+            file_test_case!("test_count_to_five.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("00 01 02 03 04 05 "));
 }
 
 #[test]
@@ -154,43 +499,82 @@ fn when_running_a_source_code_with_infinite_loop_then_render_error_of_unable_to_
 }
 
 #[test]
-fn when_the_amount_of_reads_provided_is_lower_than_1_then_render_an_error_of_invalid_argument_value()
+fn when_running_a_source_code_with_infinite_loop_then_the_error_points_to_the_loop_that_used_the_limit()
  {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--no-postmortem",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_infinite_loop.txt"),
+        ])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains(
+            "The limit of 60000 instructions was reached while the loop at line 1, column 2 ran 7500 iterations",
+        ))
+        .stderr(predicate::str::contains("test_infinite_loop.txt:1:2\n"));
+}
+
+#[test]
+fn when_the_amount_of_reads_provided_isnt_a_number_then_render_an_error_of_invalid_argument_value()
+{
     Command::cargo_bin("braincrab")
         .unwrap()
         .args([
             "run",
             "-l",
-            "0",
+            "ten",
             "-f",
             file_test_case!("test_hello_world.txt"),
         ])
         .assert()
         .failure()
         .stderr(predicate::str::contains(
-            "invalid value '0' for '-l <limit-read-instructions>': Minimum value accepted is 1",
+            "invalid value 'ten' for '--limit <limit-read-instructions>'",
         ));
 }
 
 #[test]
-fn when_the_amount_of_reads_provided_is_greater_than_the_maximum_of_reads_then_render_an_error_of_invalid_argument_value()
+fn when_the_amount_of_reads_provided_has_an_unknown_suffix_then_render_an_error_of_invalid_argument_value()
  {
     Command::cargo_bin("braincrab")
         .unwrap()
         .args([
             "run",
             "-l",
-            "100001",
+            "100X",
             "-f",
             file_test_case!("test_hello_world.txt"),
         ])
         .assert()
         .failure()
         .stderr(predicate::str::contains(
-            "invalid value '100001' for '-l <limit-read-instructions>': Maximum value accepted is 100_000",
+            "invalid value '100X' for '--limit <limit-read-instructions>': Unknown suffix \"X\"",
         ));
 }
 
+#[test]
+fn given_a_program_of_half_a_million_instructions_when_the_limit_is_raised_or_removed_then_it_completes()
+ {
+    let run = |limit: &[&str]| {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args(["run", "--ext", "txt"])
+            .args(limit)
+            .args(["-f", file_test_case!("test_half_million_instructions.txt")])
+            .assert()
+    };
+
+    run(&[]).code(5);
+    run(&["-l", "1M"]).success().stdout("A");
+    run(&["--limit", "0"]).success().stdout("A");
+    run(&["-l", "100", "--no-limit"]).success().stdout("A");
+}
+
 #[test]
 fn when_running_a_source_code_with_lack_of_open_brackets_then_render_error_of_unable_to_complete_the_program()
  {
@@ -232,8 +616,80 @@ fn give_a_memory_size_smaller_when_the_program_moves_to_position_out_of_bounds_t
         .assert()
         .failure()
         .stderr(predicate::str::contains(
-            "The program is trying to access to position out of range in the memory",
-        ));
+            "The pointer position 2 is out of range (the tape is 0..2)",
+        ))
+        .stderr(predicate::str::ends_with(concat!(
+            "The pointer is at the cell 1 with the value 4\n",
+            "[   8 |>  4<]\n",
+            "   0     1\n",
+            "       0  dec   8   4\n",
+            "          hex  08  04\n",
+            "          chr   .   .\n",
+        )));
+}
+
+#[test]
+fn given_a_program_that_moves_to_the_end_of_the_tape_when_it_uses_the_cell_then_render_a_runtime_error()
+ {
+    for program in [">>.", ">>[]", ">>,", ">>+"] {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args(["run", "-m", "2", &format!("-e={}", program)])
+            .write_stdin("a")
+            .assert()
+            .code(4)
+            .stderr(predicate::str::contains(
+                "The pointer position 2 is out of range (the tape is 0..2)",
+            ));
+    }
+}
+
+#[test]
+fn given_the_short_and_the_long_memory_size_flags_when_the_memory_is_too_small_then_both_fail_the_same()
+ {
+    let run_with = |flag: &str| {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args([
+                "run",
+                flag,
+                "5",
+                "--no-postmortem",
+                "-f",
+                file_test_case!("test_hello_world.txt"),
+            ])
+            .assert()
+            .failure()
+            .get_output()
+            .stderr
+            .clone()
+    };
+
+    let short_stderr = run_with("-m");
+
+    assert!(String::from_utf8_lossy(&short_stderr).contains("(the tape is 0..5)"));
+    assert_eq!(short_stderr, run_with("--memory-size"));
+}
+
+#[test]
+fn given_the_no_postmortem_flag_when_the_program_moves_out_of_the_memory_then_only_the_error_is_rendered()
+ {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "-m",
+            "2",
+            "--no-postmortem",
+            "-f",
+            file_test_case!("test_hello_world.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "is out of range (the tape is 0..2)",
+        ))
+        .stderr(predicate::str::contains("The pointer is at the cell").not());
 }
 
 #[test]
@@ -251,7 +707,7 @@ fn when_the_memory_size_is_lower_than_1_then_cli_return_an_error_as_the_argument
         .assert()
         .failure()
         .stderr(predicate::str::contains(
-            "invalid value '0' for '-m <memory-size>': Minimum value accepted is 1",
+            "invalid value '0' for '--memory-size <memory-size>': Minimum value accepted is 1",
         ));
 }
 
@@ -263,13 +719,2654 @@ fn when_the_memory_size_is_greather_than_the_maximum_memory_size_then_cli_return
         .args([
             "run",
             "-m",
-            "100001",
+            "1073741825",
             "-f",
             file_test_case!("test_hello_world.txt"),
         ])
         .assert()
         .failure()
         .stderr(predicate::str::contains(
-            "invalid value '100001' for '-m <memory-size>': Maximum value accepted is 30_000",
+            "invalid value '1073741825' for '--memory-size <memory-size>': Maximum value accepted is 1073741824 (1 GiB of cells)",
+        ));
+}
+
+#[test]
+fn when_the_memory_size_is_a_million_cells_then_the_program_runs() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--memory-size",
+            "1000000",
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("Hello World!\n"));
+}
+
+#[test]
+fn when_the_input_is_piped_without_a_stdin_mode_then_the_program_reads_it_without_prompting() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-f", file_test_case!("test_cat.txt")])
+        .write_stdin("abc")
+        .assert()
+        .success()
+        .stdout(predicate::eq("abc"))
+        .stderr(predicate::str::contains("Write an ascii character").not());
+}
+
+#[test]
+fn given_the_eof_value_0_when_the_piped_input_ends_then_the_cat_program_stops() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--eof-value",
+            "0",
+            "-f",
+            file_test_case!("test_cat.txt"),
+        ])
+        .write_stdin("ab")
+        .assert()
+        .success()
+        .stdout(predicate::eq("ab"));
+}
+
+#[test]
+fn given_the_eof_value_255_or_its_alias_when_the_piped_input_ends_then_the_cat_program_outputs_255_until_the_limit()
+ {
+    for eof_value in ["255", "-1"] {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args([
+                "run",
+                "--eof-value",
+                eof_value,
+                "-l",
+                "20",
+                "-f",
+                file_test_case!("test_cat.txt"),
+            ])
+            .write_stdin("ab")
+            .assert()
+            .code(5)
+            .stdout(predicate::function(|output: &[u8]| {
+                output.starts_with(b"ab\xff\xff") && output[2..].iter().all(|byte| *byte == 255)
+            }));
+    }
+}
+
+#[test]
+fn given_the_eof_value_unchanged_when_the_piped_input_ends_then_the_cat_program_repeats_the_last_byte()
+ {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--eof-value",
+            "unchanged",
+            "-l",
+            "20",
+            "-f",
+            file_test_case!("test_cat.txt"),
+        ])
+        .write_stdin("ab")
+        .assert()
+        .code(5)
+        .stdout(predicate::str::starts_with("abbb"))
+        .stdout(predicate::str::contains("a").count(1));
+}
+
+#[test]
+fn when_running_with_an_unknown_eof_value_then_the_cli_rejects_it() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--eof-value",
+            "7",
+            "-f",
+            file_test_case!("test_cat.txt"),
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "Accepted values are 0, 255 (or -1) or unchanged",
+        ));
+}
+
+#[test]
+fn when_running_hello_world_with_json_then_stdout_is_a_report_with_the_output_in_base64() {
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--json",
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Program executed succesfully").not());
+
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+    assert_eq!(report["command"], "run");
+    assert_eq!(report["outcome"], "success");
+    assert_eq!(report["error"], Value::Null);
+    assert_eq!(report["summary"]["bytes_written"], 13);
+    assert_eq!(
+        STANDARD
+            .decode(report["output_base64"].as_str().unwrap())
+            .unwrap(),
+        b"Hello World!\n"
+    );
+}
+
+#[test]
+fn when_running_a_source_code_with_lack_of_open_brackets_with_json_then_stdout_is_a_failure_report()
+{
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--json",
+            "-f",
+            file_test_case!("test_lack_open_loop.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_empty());
+
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+    assert_eq!(report["outcome"], "failure");
+    assert_eq!(report["error"]["kind"], "MissingOpenLoop");
+    assert_eq!(report["error"]["position"], 20);
+    assert_eq!(report["error"]["exit_code"], 3);
+    assert_eq!(report["summary"], Value::Null);
+}
+
+#[test]
+fn when_running_a_source_code_with_an_unclosed_loop_with_json_then_the_error_has_the_position_of_the_loop()
+ {
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "--json", "-e=+[-"])
+        .assert()
+        .code(3);
+
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+    assert_eq!(report["error"]["kind"], "MissingTerminantedLoop");
+    assert_eq!(report["error"]["position"], 1);
+}
+
+#[test]
+fn given_an_output_limit_when_the_program_outputs_in_an_infinite_loop_then_stop_at_the_limit_and_render_an_error()
+ {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--max-output",
+            "100",
+            "-f",
+            file_test_case!("test_infinite_output.txt"),
+        ])
+        .assert()
+        .failure()
+        .stdout(predicate::eq(&[1_u8; 100] as &[u8]))
+        .stderr(predicate::str::contains(
+            "The program output reached the limit of 100 bytes",
+        ));
+}
+
+#[test]
+fn given_a_reader_that_closes_the_stdout_early_when_the_program_outputs_in_an_infinite_loop_then_stop_without_errors()
+ {
+    let mut child = process::Command::new(env!("CARGO_BIN_EXE_braincrab"))
+        .args([
+            "run",
+            "--output-mode",
+            "hexdump",
+            "-l",
+            "100000",
+            "-f",
+            file_test_case!("test_infinite_output.txt"),
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Error runing the run subcommand");
+
+    // The hexdump makes the output bigger than the pipe buffer, so the program is
+    // still writing when the pipe is closed.
+    let mut first_bytes = [0_u8; 10];
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_exact(&mut first_bytes)
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(&first_bytes, b"00000000  ");
+    assert_eq!(output.status.code(), Some(141));
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+    assert!(!stderr.contains("error"), "{}", stderr)
+}
+
+#[test]
+fn when_the_output_limit_has_an_unknown_suffix_then_render_an_error_of_invalid_argument_value() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--max-output",
+            "10T",
+            "-f",
+            file_test_case!("test_hello_world.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '10T'"));
+}
+
+#[test]
+fn when_running_the_cat_program_in_utf8_mode_then_render_the_multi_byte_characters_decoded() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--output-mode",
+            "utf8",
+            "-f",
+            file_test_case!("test_cat.txt"),
+        ])
+        .write_stdin(vec![0xC3, 0xA9, 0xE2, 0x82, 0xAC, 0xE2])
+        .assert()
+        .success()
+        .stdout(predicate::eq("é€\u{FFFD}"));
+}
+
+#[test]
+fn when_running_hello_world_with_tee_in_debug_mode_then_stdout_is_quoted_and_the_file_has_the_raw_output()
+ {
+    let output_path = concat!(env!("CARGO_TARGET_TMPDIR"), "/hello_world_tee.txt");
+    let _ = remove_file(output_path);
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--output-mode",
+            "debug",
+            "--output-file",
+            output_path,
+            "--tee",
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("'H''e''l''l''o'' ''W''o''r''l''d''!''\\n'"));
+
+    assert_eq!(read(output_path).unwrap(), b"Hello World!\n");
+
+    remove_file(output_path).expect("File to be deleted")
+}
+
+#[test]
+fn when_running_the_cat_program_with_a_transcript_then_the_file_has_every_input_event_and_the_outcome()
+ {
+    let transcript_path = concat!(env!("CARGO_TARGET_TMPDIR"), "/cat_transcript.txt");
+    let _ = remove_file(transcript_path);
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--transcript",
+            transcript_path,
+            "-f",
+            file_test_case!("test_cat.txt"),
+        ])
+        .write_stdin("ab")
+        .assert()
+        .success()
+        .stdout(predicate::eq("ab"));
+
+    let transcript = String::from_utf8(read(transcript_path).unwrap()).unwrap();
+    let events: Vec<&str> = transcript
+        .lines()
+        .map(|line| line.split_once(' ').unwrap().1)
+        .collect();
+
+    assert_eq!(
+        events,
+        [
+            "input byte=97 char='a'",
+            "input byte=98 char='b'",
+            "input end_of_input",
+            "outcome success"
+        ]
+    );
+
+    remove_file(transcript_path).expect("File to be deleted")
+}
+
+#[test]
+fn given_a_transcript_that_already_exists_when_running_without_force_then_render_an_error_and_keep_the_file()
+ {
+    let transcript_path = concat!(env!("CARGO_TARGET_TMPDIR"), "/existing_transcript.txt");
+    write(transcript_path, "previous content").unwrap();
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "--transcript", transcript_path, "-e", "+."])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "already exists, use --force to overwrite it",
+        ));
+
+    assert_eq!(read(transcript_path).unwrap(), b"previous content");
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--transcript",
+            transcript_path,
+            "--force",
+            "-e",
+            "+.",
+        ])
+        .assert()
+        .success();
+
+    assert!(
+        String::from_utf8(read(transcript_path).unwrap())
+            .unwrap()
+            .ends_with("outcome success\n")
+    );
+
+    remove_file(transcript_path).expect("File to be deleted")
+}
+
+#[test]
+fn when_replaying_the_transcript_of_the_cat_program_then_the_program_gets_the_same_input() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--replay",
+            file_test_case!("test_cat_recording.txt"),
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_cat.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("ab"))
+        .stderr(predicate::str::contains("warning").not());
+}
+
+#[test]
+fn when_replaying_a_recording_shorter_than_the_program_reads_then_the_run_fails_naming_the_input() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--replay",
+            file_test_case!("test_cat_recording.txt"),
+            "-f",
+            file_test_case!("test_read_four.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The program requested a 4th input but the recording only has 3",
+        ));
+}
+
+#[test]
+fn when_replaying_a_recording_in_a_program_that_reads_fewer_inputs_then_it_warns_about_the_unused_input()
+ {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--replay",
+            file_test_case!("test_cat_recording.txt"),
+            "-f",
+            file_test_case!("test_input.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("a"))
+        .stderr(predicate::str::contains(
+            "warning: The program didn't read 1 bytes of the recorded input",
+        ));
+}
+
+#[test]
+fn when_running_hello_world_verbosely_then_the_phases_are_shown_in_the_stderr() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "-vv",
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("Hello World!\n"))
+        .stderr(
+            predicate::str::contains("phase read")
+                .and(predicate::str::contains("phase parse"))
+                .and(predicate::str::contains("phase execute"))
+                .and(predicate::str::contains("bytes written: 13"))
+                .and(predicate::str::contains("cells touched: 7"))
+                .and(predicate::str::contains("memory high-water mark: 6")),
+        );
+}
+
+#[test]
+fn when_running_with_quiet_and_verbose_then_the_cli_rejects_the_arguments() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "-q",
+            "-v",
+            "-f",
+            file_test_case!("test_hello_world.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn when_running_the_cat_program_with_mixed_line_endings_then_each_newline_mode_translates_them() {
+    for (mode, expected) in [
+        ("passthrough", &b"a\r\nb\rc\n"[..]),
+        ("lf", &b"a\nb\nc\n"[..]),
+        ("crlf", &b"a\r\nb\rc\r\n"[..]),
+    ] {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args([
+                "run",
+                "--input-newlines",
+                mode,
+                "-f",
+                file_test_case!("test_cat.txt"),
+            ])
+            .write_stdin("a\r\nb\rc\n")
+            .assert()
+            .success()
+            .stdout(predicate::eq(expected));
+    }
+}
+
+#[test]
+fn when_running_the_cat_program_with_the_same_random_seed_then_the_output_is_the_same() {
+    let run_with_seed = |seed: &str| {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args([
+                "run",
+                "--random-input",
+                seed,
+                "--max-output",
+                "64",
+                "-f",
+                file_test_case!("test_cat.txt"),
+            ])
+            .assert()
+            .failure()
+            .get_output()
+            .stdout
+            .clone()
+    };
+
+    let output = run_with_seed("1234");
+
+    assert_eq!(output.len(), 64);
+    assert_eq!(output, run_with_seed("1234"));
+    assert_ne!(output, run_with_seed("4321"));
+}
+
+#[test]
+fn when_running_with_random_input_without_seed_then_the_generated_seed_is_shown() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--max-output",
+            "8",
+            "-f",
+            file_test_case!("test_cat.txt"),
+            "--random-input",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Random input seed: "));
+}
+
+#[test]
+fn when_running_a_program_with_three_reads_driven_by_in_flags_then_the_values_are_read_in_order() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--in",
+            "65",
+            "--in",
+            "B",
+            "--in",
+            "\\n",
+            "-f",
+            file_test_case!("test_read_three.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("AB\n"));
+}
+
+#[test]
+fn when_running_with_in_flags_of_single_digits_and_prefixed_codes_then_the_digits_are_characters() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--in",
+            "a",
+            "--in",
+            "6",
+            "--in",
+            "#10",
+            "-f",
+            file_test_case!("test_read_three.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("a6\n"));
+}
+
+#[test]
+fn when_running_with_an_in_flag_that_isnt_valid_then_the_cli_rejects_it_naming_the_value() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--in",
+            "0x41",
+            "--in",
+            "300",
+            "-f",
+            file_test_case!("test_read_three.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value '300' for '--in <VALUE>'",
+        ));
+}
+
+#[test]
+fn when_running_hello_world_source_code_in_hexdump_mode_then_stdout_has_the_rows_and_the_final_offset()
+ {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--output-mode",
+            "hexdump",
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq(
+            "00000000  48 65 6c 6c 6f 20 57 6f  72 6c 64 21 0a           |Hello World!.|\n\
+             0000000d\n",
+        ));
+}
+
+#[test]
+fn when_running_a_program_printing_0_127_and_255_in_csv_mode_then_stdout_is_a_single_row() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--output-mode",
+            "csv",
+            "-f",
+            file_test_case!("test_output_csv.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("0,127,255\n"));
+}
+
+#[test]
+fn when_running_a_program_that_moves_left_from_the_start_with_the_bidirectional_memory_then_it_uses_the_cell_minus_one()
+ {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--memory",
+            "bidirectional",
+            "-f",
+            file_test_case!("test_move_left_from_start.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("A"));
+}
+
+#[test]
+fn when_running_a_program_that_moves_left_from_the_start_with_the_default_memory_then_render_an_error()
+ {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "-f",
+            file_test_case!("test_move_left_from_start.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The pointer position -1 is out of range (the tape is 0..3000)",
+        ));
+}
+
+#[test]
+fn when_running_a_program_that_writes_far_from_the_start_with_the_sparse_memory_then_the_cells_are_kept()
+ {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--memory",
+            "sparse",
+            "-l",
+            "100000",
+            "-f",
+            file_test_case!("test_far_cells.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("BA"));
+}
+
+#[test]
+fn when_running_a_program_with_dump_memory_then_the_cells_up_to_the_highest_one_reached_are_dumped()
+{
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--dump-memory",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_known_cells.txt"),
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with(concat!(
+            "       0  dec   1   2  72 105\n",
+            "          hex  01  02  48  69\n",
+            "          chr   .   .   H   i\n"
+        )));
+}
+
+#[test]
+fn when_running_a_program_with_dump_memory_and_a_range_then_only_the_range_is_dumped() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--dump-memory=2..4",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_known_cells.txt"),
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with("       2  dec  72 105\n"));
+}
+
+#[test]
+fn when_running_a_program_with_a_dump_range_larger_than_the_tape_then_the_dump_ends_with_the_tape()
+{
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--memory-size",
+            "40",
+            "--dump-memory=2..99999999999",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_known_cells.txt"),
+        ])
+        .timeout(Duration::from_secs(10))
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with("       2  dec  72 105"))
+        .stderr(predicate::str::contains("\n… 22 zero cells …\n"));
+}
+
+#[test]
+fn when_running_a_program_with_a_dump_range_that_ends_before_its_start_then_the_cli_rejects_it() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--dump-memory=4..2",
+            "-f",
+            file_test_case!("test_known_cells.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The start of the range must be before its end",
+        ));
+}
+
+#[test]
+fn when_a_program_fails_with_dump_memory_then_the_memory_is_dumped_before_the_error() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--dump-memory",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_move_left_from_start.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with("… 1 zero cell …\nerror: "));
+}
+
+#[test]
+fn when_running_a_program_with_a_data_file_at_an_offset_then_the_cells_have_its_bytes() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--data",
+            concat!(file_test_case!("test_data_hi.bin"), "@5"),
+            "-f",
+            file_test_case!("test_print_cells_five_and_six.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("HI");
+}
+
+#[test]
+fn when_running_a_program_with_several_data_files_then_all_of_them_are_loaded() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--data",
+            concat!(file_test_case!("test_data_hi.bin"), "@4"),
+            "--data",
+            concat!(file_test_case!("test_data_hi.bin"), "@6"),
+            "-f",
+            file_test_case!("test_print_cells_five_and_six.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("IH");
+}
+
+#[test]
+fn when_a_data_file_does_not_fit_in_the_memory_then_the_run_fails_before_starting() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "-m",
+            "6",
+            "--data",
+            concat!(file_test_case!("test_data_hi.bin"), "@5"),
+            "-f",
+            file_test_case!("test_print_cells_five_and_six.txt"),
+        ])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains(
+            "doesn't fit in the memory from the cell 5, it has 2 bytes",
+        ));
+}
+
+#[test]
+fn when_running_a_program_with_a_data_offset_too_big_then_the_cli_rejects_it() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--data",
+            concat!(file_test_case!("test_data_hi.bin"), "@99999999999999999999"),
+            "-f",
+            file_test_case!("test_print_cells_five_and_six.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid offset \"99999999999999999999\"",
+        ));
+}
+
+#[test]
+fn when_running_a_program_verbosely_then_the_memory_figures_are_shown() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-v", "-f", file_test_case!("test_known_cells.txt")])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "memory: 4 cells touched, 4 non-zero at exit, values range 1–105",
+        ));
+}
+
+#[test]
+fn when_running_a_program_with_json_then_the_report_has_the_memory_figures() {
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--json",
+            "-f",
+            file_test_case!("test_known_cells.txt"),
+        ])
+        .assert()
+        .success();
+
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+    assert_eq!(report["summary"]["cells_touched"], 4);
+    assert_eq!(report["summary"]["memory"]["nonzero_cells"], 4);
+    assert_eq!(report["summary"]["memory"]["min_value"], 1);
+    assert_eq!(report["summary"]["memory"]["max_value"], 105)
+}
+
+#[test]
+fn given_300_increments_when_the_cells_are_8_or_16_bits_then_the_outputs_are_different() {
+    // The 8 bits cell wraps to 44 and the 16 bits one reaches 300.
+    for (cell_size, overflow, output, cell) in [
+        ("8", "wrap", "44\n", " 44"),
+        ("8", "saturate", "255\n", "255"),
+        ("16", "wrap", "300\n", "  300"),
+    ] {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args([
+                "run",
+                "--cell-size",
+                cell_size,
+                "--cell-overflow",
+                overflow,
+                "--output-mode",
+                "numeric",
+                "--dump-memory=0..1",
+                "--ext",
+                "txt",
+                "-f",
+                file_test_case!("test_300_increments.txt"),
+            ])
+            .assert()
+            .success()
+            .stdout(predicate::eq(output))
+            .stderr(predicate::str::contains(format!("0  dec {}\n", cell)));
+    }
+}
+
+#[test]
+fn given_16_bits_cells_when_the_values_pass_255_then_the_numeric_and_csv_modes_render_the_whole_values()
+ {
+    for (output_mode, output) in [("numeric", "255\n256\n257\n"), ("csv", "255,256,257\n")] {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args([
+                "run",
+                "--cell-size",
+                "16",
+                "--output-mode",
+                output_mode,
+                "--ext",
+                "txt",
+                "-f",
+                file_test_case!("test_count_past_255.txt"),
+            ])
+            .assert()
+            .success()
+            .stdout(predicate::eq(output));
+    }
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--cell-size",
+            "16",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_count_past_255.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq([255, 0, 1].as_slice()));
+}
+
+#[test]
+fn given_a_decrement_at_zero_when_the_cells_wrap_then_the_cell_is_255() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--wrap-cells",
+            "-f",
+            file_test_case!("test_decrement_at_zero.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq([255].as_slice()));
+}
+
+#[test]
+fn given_a_decrement_at_zero_when_the_cells_saturate_then_the_cell_stays_at_0() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--cell-overflow",
+            "saturate",
+            "-f",
+            file_test_case!("test_decrement_at_zero.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq([0].as_slice()));
+}
+
+#[test]
+fn given_a_decrement_at_zero_when_the_overflow_is_an_error_then_the_program_fails() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--cell-overflow",
+            "error",
+            "-f",
+            file_test_case!("test_decrement_at_zero.txt"),
+        ])
+        .assert()
+        .code(4)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains(
+            "The cell 0 underflows subtracting 1",
+        ));
+}
+
+#[test]
+fn when_running_a_program_with_json_then_the_report_has_the_overflow_policy() {
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--cell-overflow",
+            "saturate",
+            "--json",
+            "-f",
+            file_test_case!("test_decrement_at_zero.txt"),
+        ])
+        .assert()
+        .success();
+
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+    assert_eq!(report["cell_size"], "8");
+    assert_eq!(report["cell_overflow"], "saturate")
+}
+
+#[test]
+fn when_running_a_program_with_a_cell_size_then_the_report_and_the_dump_have_it() {
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--cell-size",
+            "i32",
+            "--json",
+            "-f",
+            file_test_case!("test_300_increments.txt"),
+        ])
+        .assert()
+        .success();
+
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+    assert_eq!(report["cell_size"], "i32");
+    assert_eq!(report["summary"]["memory"]["max_value"], 300);
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--cell-size",
+            "16",
+            "--dump-memory=0..1",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_300_increments.txt"),
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with(concat!(
+            "Cell size 16\n",
+            "       0  dec   300\n",
+            "          hex  012C\n"
+        )));
+}
+
+#[test]
+fn when_running_a_program_with_an_unknown_cell_size_then_the_cli_rejects_it() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--cell-size",
+            "64",
+            "-f",
+            file_test_case!("test_300_increments.txt"),
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "Accepted values are 8, 16, 32, i8 or i32",
+        ));
+}
+
+#[test]
+fn when_running_a_program_with_a_wide_cell_size_and_the_sparse_memory_then_it_fails() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--cell-size",
+            "16",
+            "--memory",
+            "sparse",
+            "-f",
+            file_test_case!("test_300_increments.txt"),
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "The cell size 16 is only available with the vec memory",
+        ));
+}
+
+#[test]
+fn when_running_with_the_memory_bounds_and_a_memory_other_than_vec_then_it_fails() {
+    for memory in ["sparse", "bidirectional"] {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args([
+                "run",
+                "--memory",
+                memory,
+                "--memory-bounds",
+                "wrap",
+                "-e",
+                "+.",
+            ])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(
+                "The flag --memory-bounds is only available with the vec memory",
+            ));
+    }
+}
+
+#[test]
+fn when_running_with_the_initial_cells_and_a_memory_other_than_vec_then_it_fails() {
+    for (flag, value) in [("--init-value", "65"), ("--init-pattern", "1,2")] {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args(["run", "--memory", "sparse", flag, value, "-e", "+."])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(format!(
+                "The flag {} is only available with the vec memory",
+                flag
+            )));
+    }
+}
+
+#[test]
+fn when_running_verbosely_with_an_initial_value_then_the_filled_cells_are_not_counted_as_non_zero()
+{
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-v", "--init-value", "65", "-m", "20", "-e", "."])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "memory: 1 cells touched, 0 non-zero at exit, values range 65–65",
+        ));
+}
+
+#[test]
+fn given_a_program_that_leaves_42_in_the_cell_when_running_with_exit_cell_then_it_exits_with_42() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--exit-cell",
+            "-f",
+            file_test_case!("test_leave_42.txt"),
+        ])
+        .assert()
+        .code(42)
+        .stderr(predicate::str::contains("Program executed succesfully"));
+}
+
+#[test]
+fn given_a_program_that_leaves_42_in_the_cell_when_running_without_exit_cell_then_it_exits_with_0()
+{
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-f", file_test_case!("test_leave_42.txt")])
+        .assert()
+        .code(0);
+}
+
+#[test]
+fn given_a_program_that_fails_when_running_with_exit_cell_then_it_exits_with_the_code_of_the_error()
+{
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--exit-cell",
+            "-f",
+            file_test_case!("test_infinite_loop.txt"),
+        ])
+        .assert()
+        .code(5);
+}
+
+#[test]
+fn given_a_negative_cell_when_running_with_exit_cell_then_it_exits_with_the_value_modulo_256() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "--exit-cell", "--cell-size", "i8", "-q", "-e=--"])
+        .assert()
+        .code(254);
+}
+
+#[test]
+fn when_running_a_program_with_json_then_the_summary_has_the_final_cell() {
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "--json", "-f", file_test_case!("test_leave_42.txt")])
+        .assert()
+        .success();
+
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+    assert_eq!(report["summary"]["final_cell"], 42)
+}
+
+#[test]
+fn when_running_a_program_without_verbose_nor_json_then_the_memory_figures_are_not_shown() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-f", file_test_case!("test_known_cells.txt")])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("non-zero at exit").not());
+}
+
+#[test]
+fn when_the_memory_size_has_a_suffix_then_the_program_runs_with_that_many_cells() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--memory-size",
+            "64KiB",
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::eq("Hello World!\n"));
+}
+
+#[test]
+fn when_the_memory_size_has_an_unknown_suffix_then_render_an_error_of_invalid_argument_value() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--memory-size",
+            "10kk",
+            "-f",
+            file_test_case!("test_hello_world.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown suffix \"kk\""));
+}
+
+#[test]
+fn when_running_a_program_that_moves_left_from_the_start_with_wrapping_bounds_then_it_writes_the_last_cell()
+ {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--memory-bounds",
+            "wrap",
+            "-f",
+            file_test_case!("test_move_left_from_start.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("A");
+}
+
+#[test]
+fn when_running_a_program_past_the_end_of_the_memory_with_growing_bounds_then_it_runs() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "-m",
+            "2",
+            "--memory-bounds",
+            "grow",
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("Hello World!\n");
+}
+
+#[test]
+fn when_running_a_program_with_an_init_value_then_the_cells_start_with_it() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--init-value",
+            "65",
+            "-f",
+            file_test_case!("test_output_current_cell.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("A");
+}
+
+#[test]
+fn when_running_a_program_with_an_init_pattern_then_the_cells_repeat_it() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--init-pattern",
+            "72,73",
+            "-f",
+            file_test_case!("test_print_cells_five_and_six.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("IH");
+}
+
+#[test]
+fn given_the_multitape_dialect_when_the_program_writes_in_each_tape_then_the_tapes_are_isolated() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--dialect",
+            "multitape",
+            "--dump-memory",
+            "-f",
+            file_test_case!("test_multitape_ab.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("AB"))
+        .stderr(predicate::str::contains("Tape 1\n       0  dec   0  66\n"));
+}
+
+#[test]
+fn when_running_hello_world_piped_with_a_dash_path_then_the_source_is_read_from_the_stdin() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-f", "-"])
+        .write_stdin(read(file_test_case!("test_hello_world_newline.txt")).unwrap())
+        .assert()
+        .success()
+        .stdout(predicate::eq("Hello World!\n"))
+        .stderr(predicate::str::contains("Program executed succesfully"));
+}
+
+#[test]
+fn when_linting_a_program_piped_with_a_dash_path_then_the_source_is_read_from_the_stdin() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["lint", "-f", "-"])
+        .write_stdin("+[-")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("loop"));
+}
+
+#[test]
+fn when_a_program_piped_with_a_dash_path_reads_the_input_then_it_needs_another_input_source() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-f", "-"])
+        .write_stdin(",.")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The source code was read from the stdin, so the program can't read its input from it",
+        ));
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "--in", "a", "-f", "-"])
+        .write_stdin(",.")
+        .assert()
+        .success()
+        .stdout("a");
+}
+
+#[test]
+fn when_running_a_source_code_with_bytes_that_are_not_utf8_in_a_comment_then_it_runs() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-f", file_test_case!("test_non_utf8_comment.txt")])
+        .assert()
+        .success()
+        .stdout("A");
+}
+
+#[test]
+fn when_running_a_source_code_bigger_than_the_max_source_size_then_it_is_not_read() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--max-source-size",
+            "8",
+            "-f",
+            file_test_case!("test_hello_world.txt"),
+        ])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("bigger than the limit of 8 bytes"));
+}
+
+#[test]
+fn when_running_a_program_with_includes_allowed_then_the_included_file_runs_in_its_place() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--allow-includes",
+            "-f",
+            file_test_case!("test_include_main.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("AA");
+}
+
+#[test]
+fn when_running_a_program_with_includes_not_allowed_then_the_directive_is_a_comment() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-f", file_test_case!("test_include_main.txt")])
+        .assert()
+        .success()
+        .stdout("A");
+}
+
+#[test]
+fn when_running_a_program_that_includes_a_missing_file_then_it_fails_naming_the_file() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--allow-includes",
+            "-f",
+            file_test_case!("test_include_missing.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "\"test_include_missing_file.txt\"",
+        ));
+}
+
+#[test]
+fn when_linting_a_program_with_an_include_cycle_then_it_fails_with_the_chain() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "lint",
+            "--allow-includes",
+            "-f",
+            file_test_case!("test_include_cycle_a.txt"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("test_include_cycle_a.txt -> "))
+        .stderr(predicate::str::contains("test_include_cycle_b.txt -> "))
+        .stderr(predicate::str::contains(
+            "goes back to a file already included",
+        ));
+}
+
+#[test]
+fn when_linting_a_directory_with_a_good_and_a_broken_file_then_both_are_reported_and_it_fails() {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/lint_several_files");
+    let _ = remove_dir_all(directory);
+    create_dir_all(directory).unwrap();
+    write(format!("{}/good.bf", directory), "+[-]").unwrap();
+    write(format!("{}/broken.bf", directory), "+[-").unwrap();
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["lint", "-f", &format!("{}/*.bf", directory)])
+        .assert()
+        .failure()
+        .stdout(format!(
+            "{}/good.bf: All good!\n2 files checked, 1 with errors\n",
+            directory
+        ))
+        .stderr(predicate::str::contains(format!(
+            "error: {}/broken.bf: ",
+            directory
+        )));
+
+    remove_dir_all(directory).expect("Directory to be deleted")
+}
+
+#[test]
+fn when_linting_several_files_as_arguments_then_they_are_checked_in_order() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "lint",
+            file_test_case!("test_hello_world.txt"),
+            file_test_case!("test_cat.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}: All good!\n{}: All good!\n2 files checked, 0 with errors\n",
+            file_test_case!("test_cat.txt"),
+            file_test_case!("test_hello_world.txt")
+        ));
+}
+
+#[test]
+fn when_linting_a_tree_recursively_then_the_source_files_are_checked_skipping_the_hidden_and_excluded()
+ {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/lint_recursive");
+    let _ = remove_dir_all(directory);
+    for subdirectory in [".hidden", "sub", "vendor"] {
+        create_dir_all(format!("{}/{}", directory, subdirectory)).unwrap();
+    }
+    for (file, content) in [
+        ("good.bf", "+[-]"),
+        ("bad.b", "+["),
+        ("notes.txt", "["),
+        (".hidden/bad.bf", "["),
+        ("sub/good.bf", "+"),
+        ("vendor/bad.bf", "["),
+    ] {
+        write(format!("{}/{}", directory, file), content).unwrap();
+    }
+    std::os::unix::fs::symlink(directory, format!("{}/sub/loop", directory)).unwrap();
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["lint", "--recursive", "--exclude", "vendor", directory])
+        .assert()
+        .failure()
+        .stdout(format!(
+            "{0}/good.bf: All good!\n{0}/sub/good.bf: All good!\n3 files checked, 1 with errors\n",
+            directory
+        ))
+        .stderr(predicate::str::contains(format!(
+            "error: {}/bad.b: ",
+            directory
+        )));
+
+    remove_dir_all(directory).expect("Directory to be deleted")
+}
+
+#[test]
+fn given_a_directory_without_permission_when_linting_recursively_then_it_is_a_failed_file() {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/lint_unreadable");
+    let locked = format!("{}/locked", directory);
+    let _ = remove_dir_all(directory);
+    create_dir_all(&locked).unwrap();
+    write(format!("{}/good.bf", directory), "+[-]").unwrap();
+    write(format!("{}/hidden.bf", locked), "+[").unwrap();
+    set_permissions(&locked, Permissions::from_mode(0o000)).unwrap();
+
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["lint", "--recursive", directory])
+        .assert();
+
+    set_permissions(&locked, Permissions::from_mode(0o755)).unwrap();
+    assert
+        .failure()
+        .code(2)
+        .stdout(format!(
+            "{}/good.bf: All good!\n2 files checked, 1 with errors\n",
+            directory
+        ))
+        .stderr(predicate::str::contains(format!(
+            "error: {0}: Unable to read the file {0:?} due lack of permission",
+            locked
+        )));
+
+    remove_dir_all(directory).expect("Directory to be deleted")
+}
+
+fn write_gzipped(path: &str, content: &[u8]) {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content).unwrap();
+    write(path, encoder.finish().unwrap()).unwrap();
+}
+
+#[test]
+fn when_running_a_gzipped_hello_world_then_it_is_decompressed_and_run() {
+    let source_path = concat!(env!("CARGO_TARGET_TMPDIR"), "/hello_world.bf.gz");
+    write_gzipped(
+        source_path,
+        &read(file_test_case!("test_hello_world_newline.txt")).unwrap(),
+    );
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-f", source_path])
+        .assert()
+        .success()
+        .stdout(predicate::eq("Hello World!\n"));
+
+    remove_file(source_path).expect("File to be deleted")
+}
+
+#[test]
+fn when_running_a_corrupted_gzipped_source_then_it_fails_naming_the_file() {
+    let source_path = concat!(env!("CARGO_TARGET_TMPDIR"), "/corrupted.bf.gz");
+    write_gzipped(source_path, b"+[-].");
+    let compressed = read(source_path).unwrap();
+    write(source_path, &compressed[..compressed.len() / 2]).unwrap();
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-f", source_path])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Unable to decompress the file {:?}",
+            source_path
+        )));
+
+    remove_file(source_path).expect("File to be deleted")
+}
+
+#[test]
+fn when_running_twice_with_the_cache_then_the_second_run_reuses_it_until_the_source_changes() {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/cache");
+    let source_path = format!("{}/hello.bf", directory);
+    let _ = remove_dir_all(directory);
+    create_dir_all(directory).unwrap();
+    write(
+        &source_path,
+        read(file_test_case!("test_hello_world_newline.txt")).unwrap(),
+    )
+    .unwrap();
+    let run = || {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args(["run", "--cache", "-v", "-f", &source_path])
+            .assert()
+            .success()
+    };
+
+    run()
+        .stdout("Hello World!\n")
+        .stderr(predicate::str::contains("cache miss: "));
+    run()
+        .stdout("Hello World!\n")
+        .stderr(predicate::str::contains(format!(
+            "cache hit: {}/hello.bf.bfc",
+            directory
+        )));
+
+    write(&source_path, "++++++++[>++++++++<-]>+.").unwrap();
+
+    run()
+        .stdout("A")
+        .stderr(predicate::str::contains("cache miss: "));
+
+    remove_dir_all(directory).expect("Directory to be deleted")
+}
+
+#[test]
+fn given_a_source_with_the_cache_extension_when_running_with_the_cache_then_the_source_is_kept() {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/cache_source");
+    let source_path = format!("{}/hello.bfc", directory);
+    let _ = remove_dir_all(directory);
+    create_dir_all(directory).unwrap();
+    write(&source_path, "++++++++[>++++++++<-]>+.").unwrap();
+    let run = || {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args(["run", "--cache", "-f", &source_path])
+            .assert()
+            .success()
+            .stdout("A")
+    };
+
+    run();
+    run();
+
+    assert_eq!(
+        read(&source_path).unwrap(),
+        b"++++++++[>++++++++<-]>+.".to_vec()
+    );
+    assert!(std::path::Path::new(&format!("{}.bfc", source_path)).exists());
+
+    remove_dir_all(directory).expect("Directory to be deleted")
+}
+
+#[test]
+fn given_a_file_at_the_cache_path_when_running_with_the_cache_then_it_is_not_written_over() {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/cache_taken");
+    let source_path = format!("{}/hello.bf", directory);
+    let taken_path = format!("{}/hello.bf.bfc", directory);
+    let _ = remove_dir_all(directory);
+    create_dir_all(directory).unwrap();
+    write(&source_path, "++++++++[>++++++++<-]>+.").unwrap();
+    write(&taken_path, "notes").unwrap();
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "--cache", "-f", &source_path])
+        .assert()
+        .success()
+        .stdout("A")
+        .stderr(predicate::str::contains(format!(
+            "Unable to write the cache file {}: the file already exists and isn't a cache file",
+            taken_path
+        )));
+
+    assert_eq!(read(&taken_path).unwrap(), b"notes".to_vec());
+
+    remove_dir_all(directory).expect("Directory to be deleted")
+}
+
+#[test]
+fn when_running_a_source_code_with_a_shebang_then_the_shebang_line_is_not_executed() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-f", file_test_case!("test_shebang.txt")])
+        .assert()
+        .success()
+        .stdout("A");
+}
+
+#[test]
+fn when_linting_a_source_code_with_a_shebang_then_the_errors_keep_their_line() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["lint", "-f", "-"])
+        .write_stdin("#!/usr/bin/env -S braincrab run -f\n+]\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--> -:2:2"));
+}
+
+#[test]
+fn when_running_a_file_without_a_source_code_extension_then_it_warns_and_runs() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-f", file_test_case!("test_output_a.txt")])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("warning: The file "))
+        .stderr(predicate::str::contains(
+            "test_output_a.txt doesn't have the extension of a source code (b, bf)",
+        ));
+}
+
+#[test]
+fn when_running_a_file_of_prose_then_it_warns_unless_strict_fails() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_prose.txt"),
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "doesn't look like brainfuck, only 0.00% of it are commands",
+        ));
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--strict",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_prose.txt"),
+        ])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::starts_with("error: The file "));
+}
+
+#[test]
+fn when_running_several_files_then_they_run_as_one_program() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_split_loop_start.txt"),
+            "-f",
+            file_test_case!("test_split_loop_end.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("A");
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--ext",
+            "txt",
+            file_test_case!("test_split_loop_start.txt"),
+            file_test_case!("test_split_loop_end.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("A");
+}
+
+#[test]
+fn when_the_second_file_of_a_program_has_an_error_then_it_is_located_in_that_file() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_split_loop_start.txt"),
+            "-f",
+            file_test_case!("test_split_extra_close.txt"),
+        ])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains(concat!(
+            file_test_case!("test_split_extra_close.txt"),
+            ":3:1"
+        )));
+}
+
+#[test]
+fn given_an_error_of_each_category_when_running_then_the_exit_code_is_the_one_of_the_category() {
+    for (args, exit_code) in [
+        (vec!["run", "--unknown-flag"], 1),
+        (vec!["run", "-f", file_test_case!("missing.bf")], 2),
+        (
+            vec!["run", "-f", file_test_case!("test_lack_open_loop.txt")],
+            3,
+        ),
+        (
+            vec![
+                "run",
+                "-m",
+                "2",
+                "-f",
+                file_test_case!("test_hello_world.txt"),
+            ],
+            4,
+        ),
+        (
+            vec!["run", "-f", file_test_case!("test_infinite_loop.txt")],
+            5,
+        ),
+    ] {
+        Command::cargo_bin("braincrab")
+            .unwrap()
+            .args(&args)
+            .assert()
+            .code(exit_code)
+            .stderr(predicate::str::contains("panicked").not());
+    }
+}
+
+#[test]
+fn given_a_runtime_error_when_running_with_json_then_the_exit_code_is_the_one_of_the_error() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--json",
+            "-m",
+            "2",
+            "-f",
+            file_test_case!("test_hello_world.txt"),
+        ])
+        .assert()
+        .code(4)
+        .stdout(predicate::str::contains("\"OutOfRangeMemoryAccess\""));
+}
+
+#[test]
+fn when_running_an_inline_program_then_it_runs_without_a_file() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-q", "-e", "++++++++[>++++++++<-]>+."])
+        .assert()
+        .success()
+        .stdout("A")
+        .stderr("");
+}
+
+#[test]
+fn when_running_an_unbalanced_inline_program_then_the_error_has_the_column() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["--color", "never", "run", "--eval", "+[>+<-]]"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains(" --> inline program, column 8\n"));
+}
+
+#[test]
+fn given_an_inline_program_and_a_file_when_running_then_the_cli_refuses_both() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "-e",
+            "+.",
+            "-f",
+            file_test_case!("test_hello_world.txt"),
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn when_the_repl_reads_lines_from_a_pipe_then_the_memory_is_kept_between_them() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["repl"])
+        .write_stdin("++++++++[>++++++++\n<-]>+.\n:quit\n")
+        .assert()
+        .success()
+        .stdout("bf> ..> A\n[pointer 1, cell 65]\nbf> ");
+}
+
+#[test]
+fn when_the_user_types_in_the_repl_then_each_line_runs_on_the_same_tape() {
+    let mut session = spawn("cargo run -- repl").expect("Error runing the repl subcommand");
+
+    session
+        .expect(Any::boxed(vec![Box::new("bf> ")]))
+        .expect("Prompt not rendered");
+    session
+        .send_line("++++++++[>++++++++<-]")
+        .expect("Failed to write the first line");
+    session
+        .expect(Any::boxed(vec![Box::new("[pointer 0, cell 0]")]))
+        .expect("State of the first line not rendered");
+
+    session
+        .send_line(">+.")
+        .expect("Failed to write the second line");
+    session
+        .expect(Any::boxed(vec![Box::new("[pointer 1, cell 65]")]))
+        .expect("State of the second line not rendered");
+
+    session.send_line(":quit").expect("Failed to quit");
+}
+
+#[test]
+fn when_the_ast_of_a_program_is_printed_then_it_is_listed_without_running_it() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["ast", "-e", "+[-]."])
+        .assert()
+        .success()
+        .stdout(concat!(
+            "0  1:1  + increment       -> 1\n",
+            "1  1:2  [ loop            -> 2 if the cell isn't 0, 4 if it is\n",
+            "2  1:3    - decrement     -> 3\n",
+            "3  1:4  ] end loop        -> 1\n",
+            "4  1:5  . output          -> 5\n",
+        ))
+        .stderr("");
+}
+
+#[test]
+fn when_the_ast_of_nested_loops_is_printed_then_where_they_go_is_in_one_column() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["ast", "-e", "+[>[-]<-]"])
+        .assert()
+        .success()
+        .stdout(concat!(
+            "0  1:1  + increment         -> 1\n",
+            "1  1:2  [ loop              -> 2 if the cell isn't 0, 9 if it is\n",
+            "2  1:3    > move right      -> 3\n",
+            "3  1:4    [ loop            -> 4 if the cell isn't 0, 6 if it is\n",
+            "4  1:5      - decrement     -> 5\n",
+            "5  1:6    ] end loop        -> 3\n",
+            "6  1:7    < move left       -> 7\n",
+            "7  1:8    - decrement       -> 8\n",
+            "8  1:9  ] end loop          -> 1\n",
+        ))
+        .stderr("");
+}
+
+#[test]
+fn when_the_ast_of_a_file_is_printed_with_json_then_it_is_the_serialized_ast() {
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["ast", "--json", "-f", file_test_case!("test_cat.txt")])
+        .assert()
+        .success();
+
+    let ast: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+    assert_eq!(ast.as_array().unwrap().len(), 5);
+    assert_eq!(
+        ast[1]["Loop"],
+        serde_json::json!({
+            "operation": "LoopStart",
+            "next_position_as_true": 2,
+            "next_position_as_false": 5
+        })
+    );
+}
+
+#[test]
+fn when_the_ast_of_an_unbalanced_program_is_printed_then_it_fails_with_the_parse_error() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["ast", "-e", "+]"])
+        .assert()
+        .code(3)
+        .stdout("")
+        .stderr(predicate::str::contains("inline program, column 2"));
+}
+
+#[test]
+fn given_a_config_file_when_running_then_the_environment_and_the_flags_override_it() {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/config_precedence");
+    let _ = remove_dir_all(directory);
+    create_dir_all(directory).unwrap();
+    write(format!("{}/braincrub.toml", directory), "memory-size = 2\n").unwrap();
+
+    let run = |env: Option<&str>, flag: Option<&str>| {
+        let mut command = Command::cargo_bin("braincrab").unwrap();
+        command
+            .current_dir(directory)
+            .env_remove("BRAINCRUB_MEMORY_SIZE")
+            .args(["run", "-e", ">>>>>>>>"]);
+        if let Some(size) = env {
+            command.env("BRAINCRUB_MEMORY_SIZE", size);
+        }
+        if let Some(size) = flag {
+            command.args(["-m", size]);
+        }
+        command.assert().code(4)
+    };
+
+    run(None, None).stderr(predicate::str::contains("(the tape is 0..2)"));
+    run(Some("3"), None).stderr(predicate::str::contains("(the tape is 0..3)"));
+    run(Some("3"), Some("4")).stderr(predicate::str::contains("(the tape is 0..4)"));
+
+    remove_dir_all(directory).expect("Directory to be deleted")
+}
+
+#[test]
+fn given_a_config_file_with_an_unknown_key_when_running_then_it_warns_about_the_key() {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/config_unknown_key");
+    let _ = remove_dir_all(directory);
+    create_dir_all(directory).unwrap();
+    write(
+        format!("{}/braincrub.toml", directory),
+        "optimize = true\noutput-mode = \"numeric\"\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .current_dir(directory)
+        .args(["run", "-e", "+++."])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("3"))
+        .stderr(predicate::str::contains(
+            "warning: Unknown key optimize in the config file",
+        ));
+
+    remove_dir_all(directory).expect("Directory to be deleted")
+}
+
+#[test]
+fn given_a_config_file_when_running_with_no_config_then_it_is_ignored() {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/config_ignored");
+    let _ = remove_dir_all(directory);
+    create_dir_all(directory).unwrap();
+    write(format!("{}/braincrub.toml", directory), "memory-size = \n").unwrap();
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .current_dir(directory)
+        .args(["run", "-e", "+++."])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("is invalid"));
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .current_dir(directory)
+        .args(["--no-config", "run", "-e", "+++."])
+        .assert()
+        .success();
+
+    remove_dir_all(directory).expect("Directory to be deleted")
+}
+
+#[test]
+fn given_a_config_in_the_config_directory_of_the_user_when_running_then_it_is_used() {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/config_home");
+    let _ = remove_dir_all(directory);
+    create_dir_all(format!("{}/braincrub", directory)).unwrap();
+    write(
+        format!("{}/braincrub/config.toml", directory),
+        "output-mode = \"numeric\"\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .env("XDG_CONFIG_HOME", directory)
+        .args(["run", "-e", "+++."])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("3"));
+
+    remove_dir_all(directory).expect("Directory to be deleted")
+}
+
+#[test]
+fn given_a_tiny_memory_size_in_the_environment_when_running_then_the_pointer_goes_out_of_range() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .env("BRAINCRUB_MEMORY_SIZE", "1")
+        .args(["--no-config", "run", "-e", ">>"])
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains("(the tape is 0..1)"));
+}
+
+#[test]
+fn given_the_limit_and_the_output_mode_in_the_environment_when_running_then_they_are_used() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .env("BRAINCRUB_OUTPUT_MODE", "numeric")
+        .args(["--no-config", "run", "-e", "+++."])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("3\n"));
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .env("BRAINCRUB_LIMIT", "5")
+        .args(["--no-config", "run", "-e", "+++++++"])
+        .assert()
+        .code(5);
+}
+
+#[test]
+fn given_an_invalid_value_in_the_environment_when_running_then_it_fails_as_the_flag() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .env("BRAINCRUB_MEMORY_SIZE", "0")
+        .args(["--no-config", "run", "-e", "+"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--memory-size <memory-size>': Minimum value accepted is 1",
+        ));
+}
+
+#[test]
+fn given_the_suite_corpus_when_it_is_tested_then_every_case_has_a_line_and_the_summary_counts_them()
+{
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "test",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/resources/suite"),
+        ])
+        .assert()
+        .code(4)
+        .stdout(predicate::str::contains(
+            "PASS echo\nPASS hello\nERROR runaway: ",
+        ))
+        .stdout(predicate::str::contains(
+            "PASS unbalanced\nFAIL wrong: the output is \"A\" instead of \"B\"\n",
+        ))
+        .stdout(predicate::str::ends_with(
+            "5 cases: 3 passed, 1 failed, 1 with errors\n",
+        ));
+}
+
+#[test]
+fn given_a_closed_stdout_when_the_suite_is_tested_then_exit_without_panicking() {
+    let (reader, writer) = pipe().unwrap();
+    drop(reader);
+
+    let output = process::Command::new(env!("CARGO_BIN_EXE_braincrab"))
+        .args([
+            "test",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/resources/suite"),
+        ])
+        .stdout(writer)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(!stderr.contains("panicked"), "{}", stderr)
+}
+
+#[test]
+fn given_a_suite_without_outputs_when_it_is_tested_with_update_then_the_outputs_are_written() {
+    let directory = concat!(env!("CARGO_TARGET_TMPDIR"), "/suite_update");
+    let _ = remove_dir_all(directory);
+    create_dir_all(directory).unwrap();
+    write(format!("{}/echo.bf", directory), ",[.,]").unwrap();
+    write(format!("{}/echo.in", directory), "hi").unwrap();
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["test", directory])
+        .assert()
+        .code(4)
+        .stdout(predicate::str::ends_with(
+            "1 case: 0 passed, 0 failed, 1 with errors\n",
+        ));
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["test", "--update", directory])
+        .assert()
+        .success()
+        .stdout("UPDATED echo\n1 case: 0 passed, 0 failed, 0 with errors, 1 updated\n");
+
+    assert_eq!(read(format!("{}/echo.out", directory)).unwrap(), b"hi");
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["test", directory])
+        .assert()
+        .success()
+        .stdout("PASS echo\n1 case: 1 passed, 0 failed, 0 with errors\n");
+
+    remove_dir_all(directory).expect("Directory to be deleted")
+}
+
+#[test]
+fn given_a_program_with_two_levels_of_loops_when_it_is_traced_then_the_loop_events_are_nested() {
+    let trace_path = concat!(env!("CARGO_TARGET_TMPDIR"), "/trace_nested_loops.json");
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--trace-file",
+            trace_path,
+            "--trace-sample",
+            "1000",
+            "-e",
+            "++[>++[-]<-]>.",
+        ])
+        .assert()
+        .success();
+
+    let trace: Value = serde_json::from_slice(&read(trace_path).unwrap()).unwrap();
+    let events: Vec<(&str, &str)> = trace
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|event| event["ph"] != "C")
+        .map(|event| {
+            (
+                event["ph"].as_str().unwrap(),
+                event["name"].as_str().unwrap(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        events,
+        [
+            ("B", "loop at 1:3"),
+            ("B", "loop at 1:7"),
+            ("E", "loop at 1:7"),
+            ("B", "loop at 1:7"),
+            ("E", "loop at 1:7"),
+            ("E", "loop at 1:3"),
+            ("i", "output"),
+        ]
+    );
+
+    remove_file(trace_path).expect("File to be deleted")
+}
+
+#[test]
+fn given_a_run_aborted_inside_of_loops_when_it_is_traced_then_the_trace_is_still_valid_json() {
+    let trace_path = concat!(env!("CARGO_TARGET_TMPDIR"), "/trace_aborted_run.json");
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--trace-file",
+            trace_path,
+            "-l",
+            "100",
+            "-e",
+            "+[[>+<]]",
+        ])
+        .assert()
+        .code(5);
+
+    let trace: Value = serde_json::from_slice(&read(trace_path).unwrap()).unwrap();
+    let phases: Vec<&str> = trace
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|event| event["ph"].as_str().filter(|phase| *phase != "C"))
+        .collect();
+
+    assert_eq!(phases, ["B", "B", "E", "E"]);
+
+    remove_file(trace_path).expect("File to be deleted")
+}
+
+#[test]
+fn when_running_hello_world_with_time_then_the_stderr_has_the_execution_time_and_instructions() {
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--time",
+            "--ext",
+            "txt",
+            "-f",
+            file_test_case!("test_hello_world_newline.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("Hello World!\n");
+
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    let timing = stderr
+        .lines()
+        .find(|line| line.starts_with("executed in "))
+        .expect("The timing line to be printed");
+    let instructions: usize = timing
+        .split(", ")
+        .nth(1)
+        .and_then(|part| part.strip_suffix(" instructions"))
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    assert!((500..5000).contains(&instructions), "{}", timing);
+    assert!(timing.ends_with(" instructions/s"), "{}", timing);
+}
+
+#[test]
+fn when_running_with_time_and_json_then_the_timing_is_in_the_report() {
+    let assert = Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "--time", "--json", "-l", "10", "-e", "+[]"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("executed in").not());
+
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+
+    assert_eq!(report["timing"]["instructions_executed"], 10);
+    assert!(report["timing"]["execution_micros"].is_u64());
+}
+
+#[test]
+fn when_a_generator_is_piped_into_cat_then_its_output_is_shown() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "pipe",
+            "-f",
+            file_test_case!("test_output_abc.txt"),
+            "-f",
+            file_test_case!("test_cat.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("abc");
+}
+
+#[test]
+fn when_three_programs_are_piped_then_the_middle_one_transforms_the_output_of_the_first_one() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "pipe",
+            "-f",
+            file_test_case!("test_output_abc.txt"),
+            "-f",
+            file_test_case!("test_uppercase.txt"),
+            "-f",
+            file_test_case!("test_cat.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("ABC");
+}
+
+#[test]
+fn when_a_stage_of_the_pipe_fails_then_the_error_names_the_stage() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "pipe",
+            "-f",
+            file_test_case!("test_output_abc.txt"),
+            "-f",
+            file_test_case!("test_infinite_loop.txt"),
+        ])
+        .assert()
+        .code(5)
+        .stdout("")
+        .stderr(
+            predicate::str::contains("Stage 2 (").and(predicate::str::contains(
+                "test_infinite_loop.txt): Not enought reads",
+            )),
+        );
+}
+
+#[test]
+fn when_running_nested_loops_with_profile_then_the_report_has_the_top_loop() {
+    let profile_path = concat!(env!("CARGO_TARGET_TMPDIR"), "/nested_loops_profile.json");
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "-q",
+            "--profile",
+            profile_path,
+            "--profile-format",
+            "json",
+            "-f",
+            file_test_case!("test_nested_loops.txt"),
+        ])
+        .assert()
+        .success();
+
+    let profile: Value = serde_json::from_slice(&read(profile_path).unwrap()).unwrap();
+    remove_file(profile_path).unwrap();
+
+    assert_eq!(profile["timing"]["instructions_executed"], 309);
+    assert_eq!(profile["max_memory_position"], 2);
+    assert_eq!(profile["loops"][0]["position"], "1:5");
+    assert_eq!(profile["loops"][0]["iterations"], 4);
+    assert_eq!(profile["loops"][0]["instructions"], 304);
+    assert!((profile["loops"][0]["percentage"].as_f64().unwrap() - 98.38).abs() < 0.01);
+    assert_eq!(profile["loops"][1]["position"], "1:17");
+    assert_eq!(profile["loops"][1]["iterations"], 40);
+    assert_eq!(profile["opcodes"][0]["opcode"], "+");
+    assert_eq!(profile["opcodes"][0]["executions"], 84);
+}
+
+#[test]
+fn when_running_nested_loops_with_a_text_profile_then_the_loops_are_listed_by_instructions() {
+    let profile_path = concat!(env!("CARGO_TARGET_TMPDIR"), "/nested_loops_profile.txt");
+
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "-q",
+            "--profile",
+            profile_path,
+            "-f",
+            file_test_case!("test_nested_loops.txt"),
+        ])
+        .assert()
+        .success();
+
+    let profile = String::from_utf8(read(profile_path).unwrap()).unwrap();
+    remove_file(profile_path).unwrap();
+
+    // The first line has the time, which changes between runs.
+    assert_eq!(
+        profile.lines().skip(1).collect::<Vec<_>>().join("\n"),
+        "memory: 3 cells touched, up to the cell 2
+
+loops by instructions:
+  loop at 1:5: 304 instructions (98.38%), 4 iterations
+  loop at 1:17: 240 instructions (77.67%), 40 iterations
+
+commands:
+  + 84 (27.18%)
+  [ 49 (15.86%)
+  > 44 (14.24%)
+  < 44 (14.24%)
+  - 44 (14.24%)
+  ] 44 (14.24%)"
+    );
+}
+
+#[test]
+fn when_running_with_profile_and_trace_file_then_they_conflict() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "run",
+            "--profile",
+            "profile.txt",
+            "--trace-file",
+            "trace.json",
+            "-e",
+            "+",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn when_running_without_a_program_and_the_source_code_piped_then_it_is_run() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .arg("run")
+        .write_stdin("++++++++[>++++++++<-]>+.")
+        .assert()
+        .success()
+        .stdout("A")
+        .stderr(predicate::str::contains("Program executed succesfully"));
+}
+
+#[test]
+fn when_running_without_a_program_in_the_terminal_then_the_program_is_asked_for() {
+    let mut command = process::Command::new(assert_cmd::cargo::cargo_bin("braincrab"));
+    command.arg("run");
+    let mut session = Session::spawn(command).expect("Error runing the run subcommand");
+
+    session
+        .expect(Any::boxed(vec![Box::new(
+            "the program is missing, give a file with -f or as FILES, the source code with -e, or pipe it to the stdin",
+        )]))
+        .expect("Error of the missing program not rendered");
+}
+
+#[test]
+fn when_running_a_file_with_something_piped_then_the_piped_bytes_are_the_input() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args(["run", "-q", "-f", file_test_case!("test_cat.txt")])
+        .write_stdin("++.")
+        .assert()
+        .success()
+        .stdout("++.");
+}
+
+#[test]
+fn when_diffing_two_hello_worlds_that_print_a_different_byte_then_the_divergence_is_shown() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "diff",
+            "-a",
+            file_test_case!("test_hello_world_newline.txt"),
+            "-b",
+            file_test_case!("test_hello_world_exclamation_typo.txt"),
+        ])
+        .assert()
+        .code(4)
+        .stdout(
+            "outputs differ at the byte 11 (a printed 13 bytes, b printed 13 bytes)
+a: 6c 6f 20 57 6f 72 6c 64 [21] 0a
+b: 6c 6f 20 57 6f 72 6c 64 [22] 0a
+",
+        );
+}
+
+#[test]
+fn given_a_closed_stdout_when_diffing_two_programs_then_exit_without_panicking() {
+    let (reader, writer) = pipe().unwrap();
+    drop(reader);
+
+    let output = process::Command::new(env!("CARGO_BIN_EXE_braincrab"))
+        .args([
+            "diff",
+            "-a",
+            file_test_case!("test_hello_world_newline.txt"),
+            "-b",
+            file_test_case!("test_hello_world_exclamation_typo.txt"),
+        ])
+        .stdout(writer)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(141));
+    assert!(!stderr.contains("panicked"), "{}", stderr)
+}
+
+#[test]
+fn when_diffing_two_programs_written_differently_with_the_same_output_then_they_are_identical() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "diff",
+            "-a",
+            file_test_case!("test_hello_world_newline.txt"),
+            "-b",
+            file_test_case!("test_hello_world_commented.txt"),
+        ])
+        .assert()
+        .success()
+        .stdout("outputs identical (13 bytes)\n");
+}
+
+#[test]
+fn when_diffing_a_program_with_one_that_never_ends_then_the_failure_is_a_difference() {
+    Command::cargo_bin("braincrab")
+        .unwrap()
+        .args([
+            "diff",
+            "-a",
+            file_test_case!("test_cat.txt"),
+            "-b",
+            file_test_case!("test_infinite_loop.txt"),
+            "--input-file",
+            file_test_case!("test_data_hi.bin"),
+        ])
+        .assert()
+        .code(4)
+        .stdout(predicate::str::contains(
+            "a finished\nb failed: Not enought reads",
         ));
 }